@@ -557,6 +557,10 @@ mod tests {
         exec_request, exec_request_with_query, get_test_index_from_terms,
         get_test_index_from_values_and_terms,
     };
+    use crate::collector::Count;
+    use crate::query::QueryParser;
+    use crate::schema::{Schema, TextFieldIndexing, TextOptions};
+    use crate::Index;
 
     #[test]
     fn terms_aggregation_test_single_segment() -> crate::Result<()> {
@@ -1207,6 +1211,59 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn terms_aggregation_normalizer() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let country = schema_builder.add_text_field(
+            "country",
+            TextOptions::default()
+                .set_indexing_options(TextFieldIndexing::default().set_tokenizer("lowercase"))
+                .set_fast(),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            for country_name in ["US", "us", "Us", "FR"] {
+                index_writer.add_document(doc!(country => country_name))?;
+            }
+            index_writer.commit()?;
+        }
+
+        let agg_req: Aggregations = vec![(
+            "countries".to_string(),
+            Aggregation::Bucket(BucketAggregation {
+                bucket_agg: BucketAggregationType::Terms(TermsAggregation {
+                    field: "country".to_string(),
+                    ..Default::default()
+                }),
+                sub_aggregation: Default::default(),
+            }),
+        )]
+        .into_iter()
+        .collect();
+
+        let res = exec_request(agg_req, &index)?;
+        assert_eq!(res["countries"]["buckets"][0]["key"], "us");
+        assert_eq!(res["countries"]["buckets"][0]["doc_count"], 3);
+        assert_eq!(res["countries"]["buckets"][1]["key"], "fr");
+        assert_eq!(res["countries"]["buckets"][1]["doc_count"], 1);
+        assert_eq!(
+            res["countries"]["buckets"][2]["key"],
+            serde_json::Value::Null
+        );
+
+        let searcher = index.reader()?.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![country]);
+        let count =
+            |query: &str| searcher.search(&query_parser.parse_query(query).unwrap(), &Count);
+        assert_eq!(count("country:US")?, 3);
+        assert_eq!(count("country:uS")?, 3);
+        assert_eq!(count("country:[A TO Z]")?, 4);
+        assert_eq!(count("country:{FR TO Z]")?, 3);
+
+        Ok(())
+    }
+
     #[test]
     fn terms_aggregation_term_bucket_limit() -> crate::Result<()> {
         let terms: Vec<String> = (0..100_000).map(|el| el.to_string()).collect();
@@ -0,0 +1,288 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::collector::sort_key_top_collector::FastFieldValues;
+use crate::collector::top_collector::{TopCollector, TopSegmentCollector};
+use crate::collector::{Collector, SegmentCollector, SortKey, SortValue};
+use crate::schema::Field;
+use crate::{DocAddress, DocId, Order, Score, SegmentOrdinal, SegmentReader};
+
+/// The documents sharing a value of the collapse field.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CollapsedGroup {
+    /// Value of the collapse field, or [`SortValue::Missing`] for the documents without
+    /// a value.
+    pub key: SortValue,
+    /// Score of the best document of the group.
+    pub score: Score,
+    /// Address of the best document of the group.
+    pub doc_address: DocAddress,
+    /// Number of matching documents in the group.
+    pub num_docs: u64,
+    /// Best documents of the group, as requested with
+    /// [`CollapsingCollector::with_inner_hits`].
+    pub inner_hits: Vec<(Score, DocAddress)>,
+}
+
+/// Fruit of the [`CollapsingCollector`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CollapsedTopDocs {
+    /// Top groups, sorted by decreasing score of their best document.
+    pub groups: Vec<CollapsedGroup>,
+    /// Number of groups with at least one matching document.
+    pub num_groups: usize,
+}
+
+/// Collector returned by [`TopDocs::collapse_by`](super::TopDocs::collapse_by).
+///
+/// Only the best document of each distinct value of a fast field is kept, and the
+/// groups are ranked by the score of their best document. The limit and the offset
+/// apply to the groups.
+///
+/// All the groups of a segment are kept in memory until the fruits are merged, so
+/// that the group count and the inner hits are exact.
+pub struct CollapsingCollector {
+    collector: TopCollector<Score>,
+    field: Field,
+    num_inner_hits: usize,
+}
+
+impl CollapsingCollector {
+    pub(crate) fn new(collector: TopCollector<Score>, field: Field) -> CollapsingCollector {
+        CollapsingCollector {
+            collector,
+            field,
+            num_inner_hits: 0,
+        }
+    }
+
+    /// Returns the `num_inner_hits` best documents of each group along with the group.
+    #[must_use]
+    pub fn with_inner_hits(mut self, num_inner_hits: usize) -> CollapsingCollector {
+        self.num_inner_hits = num_inner_hits;
+        self
+    }
+}
+
+fn cmp_hits(left: &(Score, DocAddress), right: &(Score, DocAddress)) -> Ordering {
+    right
+        .0
+        .partial_cmp(&left.0)
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| left.1.cmp(&right.1))
+}
+
+impl Collector for CollapsingCollector {
+    type Fruit = CollapsedTopDocs;
+
+    type Child = CollapsingSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<Self::Child> {
+        Ok(CollapsingSegmentCollector {
+            field_values: FastFieldValues::open(self.field, segment_reader)?,
+            segment_ord: segment_local_id,
+            num_hits_per_group: self.num_inner_hits.max(1),
+            groups: HashMap::new(),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        true
+    }
+
+    fn merge_fruits(&self, segment_fruits: Vec<Vec<CollapsedGroup>>) -> crate::Result<Self::Fruit> {
+        // A group may have documents in several segments.
+        let key_order = SortKey::field(self.field, Order::Asc);
+        let mut segment_groups: Vec<CollapsedGroup> =
+            segment_fruits.into_iter().flatten().collect();
+        segment_groups.sort_by(|left, right| key_order.compare(&left.key, &right.key));
+        let mut groups: Vec<CollapsedGroup> = Vec::new();
+        for segment_group in segment_groups {
+            match groups.last_mut() {
+                Some(group) if key_order.compare(&group.key, &segment_group.key).is_eq() => {
+                    group.num_docs += segment_group.num_docs;
+                    group.inner_hits.extend(segment_group.inner_hits);
+                }
+                _ => groups.push(segment_group),
+            }
+        }
+        for group in &mut groups {
+            group.inner_hits.sort_by(cmp_hits);
+            let (score, doc_address) = group.inner_hits[0];
+            group.score = score;
+            group.doc_address = doc_address;
+            group.inner_hits.truncate(self.num_inner_hits);
+        }
+        let num_groups = groups.len();
+        groups.sort_by(|left, right| {
+            cmp_hits(
+                &(left.score, left.doc_address),
+                &(right.score, right.doc_address),
+            )
+        });
+        let groups = groups
+            .into_iter()
+            .skip(self.collector.offset)
+            .take(self.collector.limit)
+            .collect();
+        Ok(CollapsedTopDocs { groups, num_groups })
+    }
+}
+
+struct SegmentGroup {
+    top_docs: TopSegmentCollector<Score>,
+    num_docs: u64,
+}
+
+/// Segment Collector associated with [`CollapsingCollector`].
+pub struct CollapsingSegmentCollector {
+    field_values: FastFieldValues,
+    segment_ord: SegmentOrdinal,
+    num_hits_per_group: usize,
+    groups: HashMap<Option<u128>, SegmentGroup>,
+}
+
+impl SegmentCollector for CollapsingSegmentCollector {
+    type Fruit = Vec<CollapsedGroup>;
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        // Documents with several values are grouped by their smallest value.
+        let key = self.field_values.val(doc, &Order::Asc);
+        let segment_ord = self.segment_ord;
+        let num_hits_per_group = self.num_hits_per_group;
+        let group = self.groups.entry(key).or_insert_with(|| SegmentGroup {
+            top_docs: TopSegmentCollector::new(segment_ord, num_hits_per_group),
+            num_docs: 0,
+        });
+        group.top_docs.collect(doc, score);
+        group.num_docs += 1;
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        let field_values = &self.field_values;
+        self.groups
+            .into_iter()
+            .map(|(key, group)| {
+                let inner_hits = group.top_docs.harvest();
+                let (score, doc_address) = inner_hits[0];
+                CollapsedGroup {
+                    key: key
+                        .map(|val| field_values.sort_value(val))
+                        .unwrap_or(SortValue::Missing),
+                    score,
+                    doc_address,
+                    num_docs: group.num_docs,
+                    inner_hits,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collector::{SortValue, TopDocs};
+    use crate::query::TermQuery;
+    use crate::schema::{IndexRecordOption, Schema, FAST, STRING, TEXT};
+    use crate::{DocAddress, Index, IndexWriter, Term};
+
+    #[test]
+    fn test_collapse_across_segments() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let family = schema_builder.add_text_field("family", STRING | FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(title => "shoe", family => "sneaker"))?;
+        index_writer.add_document(doc!(title => "shoe shoe shoe", family => "boot"))?;
+        index_writer.add_document(doc!(title => "shoe"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(title => "shoe shoe", family => "sneaker"))?;
+        index_writer.add_document(doc!(title => "shoe", family => "boot"))?;
+        index_writer.add_document(doc!(title => "hat", family => "cap"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+
+        let query = TermQuery::new(
+            Term::from_field_text(title, "shoe"),
+            IndexRecordOption::WithFreqs,
+        );
+        let collector = TopDocs::with_limit(2)
+            .collapse_by(family)
+            .with_inner_hits(2);
+        let collapsed = searcher.search(&query, &collector)?;
+        assert_eq!(collapsed.num_groups, 3);
+        assert_eq!(collapsed.groups.len(), 2);
+
+        let boot = &collapsed.groups[0];
+        assert_eq!(boot.key, SortValue::Str("boot".to_string()));
+        // The order of the segments in the searcher is not deterministic.
+        let first_segment_ord = boot.doc_address.segment_ord;
+        let second_segment_ord = 1 - first_segment_ord;
+        assert_eq!(boot.doc_address, DocAddress::new(first_segment_ord, 1));
+        assert_eq!(boot.num_docs, 2);
+        let inner_docs: Vec<DocAddress> = boot.inner_hits.iter().map(|hit| hit.1).collect();
+        assert_eq!(
+            inner_docs,
+            vec![
+                DocAddress::new(first_segment_ord, 1),
+                DocAddress::new(second_segment_ord, 1)
+            ]
+        );
+
+        let sneaker = &collapsed.groups[1];
+        assert_eq!(sneaker.key, SortValue::Str("sneaker".to_string()));
+        assert_eq!(sneaker.doc_address, DocAddress::new(second_segment_ord, 0));
+        assert_eq!(sneaker.num_docs, 2);
+
+        let collector = TopDocs::with_limit(2).and_offset(2).collapse_by(family);
+        let collapsed = searcher.search(&query, &collector)?;
+        assert_eq!(collapsed.groups.len(), 1);
+        assert_eq!(collapsed.groups[0].key, SortValue::Missing);
+        assert!(collapsed.groups[0].inner_hits.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_collapse_by_numeric_field() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let product_id = schema_builder.add_u64_field("product_id", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for i in 0..10u64 {
+            index_writer.add_document(doc!(title => "shirt", product_id => i % 3))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let query = TermQuery::new(
+            Term::from_field_text(title, "shirt"),
+            IndexRecordOption::Basic,
+        );
+        let collapsed =
+            searcher.search(&query, &TopDocs::with_limit(10).collapse_by(product_id))?;
+        assert_eq!(collapsed.num_groups, 3);
+        let keys: Vec<SortValue> = collapsed
+            .groups
+            .iter()
+            .map(|group| group.key.clone())
+            .collect();
+        assert_eq!(
+            keys,
+            vec![SortValue::U64(0), SortValue::U64(1), SortValue::U64(2)]
+        );
+        let num_docs: Vec<u64> = collapsed
+            .groups
+            .iter()
+            .map(|group| group.num_docs)
+            .collect();
+        assert_eq!(num_docs, vec![4, 3, 3]);
+        Ok(())
+    }
+}
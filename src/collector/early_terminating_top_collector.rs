@@ -0,0 +1,193 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use fastfield_codecs::Column;
+
+use crate::collector::top_collector::{TopCollector, TopSegmentCollector};
+use crate::collector::{Collector, SegmentCollector, TotalHits};
+use crate::docset::{DocSet, TERMINATED};
+use crate::fastfield::FastValue;
+use crate::query::Weight;
+use crate::schema::Field;
+use crate::{DocAddress, DocId, Order, Score, SegmentOrdinal, SegmentReader, TantivyError};
+
+// Maps a fast field value to a key such that greater keys come first.
+// The mapping is its own inverse.
+fn sort_key(order: &Order, val: u64) -> u64 {
+    if order.is_asc() {
+        !val
+    } else {
+        val
+    }
+}
+
+pub(crate) struct EarlyTerminatingTopCollector<TFastValue> {
+    collector: TopCollector<u64>,
+    field: Field,
+    order: Order,
+    fast_value: PhantomData<TFastValue>,
+}
+
+impl<TFastValue: FastValue> EarlyTerminatingTopCollector<TFastValue> {
+    pub(crate) fn new(
+        collector: TopCollector<u64>,
+        field: Field,
+        order: Order,
+    ) -> EarlyTerminatingTopCollector<TFastValue> {
+        EarlyTerminatingTopCollector {
+            collector,
+            field,
+            order,
+            fast_value: PhantomData,
+        }
+    }
+
+    /// Returns true if the documents of the segment are sorted in the requested order.
+    fn is_sorted_by_field(&self, segment_reader: &SegmentReader) -> bool {
+        segment_reader
+            .sort_by_field()
+            .map(|sort_by_field| {
+                sort_by_field.field == segment_reader.schema().get_field_name(self.field)
+                    && sort_by_field.order == self.order
+            })
+            .unwrap_or(false)
+    }
+}
+
+impl<TFastValue: FastValue> Collector for EarlyTerminatingTopCollector<TFastValue> {
+    type Fruit = (Vec<(TFastValue, DocAddress)>, TotalHits);
+
+    type Child = EarlyTerminatingTopSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<Self::Child> {
+        let schema = segment_reader.schema();
+        let field_entry = schema.get_field_entry(self.field);
+        if !field_entry.is_fast() {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not a fast field.",
+                field_entry.name()
+            )));
+        }
+        let schema_type = TFastValue::to_type();
+        let requested_type = field_entry.field_type().value_type();
+        if schema_type != requested_type {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is of type {:?}!={:?}",
+                field_entry.name(),
+                schema_type,
+                requested_type
+            )));
+        }
+        let ff_reader = segment_reader
+            .fast_fields()
+            .typed_fast_field_reader(field_entry.name())?;
+        Ok(EarlyTerminatingTopSegmentCollector {
+            segment_collector: self.collector.for_segment(segment_local_id, segment_reader),
+            ff_reader,
+            order: self.order.clone(),
+            num_hits: 0,
+            terminated_early: false,
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<(Vec<(u64, DocAddress)>, TotalHits)>,
+    ) -> crate::Result<Self::Fruit> {
+        let mut total_hits = TotalHits::default();
+        let mut segment_top_docs = Vec::with_capacity(segment_fruits.len());
+        for (top_docs, segment_total_hits) in segment_fruits {
+            total_hits = total_hits + segment_total_hits;
+            segment_top_docs.push(top_docs);
+        }
+        let top_docs = self
+            .collector
+            .merge_fruits(segment_top_docs)?
+            .into_iter()
+            .map(|(key, doc_address)| {
+                (
+                    TFastValue::from_u64(sort_key(&self.order, key)),
+                    doc_address,
+                )
+            })
+            .collect();
+        Ok((top_docs, total_hits))
+    }
+
+    fn collect_segment(
+        &self,
+        weight: &dyn Weight,
+        segment_ord: u32,
+        reader: &SegmentReader,
+    ) -> crate::Result<<Self::Child as SegmentCollector>::Fruit> {
+        let mut segment_collector = self.for_segment(segment_ord, reader)?;
+        let alive_bitset = reader.alive_bitset();
+        let is_alive = |doc: DocId| {
+            alive_bitset
+                .map(|alive_bitset| alive_bitset.is_alive(doc))
+                .unwrap_or(true)
+        };
+        if !self.is_sorted_by_field(reader) {
+            weight.for_each_no_score(reader, &mut |doc| {
+                if is_alive(doc) {
+                    segment_collector.collect(doc, 0.0);
+                }
+            })?;
+            return Ok(segment_collector.harvest());
+        }
+        // The documents of the segment are sorted in the requested order: the first
+        // `limit + offset` alive documents matching the query are the top documents of
+        // the segment.
+        let num_docs_to_collect = (self.collector.limit + self.collector.offset) as u64;
+        let mut scorer = weight.scorer(reader, 1.0)?;
+        let mut doc = scorer.doc();
+        while doc != TERMINATED {
+            if is_alive(doc) {
+                if segment_collector.num_hits == num_docs_to_collect {
+                    segment_collector.terminated_early = true;
+                    break;
+                }
+                segment_collector.collect(doc, 0.0);
+            }
+            doc = scorer.advance();
+        }
+        Ok(segment_collector.harvest())
+    }
+}
+
+/// Segment Collector associated with
+/// [`TopDocs::order_by_fast_field_early_terminating`](super::TopDocs::order_by_fast_field_early_terminating).
+pub struct EarlyTerminatingTopSegmentCollector {
+    segment_collector: TopSegmentCollector<u64>,
+    ff_reader: Arc<dyn Column<u64>>,
+    order: Order,
+    num_hits: u64,
+    terminated_early: bool,
+}
+
+impl SegmentCollector for EarlyTerminatingTopSegmentCollector {
+    type Fruit = (Vec<(u64, DocAddress)>, TotalHits);
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        let key = sort_key(&self.order, self.ff_reader.get_val(doc));
+        self.segment_collector.collect(doc, key);
+        self.num_hits += 1;
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        let total_hits = if self.terminated_early {
+            TotalHits::lower_bound(self.num_hits)
+        } else {
+            TotalHits::exact(self.num_hits)
+        };
+        (self.segment_collector.harvest(), total_hits)
+    }
+}
@@ -37,7 +37,7 @@ impl<'a> Ord for Hit<'a> {
     }
 }
 
-fn facet_depth(facet_bytes: &[u8]) -> usize {
+pub(crate) fn facet_depth(facet_bytes: &[u8]) -> usize {
     if facet_bytes.is_empty() {
         0
     } else {
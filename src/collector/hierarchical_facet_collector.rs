@@ -0,0 +1,579 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Bound;
+
+use serde::Serialize;
+
+use crate::collector::facet_collector::facet_depth;
+use crate::collector::{Collector, SegmentCollector};
+use crate::fastfield::FacetReader;
+use crate::schema::{Facet, Field};
+use crate::{DocId, Score, SegmentOrdinal, SegmentReader, TERMINATED};
+
+/// A facet dimension counted by the [`HierarchicalFacetCollector`].
+///
+/// A dimension is the hierarchy of facets below a root facet of a facet field,
+/// e.g. `/category` or `/brand`. Several dimensions may share the same field.
+#[derive(Clone, Debug)]
+pub struct FacetDimension {
+    field: Field,
+    root: Facet,
+    max_depth: usize,
+    selected_facets: Vec<Facet>,
+}
+
+impl FacetDimension {
+    /// Creates a dimension counting all of the facets below `root`.
+    pub fn new<T>(field: Field, root: T) -> FacetDimension
+    where Facet: From<T> {
+        FacetDimension {
+            field,
+            root: Facet::from(root),
+            max_depth: usize::MAX,
+            selected_facets: Vec::new(),
+        }
+    }
+
+    /// Only counts the facets at most `max_depth` levels below the root.
+    ///
+    /// With a `max_depth` of 1, only the direct children of the root are counted.
+    #[must_use]
+    pub fn with_max_depth(mut self, max_depth: usize) -> FacetDimension {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Drills down into `facet`: only the documents having `facet` or one of its
+    /// descendants match the dimension.
+    ///
+    /// Selecting several facets in the same dimension matches the documents having
+    /// any of them.
+    #[must_use]
+    pub fn select<T>(mut self, facet: T) -> FacetDimension
+    where Facet: From<T> {
+        self.selected_facets.push(Facet::from(facet));
+        self
+    }
+}
+
+/// Count of a facet and of its descendants, as returned by the
+/// [`HierarchicalFacetCollector`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FacetNode {
+    /// The facet.
+    pub facet: Facet,
+    /// Number of documents having this facet or one of its descendants.
+    pub count: u64,
+    /// Children of the facet with a non-zero count, sorted by decreasing count.
+    pub children: Vec<FacetNode>,
+}
+
+/// Collector counting the facets of several dimensions in one pass, with
+/// drill-sideways.
+///
+/// The collector is given the query without any facet filter. The facets selected in
+/// the [`FacetDimension`]s act as drill-down filters:
+/// - the documents matching the selection of every dimension are passed to the wrapped
+///   collector, and counted in all of the dimensions;
+/// - the documents matching the selection of every dimension but one are counted in that
+///   dimension only.
+///
+/// The counts of a dimension are therefore the ones that would be obtained if its own
+/// selection was removed, which makes it possible to show the alternatives to the
+/// current selection.
+///
+/// The fruit is the fruit of the wrapped collector along with one tree of
+/// [`FacetNode`]s per dimension, in the order in which the dimensions were given.
+///
+/// ```rust
+/// use tantivy::collector::{Count, FacetDimension, HierarchicalFacetCollector};
+/// use tantivy::query::AllQuery;
+/// use tantivy::schema::{Facet, FacetOptions, Schema};
+/// use tantivy::{doc, Index};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let category = schema_builder.add_facet_field("category", FacetOptions::default());
+/// let brand = schema_builder.add_facet_field("brand", FacetOptions::default());
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// index_writer.add_document(doc!(
+///     category => Facet::from("/shoes/sneakers"),
+///     brand => Facet::from("/acme"),
+/// ))?;
+/// index_writer.add_document(doc!(
+///     category => Facet::from("/shoes/boots"),
+///     brand => Facet::from("/acme"),
+/// ))?;
+/// index_writer.add_document(doc!(
+///     category => Facet::from("/shoes/sneakers"),
+///     brand => Facet::from("/globex"),
+/// ))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let collector = HierarchicalFacetCollector::new(
+///     vec![
+///         FacetDimension::new(category, "/").select("/shoes/sneakers"),
+///         FacetDimension::new(brand, "/"),
+///     ],
+///     Count,
+/// );
+/// let (count, facet_trees) = searcher.search(&AllQuery, &collector)?;
+/// assert_eq!(count, 2);
+/// // The category counts ignore the category selection.
+/// let shoes = &facet_trees[0].children[0];
+/// assert_eq!(shoes.facet, Facet::from("/shoes"));
+/// assert_eq!(shoes.count, 3);
+/// assert_eq!(shoes.children[0].facet, Facet::from("/shoes/sneakers"));
+/// assert_eq!(shoes.children[0].count, 2);
+/// assert_eq!(shoes.children[1].facet, Facet::from("/shoes/boots"));
+/// assert_eq!(shoes.children[1].count, 1);
+/// // The brand counts only include sneakers.
+/// assert_eq!(facet_trees[1].count, 2);
+/// assert_eq!(facet_trees[1].children.len(), 2);
+/// # Ok(())
+/// # }
+/// ```
+pub struct HierarchicalFacetCollector<TCollector> {
+    dimensions: Vec<FacetDimension>,
+    collector: TCollector,
+}
+
+impl<TCollector: Collector> HierarchicalFacetCollector<TCollector> {
+    /// Creates a collector counting the facets of `dimensions`, and passing the
+    /// documents matching all of their selections to `collector`.
+    pub fn new(
+        dimensions: Vec<FacetDimension>,
+        collector: TCollector,
+    ) -> HierarchicalFacetCollector<TCollector> {
+        HierarchicalFacetCollector {
+            dimensions,
+            collector,
+        }
+    }
+}
+
+fn cmp_nodes(left: &FacetNode, right: &FacetNode) -> Ordering {
+    right
+        .count
+        .cmp(&left.count)
+        .then_with(|| left.facet.cmp(&right.facet))
+}
+
+fn facet_node(facet: &Facet, count: u64, facet_counts: &BTreeMap<Facet, u64>) -> FacetNode {
+    let depth = facet_depth(facet.encoded_str().as_bytes());
+    let mut children: Vec<FacetNode> = facet_counts
+        .range::<Facet, _>((Bound::Excluded(facet), Bound::Unbounded))
+        .take_while(|(descendant, _)| facet.is_prefix_of(descendant))
+        .filter(|(descendant, _)| facet_depth(descendant.encoded_str().as_bytes()) == depth + 1)
+        .map(|(child, &count)| facet_node(child, count, facet_counts))
+        .collect();
+    children.sort_by(cmp_nodes);
+    FacetNode {
+        facet: facet.clone(),
+        count,
+        children,
+    }
+}
+
+impl<TCollector: Collector> Collector for HierarchicalFacetCollector<TCollector> {
+    type Fruit = (TCollector::Fruit, Vec<FacetNode>);
+
+    type Child = HierarchicalFacetSegmentCollector<TCollector::Child>;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<Self::Child> {
+        let dimensions = self
+            .dimensions
+            .iter()
+            .map(|dimension| SegmentFacetDimension::open(dimension, segment_reader))
+            .collect::<crate::Result<_>>()?;
+        Ok(HierarchicalFacetSegmentCollector {
+            segment_collector: self
+                .collector
+                .for_segment(segment_local_id, segment_reader)?,
+            dimensions,
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        self.collector.requires_scoring()
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<(
+            <TCollector::Child as SegmentCollector>::Fruit,
+            Vec<BTreeMap<Facet, u64>>,
+        )>,
+    ) -> crate::Result<Self::Fruit> {
+        let mut facet_counts: Vec<BTreeMap<Facet, u64>> =
+            vec![BTreeMap::new(); self.dimensions.len()];
+        let mut segment_hits = Vec::with_capacity(segment_fruits.len());
+        for (hits, segment_facet_counts) in segment_fruits {
+            segment_hits.push(hits);
+            for (dimension_counts, segment_dimension_counts) in
+                facet_counts.iter_mut().zip(segment_facet_counts)
+            {
+                for (facet, count) in segment_dimension_counts {
+                    *dimension_counts.entry(facet).or_insert(0) += count;
+                }
+            }
+        }
+        let facet_trees = self
+            .dimensions
+            .iter()
+            .zip(&facet_counts)
+            .map(|(dimension, dimension_counts)| {
+                let count = dimension_counts.get(&dimension.root).copied().unwrap_or(0);
+                facet_node(&dimension.root, count, dimension_counts)
+            })
+            .collect();
+        let hits = self.collector.merge_fruits(segment_hits)?;
+        Ok((hits, facet_trees))
+    }
+}
+
+// Marks the facets whose parent is the root of the dimension.
+const NO_PARENT: u32 = u32::MAX;
+
+/// Counters of a dimension for one segment.
+///
+/// The strict descendants of the root have contiguous term ordinals. They are
+/// addressed by their offset from the first one.
+struct SegmentFacetDimension {
+    reader: FacetReader,
+    facet_ords: Vec<u64>,
+    root: Facet,
+    start_ord: u64,
+    // offset -> offset of the parent, or `NO_PARENT`.
+    parents: Vec<u32>,
+    // offset -> true if the facet is within `max_depth` of the root.
+    is_counted: Vec<bool>,
+    // offset -> true if the facet is selected, or has a selected ancestor.
+    is_selected: Vec<bool>,
+    has_selection: bool,
+    counts: Vec<u64>,
+    // offset -> last document counted for the facet.
+    last_docs: Vec<DocId>,
+    num_docs: u64,
+}
+
+impl SegmentFacetDimension {
+    fn open(
+        dimension: &FacetDimension,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<SegmentFacetDimension> {
+        let reader = segment_reader.facet_reader(dimension.field)?;
+        let root_depth = facet_depth(dimension.root.encoded_str().as_bytes());
+        let selected_facets: HashSet<&str> = dimension
+            .selected_facets
+            .iter()
+            .map(Facet::encoded_str)
+            .collect();
+        let is_root_selected = selected_facets.contains(dimension.root.encoded_str());
+
+        let mut start_ord = None;
+        let mut parents = Vec::new();
+        let mut is_counted = Vec::new();
+        let mut is_selected: Vec<bool> = Vec::new();
+        // (depth, offset) of the ancestors of the current facet.
+        let mut ancestors: Vec<(usize, u32)> = Vec::new();
+        {
+            let stream_builder = reader.facet_dict().range();
+            let mut stream = if dimension.root.is_root() {
+                stream_builder.gt(b"").into_stream()?
+            } else {
+                let root_str = dimension.root.encoded_str();
+                stream_builder
+                    .gt(format!("{}\u{0}", root_str))
+                    .lt(format!("{}\u{1}", root_str))
+                    .into_stream()?
+            };
+            while stream.advance() {
+                let facet_bytes = stream.key();
+                let offset = parents.len() as u32;
+                start_ord.get_or_insert(stream.term_ord());
+                let depth = facet_depth(facet_bytes) - root_depth;
+                while ancestors
+                    .last()
+                    .map(|&(ancestor_depth, _)| ancestor_depth >= depth)
+                    == Some(true)
+                {
+                    ancestors.pop();
+                }
+                let parent = ancestors.last().map(|&(_, parent)| parent);
+                let is_parent_selected = parent
+                    .map(|parent| is_selected[parent as usize])
+                    .unwrap_or(is_root_selected);
+                let is_facet_selected = std::str::from_utf8(facet_bytes)
+                    .map(|facet_str| selected_facets.contains(facet_str))
+                    .unwrap_or(false);
+                parents.push(parent.unwrap_or(NO_PARENT));
+                is_counted.push(depth <= dimension.max_depth);
+                is_selected.push(is_parent_selected || is_facet_selected);
+                ancestors.push((depth, offset));
+            }
+        }
+        let num_facets = parents.len();
+        Ok(SegmentFacetDimension {
+            reader,
+            facet_ords: Vec::new(),
+            root: dimension.root.clone(),
+            start_ord: start_ord.unwrap_or(0),
+            parents,
+            is_counted,
+            is_selected,
+            has_selection: !dimension.selected_facets.is_empty(),
+            counts: vec![0; num_facets],
+            last_docs: vec![TERMINATED; num_facets],
+            num_docs: 0,
+        })
+    }
+
+    /// Returns the offset of the facet if it is a strict descendant of the root.
+    fn offset(&self, facet_ord: u64) -> Option<u32> {
+        if facet_ord >= self.start_ord && facet_ord < self.start_ord + self.parents.len() as u64 {
+            Some((facet_ord - self.start_ord) as u32)
+        } else {
+            None
+        }
+    }
+
+    /// Loads the facets of `doc`, and returns true if it matches the selection.
+    fn load(&mut self, doc: DocId) -> bool {
+        self.reader.facet_ords(doc, &mut self.facet_ords);
+        !self.has_selection
+            || self.facet_ords.iter().any(|&facet_ord| {
+                self.offset(facet_ord)
+                    .map(|offset| self.is_selected[offset as usize])
+                    .unwrap_or(false)
+            })
+    }
+
+    /// Counts the facets of the last loaded document.
+    fn count(&mut self, doc: DocId) {
+        let mut has_facet = false;
+        for facet_id in 0..self.facet_ords.len() {
+            let mut offset = match self.offset(self.facet_ords[facet_id]) {
+                Some(offset) => offset,
+                None => continue,
+            };
+            has_facet = true;
+            // Ancestors of a facet already counted for this document are counted too.
+            while offset != NO_PARENT && self.last_docs[offset as usize] != doc {
+                self.last_docs[offset as usize] = doc;
+                if self.is_counted[offset as usize] {
+                    self.counts[offset as usize] += 1;
+                }
+                offset = self.parents[offset as usize];
+            }
+        }
+        self.num_docs += u64::from(has_facet);
+    }
+
+    fn harvest(self) -> BTreeMap<Facet, u64> {
+        let mut facet_counts = BTreeMap::new();
+        if self.num_docs > 0 {
+            facet_counts.insert(self.root, self.num_docs);
+        }
+        let facet_dict = self.reader.facet_dict();
+        let mut facet_bytes = Vec::new();
+        for (offset, &count) in self.counts.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let facet_ord = self.start_ord + offset as u64;
+            if facet_dict.ord_to_term(facet_ord, &mut facet_bytes).is_ok() {
+                if let Ok(facet) = Facet::from_encoded(facet_bytes.clone()) {
+                    facet_counts.insert(facet, count);
+                }
+            }
+        }
+        facet_counts
+    }
+}
+
+/// Segment Collector associated with [`HierarchicalFacetCollector`].
+pub struct HierarchicalFacetSegmentCollector<TSegmentCollector> {
+    segment_collector: TSegmentCollector,
+    dimensions: Vec<SegmentFacetDimension>,
+}
+
+impl<TSegmentCollector: SegmentCollector> SegmentCollector
+    for HierarchicalFacetSegmentCollector<TSegmentCollector>
+{
+    type Fruit = (TSegmentCollector::Fruit, Vec<BTreeMap<Facet, u64>>);
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        let mut num_unmatched = 0;
+        let mut unmatched_dimension = 0;
+        for (dimension_ord, dimension) in self.dimensions.iter_mut().enumerate() {
+            if !dimension.load(doc) {
+                num_unmatched += 1;
+                unmatched_dimension = dimension_ord;
+            }
+        }
+        match num_unmatched {
+            0 => {
+                self.segment_collector.collect(doc, score);
+                for dimension in &mut self.dimensions {
+                    dimension.count(doc);
+                }
+            }
+            // Drill-sideways: the document would match without the selection of this
+            // dimension.
+            1 => self.dimensions[unmatched_dimension].count(doc),
+            _ => {}
+        }
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        (
+            self.segment_collector.harvest(),
+            self.dimensions
+                .into_iter()
+                .map(SegmentFacetDimension::harvest)
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FacetDimension, FacetNode, HierarchicalFacetCollector};
+    use crate::collector::{Count, DocSetCollector};
+    use crate::query::AllQuery;
+    use crate::schema::{Facet, FacetOptions, Schema, STORED, STRING};
+    use crate::{Index, IndexWriter};
+
+    fn counts(node: &FacetNode) -> Vec<(String, u64)> {
+        let mut counts = vec![(node.facet.to_string(), node.count)];
+        for child in &node.children {
+            counts.extend(self::counts(child));
+        }
+        counts
+    }
+
+    #[test]
+    fn test_hierarchical_facets_with_max_depth() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let facet_field = schema_builder.add_facet_field("facet", FacetOptions::default());
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(
+            facet_field => Facet::from("/category/fiction/fantasy"),
+            facet_field => Facet::from("/category/fiction/thriller"),
+            facet_field => Facet::from("/lang/en"),
+        ))?;
+        index_writer.add_document(doc!(facet_field => Facet::from("/category/biography")))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(
+            facet_field => Facet::from("/category/fiction/fantasy"),
+            facet_field => Facet::from("/lang/fr"),
+        ))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let collector = HierarchicalFacetCollector::new(
+            vec![
+                FacetDimension::new(facet_field, "/category"),
+                FacetDimension::new(facet_field, "/lang").with_max_depth(0),
+                FacetDimension::new(facet_field, "/category").with_max_depth(1),
+            ],
+            Count,
+        );
+        let (count, facet_trees) = searcher.search(&AllQuery, &collector)?;
+        assert_eq!(count, 3);
+        assert_eq!(
+            counts(&facet_trees[0]),
+            vec![
+                ("/category".to_string(), 3),
+                ("/category/fiction".to_string(), 2),
+                ("/category/fiction/fantasy".to_string(), 2),
+                ("/category/fiction/thriller".to_string(), 1),
+                ("/category/biography".to_string(), 1),
+            ]
+        );
+        assert_eq!(counts(&facet_trees[1]), vec![("/lang".to_string(), 2)]);
+        assert_eq!(
+            counts(&facet_trees[2]),
+            vec![
+                ("/category".to_string(), 3),
+                ("/category/fiction".to_string(), 2),
+                ("/category/biography".to_string(), 1),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_hierarchical_facets_drill_sideways() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let color = schema_builder.add_facet_field("color", FacetOptions::default());
+        let size = schema_builder.add_facet_field("size", FacetOptions::default());
+        let name = schema_builder.add_text_field("name", STRING | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for (doc_name, doc_color, doc_size) in [
+            ("a", "/red", "/s"),
+            ("b", "/red", "/m"),
+            ("c", "/blue", "/s"),
+            ("d", "/blue", "/m"),
+            ("e", "/green", "/m"),
+        ] {
+            index_writer.add_document(doc!(
+                name => doc_name,
+                color => Facet::from(doc_color),
+                size => Facet::from(doc_size),
+            ))?;
+        }
+        index_writer.add_document(doc!(name => "f", size => Facet::from("/s")))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let collector = HierarchicalFacetCollector::new(
+            vec![
+                FacetDimension::new(color, "/")
+                    .select("/red")
+                    .select("/blue"),
+                FacetDimension::new(size, "/").select("/m"),
+            ],
+            DocSetCollector,
+        );
+        let (docs, facet_trees) = searcher.search(&AllQuery, &collector)?;
+        let mut doc_names: Vec<String> = docs
+            .into_iter()
+            .map(|doc_address| {
+                let doc = searcher.doc(doc_address)?;
+                Ok(doc.get_first(name).unwrap().as_text().unwrap().to_string())
+            })
+            .collect::<crate::Result<_>>()?;
+        doc_names.sort();
+        assert_eq!(doc_names, vec!["b", "d"]);
+        // Colors of the medium documents.
+        assert_eq!(
+            counts(&facet_trees[0]),
+            vec![
+                ("/".to_string(), 3),
+                ("/blue".to_string(), 1),
+                ("/green".to_string(), 1),
+                ("/red".to_string(), 1),
+            ]
+        );
+        // Sizes of the red and blue documents.
+        assert_eq!(
+            counts(&facet_trees[1]),
+            vec![
+                ("/".to_string(), 4),
+                ("/m".to_string(), 2),
+                ("/s".to_string(), 2),
+            ]
+        );
+        Ok(())
+    }
+}
@@ -139,7 +139,7 @@ pub fn add_arrays_into(acc: &mut [u64], add: &[u64]) {
     }
 }
 
-fn add_vecs(mut vals_list: Vec<Vec<u64>>, len: usize) -> Vec<u64> {
+pub(crate) fn add_vecs(mut vals_list: Vec<Vec<u64>>, len: usize) -> Vec<u64> {
     let mut acc = vals_list.pop().unwrap_or_else(|| vec![0u64; len]);
     assert_eq!(acc.len(), len);
     for vals in vals_list {
@@ -91,6 +91,11 @@ pub use self::count_collector::Count;
 mod histogram_collector;
 pub use histogram_collector::HistogramCollector;
 
+mod range_facet_collector;
+pub use self::range_facet_collector::{
+    RangeFacetCollector, RangeFacetCount, RangeFacetSegmentCollector,
+};
+
 mod multi_collector;
 pub use self::multi_collector::{FruitHandle, MultiCollector, MultiFruit};
 
@@ -99,14 +104,41 @@ mod top_collector;
 mod top_score_collector;
 pub use self::top_score_collector::TopDocs;
 
+mod early_terminating_top_collector;
+pub use self::early_terminating_top_collector::EarlyTerminatingTopSegmentCollector;
+
+mod sort_key_top_collector;
+pub use self::sort_key_top_collector::{
+    SortKey, SortKeyTopCollector, SortKeyTopSegmentCollector, SortValue,
+};
+
+mod collapsing_collector;
+pub use self::collapsing_collector::{
+    CollapsedGroup, CollapsedTopDocs, CollapsingCollector, CollapsingSegmentCollector,
+};
+
+mod track_total_hits_collector;
+pub use self::track_total_hits_collector::TrackTotalHitsTopSegmentCollector;
+
+mod total_hits;
+pub use self::total_hits::{TotalHits, TotalHitsRelation};
+
 mod custom_score_top_collector;
 pub use self::custom_score_top_collector::{CustomScorer, CustomSegmentScorer};
 
 mod tweak_score_top_collector;
 pub use self::tweak_score_top_collector::{ScoreSegmentTweaker, ScoreTweaker};
 
+mod rescorer;
+pub use self::rescorer::{QueryRescorer, Rescorer};
+
 mod facet_collector;
 pub use self::facet_collector::{FacetCollector, FacetCounts};
+
+mod hierarchical_facet_collector;
+pub use self::hierarchical_facet_collector::{
+    FacetDimension, FacetNode, HierarchicalFacetCollector, HierarchicalFacetSegmentCollector,
+};
 use crate::query::Weight;
 
 mod docset_collector;
@@ -0,0 +1,291 @@
+use std::ops::{Bound, RangeBounds};
+use std::sync::Arc;
+
+use fastfield_codecs::Column;
+
+use crate::collector::histogram_collector::add_vecs;
+use crate::collector::{Collector, SegmentCollector};
+use crate::fastfield::FastValue;
+use crate::schema::Field;
+use crate::{DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
+
+/// Count of the documents within a range, as returned by the [`RangeFacetCollector`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RangeFacetCount<TFastValue> {
+    /// Lower bound of the range.
+    pub lower: Bound<TFastValue>,
+    /// Upper bound of the range.
+    pub upper: Bound<TFastValue>,
+    /// Number of documents with a value within the range.
+    pub count: u64,
+}
+
+/// The `RangeFacetCollector` counts the documents whose fast field value falls within
+/// each of a list of user-defined ranges.
+///
+/// Ranges may be open-ended and may overlap. Unlike the [`HistogramCollector`]
+/// buckets, they do not need to have the same width.
+///
+/// The fruit contains one [`RangeFacetCount`] per range, in the order in which the
+/// ranges were added.
+///
+/// Only single-valued fast fields are supported.
+///
+/// [`HistogramCollector`]: crate::collector::HistogramCollector
+///
+/// ```rust
+/// use std::ops::Bound;
+///
+/// use tantivy::collector::{Count, MultiCollector, RangeFacetCollector};
+/// use tantivy::query::AllQuery;
+/// use tantivy::schema::{Schema, FAST};
+/// use tantivy::{doc, Index};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let price = schema_builder.add_f64_field("price", FAST);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// for doc_price in [4.99, 12.0, 19.5, 120.0] {
+///     index_writer.add_document(doc!(price => doc_price))?;
+/// }
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let mut range_facet_collector = RangeFacetCollector::new(price);
+/// range_facet_collector.add_range(..10.0);
+/// range_facet_collector.add_range(10.0..20.0);
+/// range_facet_collector.add_range(100.0..);
+/// let mut multi_collector = MultiCollector::new();
+/// let count_handle = multi_collector.add_collector(Count);
+/// let range_facet_handle = multi_collector.add_collector(range_facet_collector);
+/// let mut multi_fruit = searcher.search(&AllQuery, &multi_collector)?;
+///
+/// assert_eq!(count_handle.extract(&mut multi_fruit), 4);
+/// let range_counts = range_facet_handle.extract(&mut multi_fruit);
+/// assert_eq!(range_counts[0].upper, Bound::Excluded(10.0));
+/// let counts: Vec<u64> = range_counts.iter().map(|range| range.count).collect();
+/// assert_eq!(counts, vec![1, 2, 1]);
+/// # Ok(())
+/// # }
+/// ```
+pub struct RangeFacetCollector<TFastValue: FastValue> {
+    field: Field,
+    ranges: Vec<(Bound<TFastValue>, Bound<TFastValue>)>,
+}
+
+impl<TFastValue: FastValue> RangeFacetCollector<TFastValue> {
+    /// Creates a collector counting the values of the fast field `field`.
+    ///
+    /// Ranges are then added with [`RangeFacetCollector::add_range`].
+    pub fn new(field: Field) -> RangeFacetCollector<TFastValue> {
+        RangeFacetCollector {
+            field,
+            ranges: Vec::new(),
+        }
+    }
+
+    /// Adds a range to count, e.g. `10..20`, `..=10` or `100..`.
+    pub fn add_range<TRange: RangeBounds<TFastValue>>(&mut self, range: TRange) {
+        self.ranges
+            .push((range.start_bound().cloned(), range.end_bound().cloned()));
+    }
+}
+
+fn map_bound<TFastValue: FastValue>(bound: &Bound<TFastValue>) -> Bound<u64> {
+    match bound {
+        Bound::Included(value) => Bound::Included(value.to_u64()),
+        Bound::Excluded(value) => Bound::Excluded(value.to_u64()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+impl<TFastValue: FastValue> Collector for RangeFacetCollector<TFastValue> {
+    type Fruit = Vec<RangeFacetCount<TFastValue>>;
+
+    type Child = RangeFacetSegmentCollector<TFastValue>;
+
+    fn for_segment(
+        &self,
+        _segment_local_id: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<RangeFacetSegmentCollector<TFastValue>> {
+        let schema = segment_reader.schema();
+        let field_entry = schema.get_field_entry(self.field);
+        if !field_entry.is_fast() {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not a fast field.",
+                field_entry.name()
+            )));
+        }
+        let requested_type = TFastValue::to_type();
+        let field_schema_type = field_entry.field_type().value_type();
+        if requested_type != field_schema_type {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is of type {:?}!={:?}",
+                field_entry.name(),
+                requested_type,
+                field_schema_type
+            )));
+        }
+        let fast_field_reader = segment_reader
+            .fast_fields()
+            .typed_fast_field_reader::<TFastValue>(field_entry.name())?;
+        // Fast values are mapped to u64 in an order-preserving way.
+        let ranges = self
+            .ranges
+            .iter()
+            .map(|(lower, upper)| (map_bound(lower), map_bound(upper)))
+            .collect();
+        Ok(RangeFacetSegmentCollector {
+            fast_field_reader,
+            counts: vec![0; self.ranges.len()],
+            ranges,
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(&self, segment_counts: Vec<Vec<u64>>) -> crate::Result<Self::Fruit> {
+        let counts = add_vecs(segment_counts, self.ranges.len());
+        Ok(self
+            .ranges
+            .iter()
+            .zip(counts)
+            .map(|((lower, upper), count)| RangeFacetCount {
+                lower: *lower,
+                upper: *upper,
+                count,
+            })
+            .collect())
+    }
+}
+
+/// Segment Collector associated with [`RangeFacetCollector`].
+pub struct RangeFacetSegmentCollector<TFastValue: FastValue> {
+    fast_field_reader: Arc<dyn Column<TFastValue>>,
+    ranges: Vec<(Bound<u64>, Bound<u64>)>,
+    counts: Vec<u64>,
+}
+
+impl<TFastValue: FastValue> SegmentCollector for RangeFacetSegmentCollector<TFastValue> {
+    type Fruit = Vec<u64>;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        let value = self.fast_field_reader.get_val(doc).to_u64();
+        for (range, count) in self.ranges.iter().zip(self.counts.iter_mut()) {
+            if range.contains(&value) {
+                *count += 1;
+            }
+        }
+    }
+
+    fn harvest(self) -> Vec<u64> {
+        self.counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use super::{RangeFacetCollector, RangeFacetCount};
+    use crate::collector::Count;
+    use crate::query::{AllQuery, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, FAST, INDEXED, STRING};
+    use crate::time::{Date, Month};
+    use crate::{DateTime, Index, IndexWriter, Term};
+
+    #[test]
+    fn test_range_facet_collector_i64() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let temperature = schema_builder.add_i64_field("temperature", FAST);
+        let city = schema_builder.add_text_field("city", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for value in -10i64..10 {
+            let doc_city = if value % 2 == 0 { "paris" } else { "oslo" };
+            index_writer.add_document(doc!(temperature => value, city => doc_city))?;
+        }
+        index_writer.commit()?;
+        index_writer.add_document(doc!(temperature => 100i64, city => "paris"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let mut collector: RangeFacetCollector<i64> = RangeFacetCollector::new(temperature);
+        collector.add_range(..0);
+        collector.add_range(0..=5);
+        collector.add_range(-2..2);
+        collector.add_range(50..);
+        let range_counts = searcher.search(&AllQuery, &collector)?;
+        assert_eq!(
+            range_counts,
+            vec![
+                RangeFacetCount {
+                    lower: Bound::Unbounded,
+                    upper: Bound::Excluded(0),
+                    count: 10,
+                },
+                RangeFacetCount {
+                    lower: Bound::Included(0),
+                    upper: Bound::Included(5),
+                    count: 6,
+                },
+                RangeFacetCount {
+                    lower: Bound::Included(-2),
+                    upper: Bound::Excluded(2),
+                    count: 4,
+                },
+                RangeFacetCount {
+                    lower: Bound::Included(50),
+                    upper: Bound::Unbounded,
+                    count: 1,
+                },
+            ]
+        );
+
+        let query = TermQuery::new(
+            Term::from_field_text(city, "paris"),
+            IndexRecordOption::Basic,
+        );
+        let (count, range_counts) = searcher.search(&query, &(Count, collector))?;
+        assert_eq!(count, 11);
+        let counts: Vec<u64> = range_counts.iter().map(|range| range.count).collect();
+        assert_eq!(counts, vec![5, 3, 2, 1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_facet_collector_date() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let date_field = schema_builder.add_date_field("date", INDEXED | FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        let date = |year: i32, month: Month| {
+            DateTime::from_primitive(
+                Date::from_calendar_date(year, month, 1)
+                    .unwrap()
+                    .with_hms(0, 0, 0)
+                    .unwrap(),
+            )
+        };
+        index_writer.add_document(doc!(date_field => date(2021, Month::March)))?;
+        index_writer.add_document(doc!(date_field => date(2022, Month::January)))?;
+        index_writer.add_document(doc!(date_field => date(2022, Month::June)))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let mut collector = RangeFacetCollector::new(date_field);
+        collector.add_range(..date(2022, Month::January));
+        collector.add_range(date(2022, Month::January)..date(2023, Month::January));
+        let range_counts = searcher.search(&AllQuery, &collector)?;
+        let counts: Vec<u64> = range_counts.iter().map(|range| range.count).collect();
+        assert_eq!(counts, vec![1, 2]);
+
+        let collector: RangeFacetCollector<u64> = RangeFacetCollector::new(date_field);
+        assert!(searcher.search(&AllQuery, &collector).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,269 @@
+use std::cmp::Ordering;
+
+use crate::collector::{ScoreSegmentTweaker, ScoreTweaker};
+use crate::query::{EnableScoring, Query, Scorer};
+use crate::{DocAddress, DocSet, Score, Searcher, SegmentOrdinal};
+
+/// A `Rescorer` computes new scores for the top hits of a first search, typically
+/// with a query too expensive to be run on every matching document.
+///
+/// The hits are usually the result of a [`TopDocs`](crate::collector::TopDocs)
+/// collector, whose limit is the rescoring window.
+///
+/// Besides the [`QueryRescorer`], any [`ScoreTweaker`] is a `Rescorer`. This makes it
+/// possible to rescore hits with a closure having access to the fast fields and the
+/// stored documents of their segment.
+///
+/// ```rust
+/// use tantivy::collector::{QueryRescorer, Rescorer, TopDocs};
+/// use tantivy::query::{PhraseQuery, QueryParser};
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, Index, Term};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// index_writer.add_document(doc!(title => "The wind of the name"))?;
+/// index_writer.add_document(doc!(title => "The Name of the Wind"))?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let query = QueryParser::for_index(&index, vec![title]).parse_query("name wind")?;
+/// let top_docs = searcher.search(&query, &TopDocs::with_limit(100))?;
+///
+/// // Boosts the documents containing the phrase.
+/// let phrase_query = PhraseQuery::new(vec![
+///     Term::from_field_text(title, "name"),
+///     Term::from_field_text(title, "of"),
+///     Term::from_field_text(title, "the"),
+///     Term::from_field_text(title, "wind"),
+/// ]);
+/// let rescorer = QueryRescorer::new(Box::new(phrase_query)).with_rescore_query_weight(2.0);
+/// let rescored_top_docs = rescorer.rescore(&searcher, top_docs)?;
+/// assert_eq!(rescored_top_docs[0].1.doc_id, 1);
+/// # Ok(())
+/// # }
+/// ```
+pub trait Rescorer {
+    /// Returns the new score of each of the given hits, in the same order.
+    fn rescore_hits(
+        &self,
+        searcher: &Searcher,
+        hits: &[(Score, DocAddress)],
+    ) -> crate::Result<Vec<Score>>;
+
+    /// Rescores the given hits, and sorts them by decreasing new score.
+    fn rescore(
+        &self,
+        searcher: &Searcher,
+        hits: Vec<(Score, DocAddress)>,
+    ) -> crate::Result<Vec<(Score, DocAddress)>> {
+        let scores = self.rescore_hits(searcher, &hits)?;
+        let mut rescored_hits: Vec<(Score, DocAddress)> = scores
+            .into_iter()
+            .zip(hits)
+            .map(|(score, (_, doc_address))| (score, doc_address))
+            .collect();
+        rescored_hits.sort_by(|left, right| {
+            right
+                .0
+                .partial_cmp(&left.0)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| left.1.cmp(&right.1))
+        });
+        Ok(rescored_hits)
+    }
+}
+
+/// Returns the indexes of `hits`, grouped by segment and sorted by doc id.
+fn hits_by_segment(hits: &[(Score, DocAddress)]) -> Vec<usize> {
+    let mut hit_ids: Vec<usize> = (0..hits.len()).collect();
+    hit_ids.sort_by_key(|&hit_id| hits[hit_id].1);
+    hit_ids
+}
+
+/// Rescorer combining the first score of a hit with its score for another query.
+///
+/// The new score is `query_weight * score + rescore_query_weight * rescore_query_score`,
+/// where `rescore_query_score` is 0 for the hits that do not match the rescore query.
+/// Both weights are 1 by default.
+pub struct QueryRescorer {
+    query: Box<dyn Query>,
+    query_weight: Score,
+    rescore_query_weight: Score,
+}
+
+impl QueryRescorer {
+    /// Creates a rescorer adding the score of `query` to the first score of the hits.
+    pub fn new(query: Box<dyn Query>) -> QueryRescorer {
+        QueryRescorer {
+            query,
+            query_weight: 1.0,
+            rescore_query_weight: 1.0,
+        }
+    }
+
+    /// Sets the weight of the first score.
+    #[must_use]
+    pub fn with_query_weight(mut self, query_weight: Score) -> QueryRescorer {
+        self.query_weight = query_weight;
+        self
+    }
+
+    /// Sets the weight of the score of the rescore query.
+    #[must_use]
+    pub fn with_rescore_query_weight(mut self, rescore_query_weight: Score) -> QueryRescorer {
+        self.rescore_query_weight = rescore_query_weight;
+        self
+    }
+}
+
+impl Rescorer for QueryRescorer {
+    fn rescore_hits(
+        &self,
+        searcher: &Searcher,
+        hits: &[(Score, DocAddress)],
+    ) -> crate::Result<Vec<Score>> {
+        let weight = self
+            .query
+            .weight(EnableScoring::enabled_from_searcher(searcher))?;
+        let mut scores: Vec<Score> = hits
+            .iter()
+            .map(|(score, _)| self.query_weight * score)
+            .collect();
+        let mut segment_scorer: Option<(SegmentOrdinal, Box<dyn Scorer>)> = None;
+        for hit_id in hits_by_segment(hits) {
+            let doc_address = hits[hit_id].1;
+            let scorer = match segment_scorer {
+                Some((segment_ord, ref mut scorer)) if segment_ord == doc_address.segment_ord => {
+                    scorer
+                }
+                _ => {
+                    let segment_reader = searcher.segment_reader(doc_address.segment_ord);
+                    let scorer = weight.scorer(segment_reader, 1.0)?;
+                    &mut segment_scorer.insert((doc_address.segment_ord, scorer)).1
+                }
+            };
+            // Hits are sorted by doc id within a segment.
+            if scorer.doc() < doc_address.doc_id {
+                scorer.seek(doc_address.doc_id);
+            }
+            if scorer.doc() == doc_address.doc_id {
+                scores[hit_id] += self.rescore_query_weight * scorer.score();
+            }
+        }
+        Ok(scores)
+    }
+}
+
+impl<TScoreTweaker: ScoreTweaker<Score>> Rescorer for TScoreTweaker {
+    fn rescore_hits(
+        &self,
+        searcher: &Searcher,
+        hits: &[(Score, DocAddress)],
+    ) -> crate::Result<Vec<Score>> {
+        let mut scores: Vec<Score> = hits.iter().map(|(score, _)| *score).collect();
+        let mut segment_tweaker: Option<(SegmentOrdinal, TScoreTweaker::Child)> = None;
+        for hit_id in hits_by_segment(hits) {
+            let (score, doc_address) = hits[hit_id];
+            let segment_tweaker = match segment_tweaker {
+                Some((segment_ord, ref mut segment_tweaker))
+                    if segment_ord == doc_address.segment_ord =>
+                {
+                    segment_tweaker
+                }
+                _ => {
+                    let segment_reader = searcher.segment_reader(doc_address.segment_ord);
+                    let child = self.segment_tweaker(segment_reader)?;
+                    &mut segment_tweaker.insert((doc_address.segment_ord, child)).1
+                }
+            };
+            scores[hit_id] = segment_tweaker.score(doc_address.doc_id, score);
+        }
+        Ok(scores)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QueryRescorer, Rescorer};
+    use crate::collector::TopDocs;
+    use crate::query::{PhraseQuery, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, FAST, TEXT};
+    use crate::{DocAddress, DocId, Index, IndexWriter, Score, SegmentReader, Term};
+
+    #[test]
+    fn test_query_rescorer() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "b a c"))?;
+        index_writer.add_document(doc!(text => "a c"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(text => "a b"))?;
+        index_writer.add_document(doc!(text => "c"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let query = TermQuery::new(Term::from_field_text(text, "a"), IndexRecordOption::Basic);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+        assert_eq!(top_docs.len(), 3);
+        let phrase_query = PhraseQuery::new(vec![
+            Term::from_field_text(text, "a"),
+            Term::from_field_text(text, "b"),
+        ]);
+        let phrase_score = searcher.search(&phrase_query, &TopDocs::with_limit(1))?[0].0;
+        let phrase_doc = searcher.search(&phrase_query, &TopDocs::with_limit(1))?[0].1;
+
+        let rescorer = QueryRescorer::new(Box::new(phrase_query))
+            .with_query_weight(0.5)
+            .with_rescore_query_weight(2.0);
+        let rescored_hits = rescorer.rescore_hits(&searcher, &top_docs)?;
+        for ((score, doc_address), rescored_score) in top_docs.iter().zip(&rescored_hits) {
+            let expected_score = if *doc_address == phrase_doc {
+                0.5 * score + 2.0 * phrase_score
+            } else {
+                0.5 * score
+            };
+            assert!((rescored_score - expected_score).abs() < 1e-5);
+        }
+        let rescored_top_docs = rescorer.rescore(&searcher, top_docs)?;
+        assert_eq!(rescored_top_docs.len(), 3);
+        assert_eq!(rescored_top_docs[0].1, phrase_doc);
+        Ok(())
+    }
+
+    #[test]
+    fn test_score_tweaker_rescorer() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let popularity = schema_builder.add_u64_field("popularity", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for doc_popularity in [1u64, 5, 3] {
+            index_writer.add_document(doc!(text => "a", popularity => doc_popularity))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let query = TermQuery::new(Term::from_field_text(text, "a"), IndexRecordOption::Basic);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+        let rescorer = |segment_reader: &SegmentReader| {
+            let popularity_reader = segment_reader.fast_fields().u64("popularity").unwrap();
+            move |doc: DocId, _score: Score| popularity_reader.get_val(doc) as Score
+        };
+        let rescored_top_docs = rescorer.rescore(&searcher, top_docs)?;
+        assert_eq!(
+            rescored_top_docs,
+            vec![
+                (5.0, DocAddress::new(0, 1)),
+                (3.0, DocAddress::new(0, 2)),
+                (1.0, DocAddress::new(0, 0)),
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,758 @@
+use std::cmp::Ordering;
+use std::net::Ipv6Addr;
+use std::sync::Arc;
+
+use fastfield_codecs::{Column, MonotonicallyMappableToU64};
+
+use crate::collector::top_collector::{TopCollector, TopSegmentCollector};
+use crate::collector::{Collector, SegmentCollector};
+use crate::fastfield::{type_and_cardinality, MultiValuedFastFieldReader};
+use crate::schema::{Cardinality, Field, Type};
+use crate::{
+    DateTime, DocAddress, DocId, InvertedIndexReader, Order, Score, SegmentOrdinal, SegmentReader,
+    TantivyError,
+};
+
+#[derive(Clone, Debug)]
+enum SortKeyTarget {
+    Score,
+    Field(Field),
+    DocAddress,
+}
+
+/// A key of a multi-key sort, as used by [`TopDocs::order_by`](super::TopDocs::order_by).
+///
+/// Documents that have no value for a field key are sorted last, unless
+/// [`SortKey::missing_first`] is called.
+///
+/// Only multivalued and string fast fields record that a document has no value.
+/// Single-valued numeric, bool, date and ip fast fields store a default value (e.g. `0`)
+/// for these documents, which is then sorted like any other value.
+#[derive(Clone, Debug)]
+pub struct SortKey {
+    target: SortKeyTarget,
+    order: Order,
+    missing_first: bool,
+}
+
+impl SortKey {
+    /// Sorts documents by score.
+    pub fn score(order: Order) -> SortKey {
+        SortKey::new(SortKeyTarget::Score, order)
+    }
+
+    /// Sorts documents by the value of a fast field.
+    ///
+    /// Numeric, bool, date, ip and string fast fields are supported. For multivalued
+    /// fields, the smallest value is used in ascending order, and the greatest value in
+    /// descending order.
+    pub fn field(field: Field, order: Order) -> SortKey {
+        SortKey::new(SortKeyTarget::Field(field), order)
+    }
+
+    /// Sorts documents by their [`DocAddress`].
+    ///
+    /// This key is typically used last, to break ties when paginating with
+    /// [`SortKeyTopCollector::search_after`].
+    pub fn doc_address(order: Order) -> SortKey {
+        SortKey::new(SortKeyTarget::DocAddress, order)
+    }
+
+    fn new(target: SortKeyTarget, order: Order) -> SortKey {
+        SortKey {
+            target,
+            order,
+            missing_first: false,
+        }
+    }
+
+    /// Sorts documents without a value for the field before the other documents.
+    #[must_use]
+    pub fn missing_first(mut self) -> SortKey {
+        self.missing_first = true;
+        self
+    }
+
+    /// Sorts documents without a value for the field after the other documents.
+    #[must_use]
+    pub fn missing_last(mut self) -> SortKey {
+        self.missing_first = false;
+        self
+    }
+
+    pub(crate) fn compare(&self, left: &SortValue, right: &SortValue) -> Ordering {
+        let missing_ordering = if self.missing_first {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        };
+        let ordering = match (left, right) {
+            (SortValue::Missing, SortValue::Missing) => return Ordering::Equal,
+            (SortValue::Missing, _) => return missing_ordering,
+            (_, SortValue::Missing) => return missing_ordering.reverse(),
+            // Consistent with the order of the fast field representation.
+            (SortValue::F64(left), SortValue::F64(right)) => left.to_u64().cmp(&right.to_u64()),
+            _ => left.partial_cmp(right).unwrap_or(Ordering::Equal),
+        };
+        if self.order.is_asc() {
+            ordering
+        } else {
+            ordering.reverse()
+        }
+    }
+}
+
+/// The value of a [`SortKey`] for a document.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum SortValue {
+    /// Score of the document.
+    Score(Score),
+    /// Value of a `u64` field.
+    U64(u64),
+    /// Value of an `i64` field.
+    I64(i64),
+    /// Value of an `f64` field.
+    F64(f64),
+    /// Value of a `bool` field.
+    Bool(bool),
+    /// Value of a date field.
+    Date(DateTime),
+    /// Value of a string field.
+    Str(String),
+    /// Value of an ip field.
+    IpAddr(Ipv6Addr),
+    /// Address of the document.
+    DocAddress(DocAddress),
+    /// The document has no value for the field.
+    ///
+    /// This value is never returned for single-valued numeric, bool, date and ip fields,
+    /// whose fast fields store a default value for the documents without a value.
+    Missing,
+}
+
+// Segment level representation of the value of a sort key, such that greater
+// values come first.
+//
+// Field values are represented by their u128 fast field value, or by `2 * ord + 1`
+// for strings. The boolean is true for the values sorted first between the missing and
+// the present values.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+enum SegmentKeyValue {
+    Score(Score),
+    Value(bool, u128),
+}
+
+// Maps a score to a key such that greater keys come first.
+// The mapping is its own inverse.
+fn score_key(order: &Order, score: Score) -> Score {
+    if order.is_asc() {
+        -score
+    } else {
+        score
+    }
+}
+
+fn field_key(sort_key: &SortKey, val: Option<u128>) -> SegmentKeyValue {
+    match val {
+        Some(val) => {
+            let val = if sort_key.order.is_asc() { !val } else { val };
+            SegmentKeyValue::Value(!sort_key.missing_first, val)
+        }
+        None => SegmentKeyValue::Value(sort_key.missing_first, 0),
+    }
+}
+
+fn field_val(sort_key: &SortKey, is_first: bool, key: u128) -> Option<u128> {
+    if is_first == sort_key.missing_first {
+        return None;
+    }
+    if sort_key.order.is_asc() {
+        Some(!key)
+    } else {
+        Some(key)
+    }
+}
+
+// Value sorted first among the values of a multivalued field.
+fn first_val<T: Copy + Ord>(vals: &[T], order: &Order) -> Option<T> {
+    if order.is_asc() {
+        vals.iter().min().copied()
+    } else {
+        vals.iter().max().copied()
+    }
+}
+
+enum FieldValues {
+    U64(Arc<dyn Column<u64>>),
+    U64s(MultiValuedFastFieldReader<u64>, Vec<u64>),
+    IpAddr(Arc<dyn Column<Ipv6Addr>>),
+    IpAddrs(MultiValuedFastFieldReader<Ipv6Addr>, Vec<Ipv6Addr>),
+}
+
+/// Reads the values of a fast field, as order-preserving u128 values.
+///
+/// The values of string fields are their term ordinals in the segment.
+pub(crate) struct FastFieldValues {
+    values: FieldValues,
+    value_type: Type,
+    inverted_index: Option<Arc<InvertedIndexReader>>,
+}
+
+impl FastFieldValues {
+    pub(crate) fn open(field: Field, segment_reader: &SegmentReader) -> crate::Result<Self> {
+        let field_entry = segment_reader.schema().get_field_entry(field);
+        let field_name = field_entry.name();
+        let (_, cardinality) = type_and_cardinality(field_entry.field_type()).ok_or_else(|| {
+            TantivyError::SchemaError(format!("Field {:?} is not a fast field.", field_name))
+        })?;
+        let value_type = field_entry.field_type().value_type();
+        let fast_fields = segment_reader.fast_fields();
+        let values = match (value_type, cardinality) {
+            (Type::Facet | Type::Bytes | Type::Json, _) => {
+                return Err(TantivyError::SchemaError(format!(
+                    "Field {:?} of type {:?} is not supported.",
+                    field_name, value_type
+                )));
+            }
+            (Type::IpAddr, Cardinality::SingleValue) => {
+                FieldValues::IpAddr(fast_fields.ip_addr(field_name)?)
+            }
+            (Type::IpAddr, Cardinality::MultiValues) => {
+                FieldValues::IpAddrs(fast_fields.ip_addrs(field_name)?, Vec::new())
+            }
+            (_, Cardinality::SingleValue) => FieldValues::U64(fast_fields.u64_lenient(field_name)?),
+            (_, Cardinality::MultiValues) => {
+                FieldValues::U64s(fast_fields.u64s_lenient(field_name)?, Vec::new())
+            }
+        };
+        let inverted_index = if value_type == Type::Str {
+            Some(segment_reader.inverted_index(field)?)
+        } else {
+            None
+        };
+        Ok(FastFieldValues {
+            values,
+            value_type,
+            inverted_index,
+        })
+    }
+
+    /// Returns the value of the document, or the value sorted first in the given order
+    /// for multivalued fields.
+    pub(crate) fn val(&mut self, doc: DocId, order: &Order) -> Option<u128> {
+        match &mut self.values {
+            FieldValues::U64(column) => Some(column.get_val(doc) as u128),
+            FieldValues::U64s(reader, vals) => {
+                reader.get_vals(doc, vals);
+                first_val(vals, order).map(|val| val as u128)
+            }
+            FieldValues::IpAddr(column) => Some(u128::from(column.get_val(doc))),
+            FieldValues::IpAddrs(reader, vals) => {
+                reader.get_vals(doc, vals);
+                first_val(vals, order).map(u128::from)
+            }
+        }
+    }
+
+    /// Converts a value returned by [`FastFieldValues::val`] into a [`SortValue`].
+    pub(crate) fn sort_value(&self, val: u128) -> SortValue {
+        match self.value_type {
+            Type::I64 => SortValue::I64(i64::from_u64(val as u64)),
+            Type::F64 => SortValue::F64(f64::from_u64(val as u64)),
+            Type::Bool => SortValue::Bool(bool::from_u64(val as u64)),
+            Type::Date => SortValue::Date(DateTime::from_u64(val as u64)),
+            Type::IpAddr => SortValue::IpAddr(Ipv6Addr::from(val)),
+            Type::Str => {
+                let mut bytes = Vec::new();
+                let found = self
+                    .inverted_index
+                    .as_ref()
+                    .map_or(false, |inverted_index| {
+                        inverted_index
+                            .terms()
+                            .ord_to_term(val as u64, &mut bytes)
+                            .unwrap_or(false)
+                    });
+                if found {
+                    SortValue::Str(String::from_utf8_lossy(&bytes).into_owned())
+                } else {
+                    SortValue::Missing
+                }
+            }
+            _ => SortValue::U64(val as u64),
+        }
+    }
+}
+
+enum SegmentSortKey {
+    Score,
+    Field(FastFieldValues),
+    DocAddress,
+}
+
+impl SegmentSortKey {
+    fn open(sort_key: &SortKey, segment_reader: &SegmentReader) -> crate::Result<SegmentSortKey> {
+        match sort_key.target {
+            SortKeyTarget::Score => Ok(SegmentSortKey::Score),
+            SortKeyTarget::DocAddress => Ok(SegmentSortKey::DocAddress),
+            SortKeyTarget::Field(field) => Ok(SegmentSortKey::Field(FastFieldValues::open(
+                field,
+                segment_reader,
+            )?)),
+        }
+    }
+
+    fn key(&mut self, sort_key: &SortKey, doc: DocId, score: Score) -> SegmentKeyValue {
+        match self {
+            SegmentSortKey::Score => SegmentKeyValue::Score(score_key(&sort_key.order, score)),
+            SegmentSortKey::Field(field_values) => {
+                let val = field_values.val(doc, &sort_key.order);
+                if field_values.value_type == Type::Str {
+                    field_key(sort_key, val.map(|term_ord| 2 * term_ord + 1))
+                } else {
+                    field_key(sort_key, val)
+                }
+            }
+            SegmentSortKey::DocAddress => field_key(sort_key, Some(doc as u128 + 1)),
+        }
+    }
+
+    /// Returns the key of a `search_after` cursor value, in the segment `segment_ord`.
+    fn cursor_key(
+        &self,
+        sort_key: &SortKey,
+        value: &SortValue,
+        segment_ord: SegmentOrdinal,
+    ) -> crate::Result<SegmentKeyValue> {
+        let val = match (self, value) {
+            (SegmentSortKey::Score, SortValue::Score(score)) => {
+                return Ok(SegmentKeyValue::Score(score_key(&sort_key.order, *score)));
+            }
+            (SegmentSortKey::DocAddress, SortValue::DocAddress(doc_address)) => {
+                match doc_address.segment_ord.cmp(&segment_ord) {
+                    Ordering::Less => 0,
+                    Ordering::Equal => doc_address.doc_id as u128 + 1,
+                    Ordering::Greater => u128::MAX,
+                }
+            }
+            (SegmentSortKey::Field(_), SortValue::Missing) => {
+                return Ok(field_key(sort_key, None));
+            }
+            (SegmentSortKey::Field(field_values), value) => {
+                let value_type = field_values.value_type;
+                match (value_type, value, &field_values.inverted_index) {
+                    (Type::U64, SortValue::U64(val), _) => *val as u128,
+                    (Type::I64, SortValue::I64(val), _) => val.to_u64() as u128,
+                    (Type::F64, SortValue::F64(val), _) => val.to_u64() as u128,
+                    (Type::Bool, SortValue::Bool(val), _) => val.to_u64() as u128,
+                    (Type::Date, SortValue::Date(val), _) => val.to_u64() as u128,
+                    (Type::IpAddr, SortValue::IpAddr(val), _) => u128::from(*val),
+                    (Type::Str, SortValue::Str(text), Some(inverted_index)) => {
+                        let terms = inverted_index.terms();
+                        if let Some(term_ord) = terms.term_ord(text)? {
+                            2 * term_ord as u128 + 1
+                        } else {
+                            // The key of a missing term sits between the keys of its
+                            // neighbours.
+                            let mut stream = terms.range().ge(text).into_stream()?;
+                            let next_term_ord = if stream.advance() {
+                                stream.term_ord()
+                            } else {
+                                terms.num_terms() as u64
+                            };
+                            2 * next_term_ord as u128
+                        }
+                    }
+                    _ => {
+                        return Err(TantivyError::InvalidArgument(format!(
+                            "Sort value {:?} does not match the type {:?} of the sort key.",
+                            value, value_type
+                        )));
+                    }
+                }
+            }
+            _ => {
+                return Err(TantivyError::InvalidArgument(format!(
+                    "Sort value {:?} does not match the sort key {:?}.",
+                    value, sort_key.target
+                )));
+            }
+        };
+        Ok(field_key(sort_key, Some(val)))
+    }
+
+    fn value(
+        &self,
+        sort_key: &SortKey,
+        key: SegmentKeyValue,
+        doc_address: DocAddress,
+    ) -> SortValue {
+        let (is_first, key) = match key {
+            SegmentKeyValue::Score(score) => {
+                return SortValue::Score(score_key(&sort_key.order, score));
+            }
+            SegmentKeyValue::Value(is_first, key) => (is_first, key),
+        };
+        let field_values = match self {
+            SegmentSortKey::Field(field_values) => field_values,
+            _ => return SortValue::DocAddress(doc_address),
+        };
+        match field_val(sort_key, is_first, key) {
+            Some(val) if field_values.value_type == Type::Str => {
+                field_values.sort_value((val - 1) / 2)
+            }
+            Some(val) => field_values.sort_value(val),
+            None => SortValue::Missing,
+        }
+    }
+}
+
+/// Collector returned by [`TopDocs::order_by`](super::TopDocs::order_by).
+///
+/// Documents are sorted by the given sort keys, and then by ascending
+/// [`DocAddress`].
+pub struct SortKeyTopCollector {
+    collector: TopCollector<Vec<SortValue>>,
+    sort_keys: Vec<SortKey>,
+    search_after: Option<Vec<SortValue>>,
+}
+
+impl SortKeyTopCollector {
+    pub(crate) fn new(
+        collector: TopCollector<Vec<SortValue>>,
+        sort_keys: Vec<SortKey>,
+    ) -> SortKeyTopCollector {
+        SortKeyTopCollector {
+            collector,
+            sort_keys,
+            search_after: None,
+        }
+    }
+
+    /// Only collects the documents sorted after the given sort values, typically
+    /// the sort values of the last document of the previous page.
+    ///
+    /// Documents are skipped while they are collected, so that deep pages are as
+    /// cheap to compute as the first one. The documents whose sort values are all equal
+    /// to `sort_values` are skipped as well: adding a [`SortKey::doc_address`] key
+    /// guarantees that no document is skipped when paginating.
+    ///
+    /// The sort values must match the sort keys, or the search returns an error.
+    #[must_use]
+    pub fn search_after(mut self, sort_values: Vec<SortValue>) -> SortKeyTopCollector {
+        self.search_after = Some(sort_values);
+        self
+    }
+}
+
+impl Collector for SortKeyTopCollector {
+    type Fruit = Vec<(Vec<SortValue>, DocAddress)>;
+
+    type Child = SortKeyTopSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<Self::Child> {
+        let segment_keys = self
+            .sort_keys
+            .iter()
+            .map(|sort_key| SegmentSortKey::open(sort_key, segment_reader))
+            .collect::<crate::Result<Vec<_>>>()?;
+        let search_after = if let Some(sort_values) = &self.search_after {
+            if sort_values.len() != self.sort_keys.len() {
+                return Err(TantivyError::InvalidArgument(format!(
+                    "Expected {} sort values, got {}.",
+                    self.sort_keys.len(),
+                    sort_values.len()
+                )));
+            }
+            let cursor = segment_keys
+                .iter()
+                .zip(&self.sort_keys)
+                .zip(sort_values)
+                .map(|((segment_key, sort_key), value)| {
+                    segment_key.cursor_key(sort_key, value, segment_local_id)
+                })
+                .collect::<crate::Result<Vec<_>>>()?;
+            Some(cursor)
+        } else {
+            None
+        };
+        Ok(SortKeyTopSegmentCollector {
+            segment_collector: self.collector.for_segment(segment_local_id, segment_reader),
+            sort_keys: self.sort_keys.clone(),
+            segment_keys,
+            search_after,
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        self.sort_keys
+            .iter()
+            .any(|sort_key| matches!(sort_key.target, SortKeyTarget::Score))
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<Vec<(Vec<SortValue>, DocAddress)>>,
+    ) -> crate::Result<Self::Fruit> {
+        let mut top_docs: Vec<(Vec<SortValue>, DocAddress)> =
+            segment_fruits.into_iter().flatten().collect();
+        top_docs.sort_by(
+            |(left_values, left_address), (right_values, right_address)| {
+                self.sort_keys
+                    .iter()
+                    .zip(left_values.iter().zip(right_values))
+                    .map(|(sort_key, (left, right))| sort_key.compare(left, right))
+                    .find(|ordering| *ordering != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+                    .then_with(|| left_address.cmp(right_address))
+            },
+        );
+        Ok(top_docs
+            .into_iter()
+            .skip(self.collector.offset)
+            .take(self.collector.limit)
+            .collect())
+    }
+}
+
+/// Segment Collector associated with [`SortKeyTopCollector`].
+pub struct SortKeyTopSegmentCollector {
+    segment_collector: TopSegmentCollector<Vec<SegmentKeyValue>>,
+    sort_keys: Vec<SortKey>,
+    segment_keys: Vec<SegmentSortKey>,
+    search_after: Option<Vec<SegmentKeyValue>>,
+}
+
+impl SegmentCollector for SortKeyTopSegmentCollector {
+    type Fruit = Vec<(Vec<SortValue>, DocAddress)>;
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        let key: Vec<SegmentKeyValue> = self
+            .segment_keys
+            .iter_mut()
+            .zip(&self.sort_keys)
+            .map(|(segment_key, sort_key)| segment_key.key(sort_key, doc, score))
+            .collect();
+        if let Some(search_after) = &self.search_after {
+            if key >= *search_after {
+                return;
+            }
+        }
+        self.segment_collector.collect(doc, key);
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        let sort_keys = &self.sort_keys;
+        let segment_keys = &self.segment_keys;
+        self.segment_collector
+            .harvest()
+            .into_iter()
+            .map(|(key, doc_address)| {
+                let values = segment_keys
+                    .iter()
+                    .zip(sort_keys)
+                    .zip(key)
+                    .map(|((segment_key, sort_key), key)| {
+                        segment_key.value(sort_key, key, doc_address)
+                    })
+                    .collect();
+                (values, doc_address)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SortKey, SortValue};
+    use crate::collector::TopDocs;
+    use crate::query::AllQuery;
+    use crate::schema::{Cardinality, NumericOptions, Schema, FAST, STRING};
+    use crate::{DateTime, DocAddress, Index, IndexWriter, Order};
+
+    #[test]
+    fn test_order_by_multiple_keys() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let city = schema_builder.add_text_field("city", STRING | FAST);
+        let date = schema_builder.add_date_field("date", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        let date_1 = DateTime::from_timestamp_secs(1_000);
+        let date_2 = DateTime::from_timestamp_secs(2_000);
+        index_writer.add_document(doc!(city => "paris", date => date_1))?;
+        index_writer.add_document(doc!(date => date_2))?;
+        index_writer.add_document(doc!(city => "lyon", date => date_1))?;
+        index_writer.add_document(doc!(city => "paris", date => date_2))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let sort_keys = vec![
+            SortKey::field(city, Order::Asc),
+            SortKey::field(date, Order::Desc),
+        ];
+        let top_docs = searcher.search(
+            &AllQuery,
+            &TopDocs::with_limit(4).order_by(sort_keys.clone()),
+        )?;
+        assert_eq!(
+            top_docs,
+            vec![
+                (
+                    vec![SortValue::Str("lyon".to_string()), SortValue::Date(date_1)],
+                    DocAddress::new(0, 2)
+                ),
+                (
+                    vec![SortValue::Str("paris".to_string()), SortValue::Date(date_2)],
+                    DocAddress::new(0, 3)
+                ),
+                (
+                    vec![SortValue::Str("paris".to_string()), SortValue::Date(date_1)],
+                    DocAddress::new(0, 0)
+                ),
+                (
+                    vec![SortValue::Missing, SortValue::Date(date_2)],
+                    DocAddress::new(0, 1)
+                ),
+            ]
+        );
+
+        let sort_keys = vec![SortKey::field(city, Order::Desc).missing_first()];
+        let top_docs = searcher.search(&AllQuery, &TopDocs::with_limit(2).order_by(sort_keys))?;
+        assert_eq!(
+            top_docs,
+            vec![
+                (vec![SortValue::Missing], DocAddress::new(0, 1)),
+                (
+                    vec![SortValue::Str("paris".to_string())],
+                    DocAddress::new(0, 0)
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_after_matches_offset() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let num = schema_builder.add_i64_field("num", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for i in 0..30i64 {
+            index_writer.add_document(doc!(num => (i * 7) % 10 - 5))?;
+            if i % 10 == 9 {
+                index_writer.commit()?;
+            }
+        }
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 3);
+
+        let sort_keys = vec![
+            SortKey::field(num, Order::Desc),
+            SortKey::doc_address(Order::Asc),
+        ];
+        let mut search_after = None;
+        for page in 0..6 {
+            let collector = TopDocs::with_limit(5).and_offset(page * 5);
+            let expected = searcher.search(&AllQuery, &collector.order_by(sort_keys.clone()))?;
+            let mut collector = TopDocs::with_limit(5).order_by(sort_keys.clone());
+            if let Some(sort_values) = search_after.take() {
+                collector = collector.search_after(sort_values);
+            }
+            let top_docs = searcher.search(&AllQuery, &collector)?;
+            assert_eq!(top_docs.len(), 5);
+            assert_eq!(top_docs, expected);
+            search_after = top_docs.last().map(|(sort_values, _)| sort_values.clone());
+        }
+        let collector = TopDocs::with_limit(5)
+            .order_by(sort_keys)
+            .search_after(search_after.unwrap());
+        assert!(searcher.search(&AllQuery, &collector)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_after_string_not_in_segment() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let city = schema_builder.add_text_field("city", STRING | FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(city => "berlin"))?;
+        index_writer.add_document(doc!(city => "paris"))?;
+        index_writer.add_document(doc!())?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let collector = TopDocs::with_limit(3)
+            .order_by(vec![SortKey::field(city, Order::Asc)])
+            .search_after(vec![SortValue::Str("lyon".to_string())]);
+        let top_docs = searcher.search(&AllQuery, &collector)?;
+        assert_eq!(
+            top_docs,
+            vec![
+                (
+                    vec![SortValue::Str("paris".to_string())],
+                    DocAddress::new(0, 1)
+                ),
+                (vec![SortValue::Missing], DocAddress::new(0, 2)),
+            ]
+        );
+
+        let collector = TopDocs::with_limit(3)
+            .order_by(vec![SortKey::field(city, Order::Asc)])
+            .search_after(vec![SortValue::U64(3)]);
+        assert!(matches!(
+            searcher.search(&AllQuery, &collector),
+            Err(crate::TantivyError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_order_by_document_without_value() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let num = schema_builder.add_i64_field("num", FAST);
+        let nums = schema_builder.add_i64_field(
+            "nums",
+            NumericOptions::default().set_fast(Cardinality::MultiValues),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(num => -1i64, nums => -1i64))?;
+        index_writer.add_document(doc!())?;
+        index_writer.add_document(doc!(num => 1i64, nums => 1i64))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        // The single-valued fast field stores 0 for the document without a value.
+        let top_docs = searcher.search(
+            &AllQuery,
+            &TopDocs::with_limit(3).order_by(vec![SortKey::field(num, Order::Asc)]),
+        )?;
+        assert_eq!(
+            top_docs,
+            vec![
+                (vec![SortValue::I64(-1)], DocAddress::new(0, 0)),
+                (vec![SortValue::I64(0)], DocAddress::new(0, 1)),
+                (vec![SortValue::I64(1)], DocAddress::new(0, 2)),
+            ]
+        );
+
+        let top_docs = searcher.search(
+            &AllQuery,
+            &TopDocs::with_limit(3).order_by(vec![SortKey::field(nums, Order::Asc)]),
+        )?;
+        assert_eq!(
+            top_docs,
+            vec![
+                (vec![SortValue::I64(-1)], DocAddress::new(0, 0)),
+                (vec![SortValue::I64(1)], DocAddress::new(0, 2)),
+                (vec![SortValue::Missing], DocAddress::new(0, 1)),
+            ]
+        );
+        Ok(())
+    }
+}
@@ -6,16 +6,21 @@ use std::sync::Arc;
 use fastfield_codecs::Column;
 
 use super::Collector;
+use crate::collector::collapsing_collector::CollapsingCollector;
 use crate::collector::custom_score_top_collector::CustomScoreTopCollector;
+use crate::collector::early_terminating_top_collector::EarlyTerminatingTopCollector;
+use crate::collector::sort_key_top_collector::{SortKey, SortKeyTopCollector};
 use crate::collector::top_collector::{ComparableDoc, TopCollector, TopSegmentCollector};
+use crate::collector::track_total_hits_collector::TrackTotalHitsTopCollector;
 use crate::collector::tweak_score_top_collector::TweakedScoreTopCollector;
 use crate::collector::{
     CustomScorer, CustomSegmentScorer, ScoreSegmentTweaker, ScoreTweaker, SegmentCollector,
+    TotalHits,
 };
 use crate::fastfield::FastValue;
 use crate::query::Weight;
 use crate::schema::Field;
-use crate::{DocAddress, DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
+use crate::{DocAddress, DocId, Order, Score, SegmentOrdinal, SegmentReader, TantivyError};
 
 struct FastFieldConvertCollector<
     TCollector: Collector<Fruit = Vec<(u64, DocAddress)>>,
@@ -380,6 +385,212 @@ impl TopDocs {
         }
     }
 
+    /// Set top-K to rank documents by a given fast field, in the given order, and
+    /// count the matching documents.
+    ///
+    /// If the index is sorted by this field first (see
+    /// [`IndexSettings::sort_by`](crate::IndexSettings::sort_by)) in the
+    /// same order, the collection of each segment stops as soon as the top `limit + offset`
+    /// documents of the segment have been collected. The number of hits is then a lower bound,
+    /// as reported by [`TotalHits::relation`].
+    ///
+    /// Otherwise, all of the matching documents are visited, and the number of hits is exact.
+    ///
+    /// As for [`TopDocs::order_by_fast_field`], an error is returned at the moment of
+    /// collection if the field is not a fast field of the requested type.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tantivy::collector::{TopDocs, TotalHitsRelation};
+    /// use tantivy::query::AllQuery;
+    /// use tantivy::schema::{Schema, FAST};
+    /// use tantivy::{doc, DocAddress, Index, IndexSettings, IndexSortByField, Order};
+    ///
+    /// # fn main() -> tantivy::Result<()> {
+    /// let mut schema_builder = Schema::builder();
+    /// let timestamp = schema_builder.add_u64_field("timestamp", FAST);
+    /// let schema = schema_builder.build();
+    /// let settings = IndexSettings {
+    ///     sort_by: vec![IndexSortByField {
+    ///         field: "timestamp".to_string(),
+    ///         order: Order::Desc,
+    ///     }],
+    ///     ..Default::default()
+    /// };
+    /// let index = Index::builder().schema(schema).settings(settings).create_in_ram()?;
+    /// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+    /// for ts in 0..100u64 {
+    ///     index_writer.add_document(doc!(timestamp => ts))?;
+    /// }
+    /// index_writer.commit()?;
+    ///
+    /// let searcher = index.reader()?.searcher();
+    /// let collector =
+    ///     TopDocs::with_limit(2).order_by_fast_field_early_terminating(timestamp, Order::Desc);
+    /// let (top_docs, total_hits) = searcher.search(&AllQuery, &collector)?;
+    /// assert_eq!(
+    ///     top_docs,
+    ///     vec![(99u64, DocAddress::new(0, 0)), (98u64, DocAddress::new(0, 1))]
+    /// );
+    /// assert_eq!(total_hits.value, 2);
+    /// assert_eq!(total_hits.relation, TotalHitsRelation::Gte);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn order_by_fast_field_early_terminating<TFastValue>(
+        self,
+        fast_field: Field,
+        order: Order,
+    ) -> impl Collector<Fruit = (Vec<(TFastValue, DocAddress)>, TotalHits)>
+    where
+        TFastValue: FastValue,
+    {
+        EarlyTerminatingTopCollector::new(self.0.into_tscore(), fast_field, order)
+    }
+
+    /// Counts the matching documents along with the top-K documents, exactly up to
+    /// `threshold` hits.
+    ///
+    /// Collecting `(TopDocs, Count)` visits all of the matching documents. Once a segment has
+    /// counted `threshold` hits, this collector instead skips the documents that cannot make
+    /// it to the top-K, as [`TopDocs`] alone does. If more than `threshold` hits are counted
+    /// over all of the segments, or if some documents were skipped, the number of hits is
+    /// `threshold`, and is a lower bound, as reported by [`TotalHits::relation`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tantivy::collector::{TopDocs, TotalHitsRelation};
+    /// use tantivy::query::QueryParser;
+    /// use tantivy::schema::{Schema, TEXT};
+    /// use tantivy::{doc, Index};
+    ///
+    /// # fn main() -> tantivy::Result<()> {
+    /// let mut schema_builder = Schema::builder();
+    /// let title = schema_builder.add_text_field("title", TEXT);
+    /// let index = Index::create_in_ram(schema_builder.build());
+    /// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+    /// index_writer.add_document(doc!(title => "The Name of the Wind"))?;
+    /// index_writer.add_document(doc!(title => "The Diary of Muadib"))?;
+    /// index_writer.add_document(doc!(title => "The Diary of a Young Girl"))?;
+    /// index_writer.commit()?;
+    ///
+    /// let searcher = index.reader()?.searcher();
+    /// let query = QueryParser::for_index(&index, vec![title]).parse_query("diary")?;
+    /// let collector = TopDocs::with_limit(1).track_total_hits(10_000);
+    /// let (top_docs, total_hits) = searcher.search(&query, &collector)?;
+    /// assert_eq!(top_docs.len(), 1);
+    /// assert_eq!(total_hits.value, 2);
+    /// assert_eq!(total_hits.relation, TotalHitsRelation::Eq);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn track_total_hits(
+        self,
+        threshold: u64,
+    ) -> impl Collector<Fruit = (Vec<(Score, DocAddress)>, TotalHits)> {
+        TrackTotalHitsTopCollector::new(self.0, threshold)
+    }
+
+    /// Set top-K to rank documents by several sort keys: the score, fast fields or the
+    /// document address.
+    ///
+    /// Documents are compared on the first key, then on the second key in case of a tie, and
+    /// so on. The remaining ties are broken by ascending [`DocAddress`]. Each document is
+    /// returned along with its value for every sort key.
+    ///
+    /// Results can be paginated with [`SortKeyTopCollector::search_after`].
+    ///
+    /// An error is returned at the moment of collection if one of the fields is not a fast
+    /// field, or cannot be used as a sort key.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tantivy::collector::{SortKey, SortValue, TopDocs};
+    /// use tantivy::query::AllQuery;
+    /// use tantivy::schema::{Schema, FAST, STRING};
+    /// use tantivy::{doc, DocAddress, Index, Order};
+    ///
+    /// # fn main() -> tantivy::Result<()> {
+    /// let mut schema_builder = Schema::builder();
+    /// let category = schema_builder.add_text_field("category", STRING | FAST);
+    /// let price = schema_builder.add_u64_field("price", FAST);
+    /// let index = Index::create_in_ram(schema_builder.build());
+    /// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+    /// index_writer.add_document(doc!(category => "books", price => 12u64))?;
+    /// index_writer.add_document(doc!(category => "games", price => 40u64))?;
+    /// index_writer.add_document(doc!(category => "books", price => 30u64))?;
+    /// index_writer.commit()?;
+    ///
+    /// let searcher = index.reader()?.searcher();
+    /// let sort_keys = vec![
+    ///     SortKey::field(category, Order::Asc),
+    ///     SortKey::field(price, Order::Desc),
+    ///     SortKey::doc_address(Order::Asc),
+    /// ];
+    /// let first_page =
+    ///     searcher.search(&AllQuery, &TopDocs::with_limit(2).order_by(sort_keys.clone()))?;
+    /// assert_eq!(first_page[0].1, DocAddress::new(0, 2));
+    /// assert_eq!(first_page[1].1, DocAddress::new(0, 0));
+    ///
+    /// let (last_sort_values, _) = first_page.last().unwrap().clone();
+    /// let collector = TopDocs::with_limit(2)
+    ///     .order_by(sort_keys)
+    ///     .search_after(last_sort_values);
+    /// let second_page = searcher.search(&AllQuery, &collector)?;
+    /// assert_eq!(second_page.len(), 1);
+    /// assert_eq!(second_page[0].0[0], SortValue::Str("games".to_string()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn order_by(self, sort_keys: Vec<SortKey>) -> SortKeyTopCollector {
+        SortKeyTopCollector::new(self.0.into_tscore(), sort_keys)
+    }
+
+    /// Set top-K to return only the best document for each distinct value of a fast field.
+    ///
+    /// The limit and the offset apply to the groups, ranked by the score of their best
+    /// document. The number of groups and, if requested with
+    /// [`CollapsingCollector::with_inner_hits`], the best documents of each group are
+    /// returned as well.
+    ///
+    /// An error is returned at the moment of collection if the field is not a fast field,
+    /// or if its type is not supported.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tantivy::collector::{SortValue, TopDocs};
+    /// use tantivy::query::QueryParser;
+    /// use tantivy::schema::{Schema, FAST, TEXT};
+    /// use tantivy::{doc, Index};
+    ///
+    /// # fn main() -> tantivy::Result<()> {
+    /// let mut schema_builder = Schema::builder();
+    /// let title = schema_builder.add_text_field("title", TEXT);
+    /// let product_id = schema_builder.add_u64_field("product_id", FAST);
+    /// let index = Index::create_in_ram(schema_builder.build());
+    /// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+    /// index_writer.add_document(doc!(title => "Red shirt", product_id => 1u64))?;
+    /// index_writer.add_document(doc!(title => "Blue shirt", product_id => 1u64))?;
+    /// index_writer.add_document(doc!(title => "Shirt and tie", product_id => 2u64))?;
+    /// index_writer.commit()?;
+    ///
+    /// let searcher = index.reader()?.searcher();
+    /// let query = QueryParser::for_index(&index, vec![title]).parse_query("shirt")?;
+    /// let collapsed = searcher.search(&query, &TopDocs::with_limit(10).collapse_by(product_id))?;
+    /// assert_eq!(collapsed.num_groups, 2);
+    /// assert_eq!(collapsed.groups[0].key, SortValue::U64(1));
+    /// assert_eq!(collapsed.groups[0].num_docs, 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn collapse_by(self, field: Field) -> CollapsingCollector {
+        CollapsingCollector::new(self.0, field)
+    }
+
     /// Ranks the documents using a custom score.
     ///
     /// This method offers a convenient way to tweak or replace
@@ -711,12 +922,15 @@ impl SegmentCollector for TopScoreSegmentCollector {
 #[cfg(test)]
 mod tests {
     use super::TopDocs;
-    use crate::collector::Collector;
+    use crate::collector::{Collector, TotalHits};
     use crate::query::{AllQuery, Query, QueryParser};
-    use crate::schema::{Field, Schema, FAST, STORED, TEXT};
+    use crate::schema::{Field, Schema, Term, FAST, INDEXED, STORED, TEXT};
     use crate::time::format_description::well_known::Rfc3339;
     use crate::time::OffsetDateTime;
-    use crate::{DateTime, DocAddress, DocId, Index, IndexWriter, Score, SegmentReader};
+    use crate::{
+        DateTime, DocAddress, DocId, Index, IndexSettings, IndexSortByField, IndexWriter, Order,
+        Score, SegmentReader,
+    };
 
     fn make_index() -> crate::Result<Index> {
         let mut schema_builder = Schema::builder();
@@ -1087,6 +1301,110 @@ mod tests {
         );
     }
 
+    fn make_sorted_index(order: Order) -> crate::Result<Index> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let timestamp_field = schema_builder.add_u64_field("timestamp", FAST | INDEXED);
+        let schema = schema_builder.build();
+        let settings = IndexSettings {
+            sort_by: vec![IndexSortByField {
+                field: "timestamp".to_string(),
+                order,
+            }],
+            ..Default::default()
+        };
+        let index = Index::builder()
+            .schema(schema)
+            .settings(settings)
+            .create_in_ram()?;
+        let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+        for segment in 0..2u64 {
+            for ts in segment * 10..(segment + 1) * 10 {
+                let text = if ts % 2 == 0 { "even" } else { "odd" };
+                index_writer.add_document(doc!(text_field=>text, timestamp_field=>ts))?;
+            }
+            index_writer.commit()?;
+        }
+        index_writer.delete_term(Term::from_field_u64(timestamp_field, 18))?;
+        index_writer.commit()?;
+        Ok(index)
+    }
+
+    #[test]
+    fn test_order_by_fast_field_early_terminating() -> crate::Result<()> {
+        let index = make_sorted_index(Order::Desc)?;
+        let schema = index.schema();
+        let text_field = schema.get_field("text").unwrap();
+        let timestamp_field = schema.get_field("timestamp").unwrap();
+        let query = QueryParser::for_index(&index, vec![text_field]).parse_query("even")?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+
+        let collector = TopDocs::with_limit(3)
+            .order_by_fast_field_early_terminating::<u64>(timestamp_field, Order::Desc);
+        let (top_docs, total_hits) = searcher.search(&query, &collector)?;
+        let expected_top_docs: Vec<(u64, DocAddress)> = searcher.search(
+            &query,
+            &TopDocs::with_limit(3).order_by_fast_field(timestamp_field),
+        )?;
+        assert_eq!(top_docs, expected_top_docs);
+        assert_eq!(
+            top_docs.iter().map(|(ts, _)| *ts).collect::<Vec<u64>>(),
+            vec![16, 14, 12]
+        );
+        // Each segment stops after its top 3 documents.
+        assert_eq!(total_hits, TotalHits::lower_bound(6));
+
+        // All of the matching documents fit within the limit.
+        let collector = TopDocs::with_limit(20)
+            .order_by_fast_field_early_terminating::<u64>(timestamp_field, Order::Desc);
+        let (top_docs, total_hits) = searcher.search(&query, &collector)?;
+        assert_eq!(top_docs.len(), 9);
+        assert_eq!(total_hits, TotalHits::exact(9));
+        Ok(())
+    }
+
+    #[test]
+    fn test_order_by_fast_field_early_terminating_with_offset() -> crate::Result<()> {
+        let index = make_sorted_index(Order::Asc)?;
+        let schema = index.schema();
+        let text_field = schema.get_field("text").unwrap();
+        let timestamp_field = schema.get_field("timestamp").unwrap();
+        let query = QueryParser::for_index(&index, vec![text_field]).parse_query("odd")?;
+        let searcher = index.reader()?.searcher();
+
+        let collector = TopDocs::with_limit(2)
+            .and_offset(1)
+            .order_by_fast_field_early_terminating::<u64>(timestamp_field, Order::Asc);
+        let (top_docs, total_hits) = searcher.search(&query, &collector)?;
+        assert_eq!(
+            top_docs.iter().map(|(ts, _)| *ts).collect::<Vec<u64>>(),
+            vec![3, 5]
+        );
+        assert_eq!(total_hits, TotalHits::lower_bound(6));
+        Ok(())
+    }
+
+    #[test]
+    fn test_order_by_fast_field_early_terminating_index_sort_mismatch() -> crate::Result<()> {
+        let index = make_sorted_index(Order::Asc)?;
+        let schema = index.schema();
+        let text_field = schema.get_field("text").unwrap();
+        let timestamp_field = schema.get_field("timestamp").unwrap();
+        let query = QueryParser::for_index(&index, vec![text_field]).parse_query("even")?;
+        let searcher = index.reader()?.searcher();
+
+        let collector = TopDocs::with_limit(3)
+            .order_by_fast_field_early_terminating::<u64>(timestamp_field, Order::Desc);
+        let (top_docs, total_hits) = searcher.search(&query, &collector)?;
+        assert_eq!(
+            top_docs.iter().map(|(ts, _)| *ts).collect::<Vec<u64>>(),
+            vec![16, 14, 12]
+        );
+        assert_eq!(total_hits, TotalHits::exact(9));
+        Ok(())
+    }
+
     fn index(
         query: &str,
         query_field: Field,
@@ -0,0 +1,83 @@
+use std::ops::Add;
+
+/// Relation between [`TotalHits::value`] and the actual number of documents
+/// matching a query.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TotalHitsRelation {
+    /// The number of hits is exact.
+    Eq,
+    /// The actual number of hits is greater than or equal to the number of hits.
+    Gte,
+}
+
+/// Number of documents matching a query, as reported by collectors that may
+/// stop counting before having visited all of the matching documents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TotalHits {
+    /// Number of hits.
+    pub value: u64,
+    /// Whether `value` is exact, or a lower bound.
+    pub relation: TotalHitsRelation,
+}
+
+impl TotalHits {
+    /// Creates an exact number of hits.
+    pub fn exact(value: u64) -> TotalHits {
+        TotalHits {
+            value,
+            relation: TotalHitsRelation::Eq,
+        }
+    }
+
+    /// Creates a lower bound of the number of hits.
+    pub fn lower_bound(value: u64) -> TotalHits {
+        TotalHits {
+            value,
+            relation: TotalHitsRelation::Gte,
+        }
+    }
+
+    /// Returns true if the number of hits is exact.
+    pub fn is_exact(&self) -> bool {
+        self.relation == TotalHitsRelation::Eq
+    }
+}
+
+impl Default for TotalHits {
+    fn default() -> Self {
+        TotalHits::exact(0)
+    }
+}
+
+impl Add for TotalHits {
+    type Output = TotalHits;
+
+    fn add(self, other: TotalHits) -> TotalHits {
+        let relation = if self.is_exact() && other.is_exact() {
+            TotalHitsRelation::Eq
+        } else {
+            TotalHitsRelation::Gte
+        };
+        TotalHits {
+            value: self.value + other.value,
+            relation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TotalHits, TotalHitsRelation};
+
+    #[test]
+    fn test_total_hits_add() {
+        assert_eq!(
+            TotalHits::exact(3) + TotalHits::exact(4),
+            TotalHits::exact(7)
+        );
+        let total_hits = TotalHits::exact(3) + TotalHits::lower_bound(4);
+        assert_eq!(total_hits.value, 7);
+        assert_eq!(total_hits.relation, TotalHitsRelation::Gte);
+        assert!(!total_hits.is_exact());
+    }
+}
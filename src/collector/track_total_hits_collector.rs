@@ -0,0 +1,237 @@
+use std::collections::BinaryHeap;
+
+use crate::collector::top_collector::{ComparableDoc, TopCollector, TopSegmentCollector};
+use crate::collector::{Collector, SegmentCollector, TotalHits};
+use crate::query::Weight;
+use crate::{DocAddress, DocId, Score, SegmentOrdinal, SegmentReader};
+
+pub(crate) struct TrackTotalHitsTopCollector {
+    collector: TopCollector<Score>,
+    threshold: u64,
+}
+
+impl TrackTotalHitsTopCollector {
+    pub(crate) fn new(
+        collector: TopCollector<Score>,
+        threshold: u64,
+    ) -> TrackTotalHitsTopCollector {
+        TrackTotalHitsTopCollector {
+            collector,
+            threshold,
+        }
+    }
+}
+
+impl Collector for TrackTotalHitsTopCollector {
+    type Fruit = (Vec<(Score, DocAddress)>, TotalHits);
+
+    type Child = TrackTotalHitsTopSegmentCollector;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> crate::Result<Self::Child> {
+        Ok(TrackTotalHitsTopSegmentCollector {
+            segment_collector: self.collector.for_segment(segment_local_id, segment_reader),
+            num_hits: 0,
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        true
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<(Vec<(Score, DocAddress)>, TotalHits)>,
+    ) -> crate::Result<Self::Fruit> {
+        let mut total_hits = TotalHits::default();
+        let mut segment_top_docs = Vec::with_capacity(segment_fruits.len());
+        for (top_docs, segment_total_hits) in segment_fruits {
+            total_hits = total_hits + segment_total_hits;
+            segment_top_docs.push(top_docs);
+        }
+        let top_docs = self.collector.merge_fruits(segment_top_docs)?;
+        // Each segment counts up to `threshold` hits: the limit applies to the whole
+        // search once the segments are merged.
+        if total_hits.value > self.threshold || !total_hits.is_exact() {
+            total_hits = TotalHits::lower_bound(total_hits.value.min(self.threshold));
+        }
+        Ok((top_docs, total_hits))
+    }
+
+    fn collect_segment(
+        &self,
+        weight: &dyn Weight,
+        segment_ord: u32,
+        reader: &SegmentReader,
+    ) -> crate::Result<<Self::Child as SegmentCollector>::Fruit> {
+        let heap_len = self.collector.limit + self.collector.offset;
+        let mut heap: BinaryHeap<ComparableDoc<Score, DocId>> = BinaryHeap::with_capacity(heap_len);
+        let alive_bitset = reader.alive_bitset();
+        let mut num_hits = 0u64;
+        let mut threshold = Score::MIN;
+        let mut is_pruning = false;
+        weight.for_each_pruning(Score::MIN, reader, &mut |doc, score| {
+            if let Some(alive_bitset) = alive_bitset {
+                if alive_bitset.is_deleted(doc) {
+                    return threshold;
+                }
+            }
+            num_hits += 1;
+            let heap_item = ComparableDoc {
+                feature: score,
+                doc,
+            };
+            if heap.len() < heap_len {
+                heap.push(heap_item);
+            } else if let Some(mut head) = heap.peek_mut() {
+                // Until the threshold is reached, documents that do not make it to the
+                // top are visited as well.
+                if head.feature < score {
+                    *head = heap_item;
+                }
+            }
+            // Once enough hits have been counted, the documents that cannot make it to
+            // the top can be skipped.
+            if heap.len() == heap_len && num_hits >= self.threshold {
+                threshold = heap.peek().map(|el| el.feature).unwrap_or(Score::MIN);
+                is_pruning = true;
+            }
+            threshold
+        })?;
+
+        let top_docs = heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|cid| {
+                (
+                    cid.feature,
+                    DocAddress {
+                        segment_ord,
+                        doc_id: cid.doc,
+                    },
+                )
+            })
+            .collect();
+        let total_hits = if is_pruning {
+            TotalHits::lower_bound(num_hits)
+        } else {
+            TotalHits::exact(num_hits)
+        };
+        Ok((top_docs, total_hits))
+    }
+}
+
+/// Segment Collector associated with
+/// [`TopDocs::track_total_hits`](super::TopDocs::track_total_hits).
+pub struct TrackTotalHitsTopSegmentCollector {
+    segment_collector: TopSegmentCollector<Score>,
+    num_hits: u64,
+}
+
+impl SegmentCollector for TrackTotalHitsTopSegmentCollector {
+    type Fruit = (Vec<(Score, DocAddress)>, TotalHits);
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        self.segment_collector.collect(doc, score);
+        self.num_hits += 1;
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        (
+            self.segment_collector.harvest(),
+            TotalHits::exact(self.num_hits),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collector::{TopDocs, TotalHits, TotalHitsRelation};
+    use crate::query::TermQuery;
+    use crate::schema::{IndexRecordOption, Schema, TEXT};
+    use crate::{Index, IndexWriter, Term};
+
+    #[test]
+    fn test_track_total_hits() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for i in 0..1_000 {
+            let num_tokens = 1 + (i * 7) % 13;
+            index_writer.add_document(doc!(text => vec!["a"; num_tokens].join(" ")))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query = TermQuery::new(
+            Term::from_field_text(text, "a"),
+            IndexRecordOption::WithFreqs,
+        );
+        let expected_top_docs = searcher.search(&query, &TopDocs::with_limit(5))?;
+
+        let collector = TopDocs::with_limit(5).track_total_hits(10_000);
+        let (top_docs, total_hits) = searcher.search(&query, &collector)?;
+        assert_eq!(top_docs, expected_top_docs);
+        assert_eq!(total_hits, TotalHits::exact(1_000));
+
+        let collector = TopDocs::with_limit(5).track_total_hits(100);
+        let (top_docs, total_hits) = searcher.search(&query, &collector)?;
+        assert_eq!(top_docs, expected_top_docs);
+        assert_eq!(total_hits, TotalHits::lower_bound(100));
+        Ok(())
+    }
+
+    #[test]
+    fn test_track_total_hits_multiple_segments() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for _ in 0..4 {
+            for _ in 0..50 {
+                index_writer.add_document(doc!(text => "a"))?;
+            }
+            index_writer.commit()?;
+        }
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 4);
+        let query = TermQuery::new(Term::from_field_text(text, "a"), IndexRecordOption::Basic);
+
+        // No segment reaches the threshold, but the search does.
+        let (top_docs, total_hits) =
+            searcher.search(&query, &TopDocs::with_limit(5).track_total_hits(100))?;
+        assert_eq!(top_docs.len(), 5);
+        assert_eq!(total_hits, TotalHits::lower_bound(100));
+        assert_eq!(total_hits.relation, TotalHitsRelation::Gte);
+
+        let (_, total_hits) =
+            searcher.search(&query, &TopDocs::with_limit(5).track_total_hits(200))?;
+        assert_eq!(total_hits, TotalHits::exact(200));
+        Ok(())
+    }
+
+    #[test]
+    fn test_track_total_hits_with_deletes() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for i in 0..20 {
+            let content = if i % 2 == 0 { "a even" } else { "a odd" };
+            index_writer.add_document(doc!(text => content))?;
+        }
+        index_writer.commit()?;
+        index_writer.delete_term(Term::from_field_text(text, "odd"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query = TermQuery::new(Term::from_field_text(text, "a"), IndexRecordOption::Basic);
+        let (top_docs, total_hits) =
+            searcher.search(&query, &TopDocs::with_limit(3).track_total_hits(100))?;
+        assert_eq!(top_docs.len(), 3);
+        assert_eq!(total_hits, TotalHits::exact(10));
+        Ok(())
+    }
+}
@@ -0,0 +1,429 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::query::{Explanation, Scorer, Weight};
+use crate::{DocId, DocSet, Score, SegmentReader, TERMINATED};
+
+// Number of documents visited between two checks of the cancellation token.
+const CHECK_INTERVAL: u32 = 1_024;
+
+struct CancellationTokenInner {
+    cancelled: AtomicBool,
+    deadline: Option<Instant>,
+}
+
+/// Signals a search run with
+/// [`Searcher::search_with_cancellation`](crate::Searcher::search_with_cancellation)
+/// that it should stop.
+///
+/// A token is cancelled either explicitly by calling [`CancellationToken::cancel`],
+/// possibly from another thread, or once its deadline has passed.
+/// Clones of a token share its state.
+#[derive(Clone)]
+pub struct CancellationToken {
+    inner: Arc<CancellationTokenInner>,
+}
+
+impl CancellationToken {
+    /// Creates a token that is only cancelled by calling [`CancellationToken::cancel`].
+    pub fn new() -> CancellationToken {
+        CancellationToken::with_optional_deadline(None)
+    }
+
+    /// Creates a token that is cancelled once `timeout` has elapsed.
+    pub fn with_timeout(timeout: Duration) -> CancellationToken {
+        CancellationToken::with_deadline(Instant::now() + timeout)
+    }
+
+    /// Creates a token that is cancelled once `deadline` has passed.
+    pub fn with_deadline(deadline: Instant) -> CancellationToken {
+        CancellationToken::with_optional_deadline(Some(deadline))
+    }
+
+    fn with_optional_deadline(deadline: Option<Instant>) -> CancellationToken {
+        CancellationToken {
+            inner: Arc::new(CancellationTokenInner {
+                cancelled: AtomicBool::new(false),
+                deadline,
+            }),
+        }
+    }
+
+    /// Cancels the searches using this token.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true if the token was cancelled, or if its deadline has passed.
+    pub fn is_cancelled(&self) -> bool {
+        if self.inner.cancelled.load(Ordering::Relaxed) {
+            return true;
+        }
+        match self.inner.deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                self.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> CancellationToken {
+        CancellationToken::new()
+    }
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.inner.cancelled.load(Ordering::Relaxed))
+            .field("deadline", &self.inner.deadline)
+            .finish()
+    }
+}
+
+/// Result of [`Searcher::search_with_cancellation`](crate::Searcher::search_with_cancellation).
+#[derive(Clone, Debug)]
+pub struct SearchOutcome<TFruit> {
+    /// Fruit of the collector, computed from the documents collected before the search
+    /// was cancelled.
+    pub fruit: TFruit,
+    /// True if the search was cancelled before all of the matching documents were
+    /// collected. The fruit is then partial.
+    pub timed_out: bool,
+}
+
+/// Checks a [`CancellationToken`] every `CHECK_INTERVAL` calls.
+///
+/// This type is public so that it can appear in the signature of
+/// `Weight::for_each_pruning_until_cancelled`, but it is not exported.
+#[derive(Clone)]
+pub struct CancellationCheck {
+    token: CancellationToken,
+    timed_out: Arc<AtomicBool>,
+    num_calls: u32,
+}
+
+impl CancellationCheck {
+    pub(crate) fn is_cancelled(&mut self) -> bool {
+        self.num_calls += 1;
+        if self.num_calls < CHECK_INTERVAL {
+            return false;
+        }
+        self.num_calls = 0;
+        if self.token.is_cancelled() {
+            self.timed_out.store(true, Ordering::Relaxed);
+            return true;
+        }
+        false
+    }
+}
+
+/// Weight stopping the iteration over the matching documents once a
+/// [`CancellationToken`] is cancelled.
+pub(crate) struct CancellableWeight {
+    weight: Box<dyn Weight>,
+    check: CancellationCheck,
+}
+
+impl CancellableWeight {
+    pub(crate) fn new(weight: Box<dyn Weight>, token: CancellationToken) -> CancellableWeight {
+        CancellableWeight {
+            weight,
+            check: CancellationCheck {
+                token,
+                timed_out: Arc::new(AtomicBool::new(false)),
+                num_calls: 0,
+            },
+        }
+    }
+
+    /// Records that the collection of a segment was skipped.
+    pub(crate) fn skip_segment(&self) {
+        self.check.timed_out.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true if some matching documents were not collected.
+    pub(crate) fn timed_out(&self) -> bool {
+        self.check.timed_out.load(Ordering::Relaxed)
+    }
+}
+
+impl Weight for CancellableWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        let scorer = self.weight.scorer(reader, boost)?;
+        Ok(Box::new(CancellableScorer {
+            scorer,
+            check: self.check.clone(),
+            is_cancelled: false,
+        }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        self.weight.explain(reader, doc)
+    }
+
+    fn for_each_pruning(
+        &self,
+        threshold: Score,
+        reader: &SegmentReader,
+        callback: &mut dyn FnMut(DocId, Score) -> Score,
+    ) -> crate::Result<()> {
+        // Delegates to the inner weight to keep its optimizations (e.g. BlockWAND),
+        // while checking the token for every document or block visited.
+        let mut check = self.check.clone();
+        self.weight
+            .for_each_pruning_until_cancelled(threshold, reader, callback, &mut check)
+    }
+}
+
+struct CancellableScorer {
+    scorer: Box<dyn Scorer>,
+    check: CancellationCheck,
+    is_cancelled: bool,
+}
+
+impl CancellableScorer {
+    fn check(&mut self, doc: DocId) -> DocId {
+        if doc != TERMINATED && self.check.is_cancelled() {
+            self.is_cancelled = true;
+            return TERMINATED;
+        }
+        doc
+    }
+}
+
+impl DocSet for CancellableScorer {
+    fn advance(&mut self) -> DocId {
+        if self.is_cancelled {
+            return TERMINATED;
+        }
+        let doc = self.scorer.advance();
+        self.check(doc)
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.is_cancelled {
+            return TERMINATED;
+        }
+        let doc = self.scorer.seek(target);
+        self.check(doc)
+    }
+
+    fn doc(&self) -> DocId {
+        if self.is_cancelled {
+            return TERMINATED;
+        }
+        self.scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorer.size_hint()
+    }
+}
+
+impl Scorer for CancellableScorer {
+    fn score(&mut self) -> Score {
+        self.scorer.score()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use super::{CancellableWeight, CancellationToken};
+    use crate::collector::{Count, FilterCollector, TopDocs};
+    use crate::query::{
+        AllQuery, AllWeight, BooleanQuery, EnableScoring, Explanation, Query, Scorer, TermQuery,
+        Weight,
+    };
+    use crate::schema::{IndexRecordOption, Schema, FAST, TEXT};
+    use crate::{DocId, DocSet, Index, IndexWriter, Score, SegmentReader, Term};
+
+    /// Matches all documents, and cancels `token` once its scorer reaches `cancel_at`.
+    #[derive(Clone, Debug)]
+    struct CancellingQuery {
+        token: CancellationToken,
+        cancel_at: DocId,
+        last_doc: Arc<AtomicU32>,
+    }
+
+    impl Query for CancellingQuery {
+        fn weight(&self, _enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+            Ok(Box::new(self.clone()))
+        }
+    }
+
+    impl Weight for CancellingQuery {
+        fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+            Ok(Box::new(CancellingScorer {
+                scorer: AllWeight.scorer(reader, boost)?,
+                query: self.clone(),
+            }))
+        }
+
+        fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+            AllWeight.explain(reader, doc)
+        }
+    }
+
+    struct CancellingScorer {
+        scorer: Box<dyn Scorer>,
+        query: CancellingQuery,
+    }
+
+    impl DocSet for CancellingScorer {
+        fn advance(&mut self) -> DocId {
+            let doc = self.scorer.advance();
+            self.query.last_doc.store(doc, Ordering::Relaxed);
+            if doc == self.query.cancel_at {
+                self.query.token.cancel();
+            }
+            doc
+        }
+
+        fn doc(&self) -> DocId {
+            self.scorer.doc()
+        }
+
+        fn size_hint(&self) -> u32 {
+            self.scorer.size_hint()
+        }
+    }
+
+    impl Scorer for CancellingScorer {
+        fn score(&mut self) -> Score {
+            self.scorer.score()
+        }
+    }
+
+    #[test]
+    fn test_cancellation_token_deadline() {
+        let token = CancellationToken::with_deadline(Instant::now() + Duration::from_secs(3_600));
+        assert!(!token.is_cancelled());
+        let cloned_token = token.clone();
+        cloned_token.cancel();
+        assert!(token.is_cancelled());
+        assert!(CancellationToken::with_timeout(Duration::ZERO).is_cancelled());
+    }
+
+    #[test]
+    fn test_search_with_cancellation() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let num_field = schema_builder.add_u64_field("num", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for i in 0..10_000u64 {
+            index_writer.add_document(doc!(num_field => i))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let token = CancellationToken::new();
+        let outcome = searcher.search_with_cancellation(&AllQuery, &Count, &token)?;
+        assert_eq!(outcome.fruit, 10_000);
+        assert!(!outcome.timed_out);
+
+        token.cancel();
+        let outcome = searcher.search_with_cancellation(&AllQuery, &Count, &token)?;
+        assert_eq!(outcome.fruit, 0);
+        assert!(outcome.timed_out);
+
+        // Cancels the search while the segment is being collected.
+        let token = CancellationToken::new();
+        let cancelling_token = token.clone();
+        let collector = FilterCollector::new(
+            num_field,
+            move |num: u64| {
+                if num == 2_000 {
+                    cancelling_token.cancel();
+                }
+                true
+            },
+            (Count, TopDocs::with_limit(10)),
+        );
+        let outcome = searcher.search_with_cancellation(&AllQuery, &collector, &token)?;
+        assert!(outcome.timed_out);
+        let (count, top_docs) = outcome.fruit;
+        assert!(count > 2_000);
+        assert!(count < 10_000);
+        assert_eq!(top_docs.len(), 10);
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_with_cancellation_top_docs() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for _ in 0..10_000u64 {
+            index_writer.add_document(doc!(text_field => "a b"))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        // All of the documents have the same score, so that none of them exceeds the
+        // threshold once the top docs are full.
+        let token = CancellationToken::new();
+        let query = CancellingQuery {
+            token: token.clone(),
+            cancel_at: 2_000,
+            last_doc: Arc::new(AtomicU32::new(0)),
+        };
+        let outcome =
+            searcher.search_with_cancellation(&query, &TopDocs::with_limit(10), &token)?;
+        assert!(outcome.timed_out);
+        assert_eq!(outcome.fruit.len(), 10);
+        assert!(query.last_doc.load(Ordering::Relaxed) < 10_000);
+
+        // The BlockWAND path of term queries.
+        let term_query = TermQuery::new(
+            Term::from_field_text(text_field, "a"),
+            IndexRecordOption::WithFreqs,
+        );
+        let token = CancellationToken::new();
+        let outcome =
+            searcher.search_with_cancellation(&term_query, &TopDocs::with_limit(10), &token)?;
+        assert!(!outcome.timed_out);
+        assert_eq!(outcome.fruit.len(), 10);
+
+        // The BlockWAND path of term unions, cancelled while the segment is being collected.
+        let union_query = BooleanQuery::new_multiterms_query(vec![
+            Term::from_field_text(text_field, "a"),
+            Term::from_field_text(text_field, "b"),
+        ]);
+        let token = CancellationToken::new();
+        let weight = CancellableWeight::new(
+            union_query.weight(EnableScoring::enabled_from_searcher(&searcher))?,
+            token.clone(),
+        );
+        let mut collected_docs = Vec::new();
+        weight.for_each_pruning(
+            Score::MIN,
+            searcher.segment_reader(0),
+            &mut |doc, _score| {
+                if doc == 2_000 {
+                    token.cancel();
+                }
+                collected_docs.push(doc);
+                Score::MIN
+            },
+        )?;
+        assert!(weight.timed_out());
+        assert!(collected_docs.len() > 2_000);
+        assert!(collected_docs.len() < 10_000);
+        assert_eq!(
+            collected_docs,
+            (0..collected_docs.len() as DocId).collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+}
@@ -1,15 +1,14 @@
 use std::collections::HashSet;
 use std::fmt;
-#[cfg(feature = "mmap")]
-use std::path::Path;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use super::segment::Segment;
 use super::IndexSettings;
 use crate::core::single_segment_index_writer::SingleSegmentIndexWriter;
 use crate::core::{
-    Executor, IndexMeta, SegmentId, SegmentMeta, SegmentMetaInventory, META_FILEPATH,
+    commit_meta_filepath, CommitPoint, Executor, IndexMeta, IndexSnapshot, SegmentId, SegmentMeta,
+    SegmentMetaInventory, META_FILEPATH,
 };
 use crate::directory::error::OpenReadError;
 #[cfg(feature = "mmap")]
@@ -19,26 +18,27 @@ use crate::error::{DataCorruption, TantivyError};
 use crate::indexer::index_writer::{MAX_NUM_THREAD, MEMORY_ARENA_NUM_BYTES_MIN};
 use crate::indexer::segment_updater::save_metas;
 use crate::reader::{IndexReader, IndexReaderBuilder};
-use crate::schema::{Cardinality, Field, FieldType, Schema};
+use crate::schema::{Cardinality, Field, FieldType, Schema, TextFieldIndexing};
 use crate::tokenizer::{TextAnalyzer, TokenizerManager};
-use crate::IndexWriter;
+use crate::{IndexWriter, Opstamp};
 
 fn load_metas(
     directory: &dyn Directory,
+    meta_filepath: &Path,
     inventory: &SegmentMetaInventory,
 ) -> crate::Result<IndexMeta> {
-    let meta_data = directory.atomic_read(&META_FILEPATH)?;
+    let meta_data = directory.atomic_read(meta_filepath)?;
     let meta_string = String::from_utf8(meta_data).map_err(|_utf8_err| {
         error!("Meta data is not valid utf8.");
         DataCorruption::new(
-            META_FILEPATH.to_path_buf(),
+            meta_filepath.to_path_buf(),
             "Meta file does not contain valid utf8 file.".to_string(),
         )
     })?;
     IndexMeta::deserialize(&meta_string, inventory)
         .map_err(|e| {
             DataCorruption::new(
-                META_FILEPATH.to_path_buf(),
+                meta_filepath.to_path_buf(),
                 format!(
                     "Meta file cannot be deserialized. {:?}. Content: {:?}",
                     e, meta_string
@@ -69,6 +69,7 @@ fn save_new_metas(
             schema,
             opstamp: 0u64,
             payload: None,
+            retained_commits: Vec::new(),
         },
         directory,
     )?;
@@ -98,10 +99,10 @@ fn save_new_metas(
 ///
 /// let schema = schema_builder.build();
 /// let settings = IndexSettings{
-///     sort_by_field: Some(IndexSortByField{
+///     sort_by: vec![IndexSortByField {
 ///         field: "number".to_string(),
 ///         order: Order::Asc
-///     }),
+///     }],
 ///     ..Default::default()
 /// };
 /// let index = Index::builder().schema(schema).settings(settings).create_in_ram();
@@ -230,7 +231,7 @@ impl IndexBuilder {
 
     fn validate(&self) -> crate::Result<()> {
         if let Some(schema) = self.schema.as_ref() {
-            if let Some(sort_by_field) = self.index_settings.sort_by_field.as_ref() {
+            for sort_by_field in &self.index_settings.sort_by {
                 let schema_field = schema.get_field(&sort_by_field.field).map_err(|_| {
                     TantivyError::InvalidArgument(format!(
                         "Field to sort index {} not found in schema",
@@ -245,11 +246,40 @@ impl IndexBuilder {
                         sort_by_field.field
                     )));
                 }
-                if entry.field_type().fastfield_cardinality() != Some(Cardinality::SingleValue) {
-                    return Err(TantivyError::InvalidArgument(format!(
-                        "Only single value fast field Cardinality supported for sorting index {}",
-                        sort_by_field.field
-                    )));
+                match entry.field_type() {
+                    FieldType::Str(text_options) => {
+                        if text_options.get_indexing_options().is_none() {
+                            return Err(TantivyError::InvalidArgument(format!(
+                                "String field {} needs to be indexed to be used to sort an index",
+                                sort_by_field.field
+                            )));
+                        }
+                    }
+                    FieldType::U64(_)
+                    | FieldType::I64(_)
+                    | FieldType::F64(_)
+                    | FieldType::Bool(_)
+                    | FieldType::Date(_)
+                    | FieldType::IpAddr(_) => {
+                        if entry.field_type().fastfield_cardinality()
+                            != Some(Cardinality::SingleValue)
+                        {
+                            return Err(TantivyError::InvalidArgument(format!(
+                                "Only single value fast field Cardinality supported for sorting \
+                                 index {}",
+                                sort_by_field.field
+                            )));
+                        }
+                    }
+                    FieldType::Bytes(_)
+                    | FieldType::Facet(_)
+                    | FieldType::JsonObject(_)
+                    | FieldType::Vector(_) => {
+                        return Err(TantivyError::InvalidArgument(format!(
+                            "Field {} has a type that cannot be used to sort an index",
+                            sort_by_field.field
+                        )));
+                    }
                 }
             }
             Ok(())
@@ -289,6 +319,8 @@ pub struct Index {
     executor: Arc<Executor>,
     tokenizers: TokenizerManager,
     inventory: SegmentMetaInventory,
+    // Opstamp of the commit the index was opened at, if it is not the last commit.
+    commit_opstamp: Option<Opstamp>,
 }
 
 impl Index {
@@ -402,6 +434,7 @@ impl Index {
             tokenizers: TokenizerManager::default(),
             executor: Arc::new(Executor::single_thread()),
             inventory,
+            commit_opstamp: None,
         }
     }
 
@@ -417,9 +450,23 @@ impl Index {
 
     /// Get the tokenizer associated with a specific field.
     pub fn tokenizer_for_field(&self, field: Field) -> crate::Result<TextAnalyzer> {
+        let indexing_options = self.text_indexing_options(field)?;
+        self.get_tokenizer(field, indexing_options.tokenizer())
+    }
+
+    /// Get the tokenizer used to process the queries targeting a specific field.
+    ///
+    /// This is the field's search tokenizer if one was set, and
+    /// its indexing tokenizer otherwise.
+    /// See [`TextFieldIndexing::set_search_tokenizer()`](crate::schema::TextFieldIndexing::set_search_tokenizer).
+    pub fn search_tokenizer_for_field(&self, field: Field) -> crate::Result<TextAnalyzer> {
+        let indexing_options = self.text_indexing_options(field)?;
+        self.get_tokenizer(field, indexing_options.search_tokenizer())
+    }
+
+    fn text_indexing_options(&self, field: Field) -> crate::Result<&TextFieldIndexing> {
         let field_entry = self.schema.get_field_entry(field);
         let field_type = field_entry.field_type();
-        let tokenizer_manager: &TokenizerManager = self.tokenizers();
         let indexing_options_opt = match field_type {
             FieldType::JsonObject(options) => options.get_text_indexing_options(),
             FieldType::Str(options) => options.get_indexing_options(),
@@ -430,21 +477,21 @@ impl Index {
                 )))
             }
         };
-        let indexing_options = indexing_options_opt.ok_or_else(|| {
+        indexing_options_opt.ok_or_else(|| {
             TantivyError::InvalidArgument(format!(
                 "No indexing options set for field {:?}",
                 field_entry
             ))
-        })?;
+        })
+    }
 
-        tokenizer_manager
-            .get(indexing_options.tokenizer())
-            .ok_or_else(|| {
-                TantivyError::InvalidArgument(format!(
-                    "No Tokenizer found for field {:?}",
-                    field_entry
-                ))
-            })
+    fn get_tokenizer(&self, field: Field, tokenizer_name: &str) -> crate::Result<TextAnalyzer> {
+        self.tokenizers().get(tokenizer_name).ok_or_else(|| {
+            TantivyError::InvalidArgument(format!(
+                "No Tokenizer found for field {:?}",
+                self.schema.get_field_entry(field)
+            ))
+        })
     }
 
     /// Create a default [`IndexReader`] for the given index.
@@ -492,14 +539,86 @@ impl Index {
         let directory = directory.into();
         let directory = ManagedDirectory::wrap(directory)?;
         let inventory = SegmentMetaInventory::default();
-        let metas = load_metas(&directory, &inventory)?;
+        let metas = load_metas(&directory, &META_FILEPATH, &inventory)?;
         let index = Index::open_from_metas(directory, &metas, inventory);
         Ok(index)
     }
 
     /// Reads the index meta file from the directory.
+    ///
+    /// If the index was opened at a past commit, the meta file of this commit is read.
     pub fn load_metas(&self) -> crate::Result<IndexMeta> {
-        load_metas(self.directory(), &self.inventory)
+        match self.commit_opstamp {
+            Some(commit_opstamp) => self.load_commit_metas(commit_opstamp),
+            None => load_metas(self.directory(), &META_FILEPATH, &self.inventory),
+        }
+    }
+
+    /// Reads the meta file of a retained commit point.
+    pub(crate) fn load_commit_metas(&self, opstamp: Opstamp) -> crate::Result<IndexMeta> {
+        load_metas(
+            self.directory(),
+            &commit_meta_filepath(opstamp),
+            &self.inventory,
+        )
+    }
+
+    /// Creates a snapshot of the last commit of the index.
+    ///
+    /// The segment files of the snapshot are protected from garbage collection by the
+    /// index writers created from this `Index`, until the snapshot is dropped.
+    /// See [`IndexSnapshot`].
+    pub fn create_snapshot(&self) -> crate::Result<IndexSnapshot> {
+        IndexSnapshot::create(self)
+    }
+
+    /// Lists the commit points of the index that can be opened with
+    /// [`Index::open_at_commit`], from the oldest to the last commit.
+    ///
+    /// Commit points are only retained if
+    /// [`IndexSettings::num_retained_commits`] is greater than 0.
+    /// Otherwise, no commit point is returned.
+    pub fn list_commits(&self) -> crate::Result<Vec<CommitPoint>> {
+        let metas = load_metas(self.directory(), &META_FILEPATH, &self.inventory)?;
+        if metas.index_settings.num_retained_commits == 0 {
+            return Ok(Vec::new());
+        }
+        let mut commits = metas.retained_commits.clone();
+        commits.push(metas.commit_point());
+        Ok(commits)
+    }
+
+    /// Opens the index as it was at the commit with the given opstamp.
+    ///
+    /// The commit needs to be one of the commit points returned by [`Index::list_commits`].
+    /// The returned index is read-only: its readers search the segments of that
+    /// commit, and creating a writer returns an error.
+    ///
+    /// The files of the commit are protected from garbage collection for as long as the
+    /// commit is retained by the index writer.
+    pub fn open_at_commit(&self, opstamp: Opstamp) -> crate::Result<Index> {
+        let is_retained = self
+            .list_commits()?
+            .iter()
+            .any(|commit_point| commit_point.opstamp == opstamp);
+        if !is_retained {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Commit {opstamp} is not retained by the index."
+            )));
+        }
+        let metas = self.load_commit_metas(opstamp)?;
+        let mut index =
+            Index::open_from_metas(self.directory.clone(), &metas, self.inventory.clone());
+        index.tokenizers = self.tokenizers.clone();
+        index.executor = self.executor.clone();
+        index.commit_opstamp = Some(opstamp);
+        Ok(index)
+    }
+
+    /// Returns the opstamp of the commit the index was opened at with
+    /// [`Index::open_at_commit`], or `None` if the index follows the last commit.
+    pub fn commit_opstamp(&self) -> Option<Opstamp> {
+        self.commit_opstamp
     }
 
     /// Open a new index writer. Attempts to acquire a lockfile.
@@ -526,6 +645,11 @@ impl Index {
         num_threads: usize,
         overall_memory_arena_in_bytes: usize,
     ) -> crate::Result<IndexWriter> {
+        if let Some(commit_opstamp) = self.commit_opstamp {
+            return Err(TantivyError::InvalidArgument(format!(
+                "The index was opened at commit {commit_opstamp}, and cannot be written to."
+            )));
+        }
         let directory_lock = self
             .directory
             .acquire_lock(&INDEX_WRITER_LOCK)
@@ -672,11 +796,18 @@ impl fmt::Debug for Index {
 #[cfg(test)]
 mod tests {
     use crate::collector::Count;
+    use crate::core::commit_meta_filepath;
     use crate::directory::{RamDirectory, WatchCallback};
+    use crate::indexer::NoMergePolicy;
     use crate::query::TermQuery;
-    use crate::schema::{Field, IndexRecordOption, Schema, INDEXED, TEXT};
+    use crate::schema::{
+        Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, INDEXED, TEXT,
+    };
     use crate::tokenizer::TokenizerManager;
-    use crate::{Directory, Index, IndexBuilder, IndexReader, IndexSettings, ReloadPolicy, Term};
+    use crate::{
+        Directory, Index, IndexBuilder, IndexReader, IndexSettings, ReloadPolicy, TantivyError,
+        Term,
+    };
 
     #[test]
     fn test_indexer_for_field() {
@@ -686,12 +817,28 @@ mod tests {
         let schema = schema_builder.build();
         let index = Index::create_in_ram(schema);
         assert!(index.tokenizer_for_field(body_field).is_ok());
+        assert!(index.search_tokenizer_for_field(body_field).is_ok());
         assert_eq!(
             format!("{:?}", index.tokenizer_for_field(num_likes_field).err()),
             "Some(SchemaError(\"\\\"num_likes\\\" is not a text field.\"))"
         );
     }
 
+    #[test]
+    fn test_search_tokenizer_for_field() {
+        let mut schema_builder = Schema::builder();
+        let text_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer("raw")
+                .set_search_tokenizer("unknown"),
+        );
+        let body_field = schema_builder.add_text_field("body", text_options);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        assert!(index.tokenizer_for_field(body_field).is_ok());
+        assert!(index.search_tokenizer_for_field(body_field).is_err());
+    }
+
     #[test]
     fn test_set_tokenizer_manager() {
         let mut schema_builder = Schema::builder();
@@ -966,4 +1113,112 @@ mod tests {
         assert_eq!(count, 10);
         Ok(())
     }
+
+    #[test]
+    fn test_open_at_commit() -> crate::Result<()> {
+        let directory = RamDirectory::create();
+        let schema = throw_away_schema();
+        let field = schema.get_field("num_likes").unwrap();
+        let settings = IndexSettings {
+            num_retained_commits: 2,
+            ..IndexSettings::default()
+        };
+        let index = Index::create(directory.clone(), schema, settings)?;
+        let mut writer = index.writer_for_tests()?;
+        writer.set_merge_policy(Box::new(NoMergePolicy));
+        let mut opstamps = Vec::new();
+        for i in 0u64..3u64 {
+            writer.add_document(doc!(field => i))?;
+            let mut prepared_commit = writer.prepare_commit()?;
+            prepared_commit.set_payload(&format!("commit {i}"));
+            opstamps.push(prepared_commit.commit()?);
+        }
+        let commits = index.list_commits()?;
+        assert_eq!(
+            commits
+                .iter()
+                .map(|commit_point| commit_point.opstamp)
+                .collect::<Vec<_>>(),
+            opstamps
+        );
+        assert_eq!(commits[0].payload.as_deref(), Some("commit 0"));
+
+        // The segments of the retained commits are protected from garbage collection.
+        writer.force_merge(1)?;
+        writer.garbage_collect_files().wait()?;
+        for (num_docs, &opstamp) in opstamps.iter().enumerate() {
+            let index_at_commit = index.open_at_commit(opstamp)?;
+            assert_eq!(index_at_commit.commit_opstamp(), Some(opstamp));
+            let searcher = index_at_commit.reader()?.searcher();
+            assert_eq!(searcher.num_docs(), num_docs as u64 + 1);
+            assert!(matches!(
+                index_at_commit.writer_for_tests(),
+                Err(TantivyError::InvalidArgument(_))
+            ));
+        }
+
+        // Once it is no longer retained, a commit can't be opened anymore.
+        writer.add_document(doc!(field => 3u64))?;
+        writer.commit()?;
+        writer.garbage_collect_files().wait()?;
+        assert_eq!(index.list_commits()?.len(), 3);
+        assert!(matches!(
+            index.open_at_commit(opstamps[0]),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        assert!(!directory.exists(&commit_meta_filepath(opstamps[0]))?);
+        assert_eq!(
+            index
+                .open_at_commit(opstamps[1])?
+                .reader()?
+                .searcher()
+                .num_docs(),
+            2
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_gc_is_skipped_if_retained_commit_cannot_be_loaded() -> crate::Result<()> {
+        let directory = RamDirectory::create();
+        let schema = throw_away_schema();
+        let field = schema.get_field("num_likes").unwrap();
+        let settings = IndexSettings {
+            num_retained_commits: 2,
+            ..IndexSettings::default()
+        };
+        let index = Index::create(directory.clone(), schema, settings)?;
+        let mut writer = index.writer_for_tests()?;
+        writer.set_merge_policy(Box::new(NoMergePolicy));
+        let mut opstamps = Vec::new();
+        for i in 0u64..2u64 {
+            writer.add_document(doc!(field => i))?;
+            opstamps.push(writer.commit()?);
+        }
+        let commit_meta_path = commit_meta_filepath(opstamps[0]);
+        let commit_meta_data = directory.atomic_read(&commit_meta_path)?;
+        directory.atomic_write(&commit_meta_path, b"corrupted")?;
+
+        // The files of the first commit must not be deleted, even though its metas are
+        // unreadable.
+        writer.force_merge(1)?;
+        assert!(writer.garbage_collect_files().wait().is_err());
+        directory.atomic_write(&commit_meta_path, &commit_meta_data)?;
+        let searcher = index.open_at_commit(opstamps[0])?.reader()?.searcher();
+        assert_eq!(searcher.num_docs(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_at_commit_without_retained_commits() -> crate::Result<()> {
+        let index = Index::create_in_ram(throw_away_schema());
+        let mut writer = index.writer_for_tests()?;
+        let opstamp = writer.commit()?;
+        assert!(index.list_commits()?.is_empty());
+        assert!(matches!(
+            index.open_at_commit(opstamp),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
 }
@@ -4,18 +4,21 @@ use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 use super::SegmentComponent;
-use crate::core::SegmentId;
+use crate::core::{SegmentId, SoftDelete};
 use crate::schema::Schema;
 use crate::store::Compressor;
-use crate::{Inventory, Opstamp, TrackedObject};
+use crate::{DateTime, Inventory, Opstamp, TrackedObject};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct DeleteMeta {
     num_deleted_docs: u32,
     opstamp: Opstamp,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_zero")]
+    num_soft_deleted_docs: u32,
 }
 
 #[derive(Clone, Default)]
@@ -39,6 +42,7 @@ impl SegmentMetaInventory {
             max_doc,
             include_temp_doc_store: Arc::new(AtomicBool::new(true)),
             deletes: None,
+            num_bytes: None,
         };
         SegmentMeta::from(self.inventory.track(inner))
     }
@@ -103,6 +107,24 @@ impl SegmentMeta {
             .unwrap_or(0u32)
     }
 
+    /// Returns the number of soft-deleted documents, that is deleted documents
+    /// retained in the segment.
+    ///
+    /// Soft-deleted documents are also counted in `num_deleted_docs`.
+    pub fn num_soft_deleted_docs(&self) -> u32 {
+        self.tracked
+            .deletes
+            .as_ref()
+            .map(|delete_meta| delete_meta.num_soft_deleted_docs)
+            .unwrap_or(0u32)
+    }
+
+    /// Returns the number of deleted documents that a merge would reclaim,
+    /// that is the deleted documents that are not soft-deleted.
+    pub(crate) fn num_reclaimable_deleted_docs(&self) -> u32 {
+        self.num_deleted_docs() - self.num_soft_deleted_docs()
+    }
+
     /// Returns the list of files that
     /// are required for the segment meta.
     /// Note: Some of the returned files may not exist depending on the state of the segment.
@@ -142,6 +164,10 @@ impl SegmentMeta {
             SegmentComponent::FastFields => ".fast".to_string(),
             SegmentComponent::FieldNorms => ".fieldnorm".to_string(),
             SegmentComponent::Delete => format!(".{}.del", self.delete_opstamp().unwrap_or(0)),
+            SegmentComponent::SoftDeletes => {
+                format!(".{}.sdel", self.delete_opstamp().unwrap_or(0))
+            }
+            SegmentComponent::Vectors => ".vec".to_string(),
         });
         PathBuf::from(path)
     }
@@ -169,6 +195,15 @@ impl SegmentMeta {
             .map(|delete_meta| delete_meta.opstamp)
     }
 
+    /// Returns the number of bytes taken by the segment files on disk,
+    /// excluding its delete file.
+    ///
+    /// Returns `None` for segments created by a version of tantivy
+    /// that did not record this information.
+    pub fn num_bytes(&self) -> Option<u64> {
+        self.tracked.num_bytes
+    }
+
     /// Returns true iff the segment meta contains
     /// delete information.
     pub fn has_deletes(&self) -> bool {
@@ -187,6 +222,19 @@ impl SegmentMeta {
             max_doc,
             deletes: None,
             include_temp_doc_store: Arc::new(AtomicBool::new(true)),
+            num_bytes: None,
+        });
+        SegmentMeta { tracked }
+    }
+
+    /// Records the number of bytes taken by the segment files on disk.
+    pub(crate) fn with_num_bytes(self, num_bytes: u64) -> SegmentMeta {
+        let tracked = self.tracked.map(move |inner_meta| InnerSegmentMeta {
+            segment_id: inner_meta.segment_id,
+            max_doc: inner_meta.max_doc,
+            deletes: inner_meta.deletes.clone(),
+            include_temp_doc_store: Arc::new(AtomicBool::new(true)),
+            num_bytes: Some(num_bytes),
         });
         SegmentMeta { tracked }
     }
@@ -201,12 +249,35 @@ impl SegmentMeta {
         let delete_meta = DeleteMeta {
             num_deleted_docs,
             opstamp,
+            num_soft_deleted_docs: 0,
         };
         let tracked = self.tracked.map(move |inner_meta| InnerSegmentMeta {
             segment_id: inner_meta.segment_id,
             max_doc: inner_meta.max_doc,
             include_temp_doc_store: Arc::new(AtomicBool::new(true)),
             deletes: Some(delete_meta),
+            num_bytes: inner_meta.num_bytes,
+        });
+        SegmentMeta { tracked }
+    }
+
+    /// Updates the number of soft-deleted documents, among the deleted documents
+    /// of the `SegmentMeta`.
+    #[must_use]
+    pub(crate) fn with_num_soft_deleted_docs(self, num_soft_deleted_docs: u32) -> SegmentMeta {
+        assert!(
+            num_soft_deleted_docs <= self.num_deleted_docs(),
+            "There cannot be more soft-deleted docs than there are deleted docs."
+        );
+        let tracked = self.tracked.map(move |inner_meta| InnerSegmentMeta {
+            segment_id: inner_meta.segment_id,
+            max_doc: inner_meta.max_doc,
+            include_temp_doc_store: Arc::new(AtomicBool::new(true)),
+            deletes: inner_meta.deletes.as_ref().map(|delete_meta| DeleteMeta {
+                num_soft_deleted_docs,
+                ..delete_meta.clone()
+            }),
+            num_bytes: inner_meta.num_bytes,
         });
         SegmentMeta { tracked }
     }
@@ -222,6 +293,9 @@ struct InnerSegmentMeta {
     #[serde(skip)]
     #[serde(default = "default_temp_store")]
     pub(crate) include_temp_doc_store: Arc<AtomicBool>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_bytes: Option<u64>,
 }
 fn default_temp_store() -> Arc<AtomicBool> {
     Arc::new(AtomicBool::new(false))
@@ -243,6 +317,18 @@ fn is_true(val: &bool) -> bool {
     *val
 }
 
+fn is_false(val: &bool) -> bool {
+    !*val
+}
+
+fn is_zero(val: &u32) -> bool {
+    *val == 0
+}
+
+fn is_zero_usize(val: &usize) -> bool {
+    *val == 0
+}
+
 /// Search Index Settings.
 ///
 /// Contains settings which are applied on the whole
@@ -250,9 +336,16 @@ fn is_true(val: &bool) -> bool {
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub struct IndexSettings {
     /// Sorts the documents by information
-    /// provided in `IndexSortByField`
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub sort_by_field: Option<IndexSortByField>,
+    /// provided in `IndexSortByField`.
+    ///
+    /// Documents are sorted by the first key, then by the second key for the documents
+    /// with the same value for the first key, and so on.
+    /// The single key of the former `sort_by_field` setting is still accepted
+    /// when deserializing.
+    #[serde(default)]
+    #[serde(alias = "sort_by_field", deserialize_with = "deserialize_sort_by")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sort_by: Vec<IndexSortByField>,
     /// The `Compressor` used to compress the doc store.
     #[serde(default)]
     pub docstore_compression: Compressor,
@@ -265,6 +358,56 @@ pub struct IndexSettings {
     #[serde(default = "default_docstore_blocksize")]
     /// The size of each block that will be compressed and written to disk
     pub docstore_blocksize: usize,
+    /// Enables soft deletes: deleted documents are hidden from searches,
+    /// but are retained in the index, through merges, for the given duration.
+    ///
+    /// See [`SoftDeletesRetention`].
+    ///
+    /// Soft deletes cannot be enabled along with the write-ahead log.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub soft_deletes_retention: Option<SoftDeletesRetention>,
+    /// Number of commit points retained in addition to the last commit.
+    ///
+    /// The files of the retained commits are protected from garbage collection,
+    /// so that the index can be opened as it was at any of these commits.
+    /// See [`Index::list_commits`](crate::Index::list_commits) and
+    /// [`Index::open_at_commit`](crate::Index::open_at_commit).
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_zero_usize")]
+    pub num_retained_commits: usize,
+    /// Enables the write-ahead log of the index writer.
+    ///
+    /// The operations that have not been committed yet are appended to a log in
+    /// the index directory, and are replayed when the next index writer is created.
+    /// Each operation is synced to durable storage before it is acknowledged, so that
+    /// it is not lost if the process or the machine crashes before the commit.
+    /// As syncing is expensive, operations are best grouped with
+    /// [`IndexWriter::run`](crate::IndexWriter::run), which syncs them at once.
+    /// See [`IndexWriter`](crate::IndexWriter) for the operations that can be logged.
+    ///
+    /// Deletes by query cannot be logged, so they are rejected while the write-ahead
+    /// log is enabled. For the same reason, the write-ahead log cannot be enabled along
+    /// with soft deletes: creating an [`IndexWriter`](crate::IndexWriter) then fails.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub write_ahead_log: bool,
+}
+
+// Accepts a list of keys, or the single key of the former `sort_by_field` setting.
+fn deserialize_sort_by<'de, D>(deserializer: D) -> Result<Vec<IndexSortByField>, D::Error>
+where D: Deserializer<'de> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum SortBy {
+        Keys(Vec<IndexSortByField>),
+        Key(IndexSortByField),
+    }
+    Ok(match Option::<SortBy>::deserialize(deserializer)? {
+        Some(SortBy::Keys(sort_by)) => sort_by,
+        Some(SortBy::Key(sort_by_field)) => vec![sort_by_field],
+        None => Vec::new(),
+    })
 }
 
 /// Must be a function to be compatible with serde defaults
@@ -275,10 +418,55 @@ fn default_docstore_blocksize() -> usize {
 impl Default for IndexSettings {
     fn default() -> Self {
         Self {
-            sort_by_field: None,
+            sort_by: Vec::new(),
             docstore_compression: Compressor::default(),
             docstore_blocksize: default_docstore_blocksize(),
             docstore_compress_dedicated_thread: true,
+            soft_deletes_retention: None,
+            num_retained_commits: 0,
+            write_ahead_log: false,
+        }
+    }
+}
+
+/// Defines for how long soft-deleted documents are retained in the index.
+///
+/// While they are retained, soft-deleted documents are not visible to searches,
+/// but they can be read through
+/// [`SegmentReader::soft_deletes`](crate::SegmentReader::soft_deletes), and
+/// restored with [`IndexWriter::undelete`](crate::IndexWriter::undelete).
+///
+/// Retention is checked when segments are merged: the soft-deleted documents
+/// that are no longer retained are then removed for good.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SoftDeletesRetention {
+    /// Documents are retained as long as they have been deleted by one of the last
+    /// `n` operations, that is as long as the opstamp of the delete operation is
+    /// greater than the opstamp of the last commit minus `n`.
+    Opstamps(u64),
+    /// Documents are retained for the given number of seconds after their deletion.
+    Seconds(u64),
+}
+
+impl SoftDeletesRetention {
+    /// Returns true if a document deleted by `soft_delete` should be retained,
+    /// given the opstamp of the last commit and the current time.
+    pub(crate) fn retains(
+        &self,
+        soft_delete: &SoftDelete,
+        commit_opstamp: Opstamp,
+        now: DateTime,
+    ) -> bool {
+        match *self {
+            SoftDeletesRetention::Opstamps(num_opstamps) => {
+                soft_delete.opstamp > commit_opstamp.saturating_sub(num_opstamps)
+            }
+            SoftDeletesRetention::Seconds(num_seconds) => {
+                let elapsed_seconds =
+                    now.into_timestamp_secs() - soft_delete.timestamp.into_timestamp_secs();
+                elapsed_seconds < num_seconds as i64
+            }
         }
     }
 }
@@ -288,6 +476,11 @@ impl Default for IndexSettings {
 /// Presorting documents can greatly improve performance
 /// in some scenarios, by applying top n
 /// optimizations.
+///
+/// The field has to be a single value fast field (`u64`, `i64`, `f64`, date,
+/// `bool` or ip address), or a fast string field. String fields are sorted
+/// by their smallest term in lexicographic order, and documents without
+/// any value for them come last, whatever the order.
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub struct IndexSortByField {
     /// The field to sort the documents by
@@ -339,6 +532,22 @@ pub struct IndexMeta {
     /// This payload is entirely unused by tantivy.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub payload: Option<String>,
+    /// Commit points retained before the last commit, from the oldest to the most recent.
+    ///
+    /// See [`IndexSettings::num_retained_commits`].
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub retained_commits: Vec<CommitPoint>,
+}
+
+/// A commit point of the index.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct CommitPoint {
+    /// Opstamp of the commit.
+    pub opstamp: Opstamp,
+    /// Payload associated with the commit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -350,6 +559,8 @@ struct UntrackedIndexMeta {
     pub opstamp: Opstamp,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub payload: Option<String>,
+    #[serde(default)]
+    pub retained_commits: Vec<CommitPoint>,
 }
 
 impl UntrackedIndexMeta {
@@ -364,6 +575,7 @@ impl UntrackedIndexMeta {
             schema: self.schema,
             opstamp: self.opstamp,
             payload: self.payload,
+            retained_commits: self.retained_commits,
         }
     }
 }
@@ -381,6 +593,15 @@ impl IndexMeta {
             schema,
             opstamp: 0u64,
             payload: None,
+            retained_commits: Vec::new(),
+        }
+    }
+
+    /// Returns the commit point of the last commit.
+    pub fn commit_point(&self) -> CommitPoint {
+        CommitPoint {
+            opstamp: self.opstamp,
+            payload: self.payload.clone(),
         }
     }
 
@@ -407,11 +628,15 @@ impl fmt::Debug for IndexMeta {
 #[cfg(test)]
 mod tests {
 
-    use super::IndexMeta;
+    use std::path::PathBuf;
+
+    use super::{IndexMeta, InnerSegmentMeta, SegmentMetaInventory, SoftDeletesRetention};
     use crate::core::index_meta::UntrackedIndexMeta;
     use crate::schema::{Schema, TEXT};
     use crate::store::{Compressor, ZstdCompressor};
-    use crate::{IndexSettings, IndexSortByField, Order};
+    use crate::{
+        DateTime, IndexSettings, IndexSortByField, Order, SegmentComponent, SegmentId, SoftDelete,
+    };
 
     #[test]
     fn test_serialize_metas() {
@@ -422,21 +647,22 @@ mod tests {
         };
         let index_metas = IndexMeta {
             index_settings: IndexSettings {
-                sort_by_field: Some(IndexSortByField {
+                sort_by: vec![IndexSortByField {
                     field: "text".to_string(),
                     order: Order::Asc,
-                }),
+                }],
                 ..Default::default()
             },
             segments: Vec::new(),
             schema,
             opstamp: 0u64,
             payload: None,
+            retained_commits: Vec::new(),
         };
         let json = serde_json::ser::to_string(&index_metas).expect("serialization failed");
         assert_eq!(
             json,
-            r#"{"index_settings":{"sort_by_field":{"field":"text","order":"Asc"},"docstore_compression":"lz4","docstore_blocksize":16384},"segments":[],"schema":[{"name":"text","type":"text","options":{"indexing":{"record":"position","fieldnorms":true,"tokenizer":"default"},"stored":false,"fast":false}}],"opstamp":0}"#
+            r#"{"index_settings":{"sort_by":[{"field":"text","order":"Asc"}],"docstore_compression":"lz4","docstore_blocksize":16384},"segments":[],"schema":[{"name":"text","type":"text","options":{"indexing":{"record":"position","fieldnorms":true,"tokenizer":"default"},"stored":false,"fast":false}}],"opstamp":0}"#
         );
 
         let deser_meta: UntrackedIndexMeta = serde_json::from_str(&json).unwrap();
@@ -454,25 +680,29 @@ mod tests {
         };
         let index_metas = IndexMeta {
             index_settings: IndexSettings {
-                sort_by_field: Some(IndexSortByField {
+                sort_by: vec![IndexSortByField {
                     field: "text".to_string(),
                     order: Order::Asc,
-                }),
+                }],
                 docstore_compression: crate::store::Compressor::Zstd(ZstdCompressor {
                     compression_level: Some(4),
                 }),
                 docstore_blocksize: 1_000_000,
                 docstore_compress_dedicated_thread: true,
+                soft_deletes_retention: None,
+                num_retained_commits: 0,
+                write_ahead_log: false,
             },
             segments: Vec::new(),
             schema,
             opstamp: 0u64,
             payload: None,
+            retained_commits: Vec::new(),
         };
         let json = serde_json::ser::to_string(&index_metas).expect("serialization failed");
         assert_eq!(
             json,
-            r#"{"index_settings":{"sort_by_field":{"field":"text","order":"Asc"},"docstore_compression":"zstd(compression_level=4)","docstore_blocksize":1000000},"segments":[],"schema":[{"name":"text","type":"text","options":{"indexing":{"record":"position","fieldnorms":true,"tokenizer":"default"},"stored":false,"fast":false}}],"opstamp":0}"#
+            r#"{"index_settings":{"sort_by":[{"field":"text","order":"Asc"}],"docstore_compression":"zstd(compression_level=4)","docstore_blocksize":1000000},"segments":[],"schema":[{"name":"text","type":"text","options":{"indexing":{"record":"position","fieldnorms":true,"tokenizer":"default"},"stored":false,"fast":false}}],"opstamp":0}"#
         );
 
         let deser_meta: UntrackedIndexMeta = serde_json::from_str(&json).unwrap();
@@ -481,6 +711,39 @@ mod tests {
         assert_eq!(index_metas.opstamp, deser_meta.opstamp);
     }
 
+    #[test]
+    fn test_deserialize_sort_by_field() {
+        let index_settings: IndexSettings = serde_json::from_str(
+            r#"{"sort_by_field":{"field":"text","order":"Desc"},"docstore_compression":"lz4","docstore_blocksize":16384}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            index_settings.sort_by,
+            vec![IndexSortByField {
+                field: "text".to_string(),
+                order: Order::Desc,
+            }]
+        );
+
+        let index_settings: IndexSettings = serde_json::from_str(
+            r#"{"sort_by":[{"field":"text","order":"Desc"},{"field":"num","order":"Asc"}],"docstore_compression":"lz4","docstore_blocksize":16384}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            index_settings.sort_by,
+            vec![
+                IndexSortByField {
+                    field: "text".to_string(),
+                    order: Order::Desc,
+                },
+                IndexSortByField {
+                    field: "num".to_string(),
+                    order: Order::Asc,
+                },
+            ]
+        );
+    }
+
     #[test]
     fn test_serialize_metas_invalid_comp() {
         let json = r#"{"index_settings":{"sort_by_field":{"field":"text","order":"Asc"},"docstore_compression":"zsstd","docstore_blocksize":1000000},"segments":[],"schema":[{"name":"text","type":"text","options":{"indexing":{"record":"position","fieldnorms":true,"tokenizer":"default"},"stored":false,"fast":false}}],"opstamp":0}"#;
@@ -509,10 +772,13 @@ mod tests {
         assert_eq!(
             index_settings,
             IndexSettings {
-                sort_by_field: None,
+                sort_by: Vec::new(),
                 docstore_compression: Compressor::default(),
                 docstore_compress_dedicated_thread: true,
-                docstore_blocksize: 16_384
+                docstore_blocksize: 16_384,
+                soft_deletes_retention: None,
+                num_retained_commits: 0,
+                write_ahead_log: false,
             }
         );
         {
@@ -544,4 +810,53 @@ mod tests {
             assert_eq!(index_settings_deser, index_settings);
         }
     }
+    #[test]
+    fn test_index_settings_soft_deletes_retention() {
+        let index_settings = IndexSettings {
+            soft_deletes_retention: Some(SoftDeletesRetention::Seconds(3600)),
+            ..IndexSettings::default()
+        };
+        let index_settings_json = serde_json::to_value(&index_settings).unwrap();
+        assert_eq!(
+            index_settings_json["soft_deletes_retention"],
+            serde_json::json!({ "seconds": 3600 })
+        );
+        let index_settings_deser: IndexSettings =
+            serde_json::from_value(index_settings_json).unwrap();
+        assert_eq!(index_settings_deser, index_settings);
+    }
+
+    #[test]
+    fn test_soft_deletes_retention() {
+        let soft_delete = SoftDelete {
+            opstamp: 10,
+            timestamp: DateTime::from_timestamp_secs(1_000),
+        };
+        let now = DateTime::from_timestamp_secs(1_100);
+        assert!(SoftDeletesRetention::Opstamps(5).retains(&soft_delete, 14, now));
+        assert!(!SoftDeletesRetention::Opstamps(5).retains(&soft_delete, 15, now));
+        assert!(SoftDeletesRetention::Seconds(101).retains(&soft_delete, 100, now));
+        assert!(!SoftDeletesRetention::Seconds(100).retains(&soft_delete, 100, now));
+    }
+
+    #[test]
+    fn test_segment_meta_soft_deletes_serialization() {
+        let inventory = SegmentMetaInventory::default();
+        let segment_meta = inventory
+            .new_segment_meta(SegmentId::generate_random(), 10)
+            .with_delete_meta(3, 7)
+            .with_num_soft_deleted_docs(2);
+        assert_eq!(segment_meta.num_deleted_docs(), 3);
+        assert_eq!(segment_meta.num_soft_deleted_docs(), 2);
+        assert_eq!(segment_meta.num_reclaimable_deleted_docs(), 1);
+        let json = serde_json::to_string(&segment_meta).unwrap();
+        assert!(json.contains(r#""num_soft_deleted_docs":2"#));
+        let segment_meta_deser: InnerSegmentMeta = serde_json::from_str(&json).unwrap();
+        let segment_meta_deser = segment_meta_deser.track(&inventory);
+        assert_eq!(segment_meta_deser.num_soft_deleted_docs(), 2);
+        assert_eq!(
+            segment_meta_deser.relative_path(SegmentComponent::SoftDeletes),
+            PathBuf::from(format!("{}.7.sdel", segment_meta.id().uuid_string()))
+        );
+    }
 }
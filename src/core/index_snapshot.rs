@@ -0,0 +1,176 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+use crc32fast::Hasher;
+
+use crate::core::{IndexMeta, SegmentComponent};
+use crate::directory::{Directory, ManagedDirectory, TerminatingWrite, META_LOCK};
+use crate::error::{DataCorruption, TantivyError};
+use crate::indexer::segment_updater::save_metas;
+use crate::Index;
+
+/// A file of an [`IndexSnapshot`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SnapshotFile {
+    /// Path of the file, relative to the index directory.
+    pub path: PathBuf,
+    /// Length of the file in bytes, footer included.
+    pub num_bytes: u64,
+    /// CRC32 checksum of the file content, as recorded in its footer.
+    pub crc: u32,
+}
+
+/// A point-in-time view of the files of an index, typically used to back it up.
+///
+/// An `IndexSnapshot` is created by [`Index::create_snapshot`] or
+/// [`IndexWriter::snapshot`](crate::IndexWriter::snapshot), and captures the last commit of
+/// the index.
+///
+/// As long as the snapshot is alive, the files of its segments are protected from
+/// garbage collection by the index writers created from the same `Index`, including the
+/// garbage collection following merges. The protection is released when the snapshot
+/// is dropped.
+pub struct IndexSnapshot {
+    index: Index,
+    meta: IndexMeta,
+    files: Vec<SnapshotFile>,
+}
+
+impl IndexSnapshot {
+    pub(crate) fn create(index: &Index) -> crate::Result<IndexSnapshot> {
+        let directory = index.directory();
+        // Prevents segment files from getting deleted while we are listing them.
+        let _meta_lock = directory.acquire_lock(&META_LOCK)?;
+        let meta = index.load_metas()?;
+        let mut files = Vec::new();
+        for segment_meta in &meta.segments {
+            let mut paths: Vec<PathBuf> = SegmentComponent::iterator()
+                .filter(|component| **component != SegmentComponent::TempStore)
+                .map(|component| segment_meta.relative_path(*component))
+                .collect();
+            paths.sort();
+            for path in paths {
+                if !directory.exists(&path)? {
+                    continue;
+                }
+                let (footer, num_bytes) = directory.read_footer(&path)?;
+                files.push(SnapshotFile {
+                    path,
+                    num_bytes,
+                    crc: footer.crc(),
+                });
+            }
+        }
+        Ok(IndexSnapshot {
+            index: index.clone(),
+            meta,
+            files,
+        })
+    }
+
+    /// Returns the meta of the commit captured by the snapshot.
+    pub fn meta(&self) -> &IndexMeta {
+        &self.meta
+    }
+
+    /// Returns the segment files of the snapshot.
+    ///
+    /// The `meta.json` file is not part of this list, as it is rewritten by
+    /// [`IndexSnapshot::copy_to`].
+    pub fn files(&self) -> &[SnapshotFile] {
+        &self.files
+    }
+
+    /// Returns the total number of bytes of the segment files of the snapshot.
+    pub fn num_bytes(&self) -> u64 {
+        self.files.iter().map(|file| file.num_bytes).sum()
+    }
+
+    /// Copies the snapshot into another directory.
+    ///
+    /// The checksum of every file is verified as it is copied. Once the copy is done,
+    /// the target directory holds an index that can be opened with [`Index::open`].
+    ///
+    /// If the target directory already contains an index, a
+    /// [`TantivyError::IndexAlreadyExists`] error is returned.
+    pub fn copy_to(&self, directory: &dyn Directory) -> crate::Result<()> {
+        if Index::exists(directory)? {
+            return Err(TantivyError::IndexAlreadyExists);
+        }
+        let source = self.index.directory();
+        let target = ManagedDirectory::wrap(directory.box_clone())?;
+        for file in &self.files {
+            let bytes = source.open_read(&file.path)?.read_bytes()?;
+            let mut hasher = Hasher::new();
+            hasher.update(bytes.as_slice());
+            if hasher.finalize() != file.crc {
+                return Err(DataCorruption::new(
+                    file.path.clone(),
+                    "Checksum mismatch while copying the snapshot.".to_string(),
+                )
+                .into());
+            }
+            let mut write = target.open_write(&file.path)?;
+            write.write_all(bytes.as_slice())?;
+            write.terminate()?;
+        }
+        // The commit points retained by the source index are not part of the snapshot.
+        let mut meta = self.meta.clone();
+        meta.retained_commits.clear();
+        save_metas(&meta, &target)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::directory::{Directory, RamDirectory};
+    use crate::schema::{Schema, INDEXED};
+    use crate::{Index, IndexWriter};
+
+    #[test]
+    fn test_snapshot_survives_merge() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let num_field = schema_builder.add_u64_field("num", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for i in 0..3u64 {
+            index_writer.add_document(doc!(num_field => i))?;
+            index_writer.commit()?;
+        }
+        let snapshot = index_writer.snapshot()?;
+        assert_eq!(snapshot.meta().segments.len(), 3);
+        assert!(!snapshot.files().is_empty());
+        for file in snapshot.files() {
+            assert!(index.directory().validate_checksum(&file.path)?);
+        }
+
+        index_writer.add_document(doc!(num_field => 3u64))?;
+        index_writer.commit()?;
+        let segment_ids = index.searchable_segment_ids()?;
+        index_writer.merge(&segment_ids).wait()?;
+        index_writer.garbage_collect_files().wait()?;
+        for file in snapshot.files() {
+            assert!(index.directory().exists(&file.path)?);
+        }
+
+        let backup_directory = RamDirectory::create();
+        snapshot.copy_to(&backup_directory)?;
+        let backup = Index::open(backup_directory.clone())?;
+        let searcher = backup.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 3);
+        assert_eq!(searcher.num_docs(), 3);
+        assert!(matches!(
+            snapshot.copy_to(&backup_directory),
+            Err(crate::TantivyError::IndexAlreadyExists)
+        ));
+
+        let files = snapshot.files().to_vec();
+        drop(snapshot);
+        index_writer.garbage_collect_files().wait()?;
+        for file in files {
+            assert!(!index.directory().exists(&file.path)?);
+        }
+        Ok(())
+    }
+}
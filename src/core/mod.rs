@@ -1,6 +1,8 @@
+mod cancellation;
 mod executor;
 pub mod index;
 mod index_meta;
+mod index_snapshot;
 mod inverted_index_reader;
 pub mod searcher;
 mod segment;
@@ -8,16 +10,23 @@ mod segment_component;
 mod segment_id;
 mod segment_reader;
 mod single_segment_index_writer;
+mod soft_deletes;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use once_cell::sync::Lazy;
 
+use crate::Opstamp;
+
+pub(crate) use self::cancellation::{CancellableWeight, CancellationCheck};
+pub use self::cancellation::{CancellationToken, SearchOutcome};
 pub use self::executor::Executor;
 pub use self::index::{Index, IndexBuilder};
 pub use self::index_meta::{
-    IndexMeta, IndexSettings, IndexSortByField, Order, SegmentMeta, SegmentMetaInventory,
+    CommitPoint, IndexMeta, IndexSettings, IndexSortByField, Order, SegmentMeta,
+    SegmentMetaInventory, SoftDeletesRetention,
 };
+pub use self::index_snapshot::{IndexSnapshot, SnapshotFile};
 pub use self::inverted_index_reader::InvertedIndexReader;
 pub use self::searcher::{Searcher, SearcherGeneration};
 pub use self::segment::Segment;
@@ -25,11 +34,19 @@ pub use self::segment_component::SegmentComponent;
 pub use self::segment_id::SegmentId;
 pub use self::segment_reader::SegmentReader;
 pub use self::single_segment_index_writer::SingleSegmentIndexWriter;
+pub use self::soft_deletes::{SoftDelete, SoftDeletes};
 
 /// The meta file contains all the information about the list of segments and the schema
 /// of the index.
 pub static META_FILEPATH: Lazy<&'static Path> = Lazy::new(|| Path::new("meta.json"));
 
+/// Returns the path of the meta file of a retained commit point.
+///
+/// It is a copy of the `meta.json` file, as it was written by the commit.
+pub(crate) fn commit_meta_filepath(opstamp: Opstamp) -> PathBuf {
+    PathBuf::from(format!("meta.{opstamp}.json"))
+}
+
 /// The managed file contains a list of files that were created by the tantivy
 /// and will therefore be garbage collected when they are deemed useless by tantivy.
 ///
@@ -1,10 +1,11 @@
 use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::time::Instant;
 use std::{fmt, io};
 
 use crate::collector::Collector;
-use crate::core::{Executor, SegmentReader};
-use crate::query::{EnableScoring, Query};
+use crate::core::{CancellableWeight, CancellationToken, Executor, SearchOutcome, SegmentReader};
+use crate::query::{EnableScoring, Query, QueryProfiler, SearchProfile, SegmentProfile};
 use crate::schema::{Document, Schema, Term};
 use crate::space_usage::SearcherSpaceUsage;
 use crate::store::{CacheStats, StoreReader};
@@ -214,6 +215,155 @@ impl Searcher {
         collector.merge_fruits(fruits)
     }
 
+    /// Same as [`search(...)`](Searcher::search), but stops once the given
+    /// [`CancellationToken`] is cancelled or its deadline has passed.
+    ///
+    /// The token is checked before the collection of each segment, and periodically while
+    /// iterating over the documents matching the query. The creation of the scorer of a
+    /// segment, however, is not interrupted.
+    ///
+    /// A cancelled search does not return an error: the fruit is computed from the
+    /// documents collected so far, and [`SearchOutcome::timed_out`] is set.
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    ///
+    /// use tantivy::collector::Count;
+    /// use tantivy::query::AllQuery;
+    /// use tantivy::schema::{Schema, TEXT};
+    /// use tantivy::{doc, CancellationToken, Index};
+    ///
+    /// # fn main() -> tantivy::Result<()> {
+    /// let mut schema_builder = Schema::builder();
+    /// let title = schema_builder.add_text_field("title", TEXT);
+    /// let index = Index::create_in_ram(schema_builder.build());
+    /// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+    /// index_writer.add_document(doc!(title => "The Name of the Wind"))?;
+    /// index_writer.commit()?;
+    ///
+    /// let searcher = index.reader()?.searcher();
+    /// let token = CancellationToken::with_timeout(Duration::from_millis(100));
+    /// let outcome = searcher.search_with_cancellation(&AllQuery, &Count, &token)?;
+    /// if !outcome.timed_out {
+    ///     assert_eq!(outcome.fruit, 1);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search_with_cancellation<C: Collector>(
+        &self,
+        query: &dyn Query,
+        collector: &C,
+        cancellation_token: &CancellationToken,
+    ) -> crate::Result<SearchOutcome<C::Fruit>> {
+        let enabled_scoring = if collector.requires_scoring() {
+            EnableScoring::enabled_from_searcher(self)
+        } else {
+            EnableScoring::disabled_from_searcher(self)
+        };
+        let weight =
+            CancellableWeight::new(query.weight(enabled_scoring)?, cancellation_token.clone());
+        let executor = self.inner.index.search_executor();
+        let segment_readers = self.segment_readers();
+        let fruits = executor.map(
+            |(segment_ord, segment_reader)| {
+                if cancellation_token.is_cancelled() {
+                    weight.skip_segment();
+                    return Ok(None);
+                }
+                collector
+                    .collect_segment(&weight, segment_ord as u32, segment_reader)
+                    .map(Some)
+            },
+            segment_readers.iter().enumerate(),
+        )?;
+        let fruit = collector.merge_fruits(fruits.into_iter().flatten().collect())?;
+        Ok(SearchOutcome {
+            fruit,
+            timed_out: weight.timed_out(),
+        })
+    }
+
+    /// Same as [`search(...)`](Searcher::search), but also records where the time was
+    /// spent, per segment and per query node.
+    ///
+    /// Each scorer is instrumented, which adds some overhead and disables some
+    /// optimizations (e.g. BlockWAND), so the timings should be compared with each
+    /// other rather than with the ones of a regular search.
+    ///
+    /// ```rust
+    /// use tantivy::collector::TopDocs;
+    /// use tantivy::query::QueryParser;
+    /// use tantivy::schema::{Schema, TEXT};
+    /// use tantivy::{doc, Index};
+    ///
+    /// # fn main() -> tantivy::Result<()> {
+    /// let mut schema_builder = Schema::builder();
+    /// let title = schema_builder.add_text_field("title", TEXT);
+    /// let index = Index::create_in_ram(schema_builder.build());
+    /// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+    /// index_writer.add_document(doc!(title => "The Name of the Wind"))?;
+    /// index_writer.add_document(doc!(title => "The Wise Man's Fear"))?;
+    /// index_writer.commit()?;
+    ///
+    /// let searcher = index.reader()?.searcher();
+    /// let query = QueryParser::for_index(&index, vec![title]).parse_query("name wind")?;
+    /// let (top_docs, profile) = searcher.search_with_profile(&query, &TopDocs::with_limit(10))?;
+    /// assert_eq!(top_docs.len(), 1);
+    /// println!("{}", profile.to_pretty_json());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search_with_profile<C: Collector>(
+        &self,
+        query: &dyn Query,
+        collector: &C,
+    ) -> crate::Result<(C::Fruit, SearchProfile)> {
+        let enabled_scoring = if collector.requires_scoring() {
+            EnableScoring::enabled_from_searcher(self)
+        } else {
+            EnableScoring::disabled_from_searcher(self)
+        };
+        let mut profiler = QueryProfiler::default();
+        let weight = query.profiled_weight(enabled_scoring, &mut profiler)?;
+        let executor = self.inner.index.search_executor();
+        let segment_readers = self.segment_readers();
+        let fruits_and_timings = executor.map(
+            |(segment_ord, segment_reader)| {
+                let start = Instant::now();
+                let fruit = collector.collect_segment(
+                    weight.as_ref(),
+                    segment_ord as u32,
+                    segment_reader,
+                )?;
+                Ok((fruit, start.elapsed().as_nanos() as u64))
+            },
+            segment_readers.iter().enumerate(),
+        )?;
+        let mut fruits = Vec::with_capacity(fruits_and_timings.len());
+        let mut segments = Vec::with_capacity(fruits_and_timings.len());
+        for (segment_ord, (fruit, total_nanos)) in fruits_and_timings.into_iter().enumerate() {
+            fruits.push(fruit);
+            let segment_id = segment_readers[segment_ord].segment_id();
+            let (query_profile, query_nanos) =
+                profiler.root_profile(segment_id).ok_or_else(|| {
+                    crate::TantivyError::InternalError(format!(
+                        "Query {:?} did not record its profile.",
+                        query
+                    ))
+                })?;
+            segments.push(SegmentProfile {
+                segment_ord: segment_ord as u32,
+                segment_id,
+                total_nanos,
+                collect_nanos: total_nanos.saturating_sub(query_nanos),
+                query: query_profile,
+            });
+        }
+        let fruit = collector.merge_fruits(fruits)?;
+        Ok((fruit, SearchProfile { segments }))
+    }
+
     /// Summarize total space usage of this searcher.
     pub fn space_usage(&self) -> io::Result<SearcherSpaceUsage> {
         let mut space_usage = SearcherSpaceUsage::new();
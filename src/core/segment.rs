@@ -1,6 +1,8 @@
 use std::fmt;
 use std::path::PathBuf;
 
+use common::HasLen;
+
 use super::SegmentComponent;
 use crate::core::{Index, SegmentId, SegmentMeta};
 use crate::directory::error::{OpenReadError, OpenWriteError};
@@ -53,6 +55,34 @@ impl Segment {
         }
     }
 
+    /// Records the number of bytes taken by the segment files in the `SegmentMeta`.
+    ///
+    /// This method is called once the segment files have been written.
+    pub(crate) fn with_num_bytes(self) -> Segment {
+        let num_bytes = self.num_bytes();
+        Segment {
+            index: self.index,
+            meta: self.meta.with_num_bytes(num_bytes),
+        }
+    }
+
+    // Returns the number of bytes taken by the segment files in the directory,
+    // excluding the delete files and the temporary doc store.
+    fn num_bytes(&self) -> u64 {
+        SegmentComponent::iterator()
+            .filter(|component| {
+                !matches!(
+                    component,
+                    SegmentComponent::Delete
+                        | SegmentComponent::SoftDeletes
+                        | SegmentComponent::TempStore
+                )
+            })
+            .filter_map(|component| self.open_read(*component).ok())
+            .map(|file_slice| file_slice.len() as u64)
+            .sum()
+    }
+
     #[doc(hidden)]
     #[must_use]
     pub fn with_delete_meta(self, num_deleted_docs: u32, opstamp: Opstamp) -> Segment {
@@ -62,6 +92,14 @@ impl Segment {
         }
     }
 
+    #[must_use]
+    pub(crate) fn with_num_soft_deleted_docs(self, num_soft_deleted_docs: u32) -> Segment {
+        Segment {
+            index: self.index,
+            meta: self.meta.with_num_soft_deleted_docs(num_soft_deleted_docs),
+        }
+    }
+
     /// Returns the segment's id.
     pub fn id(&self) -> SegmentId {
         self.meta.id()
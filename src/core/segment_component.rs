@@ -27,12 +27,18 @@ pub enum SegmentComponent {
     /// Bitset describing which document of the segment is alive.
     /// (It was representing deleted docs but changed to represent alive docs from v0.17)
     Delete,
+    /// Deletion of the soft-deleted documents of the segment.
+    /// Like the delete component, it takes an
+    /// `segment_uuid`.`delete_opstamp`.`component_extension` file name.
+    SoftDeletes,
+    /// Dense vectors of the vector fields, along with their HNSW graph.
+    Vectors,
 }
 
 impl SegmentComponent {
     /// Iterates through the components.
     pub fn iterator() -> slice::Iter<'static, SegmentComponent> {
-        static SEGMENT_COMPONENTS: [SegmentComponent; 8] = [
+        static SEGMENT_COMPONENTS: [SegmentComponent; 10] = [
             SegmentComponent::Postings,
             SegmentComponent::Positions,
             SegmentComponent::FastFields,
@@ -41,6 +47,8 @@ impl SegmentComponent {
             SegmentComponent::Store,
             SegmentComponent::TempStore,
             SegmentComponent::Delete,
+            SegmentComponent::SoftDeletes,
+            SegmentComponent::Vectors,
         ];
         SEGMENT_COMPONENTS.iter()
     }
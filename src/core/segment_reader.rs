@@ -4,7 +4,12 @@ use std::{fmt, io};
 
 use fail::fail_point;
 
-use crate::core::{InvertedIndexReader, Segment, SegmentComponent, SegmentId};
+use common::BitSet;
+
+use crate::core::{
+    InvertedIndexReader, Segment, SegmentComponent, SegmentId, SoftDelete, SoftDeletes,
+};
+use crate::directory::error::OpenReadError;
 use crate::directory::{CompositeFile, FileSlice};
 use crate::error::DataCorruption;
 use crate::fastfield::{intersect_alive_bitsets, AliveBitSet, FacetReader, FastFieldReaders};
@@ -13,7 +18,8 @@ use crate::schema::{Field, FieldType, IndexRecordOption, Schema};
 use crate::space_usage::SegmentSpaceUsage;
 use crate::store::StoreReader;
 use crate::termdict::TermDictionary;
-use crate::{DocId, Opstamp};
+use crate::vector::{VectorReader, VectorReaders};
+use crate::{DocId, IndexSortByField, Opstamp};
 
 /// Entry point to access all of the datastructures of the `Segment`
 ///
@@ -40,10 +46,13 @@ pub struct SegmentReader {
     positions_composite: CompositeFile,
     fast_fields_readers: Arc<FastFieldReaders>,
     fieldnorm_readers: FieldNormReaders,
+    vector_readers: VectorReaders,
 
     store_file: FileSlice,
     alive_bitset_opt: Option<AliveBitSet>,
+    soft_deletes: Arc<SoftDeletes>,
     schema: Schema,
+    sort_by_field: Option<IndexSortByField>,
 }
 
 impl SegmentReader {
@@ -64,6 +73,12 @@ impl SegmentReader {
         &self.schema
     }
 
+    /// Returns the first field the documents of this segment are sorted by,
+    /// if the index is sorted.
+    pub fn sort_by_field(&self) -> Option<&IndexSortByField> {
+        self.sort_by_field.as_ref()
+    }
+
     /// Return the number of documents that have been
     /// deleted in the segment.
     pub fn num_deleted_docs(&self) -> DocId {
@@ -75,6 +90,15 @@ impl SegmentReader {
         self.num_deleted_docs() > 0
     }
 
+    /// Returns the soft-deleted documents of the segment.
+    ///
+    /// Soft-deleted documents are deleted, and are not returned by searches, but their
+    /// stored fields and fast fields can still be read.
+    /// The soft deletes are empty unless soft deletes are enabled in the index settings.
+    pub fn soft_deletes(&self) -> &SoftDeletes {
+        &self.soft_deletes
+    }
+
     /// Accessor to a segment's fast field reader given a field.
     ///
     /// Returns the u64 fast value reader if the field
@@ -134,6 +158,19 @@ impl SegmentReader {
         &self.fieldnorm_readers
     }
 
+    /// Accessor to the `VectorReader` associated with a given vector `Field`.
+    ///
+    /// Returns an error if the field is not a vector field.
+    pub fn vector_reader(&self, field: Field) -> crate::Result<VectorReader> {
+        self.vector_readers.get_field(field)?.ok_or_else(|| {
+            let field_name = self.schema.get_field_name(field);
+            crate::TantivyError::SchemaError(format!(
+                "Field {:?} is not a vector field.",
+                field_name
+            ))
+        })
+    }
+
     /// Accessor to the segment's `StoreReader`.
     pub fn get_store_reader(&self, cache_size: usize) -> io::Result<StoreReader> {
         StoreReader::open(self.store_file.clone(), cache_size)
@@ -176,6 +213,13 @@ impl SegmentReader {
         let fieldnorm_data = segment.open_read(SegmentComponent::FieldNorms)?;
         let fieldnorm_readers = FieldNormReaders::open(fieldnorm_data)?;
 
+        // Segments written before the introduction of vector fields have no vectors file.
+        let vector_readers = match segment.open_read(SegmentComponent::Vectors) {
+            Ok(vectors_data) => VectorReaders::open(vectors_data, schema.clone())?,
+            Err(OpenReadError::FileDoesNotExist(_)) => VectorReaders::empty(schema.clone()),
+            Err(open_read_error) => return Err(open_read_error.into()),
+        };
+
         let original_bitset = if segment.meta().has_deletes() {
             let alive_doc_file_slice = segment.open_read(SegmentComponent::Delete)?;
             let alive_doc_data = alive_doc_file_slice.read_bytes()?;
@@ -186,6 +230,15 @@ impl SegmentReader {
 
         let alive_bitset_opt = intersect_alive_bitset(original_bitset, custom_bitset);
 
+        let soft_deletes = if segment.meta().num_soft_deleted_docs() > 0 {
+            let soft_deletes_data = segment
+                .open_read(SegmentComponent::SoftDeletes)?
+                .read_bytes()?;
+            SoftDeletes::open(soft_deletes_data)?
+        } else {
+            SoftDeletes::default()
+        };
+
         let max_doc = segment.meta().max_doc();
         let num_docs = alive_bitset_opt
             .as_ref()
@@ -200,12 +253,15 @@ impl SegmentReader {
             postings_composite,
             fast_fields_readers,
             fieldnorm_readers,
+            vector_readers,
             segment_id: segment.id(),
             delete_opstamp: segment.meta().delete_opstamp(),
             store_file,
             alive_bitset_opt,
+            soft_deletes: Arc::new(soft_deletes),
             positions_composite,
             schema,
+            sort_by_field: segment.index().settings().sort_by.first().cloned(),
         })
     }
 
@@ -310,6 +366,38 @@ impl SegmentReader {
             .unwrap_or(false)
     }
 
+    /// Makes the soft-deleted documents retained by `retains` alive again, and drops the
+    /// other ones from the soft deletes.
+    ///
+    /// This is used to carry the retained soft-deleted documents over to a merged segment.
+    pub(crate) fn retain_soft_deletes(
+        mut self,
+        retains: impl Fn(&SoftDelete) -> bool,
+    ) -> SegmentReader {
+        let mut soft_deletes = SoftDeletes::default();
+        for (doc, soft_delete) in self.soft_deletes.iter() {
+            if retains(soft_delete) {
+                soft_deletes.insert(doc, *soft_delete);
+            }
+        }
+        if let Some(alive_bitset) = self.alive_bitset_opt.as_ref() {
+            if !soft_deletes.is_empty() {
+                let mut bitset = BitSet::with_max_value(self.max_doc);
+                for doc in alive_bitset
+                    .iter_alive()
+                    .chain(soft_deletes.iter().map(|(doc, _)| doc))
+                {
+                    bitset.insert(doc);
+                }
+                let alive_bitset = AliveBitSet::from_bitset(&bitset);
+                self.num_docs = alive_bitset.num_alive_docs() as u32;
+                self.alive_bitset_opt = Some(alive_bitset);
+            }
+        }
+        self.soft_deletes = Arc::new(soft_deletes);
+        self
+    }
+
     /// Returns an iterator that will iterate over the alive document ids
     pub fn doc_ids_alive(&self) -> Box<dyn Iterator<Item = DocId> + '_> {
         if let Some(alive_bitset) = &self.alive_bitset_opt {
@@ -333,6 +421,8 @@ impl SegmentReader {
                 .as_ref()
                 .map(AliveBitSet::space_usage)
                 .unwrap_or(0),
+            self.soft_deletes.space_usage(),
+            self.vector_readers.space_usage(),
         ))
     }
 }
@@ -379,8 +469,8 @@ mod test {
             index_writer.add_document(doc!(name => "jockey"))?;
             index_writer.add_document(doc!(name => "cap"))?;
             // we should now have one segment with two docs
-            index_writer.delete_term(Term::from_field_text(name, "horse"));
-            index_writer.delete_term(Term::from_field_text(name, "cap"));
+            index_writer.delete_term(Term::from_field_text(name, "horse"))?;
+            index_writer.delete_term(Term::from_field_text(name, "cap"))?;
 
             // ok, now we should have a deleted doc
             index_writer.commit()?;
@@ -410,8 +500,8 @@ mod test {
 
         {
             let mut index_writer2 = index.writer(50_000_000)?;
-            index_writer2.delete_term(Term::from_field_text(name, "horse"));
-            index_writer2.delete_term(Term::from_field_text(name, "cap"));
+            index_writer2.delete_term(Term::from_field_text(name, "horse"))?;
+            index_writer2.delete_term(Term::from_field_text(name, "cap"))?;
 
             // ok, now we should have a deleted doc
             index_writer2.commit()?;
@@ -43,6 +43,7 @@ impl SingleSegmentIndexWriter {
             schema: index.schema(),
             opstamp: 0,
             payload: None,
+            retained_commits: Vec::new(),
         };
         save_metas(&index_meta, index.directory())?;
         index.directory().sync_directory()?;
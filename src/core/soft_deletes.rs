@@ -0,0 +1,157 @@
+//! Soft deletes of a segment, stored in the `.sdel` segment component.
+//!
+//! A soft-deleted document is removed from the alive bitset like any deleted document,
+//! so that searches skip it. The alive bitset does not say whether a dead document may
+//! still be restored, nor when it was deleted, which is what the retention of
+//! soft deletes is checked against. These are recorded for each soft-deleted document,
+//! in a separate component for the following reasons:
+//!
+//! - a fast field cannot hold them, as fast fields are written once, with the segment,
+//!   while deletes are applied to segments that are already committed.
+//! - the alive bitset file only holds a bitset, and is read by every segment reader. Adding
+//!   a per-document opstamp and timestamp to it would change its format for the indexes
+//!   that do not use soft deletes.
+//!
+//! The `.sdel` file is a generation of the alive bitset: when soft deletes are enabled,
+//! it is written along with each `.del` file, and named after the same delete opstamp.
+//! It is therefore listed by [`SegmentMeta::list_files`](crate::SegmentMeta::list_files),
+//! which garbage collection and replication rely on, and merges rewrite it along with
+//! the alive bitset.
+//! Soft deletes cannot be logged to the write-ahead log, see
+//! [`IndexSettings::write_ahead_log`](crate::IndexSettings::write_ahead_log).
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use common::{BinarySerializable, OwnedBytes};
+
+use crate::space_usage::ByteCount;
+use crate::{DateTime, DocId, Opstamp};
+
+/// Deletion of a document, as recorded when soft deletes are enabled.
+///
+/// See [`IndexSettings::soft_deletes_retention`](crate::IndexSettings::soft_deletes_retention).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SoftDelete {
+    /// Opstamp of the delete operation.
+    pub opstamp: Opstamp,
+    /// Time at which the delete operation was issued.
+    pub timestamp: DateTime,
+}
+
+/// Soft-deleted documents of a segment, along with their deletion.
+///
+/// Soft-deleted documents are deleted documents, which are retained in the segment.
+/// Their stored fields and fast fields can still be read.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SoftDeletes {
+    soft_deletes: BTreeMap<DocId, SoftDelete>,
+}
+
+impl SoftDeletes {
+    /// Opens the soft deletes of a segment given its file.
+    pub(crate) fn open(bytes: OwnedBytes) -> io::Result<SoftDeletes> {
+        let mut data = bytes.as_slice();
+        let num_soft_deletes = u32::deserialize(&mut data)?;
+        let mut soft_deletes = BTreeMap::new();
+        for _ in 0..num_soft_deletes {
+            let doc = DocId::deserialize(&mut data)?;
+            let opstamp = Opstamp::deserialize(&mut data)?;
+            let timestamp = DateTime::from_timestamp_micros(i64::deserialize(&mut data)?);
+            soft_deletes.insert(doc, SoftDelete { opstamp, timestamp });
+        }
+        Ok(SoftDeletes { soft_deletes })
+    }
+
+    /// Writes the soft deletes.
+    ///
+    /// Warning: this function does not call terminate. The caller is in charge of
+    /// closing the writer properly.
+    pub(crate) fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        (self.soft_deletes.len() as u32).serialize(writer)?;
+        for (doc, soft_delete) in &self.soft_deletes {
+            doc.serialize(writer)?;
+            soft_delete.opstamp.serialize(writer)?;
+            soft_delete
+                .timestamp
+                .into_timestamp_micros()
+                .serialize(writer)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the deletion of a document, if it is soft-deleted.
+    pub fn get(&self, doc: DocId) -> Option<&SoftDelete> {
+        self.soft_deletes.get(&doc)
+    }
+
+    /// Iterates over the soft-deleted documents, in increasing `DocId` order.
+    pub fn iter(&self) -> impl Iterator<Item = (DocId, &SoftDelete)> + '_ {
+        self.soft_deletes
+            .iter()
+            .map(|(&doc, soft_delete)| (doc, soft_delete))
+    }
+
+    /// Returns the number of soft-deleted documents.
+    pub fn len(&self) -> usize {
+        self.soft_deletes.len()
+    }
+
+    /// Returns true if there are no soft-deleted documents.
+    pub fn is_empty(&self) -> bool {
+        self.soft_deletes.is_empty()
+    }
+
+    /// Summarize total space usage of the soft deletes.
+    pub fn space_usage(&self) -> ByteCount {
+        // The number of soft deletes, then for each of them a doc id, an opstamp and a
+        // timestamp.
+        4 + self.soft_deletes.len() * (4 + 8 + 8)
+    }
+
+    pub(crate) fn insert(&mut self, doc: DocId, soft_delete: SoftDelete) {
+        self.soft_deletes.insert(doc, soft_delete);
+    }
+
+    pub(crate) fn remove(&mut self, doc: DocId) -> Option<SoftDelete> {
+        self.soft_deletes.remove(&doc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::OwnedBytes;
+
+    use super::{SoftDelete, SoftDeletes};
+    use crate::DateTime;
+
+    #[test]
+    fn test_soft_deletes_serialization() {
+        let mut soft_deletes = SoftDeletes::default();
+        soft_deletes.insert(
+            7,
+            SoftDelete {
+                opstamp: 3,
+                timestamp: DateTime::from_timestamp_secs(1_000),
+            },
+        );
+        soft_deletes.insert(
+            2,
+            SoftDelete {
+                opstamp: 5,
+                timestamp: DateTime::from_timestamp_secs(2_000),
+            },
+        );
+        let mut buffer = Vec::new();
+        soft_deletes.serialize(&mut buffer).unwrap();
+        let soft_deletes_deser = SoftDeletes::open(OwnedBytes::new(buffer)).unwrap();
+        assert_eq!(soft_deletes_deser, soft_deletes);
+        assert_eq!(
+            soft_deletes_deser
+                .iter()
+                .map(|(doc, soft_delete)| (doc, soft_delete.opstamp))
+                .collect::<Vec<_>>(),
+            vec![(2, 5), (7, 3)]
+        );
+    }
+}
@@ -151,6 +151,10 @@ impl<W: TerminatingWrite> TerminatingWrite for FooterProxy<W> {
         footer.append_footer(&mut writer)?;
         writer.terminate()
     }
+
+    fn sync_data(&mut self) -> io::Result<()> {
+        self.writer.as_mut().unwrap().sync_data()
+    }
 }
 
 #[cfg(test)]
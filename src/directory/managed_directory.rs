@@ -4,6 +4,7 @@ use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock, RwLockWriteGuard};
 use std::{io, result};
 
+use common::HasLen;
 use crc32fast::Hasher;
 
 use crate::core::MANAGED_FILEPATH;
@@ -109,6 +110,17 @@ impl ManagedDirectory {
     pub fn garbage_collect<L: FnOnce() -> HashSet<PathBuf>>(
         &mut self,
         get_living_files: L,
+    ) -> crate::Result<GarbageCollectionResult> {
+        self.try_garbage_collect(|| Ok(get_living_files()))
+    }
+
+    /// Garbage collect unused files, if the list of living files can be computed.
+    ///
+    /// Same as [`ManagedDirectory::garbage_collect`], except that no file is deleted
+    /// if `get_living_files` returns an error. The error is returned instead.
+    pub fn try_garbage_collect<L: FnOnce() -> crate::Result<HashSet<PathBuf>>>(
+        &mut self,
+        get_living_files: L,
     ) -> crate::Result<GarbageCollectionResult> {
         info!("Garbage collect");
         let mut files_to_delete = vec![];
@@ -137,7 +149,7 @@ impl ManagedDirectory {
             // 4) gc removes a file that was useful for process B, before process B opened it.
             match self.acquire_lock(&META_LOCK) {
                 Ok(_meta_lock) => {
-                    let living_files = get_living_files();
+                    let living_files = get_living_files()?;
                     for managed_path in &meta_informations_rlock.managed_paths {
                         if !living_files.contains(managed_path) {
                             files_to_delete.push(managed_path.clone());
@@ -239,6 +251,24 @@ impl ManagedDirectory {
         Ok(())
     }
 
+    /// Returns the wrapped directory.
+    ///
+    /// The files written through it are neither managed nor given a footer.
+    pub(crate) fn inner_directory(&self) -> &dyn Directory {
+        self.directory.as_ref()
+    }
+
+    /// Reads the footer of a managed file.
+    ///
+    /// Returns the footer along with the length of the file, footer included.
+    pub(crate) fn read_footer(&self, path: &Path) -> result::Result<(Footer, u64), OpenReadError> {
+        let reader = self.directory.open_read(path)?;
+        let num_bytes = reader.len() as u64;
+        let (footer, _) = Footer::extract_footer(reader)
+            .map_err(|io_error| OpenReadError::wrap_io_error(io_error, path.to_path_buf()))?;
+        Ok((footer, num_bytes))
+    }
+
     /// Verify checksum of a managed file
     pub fn validate_checksum(&self, path: &Path) -> result::Result<bool, OpenReadError> {
         let reader = self.directory.open_read(path)?;
@@ -275,6 +275,10 @@ impl Seek for SafeFileWriter {
 
 impl TerminatingWrite for SafeFileWriter {
     fn terminate_ref(&mut self, _: AntiCallToken) -> io::Result<()> {
+        self.sync_data()
+    }
+
+    fn sync_data(&mut self) -> io::Result<()> {
         self.0.flush()?;
         self.0.sync_data()?;
         Ok(())
@@ -505,6 +509,20 @@ mod tests {
         assert_eq!(readonlymap.len(), 0);
     }
 
+    #[test]
+    fn test_sync_data() {
+        let mmap_directory = MmapDirectory::create_from_tempdir().unwrap();
+        let path = PathBuf::from("synced");
+        let mut write = mmap_directory.open_write(&path).unwrap();
+        write.write_all(b"abc").unwrap();
+        write.sync_data().unwrap();
+        assert_eq!(mmap_directory.atomic_read(&path).unwrap(), b"abc");
+        // The writer can still be used after it was synced.
+        write.write_all(b"def").unwrap();
+        write.terminate().unwrap();
+        assert_eq!(mmap_directory.atomic_read(&path).unwrap(), b"abcdef");
+    }
+
     #[test]
     fn test_cache() {
         let content = b"abc";
@@ -622,7 +640,7 @@ mod tests {
             let num_segments = reader.searcher().segment_readers().len();
             assert!(num_segments <= 4);
             let num_components_except_deletes_and_tempstore =
-                crate::core::SegmentComponent::iterator().len() - 2;
+                crate::core::SegmentComponent::iterator().len() - 3;
             let max_num_mmapped = num_components_except_deletes_and_tempstore * num_segments;
             assert_eventually(|| {
                 let num_mmapped = mmap_directory.get_cache_info().mmapped.len();
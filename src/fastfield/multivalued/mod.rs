@@ -308,7 +308,7 @@ mod tests {
                     };
                 }
                 IndexingOp::DeleteDoc { id } => {
-                    index_writer.delete_term(Term::from_field_u64(field, id as u64));
+                    index_writer.delete_term(Term::from_field_u64(field, id as u64))?;
                 }
                 IndexingOp::Commit => {
                     index_writer.commit().unwrap();
@@ -42,6 +42,16 @@ impl<T: PartialOrd + MakeZero + Copy + fmt::Debug> MultiValuedFastFieldReader<T>
         Some(self.vals_reader.get_val(range.start))
     }
 
+    /// Returns the smallest of the values associated to the given `doc`.
+    #[inline]
+    pub(crate) fn get_min_val(&self, doc: DocId) -> Option<T>
+    where T: Ord {
+        self.idx_reader
+            .range(doc)
+            .map(|idx| self.vals_reader.get_val(idx))
+            .min()
+    }
+
     /// Returns the array of values associated to the given `doc`.
     #[inline]
     fn get_vals_for_range(&self, range: Range<u32>, vals: &mut Vec<T>) {
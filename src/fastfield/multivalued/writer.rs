@@ -108,7 +108,7 @@ impl MultiValuedFastFieldWriter {
     /// Normally the order is simply iterating self.doc_id_index.
     /// With doc_id_map it accounts for the new mapping, returning values in the order of the
     /// new doc_ids.
-    fn get_ordered_values<'a: 'b, 'b>(
+    pub(crate) fn get_ordered_values<'a: 'b, 'b>(
         &'a self,
         doc_id_map: Option<&'b DocIdMapping>,
     ) -> impl Iterator<Item = &'b [u64]> {
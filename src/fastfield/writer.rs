@@ -123,7 +123,7 @@ impl FastFieldsWriter {
                         }
                     }
                 }
-                FieldType::Str(_) | FieldType::JsonObject(_) => {}
+                FieldType::Str(_) | FieldType::JsonObject(_) | FieldType::Vector(_) => {}
             }
         }
         FastFieldsWriter {
@@ -185,6 +185,14 @@ impl FastFieldsWriter {
             .find(|field_writer| field_writer.field() == field)
     }
 
+    /// Get the `U128FastFieldWriter` associated with a field.
+    pub fn get_u128_field_writer(&self, field: Field) -> Option<&U128FastFieldWriter> {
+        // TODO optimize
+        self.u128_value_writers
+            .iter()
+            .find(|field_writer| field_writer.field() == field)
+    }
+
     /// Get the `FastFieldWriter` associated with a field.
     pub fn get_field_writer_mut(&mut self, field: Field) -> Option<&mut IntFastFieldWriter> {
         // TODO optimize
@@ -318,6 +326,11 @@ impl U128FastFieldWriter {
         self.vals.len() * 16
     }
 
+    /// Returns the field that this writer is targeting.
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
     /// Records a new value.
     ///
     /// The n-th value being recorded is implicitely
@@ -347,6 +360,11 @@ impl U128FastFieldWriter {
         Ok(())
     }
 
+    /// get iterator over the data
+    pub(crate) fn iter(&self) -> impl Iterator<Item = u128> + '_ {
+        self.vals.iter().cloned()
+    }
+
     /// Push the fast fields value to the `FastFieldWriter`.
     pub fn serialize(
         &self,
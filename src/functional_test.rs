@@ -40,7 +40,7 @@ fn test_functional_store() -> crate::Result<()> {
         if !doc_set.is_empty() {
             let doc_to_remove_id = rng.gen_range(0..doc_set.len());
             let removed_doc_id = doc_set.swap_remove(doc_to_remove_id);
-            index_writer.delete_term(Term::from_field_u64(id_field, removed_doc_id));
+            index_writer.delete_term(Term::from_field_u64(id_field, removed_doc_id))?;
         }
         for _ in 0..num_docs {
             doc_set.push(doc_id);
@@ -78,10 +78,10 @@ fn test_functional_indexing_sorted() -> crate::Result<()> {
 
     let mut index_builder = Index::builder().schema(schema);
     index_builder = index_builder.settings(IndexSettings {
-        sort_by_field: Some(IndexSortByField {
+        sort_by: vec![IndexSortByField {
             field: "id".to_string(),
             order: Order::Desc,
-        }),
+        }],
         ..Default::default()
     });
     let index = index_builder.create_from_tempdir().unwrap();
@@ -110,7 +110,7 @@ fn test_functional_indexing_sorted() -> crate::Result<()> {
             )?;
         } else if committed_docs.remove(&random_val) || uncommitted_docs.remove(&random_val) {
             let doc_id_term = Term::from_field_u64(id_field, random_val);
-            index_writer.delete_term(doc_id_term);
+            index_writer.delete_term(doc_id_term)?;
         } else {
             uncommitted_docs.insert(random_val);
             let mut doc = Document::new();
@@ -185,7 +185,7 @@ fn test_functional_indexing_unsorted() -> crate::Result<()> {
             )?;
         } else if committed_docs.remove(&random_val) || uncommitted_docs.remove(&random_val) {
             let doc_id_term = Term::from_field_u64(id_field, random_val);
-            index_writer.delete_term(doc_id_term);
+            index_writer.delete_term(doc_id_term)?;
         } else {
             uncommitted_docs.insert(random_val);
             let mut doc = Document::new();
@@ -247,7 +247,7 @@ mod tests {
 
     use super::{DeleteOperation, DeleteQueue};
     use crate::query::{Explanation, Scorer, Weight};
-    use crate::{DocId, Score, SegmentReader};
+    use crate::{DateTime, DocId, Score, SegmentReader};
 
     struct DummyWeight;
     impl Weight for DummyWeight {
@@ -267,6 +267,8 @@ mod tests {
         let make_op = |i: usize| DeleteOperation {
             opstamp: i as u64,
             target: Box::new(DummyWeight),
+            timestamp: DateTime::from_timestamp_secs(0),
+            undelete: false,
         };
 
         delete_queue.push(make_op(1));
@@ -1,11 +1,20 @@
 //! This module is used when sorting the index by a property, e.g.
 //! to get mappings from old doc_id to new doc_id and vice versa, after sorting
 
-use std::cmp::Reverse;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::net::Ipv6Addr;
+use std::sync::Arc;
+
+use common::BitSet;
+use fastfield_codecs::{Column, MonotonicallyMappableToU128};
+use rustc_hash::FxHashMap;
 
 use super::SegmentWriter;
-use crate::schema::{Field, Schema};
-use crate::{DocAddress, DocId, IndexSortByField, Order, TantivyError};
+use crate::fastfield::MultiValuedFastFieldReader;
+use crate::postings::UnorderedTermId;
+use crate::schema::{Field, FieldType, Schema, Term};
+use crate::{DocAddress, DocId, IndexSortByField, Order, SegmentReader, TantivyError};
 
 /// Struct to provide mapping from new doc_id to old doc_id and segment.
 #[derive(Clone)]
@@ -107,39 +116,245 @@ pub(crate) fn expect_field_id_for_sort_field(
     })
 }
 
+/// Values of the documents of a segment for one of the keys the index is sorted by.
+///
+/// The values of a segment reader are read from its fast fields on demand, while the
+/// values of the segment being written are collected from its fast field writers.
+enum SortKeyValues {
+    /// Values of a `u64`, `i64`, `f64`, `bool` or date fast field.
+    Column(Arc<dyn Column<u64>>),
+    /// Values of an ip address fast field.
+    IpAddrColumn(Arc<dyn Column<Ipv6Addr>>),
+    /// Term ordinals of a string fast field, and the rank of each of these terms.
+    TermOrds {
+        term_ords: MultiValuedFastFieldReader<u64>,
+        term_ranks: Vec<u64>,
+    },
+    /// Values collected from a fast field writer. For string fields, these are the
+    /// ranks of the terms, and documents without any term are in `missing`.
+    Values { values: Vec<u64>, missing: BitSet },
+    /// Values collected from an ip address fast field writer.
+    IpAddrValues(Vec<u128>),
+}
+
+impl SortKeyValues {
+    /// Returns the value of `doc`, mapped to `u128` in a way that preserves its order.
+    fn value(&self, doc: DocId) -> Option<u128> {
+        match self {
+            SortKeyValues::Column(column) => Some(u128::from(column.get_val(doc))),
+            SortKeyValues::IpAddrColumn(column) => Some(column.get_val(doc).to_u128()),
+            SortKeyValues::TermOrds {
+                term_ords,
+                term_ranks,
+            } => {
+                // Term ranks follow the order of the term ordinals.
+                let term_ord = term_ords.get_min_val(doc)?;
+                Some(u128::from(term_ranks[term_ord as usize]))
+            }
+            SortKeyValues::Values { values, missing } => {
+                if missing.contains(doc) {
+                    None
+                } else {
+                    Some(u128::from(values[doc as usize]))
+                }
+            }
+            SortKeyValues::IpAddrValues(values) => Some(values[doc as usize]),
+        }
+    }
+}
+
+/// One of the keys the index is sorted by.
+///
+/// Documents without a value come after all of the other documents, whatever the order.
+struct SortKey {
+    order: Order,
+    values: SortKeyValues,
+}
+
+fn cmp_sort_values(order: &Order, left: Option<u128>, right: Option<u128>) -> Ordering {
+    match (left, right) {
+        (Some(left), Some(right)) => {
+            if order.is_asc() {
+                left.cmp(&right)
+            } else {
+                right.cmp(&left)
+            }
+        }
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Values of the documents of a segment for all of the keys the index is sorted by.
+///
+/// String values are ranks of the terms, that need to be consistent across the
+/// segments being compared.
+pub(crate) struct SegmentSortValues {
+    keys: Vec<SortKey>,
+}
+
+impl SegmentSortValues {
+    fn new() -> SegmentSortValues {
+        SegmentSortValues { keys: Vec::new() }
+    }
+
+    fn push_key(&mut self, order: Order, values: SortKeyValues) {
+        self.keys.push(SortKey { order, values });
+    }
+
+    /// Compares the document `doc` of this segment with the document `other_doc`
+    /// of the `other` segment.
+    pub(crate) fn cmp_docs(
+        &self,
+        doc: DocId,
+        other: &SegmentSortValues,
+        other_doc: DocId,
+    ) -> Ordering {
+        for (key, other_key) in self.keys.iter().zip(other.keys.iter()) {
+            let ordering = cmp_sort_values(
+                &key.order,
+                key.values.value(doc),
+                other_key.values.value(other_doc),
+            );
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    }
+
+    /// Returns the sort values of the documents of a segment reader.
+    ///
+    /// `term_ranks` contains, for each of the string sort fields, the rank of the
+    /// segment terms, in the order of the term ordinals.
+    pub(crate) fn for_reader(
+        reader: &SegmentReader,
+        sort_by_fields: &[IndexSortByField],
+        term_ranks: &mut HashMap<Field, Vec<u64>>,
+    ) -> crate::Result<SegmentSortValues> {
+        let schema = reader.schema();
+        let mut sort_values = SegmentSortValues::new();
+        for sort_by_field in sort_by_fields {
+            let field = expect_field_id_for_sort_field(schema, sort_by_field)?;
+            let field_name = sort_by_field.field.as_str();
+            let values = match schema.get_field_entry(field).field_type() {
+                FieldType::Str(_) => {
+                    let term_ranks = term_ranks.remove(&field).ok_or_else(|| {
+                        TantivyError::InternalError(format!(
+                            "missing term ranks for sort field {:?}",
+                            field_name
+                        ))
+                    })?;
+                    SortKeyValues::TermOrds {
+                        term_ords: reader.fast_fields().u64s_lenient(field_name)?,
+                        term_ranks,
+                    }
+                }
+                FieldType::IpAddr(_) => {
+                    SortKeyValues::IpAddrColumn(reader.fast_fields().ip_addr(field_name)?)
+                }
+                _ => SortKeyValues::Column(reader.fast_fields().u64_lenient(field_name)?),
+            };
+            sort_values.push_key(sort_by_field.order.clone(), values);
+        }
+        Ok(sort_values)
+    }
+
+    /// Returns the sort values of the documents of the segment being written.
+    fn for_segment_writer(
+        segment_writer: &SegmentWriter,
+        sort_by_fields: &[IndexSortByField],
+    ) -> crate::Result<SegmentSortValues> {
+        let schema = segment_writer.segment_serializer.segment().schema();
+        let max_doc = segment_writer.max_doc;
+        let mut sort_values = SegmentSortValues::new();
+        for sort_by_field in sort_by_fields {
+            let field = expect_field_id_for_sort_field(&schema, sort_by_field)?;
+            let not_a_fast_field = || {
+                TantivyError::InvalidArgument(format!(
+                    "sort index by field is required to be a fast field {:?}",
+                    sort_by_field.field
+                ))
+            };
+            let fast_field_writers = &segment_writer.fast_field_writers;
+            let values = match schema.get_field_entry(field).field_type() {
+                FieldType::Str(_) => {
+                    let term_id_writer = fast_field_writers
+                        .get_term_id_writer(field)
+                        .ok_or_else(not_a_fast_field)?;
+                    let term_ranks = unordered_term_id_ranks(segment_writer, field);
+                    let mut missing = BitSet::with_max_value(max_doc);
+                    let values = term_id_writer
+                        .get_ordered_values(None)
+                        .enumerate()
+                        .map(|(doc, unordered_term_ids)| {
+                            let min_rank = unordered_term_ids
+                                .iter()
+                                .filter_map(|unordered_term_id| term_ranks.get(unordered_term_id))
+                                .min();
+                            if min_rank.is_none() {
+                                missing.insert(doc as DocId);
+                            }
+                            min_rank.copied().unwrap_or(0)
+                        })
+                        .collect();
+                    SortKeyValues::Values { values, missing }
+                }
+                FieldType::IpAddr(_) => SortKeyValues::IpAddrValues(
+                    fast_field_writers
+                        .get_u128_field_writer(field)
+                        .ok_or_else(not_a_fast_field)?
+                        .iter()
+                        .collect(),
+                ),
+                _ => SortKeyValues::Values {
+                    values: fast_field_writers
+                        .get_field_writer(field)
+                        .ok_or_else(not_a_fast_field)?
+                        .iter()
+                        .collect(),
+                    missing: BitSet::with_max_value(max_doc),
+                },
+            };
+            sort_values.push_key(sort_by_field.order.clone(), values);
+        }
+        Ok(sort_values)
+    }
+}
+
+// Returns the rank, in lexicographic order, of the terms of `field`
+// indexed in the segment being written.
+fn unordered_term_id_ranks(
+    segment_writer: &SegmentWriter,
+    field: Field,
+) -> FxHashMap<UnorderedTermId, u64> {
+    let mut terms: Vec<(&[u8], UnorderedTermId)> = segment_writer
+        .ctx
+        .term_index
+        .iter()
+        .filter(|(term_bytes, _, _)| Term::wrap(term_bytes).field() == field)
+        .map(|(term_bytes, _, unordered_term_id)| (term_bytes, unordered_term_id))
+        .collect();
+    terms.sort_unstable();
+    terms
+        .into_iter()
+        .enumerate()
+        .map(|(rank, (_, unordered_term_id))| (unordered_term_id, rank as u64))
+        .collect()
+}
+
 // Generates a document mapping in the form of [index new doc_id] -> old doc_id
 // TODO detect if field is already sorted and discard mapping
-pub(crate) fn get_doc_id_mapping_from_field(
-    sort_by_field: IndexSortByField,
+pub(crate) fn get_doc_id_mapping_from_fields(
+    sort_by_fields: &[IndexSortByField],
     segment_writer: &SegmentWriter,
 ) -> crate::Result<DocIdMapping> {
-    let schema = segment_writer.segment_serializer.segment().schema();
-    let field_id = expect_field_id_for_sort_field(&schema, &sort_by_field)?; // for now expect fastfield, but not strictly required
-    let fast_field = segment_writer
-        .fast_field_writers
-        .get_field_writer(field_id)
-        .ok_or_else(|| {
-            TantivyError::InvalidArgument(format!(
-                "sort index by field is required to be a fast field {:?}",
-                sort_by_field.field
-            ))
-        })?;
-
+    let sort_values = SegmentSortValues::for_segment_writer(segment_writer, sort_by_fields)?;
     // create new doc_id to old doc_id index (used in fast_field_writers)
-    let mut doc_id_and_data = fast_field
-        .iter()
-        .enumerate()
-        .map(|el| (el.0 as DocId, el.1))
-        .collect::<Vec<_>>();
-    if sort_by_field.order == Order::Desc {
-        doc_id_and_data.sort_by_key(|k| Reverse(k.1));
-    } else {
-        doc_id_and_data.sort_by_key(|k| k.1);
-    }
-    let new_doc_id_to_old = doc_id_and_data
-        .into_iter()
-        .map(|el| el.0)
-        .collect::<Vec<_>>();
+    let mut new_doc_id_to_old: Vec<DocId> = (0..segment_writer.max_doc).collect();
+    // The sort is stable: documents with the same sort values keep their insertion order.
+    new_doc_id_to_old.sort_by(|&left, &right| sort_values.cmp_docs(left, &sort_values, right));
     Ok(DocIdMapping::from_new_id_to_old_id(new_doc_id_to_old))
 }
 
@@ -149,7 +364,7 @@ mod tests_indexsorting {
     use crate::indexer::doc_id_mapping::DocIdMapping;
     use crate::query::QueryParser;
     use crate::schema::{Schema, *};
-    use crate::{DocAddress, Index, IndexSettings, IndexSortByField, Order};
+    use crate::{DateTime, DocAddress, Index, IndexSettings, IndexSortByField, Order};
 
     fn create_test_index(
         index_settings: Option<IndexSettings>,
@@ -227,10 +442,10 @@ mod tests_indexsorting {
             // sort by field asc
             let index = create_test_index(
                 Some(IndexSettings {
-                    sort_by_field: Some(IndexSortByField {
+                    sort_by: vec![IndexSortByField {
                         field: "my_number".to_string(),
                         order: Order::Asc,
-                    }),
+                    }],
                     ..Default::default()
                 }),
                 option.clone(),
@@ -259,10 +474,10 @@ mod tests_indexsorting {
             // sort by field desc
             let index = create_test_index(
                 Some(IndexSettings {
-                    sort_by_field: Some(IndexSortByField {
+                    sort_by: vec![IndexSortByField {
                         field: "my_number".to_string(),
                         order: Order::Desc,
-                    }),
+                    }],
                     ..Default::default()
                 }),
                 option.clone(),
@@ -318,10 +533,10 @@ mod tests_indexsorting {
         // sort by field asc
         let index = create_test_index(
             Some(IndexSettings {
-                sort_by_field: Some(IndexSortByField {
+                sort_by: vec![IndexSortByField {
                     field: "my_number".to_string(),
                     order: Order::Asc,
-                }),
+                }],
                 ..Default::default()
             }),
             get_text_options(),
@@ -343,10 +558,10 @@ mod tests_indexsorting {
         // sort by field desc
         let index = create_test_index(
             Some(IndexSettings {
-                sort_by_field: Some(IndexSortByField {
+                sort_by: vec![IndexSortByField {
                     field: "my_number".to_string(),
                     order: Order::Desc,
-                }),
+                }],
                 ..Default::default()
             }),
             get_text_options(),
@@ -378,10 +593,10 @@ mod tests_indexsorting {
 
         let index = create_test_index(
             Some(IndexSettings {
-                sort_by_field: Some(IndexSortByField {
+                sort_by: vec![IndexSortByField {
                     field: "my_number".to_string(),
                     order: Order::Asc,
-                }),
+                }],
                 ..Default::default()
             }),
             get_text_options(),
@@ -409,10 +624,10 @@ mod tests_indexsorting {
         // sort by field desc
         let index = create_test_index(
             Some(IndexSettings {
-                sort_by_field: Some(IndexSortByField {
+                sort_by: vec![IndexSortByField {
                     field: "my_number".to_string(),
                     order: Order::Desc,
-                }),
+                }],
                 ..Default::default()
             }),
             get_text_options(),
@@ -445,18 +660,15 @@ mod tests_indexsorting {
     fn test_sort_index_fast_field() -> crate::Result<()> {
         let index = create_test_index(
             Some(IndexSettings {
-                sort_by_field: Some(IndexSortByField {
+                sort_by: vec![IndexSortByField {
                     field: "my_number".to_string(),
                     order: Order::Asc,
-                }),
+                }],
                 ..Default::default()
             }),
             get_text_options(),
         )?;
-        assert_eq!(
-            index.settings().sort_by_field.as_ref().unwrap().field,
-            "my_number".to_string()
-        );
+        assert_eq!(index.settings().sort_by[0].field, "my_number".to_string());
 
         let searcher = index.reader()?.searcher();
         assert_eq!(searcher.segment_readers().len(), 1);
@@ -483,6 +695,58 @@ mod tests_indexsorting {
         Ok(())
     }
 
+    #[test]
+    fn test_sort_index_multiple_fields() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let tenant = schema_builder.add_text_field("tenant", STRING | FAST);
+        let timestamp = schema_builder.add_date_field(
+            "timestamp",
+            DateOptions::default().set_fast(Cardinality::SingleValue),
+        );
+        let id = schema_builder.add_u64_field(
+            "id",
+            NumericOptions::default().set_fast(Cardinality::SingleValue),
+        );
+        let schema = schema_builder.build();
+        let settings = IndexSettings {
+            sort_by: vec![
+                IndexSortByField {
+                    field: "tenant".to_string(),
+                    order: Order::Asc,
+                },
+                IndexSortByField {
+                    field: "timestamp".to_string(),
+                    order: Order::Desc,
+                },
+            ],
+            ..Default::default()
+        };
+        let index = Index::builder()
+            .schema(schema)
+            .settings(settings)
+            .create_in_ram()?;
+        let mut index_writer = index.writer_for_tests()?;
+        let date = DateTime::from_timestamp_secs;
+        index_writer.add_document(doc!(id=>0u64, tenant=>"b", timestamp=>date(10)))?;
+        index_writer.add_document(doc!(id=>1u64, timestamp=>date(50)))?;
+        index_writer.add_document(doc!(id=>2u64, tenant=>"a", timestamp=>date(10)))?;
+        index_writer.add_document(doc!(id=>3u64, tenant=>"b", timestamp=>date(30)))?;
+        index_writer.add_document(doc!(id=>4u64, tenant=>"a", timestamp=>date(20)))?;
+        index_writer.add_document(doc!(id=>5u64, tenant=>"b", timestamp=>date(30)))?;
+        index_writer.commit()?;
+
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let ids = searcher.segment_reader(0).fast_fields().u64("id")?;
+        // Documents with the same sort values keep their insertion order,
+        // and documents without a tenant come last.
+        assert_eq!(
+            (0..6).map(|doc| ids.get_val(doc)).collect::<Vec<_>>(),
+            vec![4, 2, 3, 5, 0, 1]
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_doc_mapping() {
         let doc_mapping = DocIdMapping::from_new_id_to_old_id(vec![3, 2, 5]);
@@ -9,7 +9,10 @@ use smallvec::smallvec;
 use super::operation::{AddOperation, UserOperation};
 use super::segment_updater::SegmentUpdater;
 use super::{AddBatch, AddBatchReceiver, AddBatchSender, PreparedCommit};
-use crate::core::{Index, Segment, SegmentComponent, SegmentId, SegmentMeta, SegmentReader};
+use crate::core::{
+    Index, IndexSnapshot, Segment, SegmentComponent, SegmentId, SegmentMeta, SegmentReader,
+    SoftDelete, SoftDeletes,
+};
 use crate::directory::{DirectoryLock, GarbageCollectionResult, TerminatingWrite};
 use crate::error::TantivyError;
 use crate::fastfield::write_alive_bitset;
@@ -18,10 +21,14 @@ use crate::indexer::doc_opstamp_mapping::DocToOpstampMapping;
 use crate::indexer::index_writer_status::IndexWriterStatus;
 use crate::indexer::operation::DeleteOperation;
 use crate::indexer::stamper::Stamper;
+use crate::indexer::tiered_merge_policy::{bytes_per_doc, estimated_num_bytes};
+use crate::indexer::write_ahead_log::{WalOperation, WalRecord, WriteAheadLog};
 use crate::indexer::{MergePolicy, SegmentEntry, SegmentWriter};
 use crate::query::{EnableScoring, Query, TermQuery};
 use crate::schema::{Document, IndexRecordOption, Term};
-use crate::{FutureResult, Opstamp};
+use crate::time::OffsetDateTime;
+use crate::vector::validate_vectors;
+use crate::{DateTime, FutureResult, Opstamp};
 
 // Size of the margin for the `memory_arena`. A segment is closed when the remaining memory
 // in the `memory_arena` goes below MARGIN_IN_BYTES.
@@ -51,6 +58,14 @@ fn error_in_index_worker_thread(context: &str) -> TantivyError {
 /// indexing queue.
 /// Each indexing thread builds its own independent [`Segment`], via
 /// a `SegmentWriter` object.
+///
+/// If the write-ahead log is enabled in the index settings
+/// (see [`IndexSettings::write_ahead_log`](crate::IndexSettings::write_ahead_log)),
+/// adds, deletes by term and updates are logged before they are applied,
+/// and the operations that were not committed are replayed when the next
+/// `IndexWriter` is created. Queries cannot be logged: deletes by query return an error,
+/// and the write-ahead log cannot be enabled along with soft deletes, which can
+/// be restored by query with [`IndexWriter::undelete`].
 pub struct IndexWriter {
     // the lock is just used to bind the
     // lifetime of the lock with that of the IndexWriter.
@@ -75,34 +90,65 @@ pub struct IndexWriter {
 
     stamper: Stamper,
     committed_opstamp: Opstamp,
+
+    write_ahead_log: Option<Arc<WriteAheadLog>>,
 }
 
 fn compute_deleted_bitset(
     alive_bitset: &mut BitSet,
+    mut soft_deletes_opt: Option<&mut SoftDeletes>,
     segment_reader: &SegmentReader,
     delete_cursor: &mut DeleteCursor,
     doc_opstamps: &DocToOpstampMapping,
     target_opstamp: Opstamp,
 ) -> crate::Result<bool> {
-    let mut might_have_changed = false;
+    let mut has_changed = false;
     while let Some(delete_op) = delete_cursor.get() {
         if delete_op.opstamp > target_opstamp {
             break;
         }
 
+        if delete_op.undelete {
+            // An undelete operation only restores documents that were soft-deleted.
+            if let Some(soft_deletes) = soft_deletes_opt.as_deref_mut() {
+                delete_op.target.for_each_no_score(
+                    segment_reader,
+                    &mut |doc_matching_undelete_query| {
+                        if doc_opstamps.is_deleted(doc_matching_undelete_query, delete_op.opstamp)
+                            && soft_deletes.remove(doc_matching_undelete_query).is_some()
+                        {
+                            alive_bitset.insert(doc_matching_undelete_query);
+                            has_changed = true;
+                        }
+                    },
+                )?;
+            }
+            delete_cursor.advance();
+            continue;
+        }
+
         // A delete operation should only affect
         // document that were inserted before it.
         delete_op
             .target
             .for_each_no_score(segment_reader, &mut |doc_matching_delete_query| {
-                if doc_opstamps.is_deleted(doc_matching_delete_query, delete_op.opstamp) {
+                if doc_opstamps.is_deleted(doc_matching_delete_query, delete_op.opstamp)
+                    && alive_bitset.contains(doc_matching_delete_query)
+                {
                     alive_bitset.remove(doc_matching_delete_query);
-                    might_have_changed = true;
+                    if let Some(soft_deletes) = soft_deletes_opt.as_deref_mut() {
+                        let soft_delete = SoftDelete {
+                            opstamp: delete_op.opstamp,
+                            timestamp: delete_op.timestamp,
+                        };
+                        soft_deletes.insert(doc_matching_delete_query, soft_delete);
+                    }
+                    has_changed = true;
                 }
             })?;
         delete_cursor.advance();
     }
-    Ok(might_have_changed)
+    Ok(has_changed)
 }
 
 /// Advance delete for the given segment up to the target opstamp.
@@ -133,35 +179,61 @@ pub(crate) fn advance_deletes(
         Some(previous_alive_bitset) => (*previous_alive_bitset).clone(),
         None => BitSet::with_max_value_and_full(max_doc),
     };
+    if let Some(seg_alive_bitset) = segment_reader.alive_bitset() {
+        alive_bitset.intersect_update(seg_alive_bitset.bitset());
+    }
+
+    let mut soft_deletes_opt = if segment.index().settings().soft_deletes_retention.is_some() {
+        Some(match segment_entry.soft_deletes() {
+            Some(previous_soft_deletes) => previous_soft_deletes.clone(),
+            None => segment_reader.soft_deletes().clone(),
+        })
+    } else {
+        None
+    };
 
     let num_deleted_docs_before = segment.meta().num_deleted_docs();
 
-    compute_deleted_bitset(
+    let has_changed = compute_deleted_bitset(
         &mut alive_bitset,
+        soft_deletes_opt.as_mut(),
         &segment_reader,
         segment_entry.delete_cursor(),
         &DocToOpstampMapping::None,
         target_opstamp,
     )?;
 
-    if let Some(seg_alive_bitset) = segment_reader.alive_bitset() {
-        alive_bitset.intersect_update(seg_alive_bitset.bitset());
-    }
-
     let num_alive_docs: u32 = alive_bitset.len() as u32;
     let num_deleted_docs = max_doc - num_alive_docs;
-    if num_deleted_docs > num_deleted_docs_before {
+    if has_changed || num_deleted_docs != num_deleted_docs_before {
         // There are new deletes. We need to write a new delete file.
         segment = segment.with_delete_meta(num_deleted_docs, target_opstamp);
         let mut alive_doc_file = segment.open_write(SegmentComponent::Delete)?;
         write_alive_bitset(&alive_bitset, &mut alive_doc_file)?;
         alive_doc_file.terminate()?;
+        if let Some(soft_deletes) = soft_deletes_opt {
+            segment = segment.with_num_soft_deleted_docs(soft_deletes.len() as u32);
+            write_soft_deletes(&mut segment, &soft_deletes)?;
+        }
+        // The deletes are now all reflected in the delete files.
+        segment_entry.clear_deletes();
     }
 
     segment_entry.set_meta(segment.meta().clone());
     Ok(())
 }
 
+/// Writes the soft deletes file of the segment.
+pub(crate) fn write_soft_deletes(
+    segment: &mut Segment,
+    soft_deletes: &SoftDeletes,
+) -> crate::Result<()> {
+    let mut soft_deletes_file = segment.open_write(SegmentComponent::SoftDeletes)?;
+    soft_deletes.serialize(&mut soft_deletes_file)?;
+    soft_deletes_file.terminate()?;
+    Ok(())
+}
+
 fn index_documents(
     memory_budget: usize,
     segment: Segment,
@@ -196,28 +268,33 @@ fn index_documents(
 
     let doc_opstamps: Vec<Opstamp> = segment_writer.finalize()?;
 
-    let segment_with_max_doc = segment.with_max_doc(max_doc);
+    let segment_with_max_doc = segment.with_max_doc(max_doc).with_num_bytes();
 
-    let alive_bitset_opt = apply_deletes(&segment_with_max_doc, &mut delete_cursor, &doc_opstamps)?;
+    let (alive_bitset_opt, soft_deletes_opt) =
+        apply_deletes(&segment_with_max_doc, &mut delete_cursor, &doc_opstamps)?;
 
     let meta = segment_with_max_doc.meta().clone();
     meta.untrack_temp_docstore();
     // update segment_updater inventory to remove tempstore
-    let segment_entry = SegmentEntry::new(meta, delete_cursor, alive_bitset_opt);
+    let segment_entry = SegmentEntry::new(meta, delete_cursor, alive_bitset_opt)
+        .with_soft_deletes(soft_deletes_opt);
     segment_updater.schedule_add_segment(segment_entry).wait()?;
     Ok(())
 }
 
 /// `doc_opstamps` is required to be non-empty.
+///
+/// Returns the alive bitset of the segment, along with its soft deletes
+/// if soft deletes are enabled.
 fn apply_deletes(
     segment: &Segment,
     delete_cursor: &mut DeleteCursor,
     doc_opstamps: &[Opstamp],
-) -> crate::Result<Option<BitSet>> {
+) -> crate::Result<(Option<BitSet>, Option<SoftDeletes>)> {
     if delete_cursor.get().is_none() {
         // if there are no delete operation in the queue, no need
         // to even open the segment.
-        return Ok(None);
+        return Ok((None, None));
     }
 
     let max_doc_opstamp: Opstamp = doc_opstamps
@@ -231,17 +308,24 @@ fn apply_deletes(
 
     let max_doc = segment.meta().max_doc();
     let mut deleted_bitset = BitSet::with_max_value_and_full(max_doc);
-    let may_have_deletes = compute_deleted_bitset(
+    let mut soft_deletes_opt = segment
+        .index()
+        .settings()
+        .soft_deletes_retention
+        .as_ref()
+        .map(|_| SoftDeletes::default());
+    let has_deletes = compute_deleted_bitset(
         &mut deleted_bitset,
+        soft_deletes_opt.as_mut(),
         &segment_reader,
         delete_cursor,
         &doc_to_opstamps,
         max_doc_opstamp,
     )?;
-    Ok(if may_have_deletes {
-        Some(deleted_bitset)
+    Ok(if has_deletes {
+        (Some(deleted_bitset), soft_deletes_opt)
     } else {
-        None
+        (None, None)
     })
 }
 
@@ -260,6 +344,8 @@ impl IndexWriter {
     /// If the lockfile already exists, returns `Error::FileAlreadyExists`.
     /// If the memory arena per thread is too small or too big, returns
     /// `TantivyError::InvalidArgument`
+    /// If both the write-ahead log and soft deletes are enabled in the index settings,
+    /// returns `TantivyError::InvalidArgument`
     pub(crate) fn new(
         index: &Index,
         num_threads: usize,
@@ -280,6 +366,14 @@ impl IndexWriter {
             );
             return Err(TantivyError::InvalidArgument(err_msg));
         }
+        let settings = index.settings();
+        if settings.write_ahead_log && settings.soft_deletes_retention.is_some() {
+            return Err(TantivyError::InvalidArgument(
+                "The write-ahead log cannot be enabled along with soft deletes: undeletes cannot \
+                 be logged."
+                    .to_string(),
+            ));
+        }
         let (document_sender, document_receiver): (AddBatchSender, AddBatchReceiver) =
             crossbeam_channel::bounded(PIPELINE_MAX_SIZE_IN_DOCS);
 
@@ -311,11 +405,77 @@ impl IndexWriter {
             stamper,
 
             worker_id: 0,
+
+            write_ahead_log: None,
         };
         index_writer.start_workers()?;
+        if index.settings().write_ahead_log {
+            let directory = index.directory().inner_directory().box_clone();
+            let (write_ahead_log, records) = WriteAheadLog::open(directory, current_opstamp)?;
+            // Replayed operations are not logged again: they remain in the files
+            // they were read from, until the next commit.
+            index_writer.replay(records)?;
+            index_writer.write_ahead_log = Some(Arc::new(write_ahead_log));
+        }
         Ok(index_writer)
     }
 
+    // Replays the records of the write-ahead log.
+    fn replay(&self, records: Vec<WalRecord>) -> crate::Result<()> {
+        for record in records {
+            match record {
+                WalRecord::Operations(operations) => {
+                    // A group that cannot be run was rejected the same way when it was
+                    // logged: none of its operations were applied.
+                    match self.run(operations) {
+                        Ok(_) => {}
+                        Err(err @ TantivyError::ErrorInThread(_)) => return Err(err),
+                        Err(err) => {
+                            warn!("Skipping operations of the write-ahead log: {:?}", err);
+                        }
+                    }
+                }
+                WalRecord::DeleteAllDocuments => {
+                    self.delete_all_documents()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write_ahead_log(&self) -> Option<&Arc<WriteAheadLog>> {
+        self.write_ahead_log.as_ref()
+    }
+
+    // Stamps operations with `stamp`, and appends them to the write-ahead log
+    // if it is enabled.
+    //
+    // The opstamps are taken while holding the lock of the log, so that
+    // the records are appended in the order of their opstamps.
+    fn stamp_and_log<T>(
+        &self,
+        operations: &[WalOperation],
+        stamp: impl FnOnce() -> (T, Opstamp),
+    ) -> crate::Result<T> {
+        let write_ahead_log = match &self.write_ahead_log {
+            Some(write_ahead_log) => write_ahead_log,
+            None => return Ok(stamp().0),
+        };
+        let mut wal_writer = write_ahead_log.lock();
+        let (stamped, opstamp) = stamp();
+        wal_writer.append_operations(opstamp, operations)?;
+        Ok(stamped)
+    }
+
+    fn check_query_deletes_allowed(&self) -> crate::Result<()> {
+        if self.write_ahead_log.is_some() {
+            return Err(TantivyError::InvalidArgument(
+                "Deletes by query cannot be logged in the write-ahead log.".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
     fn drop_sender(&mut self) {
         let (sender, _receiver) = crossbeam_channel::bounded(1);
         self.operation_sender = sender;
@@ -460,6 +620,14 @@ impl IndexWriter {
         self.segment_updater.schedule_garbage_collect()
     }
 
+    /// Creates a snapshot of the last commit of the index.
+    ///
+    /// The segment files of the snapshot are not garbage collected by this writer
+    /// until the snapshot is dropped. See [`Index::create_snapshot`].
+    pub fn snapshot(&self) -> crate::Result<IndexSnapshot> {
+        self.index.create_snapshot()
+    }
+
     /// Deletes all documents from the index
     ///
     /// Requires `commit`ing
@@ -497,6 +665,14 @@ impl IndexWriter {
     /// }
     /// ```
     pub fn delete_all_documents(&self) -> crate::Result<Opstamp> {
+        // The log stays locked until the stamper is reverted.
+        let mut wal_writer_opt = self
+            .write_ahead_log
+            .as_ref()
+            .map(|write_ahead_log| write_ahead_log.lock());
+        if let Some(wal_writer) = wal_writer_opt.as_mut() {
+            wal_writer.append_delete_all_documents(self.committed_opstamp)?;
+        }
         // Delete segments
         self.segment_updater.remove_all_segments();
         // Return new stamp - reverted stamp
@@ -515,6 +691,88 @@ impl IndexWriter {
         segment_updater.start_merge(merge_operation)
     }
 
+    /// Merges the committed segments until there are at most `max_num_segments` of them.
+    ///
+    /// The largest segments on disk are kept as is, unless they contain deleted documents,
+    /// and all of the other segments are merged together. If the index already has
+    /// at most `max_num_segments` segments and none of them have deleted documents,
+    /// no merge happens.
+    ///
+    /// Segments that are already being merged are ignored. Documents that have not been
+    /// committed yet are not affected.
+    ///
+    /// This call blocks until the merge is over, and returns the metas of the
+    /// resulting segments.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `max_num_segments` is 0.
+    pub fn force_merge(&mut self, max_num_segments: usize) -> crate::Result<Vec<SegmentMeta>> {
+        if max_num_segments == 0 {
+            return Err(TantivyError::InvalidArgument(
+                "max_num_segments must be greater than 0".to_string(),
+            ));
+        }
+        let (mut committed_segments, _) = self.segment_updater.get_mergeable_segments();
+        if committed_segments.len() <= max_num_segments
+            && committed_segments
+                .iter()
+                .all(|segment_meta| !segment_meta.has_deletes())
+        {
+            return Ok(Vec::new());
+        }
+        let bytes_per_doc = bytes_per_doc(&committed_segments);
+        committed_segments.sort_by_key(|segment_meta| {
+            std::cmp::Reverse(estimated_num_bytes(segment_meta, bytes_per_doc))
+        });
+        let mut segment_ids_to_merge = Vec::new();
+        let mut num_segments_kept = 0;
+        for segment_meta in &committed_segments {
+            if num_segments_kept + 1 < max_num_segments && !segment_meta.has_deletes() {
+                num_segments_kept += 1;
+            } else {
+                segment_ids_to_merge.push(segment_meta.id());
+            }
+        }
+        self.merge_and_wait(vec![segment_ids_to_merge])
+    }
+
+    /// Rewrites the committed segments that contain deleted documents, in order to
+    /// reclaim the space taken by these documents.
+    ///
+    /// Each of these segments is rewritten on its own. Segments that are
+    /// already being merged are ignored.
+    ///
+    /// Soft-deleted documents are reclaimed only once they are no longer retained,
+    /// see [`IndexSettings::soft_deletes_retention`](crate::IndexSettings::soft_deletes_retention).
+    ///
+    /// This call blocks until the merges are over, and returns the metas of the
+    /// resulting segments.
+    pub fn expunge_deletes(&mut self) -> crate::Result<Vec<SegmentMeta>> {
+        let (committed_segments, _) = self.segment_updater.get_mergeable_segments();
+        let merges = committed_segments
+            .iter()
+            .filter(|segment_meta| segment_meta.has_deletes())
+            .map(|segment_meta| vec![segment_meta.id()])
+            .collect();
+        self.merge_and_wait(merges)
+    }
+
+    fn merge_and_wait(&mut self, merges: Vec<Vec<SegmentId>>) -> crate::Result<Vec<SegmentMeta>> {
+        let merge_results: Vec<FutureResult<Option<SegmentMeta>>> = merges
+            .iter()
+            .filter(|segment_ids| !segment_ids.is_empty())
+            .map(|segment_ids| self.merge(segment_ids))
+            .collect();
+        let mut segment_metas = Vec::new();
+        for merge_result in merge_results {
+            if let Some(segment_meta) = merge_result.wait()? {
+                segment_metas.push(segment_meta);
+            }
+        }
+        Ok(segment_metas)
+    }
+
     /// Closes the current document channel send.
     /// and replace all the channels by new ones.
     ///
@@ -551,6 +809,11 @@ impl IndexWriter {
             .take()
             .expect("The IndexWriter does not have any lock. This is a bug, please report.");
 
+        // The operations that were not committed are discarded from the log too.
+        if let Some(write_ahead_log) = &self.write_ahead_log {
+            write_ahead_log.clear()?;
+        }
+
         let new_index_writer: IndexWriter = IndexWriter::new(
             &self.index,
             self.num_threads,
@@ -625,6 +888,11 @@ impl IndexWriter {
         }
 
         let commit_opstamp = self.stamper.stamp();
+        if let Some(write_ahead_log) = &self.write_ahead_log {
+            // The operations stamped from now on are logged in a new file,
+            // as they are not part of this commit.
+            write_ahead_log.rotate(commit_opstamp, self.index.load_metas()?.opstamp)?;
+        }
         let prepared_commit = PreparedCommit::new(self, commit_opstamp);
         info!("Prepared commit {}", commit_opstamp);
         Ok(prepared_commit)
@@ -659,12 +927,28 @@ impl IndexWriter {
     ///
     /// Like adds, the deletion itself will be visible
     /// only after calling `commit()`.
-    pub fn delete_term(&self, term: Term) -> Opstamp {
-        let query = TermQuery::new(term, IndexRecordOption::Basic);
+    ///
+    /// Returns an error, and the deletion is not applied, if the write-ahead log is
+    /// enabled and the deletion cannot be logged.
+    pub fn delete_term(&self, term: Term) -> crate::Result<Opstamp> {
+        let opstamp = self.log_delete_term(&term)?;
+        self.push_delete_term(term, opstamp);
+        Ok(opstamp)
+    }
+
+    fn log_delete_term(&self, term: &Term) -> crate::Result<Opstamp> {
+        self.stamp_and_log(&[WalOperation::Delete(term)], || {
+            let opstamp = self.stamper.stamp();
+            (opstamp, opstamp)
+        })
+    }
+
+    fn push_delete_term(&self, term: Term, opstamp: Opstamp) {
         // For backward compatibility, if Term is invalid for the index, do nothing but return an
         // Opstamp
-        self.delete_query(Box::new(query))
-            .unwrap_or_else(|_| self.stamper.stamp())
+        if let Ok(delete_operation) = self.delete_term_operation(term, opstamp) {
+            self.delete_queue.push(delete_operation);
+        }
     }
 
     /// Delete all documents matching a given query.
@@ -676,18 +960,49 @@ impl IndexWriter {
     ///
     /// Like adds, the deletion itself will be visible
     /// only after calling `commit()`.
+    ///
+    /// Returns an `Err` if the write-ahead log is enabled.
     #[doc(hidden)]
     pub fn delete_query(&self, query: Box<dyn Query>) -> crate::Result<Opstamp> {
+        self.check_query_deletes_allowed()?;
         let weight = query.weight(EnableScoring::disabled_from_schema(&self.index.schema()))?;
         let opstamp = self.stamper.stamp();
         let delete_operation = DeleteOperation {
             opstamp,
             target: weight,
+            timestamp: DateTime::from_utc(OffsetDateTime::now_utc()),
+            undelete: false,
         };
         self.delete_queue.push(delete_operation);
         Ok(opstamp)
     }
 
+    /// Restores the soft-deleted documents matching a given query.
+    ///
+    /// Soft deletes need to be enabled in the index settings,
+    /// see [`IndexSettings::soft_deletes_retention`](crate::IndexSettings::soft_deletes_retention).
+    /// Only the documents that are still retained in the index can be restored.
+    /// Returns an `Err` if soft deletes are disabled, or if the query can't be executed.
+    ///
+    /// Like deletes, the restoration will be visible only after calling `commit()`.
+    pub fn undelete(&self, query: Box<dyn Query>) -> crate::Result<Opstamp> {
+        if self.index.settings().soft_deletes_retention.is_none() {
+            return Err(TantivyError::InvalidArgument(
+                "Undelete requires soft deletes to be enabled in the index settings.".to_string(),
+            ));
+        }
+        let weight = query.weight(EnableScoring::disabled_from_schema(&self.index.schema()))?;
+        let opstamp = self.stamper.stamp();
+        let undelete_operation = DeleteOperation {
+            opstamp,
+            target: weight,
+            timestamp: DateTime::from_utc(OffsetDateTime::now_utc()),
+            undelete: true,
+        };
+        self.delete_queue.push(undelete_operation);
+        Ok(opstamp)
+    }
+
     /// Returns the opstamp of the last successful commit.
     ///
     /// This is, for instance, the opstamp the index will
@@ -706,12 +1021,71 @@ impl IndexWriter {
     /// The opstamp is an increasing `u64` that can
     /// be used by the client to align commits with its own
     /// document queue.
+    ///
+    /// Returns an error if one of the vectors of the document does not match
+    /// the options of its field.
     pub fn add_document(&self, document: Document) -> crate::Result<Opstamp> {
-        let opstamp = self.stamper.stamp();
+        validate_vectors(&self.index.schema(), &document)?;
+        let opstamp = self.stamp_and_log(&[WalOperation::Add(&document)], || {
+            let opstamp = self.stamper.stamp();
+            (opstamp, opstamp)
+        })?;
+        self.send_add_documents_batch(smallvec![AddOperation { opstamp, document }])?;
+        Ok(opstamp)
+    }
+
+    /// Replaces the documents containing the `key` term by a new document.
+    ///
+    /// The deletion of the documents containing `key` and the addition
+    /// of `document` share the same opstamp: the deletion affects all of the
+    /// documents added before this operation, but never `document` itself,
+    /// even if it contains `key`.
+    ///
+    /// Like adds and deletes, the update will be visible
+    /// only after calling `commit()`, and both of its parts
+    /// will be part of the same commit.
+    ///
+    /// Returns an error if one of the vectors of the document does not match
+    /// the options of its field.
+    pub fn update_document(&self, key: Term, document: Document) -> crate::Result<Opstamp> {
+        validate_vectors(&self.index.schema(), &document)?;
+        let opstamp = self.stamp_and_log(&[WalOperation::Update(&key, &document)], || {
+            let opstamp = self.stamper.stamp();
+            (opstamp, opstamp)
+        })?;
+        let query = TermQuery::new(key, IndexRecordOption::Basic);
+        let delete_operation = self.delete_operation(&query, opstamp)?;
+        self.delete_queue.push(delete_operation);
         self.send_add_documents_batch(smallvec![AddOperation { opstamp, document }])?;
         Ok(opstamp)
     }
 
+    // Builds the deletion of the documents matching `query`.
+    //
+    // The deletion only affects documents with an opstamp strictly lower than `opstamp`.
+    fn delete_operation(
+        &self,
+        query: &dyn Query,
+        opstamp: Opstamp,
+    ) -> crate::Result<DeleteOperation> {
+        let weight = query.weight(EnableScoring::disabled_from_schema(&self.index.schema()))?;
+        Ok(DeleteOperation {
+            opstamp,
+            target: weight,
+            timestamp: DateTime::from_utc(OffsetDateTime::now_utc()),
+            undelete: false,
+        })
+    }
+
+    fn delete_term_operation(
+        &self,
+        term: Term,
+        opstamp: Opstamp,
+    ) -> crate::Result<DeleteOperation> {
+        let query = TermQuery::new(term, IndexRecordOption::Basic);
+        self.delete_operation(&query, opstamp)
+    }
+
     /// Gets a range of stamps from the stamper and "pops" the last stamp
     /// from the range returning a tuple of the last optstamp and the popped
     /// range.
@@ -738,41 +1112,75 @@ impl IndexWriter {
     /// `user_operations`, an empty `Vec<UserOperation>`, still receives
     /// a valid opstamp even though no changes were _actually_ made to the index.
     ///
-    /// Like adds and deletes (see `IndexWriter.add_document` and
-    /// `IndexWriter.delete_term`), the changes made by calling `run` will be
-    /// visible to readers only after calling `commit()`.
+    /// Deletes, including the deletes of updates, only affect the documents added
+    /// before them, be it in previous commits or earlier in the same group.
+    /// If the query of one of the deletes cannot be executed, or if one of the
+    /// vectors of a document does not match the options of its field, an error is
+    /// returned and none of the operations of the group are applied.
+    ///
+    /// Like adds, deletes and updates (see `IndexWriter.add_document`,
+    /// `IndexWriter.delete_term` and `IndexWriter.update_document`), the changes
+    /// made by calling `run` will be visible to readers only after calling `commit()`.
     pub fn run<I>(&self, user_operations: I) -> crate::Result<Opstamp>
     where
         I: IntoIterator<Item = UserOperation>,
         I::IntoIter: ExactSizeIterator,
     {
-        let user_operations_it = user_operations.into_iter();
-        let count = user_operations_it.len() as u64;
+        let user_operations: Vec<UserOperation> = user_operations.into_iter().collect();
+        let count = user_operations.len() as u64;
         if count == 0 {
             return Ok(self.stamper.stamp());
         }
-        let (batch_opstamp, stamps) = self.get_batch_opstamps(count);
+        let schema = self.index.schema();
+        for user_operation in &user_operations {
+            if let UserOperation::Add(document) | UserOperation::Update(_, document) =
+                user_operation
+            {
+                validate_vectors(&schema, document)?;
+            }
+        }
+        let mut wal_operations = Vec::new();
+        if self.write_ahead_log.is_some() {
+            for user_operation in &user_operations {
+                match WalOperation::from_user_operation(user_operation) {
+                    Some(wal_operation) => wal_operations.push(wal_operation),
+                    None => self.check_query_deletes_allowed()?,
+                }
+            }
+        }
+        let (batch_opstamp, stamps) = self.stamp_and_log(&wal_operations, || {
+            let (batch_opstamp, stamps) = self.get_batch_opstamps(count);
+            let first_opstamp = stamps.start;
+            ((batch_opstamp, stamps), first_opstamp)
+        })?;
 
         let mut adds = AddBatch::default();
+        let mut deletes = Vec::new();
 
-        for (user_op, opstamp) in user_operations_it.zip(stamps) {
+        for (user_op, opstamp) in user_operations.into_iter().zip(stamps) {
             match user_op {
                 UserOperation::Delete(term) => {
-                    let query = TermQuery::new(term, IndexRecordOption::Basic);
-                    let weight =
-                        query.weight(EnableScoring::disabled_from_schema(&self.index.schema()))?;
-                    let delete_operation = DeleteOperation {
-                        opstamp,
-                        target: weight,
-                    };
-                    self.delete_queue.push(delete_operation);
+                    deletes.push(self.delete_term_operation(term, opstamp)?);
+                }
+                UserOperation::DeleteQuery(query) => {
+                    deletes.push(self.delete_operation(query.as_ref(), opstamp)?);
                 }
                 UserOperation::Add(document) => {
                     let add_operation = AddOperation { opstamp, document };
                     adds.push(add_operation);
                 }
+                UserOperation::Update(term, document) => {
+                    deletes.push(self.delete_term_operation(term, opstamp)?);
+                    let add_operation = AddOperation { opstamp, document };
+                    adds.push(add_operation);
+                }
             }
         }
+        // The deletes are only pushed once all of them have been built,
+        // so that the group is applied entirely or not at all.
+        for delete_operation in deletes {
+            self.delete_queue.push(delete_operation);
+        }
         self.send_add_documents_batch(adds)?;
         Ok(batch_opstamp)
     }
@@ -807,18 +1215,19 @@ mod tests {
     use proptest::strategy::Strategy;
 
     use super::super::operation::UserOperation;
-    use crate::collector::TopDocs;
+    use crate::collector::{Count, TopDocs};
     use crate::directory::error::LockError;
     use crate::error::*;
     use crate::indexer::NoMergePolicy;
-    use crate::query::{BooleanQuery, Occur, Query, QueryParser, TermQuery};
+    use crate::query::{AllQuery, BooleanQuery, Occur, Query, QueryParser, RangeQuery, TermQuery};
     use crate::schema::{
         self, Cardinality, Facet, FacetOptions, IndexRecordOption, IpAddrOptions, NumericOptions,
-        TextFieldIndexing, TextOptions, FAST, INDEXED, STORED, STRING, TEXT,
+        TextFieldIndexing, TextOptions, VectorOptions, FAST, INDEXED, STORED, STRING, TEXT,
     };
     use crate::store::DOCSTORE_CACHE_CAPACITY;
     use crate::{
-        DateTime, DocAddress, Index, IndexSettings, IndexSortByField, Order, ReloadPolicy, Term,
+        DateTime, DocAddress, Index, IndexSettings, IndexSortByField, Order, ReloadPolicy,
+        SoftDeletesRetention, Term,
     };
 
     const LOREM: &str = "Doc Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do \
@@ -864,7 +1273,9 @@ mod tests {
         assert_eq!(searcher.segment_readers().len(), 1);
         assert_eq!(searcher.segment_reader(0u32).num_docs(), 2);
 
-        index_writer.delete_term(Term::from_field_text(text_field, "hello1"));
+        index_writer
+            .delete_term(Term::from_field_text(text_field, "hello1"))
+            .unwrap();
         assert!(index_writer.commit().is_ok());
 
         assert!(reader.reload().is_ok());
@@ -876,7 +1287,9 @@ mod tests {
 
         // All docs containing hello1 have been already removed.
         // We should not update the delete meta.
-        index_writer.delete_term(Term::from_field_text(text_field, "hello1"));
+        index_writer
+            .delete_term(Term::from_field_text(text_field, "hello1"))
+            .unwrap();
         assert!(index_writer.commit().is_ok());
 
         assert!(reader.reload().is_ok());
@@ -937,6 +1350,559 @@ mod tests {
         assert_eq!(b_docs.len(), 0);
     }
 
+    #[test]
+    fn test_update_document() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let id_field = schema_builder.add_text_field("id", STRING);
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(id_field=>"1", text_field=>"first"))?;
+        index_writer.add_document(doc!(id_field=>"2", text_field=>"first"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(id_field=>"1", text_field=>"second"))?;
+        let key = Term::from_field_text(id_field, "1");
+        index_writer.update_document(key.clone(), doc!(id_field=>"1", text_field=>"third"))?;
+        // Updating a key that does not exist yet adds the document.
+        let new_key = Term::from_field_text(id_field, "3");
+        index_writer.update_document(new_key.clone(), doc!(id_field=>"3", text_field=>"third"))?;
+        index_writer.commit()?;
+
+        let searcher = index.reader()?.searcher();
+        let count = |term: &Term| {
+            let query = TermQuery::new(term.clone(), IndexRecordOption::Basic);
+            searcher.search(&query, &Count).unwrap()
+        };
+        assert_eq!(searcher.num_docs(), 3);
+        assert_eq!(count(&key), 1);
+        assert_eq!(count(&new_key), 1);
+        assert_eq!(count(&Term::from_field_text(text_field, "third")), 2);
+        assert_eq!(count(&Term::from_field_text(text_field, "second")), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_document_in_operations_group() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let id_field = schema_builder.add_text_field("id", STRING);
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        let key = Term::from_field_text(id_field, "1");
+        let operations = vec![
+            UserOperation::Add(doc!(id_field=>"1", text_field=>"first")),
+            UserOperation::Update(key.clone(), doc!(id_field=>"1", text_field=>"second")),
+            UserOperation::Update(key.clone(), doc!(id_field=>"1", text_field=>"third")),
+            UserOperation::Add(doc!(id_field=>"1", text_field=>"fourth")),
+        ];
+        index_writer.run(operations)?;
+        index_writer.commit()?;
+
+        let searcher = index.reader()?.searcher();
+        let count = |term: Term| {
+            let query = TermQuery::new(term, IndexRecordOption::Basic);
+            searcher.search(&query, &Count).unwrap()
+        };
+        assert_eq!(count(key), 2);
+        assert_eq!(count(Term::from_field_text(text_field, "third")), 1);
+        assert_eq!(count(Term::from_field_text(text_field, "fourth")), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_query_in_operations_group() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let tenant_field = schema_builder.add_text_field("tenant", STRING);
+        let timestamp_field = schema_builder.add_u64_field("timestamp", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        for timestamp in 0..4u64 {
+            index_writer.add_document(doc!(tenant_field=>"x", timestamp_field=>timestamp))?;
+            index_writer.add_document(doc!(tenant_field=>"y", timestamp_field=>timestamp))?;
+        }
+        index_writer.commit()?;
+
+        let old_docs_of_tenant_x: Box<dyn Query> = Box::new(BooleanQuery::intersection(vec![
+            Box::new(TermQuery::new(
+                Term::from_field_text(tenant_field, "x"),
+                IndexRecordOption::Basic,
+            )),
+            Box::new(RangeQuery::new_u64("timestamp".to_string(), 0..10)),
+        ]));
+        let operations = vec![
+            UserOperation::Add(doc!(tenant_field=>"x", timestamp_field=>5u64)),
+            UserOperation::DeleteQuery(old_docs_of_tenant_x),
+            UserOperation::Add(doc!(tenant_field=>"x", timestamp_field=>2u64)),
+            UserOperation::Add(doc!(tenant_field=>"x", timestamp_field=>3u64)),
+        ];
+        index_writer.run(operations)?;
+        index_writer.commit()?;
+
+        let searcher = index.reader()?.searcher();
+        let count = |term: Term| {
+            let query = TermQuery::new(term, IndexRecordOption::Basic);
+            searcher.search(&query, &Count).unwrap()
+        };
+        assert_eq!(count(Term::from_field_text(tenant_field, "x")), 2);
+        assert_eq!(count(Term::from_field_text(tenant_field, "y")), 4);
+        assert_eq!(count(Term::from_field_u64(timestamp_field, 2)), 2);
+        assert_eq!(count(Term::from_field_u64(timestamp_field, 5)), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_query_in_operations_group_error() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let id_field = schema_builder.add_text_field("id", STRING);
+        let stored_field = schema_builder.add_text_field("stored", STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(id_field=>"1"))?;
+        index_writer.commit()?;
+
+        // The field is not indexed, so the query cannot be executed.
+        let invalid_query: Box<dyn Query> = Box::new(TermQuery::new(
+            Term::from_field_text(stored_field, "a"),
+            IndexRecordOption::Basic,
+        ));
+        let operations = vec![
+            UserOperation::Delete(Term::from_field_text(id_field, "1")),
+            UserOperation::Add(doc!(id_field=>"2")),
+            UserOperation::DeleteQuery(invalid_query),
+        ];
+        assert!(index_writer.run(operations).is_err());
+        index_writer.commit()?;
+
+        let searcher = index.reader()?.searcher();
+        let count = |term: Term| {
+            let query = TermQuery::new(term, IndexRecordOption::Basic);
+            searcher.search(&query, &Count).unwrap()
+        };
+        assert_eq!(count(Term::from_field_text(id_field, "1")), 1);
+        assert_eq!(count(Term::from_field_text(id_field, "2")), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_vector_is_rejected() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let id_field = schema_builder.add_text_field("id", STRING);
+        let embedding = schema_builder.add_vector_field("embedding", VectorOptions::new(2));
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        let doc_with_vector = |id: &str, vector: Vec<f32>| {
+            let mut doc = doc!(id_field=>id);
+            doc.add_vector(embedding, vector);
+            doc
+        };
+        index_writer.add_document(doc_with_vector("1", vec![1.0, 2.0]))?;
+        assert!(matches!(
+            index_writer.add_document(doc_with_vector("2", vec![1.0, 2.0, 3.0])),
+            Err(TantivyError::SchemaError(_))
+        ));
+        assert!(index_writer
+            .update_document(
+                Term::from_field_text(id_field, "1"),
+                doc_with_vector("3", vec![1.0])
+            )
+            .is_err());
+        let operations = vec![
+            UserOperation::Add(doc_with_vector("4", vec![3.0, 4.0])),
+            UserOperation::Add(doc_with_vector("5", vec![])),
+        ];
+        assert!(index_writer.run(operations).is_err());
+        index_writer.add_document(doc_with_vector("6", vec![5.0, 6.0]))?;
+        index_writer.commit()?;
+
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.num_docs(), 2);
+        let count = |id: &str| {
+            let query = TermQuery::new(
+                Term::from_field_text(id_field, id),
+                IndexRecordOption::Basic,
+            );
+            searcher.search(&query, &Count).unwrap()
+        };
+        assert_eq!(count("1"), 1);
+        assert_eq!(count("6"), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_force_merge() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        for segment_ord in 0..4u64 {
+            for _ in 0..=segment_ord {
+                index_writer.add_document(doc!(text_field=>format!("doc{}", segment_ord)))?;
+            }
+            index_writer.commit()?;
+        }
+        assert_eq!(index.searchable_segment_metas()?.len(), 4);
+        assert!(index
+            .searchable_segment_metas()?
+            .iter()
+            .all(|segment_meta| segment_meta.num_bytes().unwrap() > 0));
+
+        let merged_segment_metas = index_writer.force_merge(2)?;
+        assert_eq!(merged_segment_metas.len(), 1);
+        assert_eq!(merged_segment_metas[0].num_docs(), 6);
+        assert!(merged_segment_metas[0].num_bytes().is_some());
+        let mut num_docs: Vec<u32> = index
+            .searchable_segment_metas()?
+            .iter()
+            .map(|segment_meta| segment_meta.num_docs())
+            .collect();
+        num_docs.sort_unstable();
+        assert_eq!(num_docs, vec![4, 6]);
+
+        // Nothing left to merge.
+        assert!(index_writer.force_merge(2)?.is_empty());
+
+        assert!(matches!(
+            index_writer.force_merge(0),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+
+        index_writer.force_merge(1)?;
+        assert_eq!(index.searchable_segment_metas()?.len(), 1);
+        index_writer.wait_merging_threads()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_force_merge_keeps_the_largest_segments_on_disk() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text", STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        // Pseudo-random text, so that the doc store cannot compress it.
+        let mut state = 1u64;
+        let large_text: String = (0..100_000)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                char::from(b'a' + (state >> 59) as u8 % 26)
+            })
+            .collect();
+        index_writer.add_document(doc!(text_field=>large_text))?;
+        index_writer.commit()?;
+        for _ in 0..2 {
+            for _ in 0..10 {
+                index_writer.add_document(doc!(text_field=>"small"))?;
+            }
+            index_writer.commit()?;
+        }
+
+        let merged_segment_metas = index_writer.force_merge(2)?;
+        assert_eq!(merged_segment_metas.len(), 1);
+        assert_eq!(merged_segment_metas[0].num_docs(), 20);
+        let mut num_docs: Vec<u32> = index
+            .searchable_segment_metas()?
+            .iter()
+            .map(|segment_meta| segment_meta.num_docs())
+            .collect();
+        num_docs.sort_unstable();
+        assert_eq!(num_docs, vec![1, 20]);
+        index_writer.wait_merging_threads()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_expunge_deletes() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        for text in ["a", "b", "c"] {
+            index_writer.add_document(doc!(text_field=>text))?;
+            index_writer.add_document(doc!(text_field=>"z"))?;
+            index_writer.commit()?;
+        }
+        index_writer.delete_term(Term::from_field_text(text_field, "a"))?;
+        index_writer.delete_term(Term::from_field_text(text_field, "b"))?;
+        index_writer.commit()?;
+
+        let merged_segment_metas = index_writer.expunge_deletes()?;
+        assert_eq!(merged_segment_metas.len(), 2);
+        let segment_metas = index.searchable_segment_metas()?;
+        assert_eq!(segment_metas.len(), 3);
+        assert!(segment_metas
+            .iter()
+            .all(|segment_meta| !segment_meta.has_deletes()));
+        assert_eq!(
+            segment_metas
+                .iter()
+                .map(|segment_meta| segment_meta.max_doc())
+                .sum::<u32>(),
+            4
+        );
+        index_writer.wait_merging_threads()?;
+        Ok(())
+    }
+
+    fn create_soft_deletes_index(retention: SoftDeletesRetention) -> crate::Result<Index> {
+        let mut schema_builder = schema::Schema::builder();
+        schema_builder.add_text_field("id", STRING | STORED);
+        let settings = IndexSettings {
+            soft_deletes_retention: Some(retention),
+            ..IndexSettings::default()
+        };
+        Index::builder()
+            .schema(schema_builder.build())
+            .settings(settings)
+            .create_in_ram()
+    }
+
+    #[test]
+    fn test_soft_deletes() -> crate::Result<()> {
+        let index = create_soft_deletes_index(SoftDeletesRetention::Opstamps(100))?;
+        let id_field = index.schema().get_field("id").unwrap();
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        index_writer.add_document(doc!(id_field=>"a"))?;
+        index_writer.add_document(doc!(id_field=>"b"))?;
+        index_writer.commit()?;
+        // "b" is deleted from a committed segment, "c" from the segment being written.
+        index_writer.add_document(doc!(id_field=>"c"))?;
+        let delete_b_opstamp = index_writer.delete_term(Term::from_field_text(id_field, "b"))?;
+        index_writer.delete_term(Term::from_field_text(id_field, "c"))?;
+        index_writer.commit()?;
+
+        let reader = index.reader()?;
+        let count = |searcher: &crate::Searcher, id: &str| {
+            let query = TermQuery::new(
+                Term::from_field_text(id_field, id),
+                IndexRecordOption::Basic,
+            );
+            searcher.search(&query, &Count).unwrap()
+        };
+        let searcher = reader.searcher();
+        assert_eq!(count(&searcher, "a"), 1);
+        assert_eq!(count(&searcher, "b"), 0);
+        assert_eq!(count(&searcher, "c"), 0);
+        let mut soft_deleted_ids = Vec::new();
+        for segment_reader in searcher.segment_readers() {
+            assert_eq!(
+                segment_reader.soft_deletes().len() as u32,
+                segment_reader.num_deleted_docs()
+            );
+            let store_reader = segment_reader.get_store_reader(0)?;
+            for (doc, soft_delete) in segment_reader.soft_deletes().iter() {
+                let doc = store_reader.get(doc)?;
+                let id = doc.get_first(id_field).unwrap().as_text().unwrap();
+                soft_deleted_ids.push((id.to_string(), soft_delete.opstamp));
+            }
+        }
+        soft_deleted_ids.sort();
+        assert_eq!(
+            soft_deleted_ids,
+            vec![
+                ("b".to_string(), delete_b_opstamp),
+                ("c".to_string(), delete_b_opstamp + 1)
+            ]
+        );
+
+        index_writer.undelete(Box::new(TermQuery::new(
+            Term::from_field_text(id_field, "b"),
+            IndexRecordOption::Basic,
+        )))?;
+        index_writer.commit()?;
+        reader.reload()?;
+        let searcher = reader.searcher();
+        assert_eq!(count(&searcher, "b"), 1);
+        assert_eq!(count(&searcher, "c"), 0);
+        let num_soft_deletes: usize = searcher
+            .segment_readers()
+            .iter()
+            .map(|segment_reader| segment_reader.soft_deletes().len())
+            .sum();
+        assert_eq!(num_soft_deletes, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_soft_deletes_retained_through_merge() -> crate::Result<()> {
+        let index = create_soft_deletes_index(SoftDeletesRetention::Opstamps(5))?;
+        let id_field = index.schema().get_field("id").unwrap();
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        for id in ["a", "b", "c"] {
+            index_writer.add_document(doc!(id_field=>id))?;
+            index_writer.commit()?;
+        }
+        index_writer.delete_term(Term::from_field_text(id_field, "a"))?;
+        index_writer.commit()?;
+
+        // The soft-deleted document is retained through the merge.
+        let segment_metas = index_writer.force_merge(1)?;
+        assert_eq!(segment_metas.len(), 1);
+        assert_eq!(segment_metas[0].max_doc(), 3);
+        assert_eq!(segment_metas[0].num_deleted_docs(), 1);
+        assert_eq!(segment_metas[0].num_soft_deleted_docs(), 1);
+
+        // ... and can still be restored.
+        index_writer.undelete(Box::new(TermQuery::new(
+            Term::from_field_text(id_field, "a"),
+            IndexRecordOption::Basic,
+        )))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.search(&AllQuery, &Count)?, 3);
+
+        // Once it is no longer retained, a merge drops it.
+        index_writer.delete_term(Term::from_field_text(id_field, "b"))?;
+        for id in ["d", "e", "f", "g", "h"] {
+            index_writer.add_document(doc!(id_field=>id))?;
+        }
+        index_writer.commit()?;
+        let segment_metas = index_writer.force_merge(1)?;
+        assert_eq!(segment_metas.len(), 1);
+        assert_eq!(segment_metas[0].max_doc(), 7);
+        assert!(!segment_metas[0].has_deletes());
+        index_writer.wait_merging_threads()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_undelete_without_soft_deletes_error() {
+        let mut schema_builder = schema::Schema::builder();
+        let id_field = schema_builder.add_text_field("id", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let index_writer = index.writer_for_tests().unwrap();
+        let query = TermQuery::new(
+            Term::from_field_text(id_field, "a"),
+            IndexRecordOption::Basic,
+        );
+        assert!(matches!(
+            index_writer.undelete(Box::new(query)),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_write_ahead_log_with_soft_deletes_is_rejected() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        schema_builder.add_text_field("id", STRING);
+        let settings = IndexSettings {
+            write_ahead_log: true,
+            soft_deletes_retention: Some(SoftDeletesRetention::Seconds(3600)),
+            ..IndexSettings::default()
+        };
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(settings)
+            .create_in_ram()?;
+        assert!(matches!(
+            index.writer_for_tests(),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_ahead_log_replay() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let id_field = schema_builder.add_text_field("id", STRING);
+        let settings = IndexSettings {
+            write_ahead_log: true,
+            ..IndexSettings::default()
+        };
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(settings)
+            .create_in_ram()?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let num_docs = |id: &str| {
+            reader.reload().unwrap();
+            let query = TermQuery::new(
+                Term::from_field_text(id_field, id),
+                IndexRecordOption::Basic,
+            );
+            reader.searcher().search(&query, &Count).unwrap()
+        };
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            index_writer.add_document(doc!(id_field=>"a"))?;
+            index_writer.commit()?;
+            index_writer.add_document(doc!(id_field=>"b"))?;
+            index_writer.run(vec![
+                UserOperation::Add(doc!(id_field=>"c")),
+                UserOperation::Delete(Term::from_field_text(id_field, "a")),
+            ])?;
+            assert!(matches!(
+                index_writer.delete_query(Box::new(AllQuery)),
+                Err(TantivyError::InvalidArgument(_))
+            ));
+            // The writer is dropped without committing, as if the process crashed.
+        }
+        assert_eq!(num_docs("a"), 1);
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            index_writer.commit()?;
+        }
+        assert_eq!(num_docs("a"), 0);
+        assert_eq!(num_docs("b"), 1);
+        assert_eq!(num_docs("c"), 1);
+
+        // Committed operations are not replayed, and rolled back operations are discarded.
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(id_field=>"d"))?;
+        index_writer.rollback()?;
+        drop(index_writer);
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.commit()?;
+        assert_eq!(reader.searcher().num_docs(), 2);
+        assert_eq!(num_docs("d"), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_term_failing_to_be_logged() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let id_field = schema_builder.add_text_field("id", STRING);
+        let settings = IndexSettings {
+            write_ahead_log: true,
+            ..IndexSettings::default()
+        };
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(settings)
+            .create_in_ram()?;
+        let mut index_writer = index.writer_for_tests()?;
+        index_writer.add_document(doc!(id_field=>"a"))?;
+        index_writer.add_document(doc!(id_field=>"b"))?;
+        index_writer.commit()?;
+
+        // Records cannot be appended to a cleared log.
+        index_writer.write_ahead_log().unwrap().clear()?;
+        assert!(index_writer
+            .delete_term(Term::from_field_text(id_field, "a"))
+            .is_err());
+        index_writer.commit()?;
+
+        let searcher = index.reader()?.searcher();
+        let count = |id: &str| {
+            let query = TermQuery::new(
+                Term::from_field_text(id_field, id),
+                IndexRecordOption::Basic,
+            );
+            searcher.search(&query, &Count).unwrap()
+        };
+        assert_eq!(count("a"), 1);
+        assert_eq!(count("b"), 1);
+        Ok(())
+    }
+
     #[test]
     fn test_empty_operations_group() {
         let schema_builder = schema::Schema::builder();
@@ -1065,7 +2031,7 @@ mod tests {
         reader.reload().unwrap();
         assert_eq!(num_docs_containing("a"), 1);
 
-        index_writer.delete_term(Term::from_field_text(text_field, "a"));
+        index_writer.delete_term(Term::from_field_text(text_field, "a"))?;
         index_writer.commit()?;
 
         reader.reload().unwrap();
@@ -1111,7 +2077,7 @@ mod tests {
         reader.reload().unwrap();
         assert_eq!(num_docs_containing("a"), 4);
 
-        index_writer.delete_term(Term::from_field_text(text_field, "a"));
+        index_writer.delete_term(Term::from_field_text(text_field, "a"))?;
         index_writer.commit()?;
 
         reader.reload().unwrap();
@@ -1403,10 +2369,10 @@ mod tests {
         let schema = schema_builder.build();
 
         let settings = IndexSettings {
-            sort_by_field: Some(IndexSortByField {
+            sort_by: vec![IndexSortByField {
                 field: "id".to_string(),
                 order: Order::Desc,
-            }),
+            }],
             ..Default::default()
         };
 
@@ -1432,10 +2398,10 @@ mod tests {
         let schema = schema_builder.build();
 
         let settings = IndexSettings {
-            sort_by_field: Some(IndexSortByField {
+            sort_by: vec![IndexSortByField {
                 field: "id".to_string(),
                 order: Order::Desc,
-            }),
+            }],
             ..Default::default()
         };
 
@@ -1451,7 +2417,7 @@ mod tests {
             index_writer.add_document(doc!(id_field => id))?;
         }
         for id in 2u64..4u64 {
-            index_writer.delete_term(Term::from_field_u64(id_field, id));
+            index_writer.delete_term(Term::from_field_u64(id_field, id))?;
         }
         for id in 5u64..10u64 {
             index_writer.add_document(doc!(id_field => id))?;
@@ -1482,10 +2448,10 @@ mod tests {
         let schema = schema_builder.build();
 
         let settings = IndexSettings {
-            sort_by_field: Some(IndexSortByField {
+            sort_by: vec![IndexSortByField {
                 field: "id".to_string(),
                 order: Order::Desc,
-            }),
+            }],
             ..Default::default()
         };
 
@@ -1654,10 +2620,10 @@ mod tests {
         let schema = schema_builder.build();
         let settings = if sort_index {
             IndexSettings {
-                sort_by_field: Some(IndexSortByField {
+                sort_by: vec![IndexSortByField {
                     field: "id".to_string(),
                     order: Order::Asc,
-                }),
+                }],
                 ..Default::default()
             }
         } else {
@@ -1733,7 +2699,9 @@ mod tests {
                     }
                 }
                 IndexingOp::DeleteDoc { id } => {
-                    index_writer.delete_term(Term::from_field_u64(id_field, id));
+                    index_writer
+                        .delete_term(Term::from_field_u64(id_field, id))
+                        .unwrap();
                 }
                 IndexingOp::DeleteDocQuery { id } => {
                     let term = Term::from_field_u64(id_field, id);
@@ -2238,10 +3206,10 @@ mod tests {
         let schema = schema_builder.build();
 
         let settings = IndexSettings {
-            sort_by_field: Some(IndexSortByField {
+            sort_by: vec![IndexSortByField {
                 field: "sort_by".to_string(),
                 order: Order::Asc,
-            }),
+            }],
             ..Default::default()
         };
 
@@ -2254,7 +3222,7 @@ mod tests {
         // We add a doc...
         index_writer.add_document(doc!(sort_by_field => 2u64, id_field => 0u64))?;
         // And remove it.
-        index_writer.delete_term(Term::from_field_u64(id_field, 0u64));
+        index_writer.delete_term(Term::from_field_u64(id_field, 0u64))?;
         // We add another doc.
         index_writer.add_document(doc!(sort_by_field=>1u64, id_field => 0u64))?;
 
@@ -2337,7 +3305,7 @@ mod tests {
         index_writer.add_document(doc!(
             id_field=>deleted_id,
         ))?;
-        index_writer.delete_term(Term::from_field_u64(id_field, deleted_id));
+        index_writer.delete_term(Term::from_field_u64(id_field, deleted_id))?;
         index_writer.commit()?;
 
         // Merge
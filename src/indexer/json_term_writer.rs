@@ -235,13 +235,14 @@ pub(crate) fn set_fastvalue_and_get_term<T: FastValue>(
     json_term_writer.term().clone()
 }
 
-// helper function to generate a list of terms with their positions from a textual json value
+// helper function to generate a list of terms with their positions and position lengths
+// from a textual json value
 pub(crate) fn set_string_and_get_terms(
     json_term_writer: &mut JsonTermWriter,
     value: &str,
     text_analyzer: &TextAnalyzer,
-) -> Vec<(usize, Term)> {
-    let mut positions_and_terms = Vec::<(usize, Term)>::new();
+) -> Vec<(usize, usize, Term)> {
+    let mut positions_and_terms = Vec::<(usize, usize, Term)>::new();
     json_term_writer.close_path_and_set_type(Type::Str);
     let term_num_bytes = json_term_writer.term_buffer.len_bytes();
     let mut token_stream = text_analyzer.token_stream(value);
@@ -252,7 +253,11 @@ pub(crate) fn set_string_and_get_terms(
         json_term_writer
             .term_buffer
             .append_bytes(token.text.as_bytes());
-        positions_and_terms.push((token.position, json_term_writer.term().clone()));
+        positions_and_terms.push((
+            token.position,
+            token.position_length,
+            json_term_writer.term().clone(),
+        ));
     });
     positions_and_terms
 }
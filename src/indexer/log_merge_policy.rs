@@ -83,11 +83,13 @@ impl LogMergePolicy {
     }
 }
 
+// Soft-deleted documents are not taken in account, as merging would not reclaim them
+// as long as they are retained.
 fn deletes_ratio(segment: &SegmentMeta) -> f32 {
     if segment.max_doc() == 0 {
         return 0f32;
     }
-    segment.num_deleted_docs() as f32 / segment.max_doc() as f32
+    segment.num_reclaimable_deleted_docs() as f32 / segment.max_doc() as f32
 }
 
 impl MergePolicy for LogMergePolicy {
@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::io::Write;
 use std::sync::Arc;
@@ -8,7 +9,7 @@ use measure_time::debug_time;
 
 use super::flat_map_with_buffer::FlatMapWithBufferIter;
 use super::sorted_doc_id_multivalue_column::RemappedDocIdMultiValueIndexColumn;
-use crate::core::{Segment, SegmentReader};
+use crate::core::{Segment, SegmentReader, SoftDelete, SoftDeletes};
 use crate::docset::{DocSet, TERMINATED};
 use crate::error::DataCorruption;
 use crate::fastfield::{
@@ -16,7 +17,7 @@ use crate::fastfield::{
     MultiValueIndex, MultiValuedFastFieldReader,
 };
 use crate::fieldnorm::{FieldNormReader, FieldNormReaders, FieldNormsSerializer, FieldNormsWriter};
-use crate::indexer::doc_id_mapping::SegmentDocIdMapping;
+use crate::indexer::doc_id_mapping::{SegmentDocIdMapping, SegmentSortValues};
 use crate::indexer::sorted_doc_id_column::RemappedDocIdColumn;
 use crate::indexer::sorted_doc_id_multivalue_column::RemappedDocIdMultiValueColumn;
 use crate::indexer::SegmentSerializer;
@@ -24,9 +25,10 @@ use crate::postings::{InvertedIndexSerializer, Postings, SegmentPostings};
 use crate::schema::{Cardinality, Field, FieldType, Schema};
 use crate::store::StoreWriter;
 use crate::termdict::{TermMerger, TermOrdinal};
+use crate::vector::{VectorReader, VectorsSerializer};
 use crate::{
-    DocAddress, DocId, IndexSettings, IndexSortByField, InvertedIndexReader, Order,
-    SegmentComponent, SegmentOrdinal,
+    DocAddress, DocId, IndexSettings, IndexSortByField, InvertedIndexReader, SegmentComponent,
+    SegmentOrdinal,
 };
 
 /// Segment's max doc must be `< MAX_DOC_LIMIT`.
@@ -88,6 +90,8 @@ pub struct IndexMerger {
     index_settings: IndexSettings,
     schema: Schema,
     pub(crate) readers: Vec<SegmentReader>,
+    // Sort values of the readers, if the index is sorted.
+    sort_values: Vec<SegmentSortValues>,
     max_doc: u32,
 }
 
@@ -174,12 +178,37 @@ impl IndexMerger {
                 readers.push(reader);
             }
         }
+        Self::from_readers(schema, index_settings, readers)
+    }
 
-        let max_doc = readers.iter().map(|reader| reader.num_docs()).sum();
-        if let Some(sort_by_field) = index_settings.sort_by_field.as_ref() {
-            readers = Self::sort_readers_by_min_sort_field(readers, sort_by_field)?;
+    // Create merge keeping the soft-deleted documents for which `retains` returns true.
+    //
+    // The retained documents are soft-deleted in the merged segment, see
+    // `write_with_soft_deletes`.
+    pub(crate) fn open_retaining_soft_deletes(
+        schema: Schema,
+        index_settings: IndexSettings,
+        segments: &[Segment],
+        retains: impl Fn(&SoftDelete) -> bool,
+    ) -> crate::Result<IndexMerger> {
+        let mut readers = vec![];
+        for segment in segments {
+            let reader = SegmentReader::open(segment)?.retain_soft_deletes(&retains);
+            if reader.num_docs() > 0 {
+                readers.push(reader);
+            }
         }
+        Self::from_readers(schema, index_settings, readers)
+    }
+
+    fn from_readers(
+        schema: Schema,
+        index_settings: IndexSettings,
+        readers: Vec<SegmentReader>,
+    ) -> crate::Result<IndexMerger> {
+        let max_doc = readers.iter().map(|reader| reader.num_docs()).sum();
         // sort segments by their natural sort setting
+        let (readers, sort_values) = Self::sort_readers(readers, &index_settings)?;
         if max_doc >= MAX_DOC_LIMIT {
             let err_msg = format!(
                 "The segment resulting from this merge would have {} docs,which exceeds the limit \
@@ -192,32 +221,87 @@ impl IndexMerger {
             index_settings,
             schema,
             readers,
+            sort_values,
             max_doc,
         })
     }
 
-    fn sort_readers_by_min_sort_field(
+    /// Loads the sort values of the readers, and presorts the readers by their first alive
+    /// document, so that when they are disjunct, we can use the regular merge logic
+    /// (implicitly sorted).
+    fn sort_readers(
         readers: Vec<SegmentReader>,
-        sort_by_field: &IndexSortByField,
-    ) -> crate::Result<Vec<SegmentReader>> {
-        // presort the readers by their min_values, so that when they are disjunct, we can use
-        // the regular merge logic (implicitly sorted)
-        let mut readers_with_min_sort_values = readers
+        index_settings: &IndexSettings,
+    ) -> crate::Result<(Vec<SegmentReader>, Vec<SegmentSortValues>)> {
+        let sort_by_fields = &index_settings.sort_by;
+        if sort_by_fields.is_empty() {
+            return Ok((readers, Vec::new()));
+        }
+        let per_reader_term_ranks = Self::compute_sort_term_ranks(&readers, sort_by_fields)?;
+        let mut readers_with_sort_values = readers
             .into_iter()
-            .map(|reader| {
-                let accessor = Self::get_sort_field_accessor(&reader, sort_by_field)?;
-                Ok((reader, accessor.min_value()))
+            .zip(per_reader_term_ranks)
+            .map(|(reader, mut term_ranks)| {
+                let sort_values =
+                    SegmentSortValues::for_reader(&reader, sort_by_fields, &mut term_ranks)?;
+                let first_alive_doc = reader.doc_ids_alive().next();
+                Ok((reader, sort_values, first_alive_doc))
             })
             .collect::<crate::Result<Vec<_>>>()?;
-        if sort_by_field.order.is_asc() {
-            readers_with_min_sort_values.sort_by_key(|(_, min_val)| *min_val);
-        } else {
-            readers_with_min_sort_values.sort_by_key(|(_, min_val)| std::cmp::Reverse(*min_val));
-        }
-        Ok(readers_with_min_sort_values
+        readers_with_sort_values.sort_by(|left, right| match (left.2, right.2) {
+            (Some(left_doc), Some(right_doc)) => left.1.cmp_docs(left_doc, &right.1, right_doc),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+        Ok(readers_with_sort_values
             .into_iter()
-            .map(|(reader, _)| reader)
-            .collect())
+            .map(|(reader, sort_values, _)| (reader, sort_values))
+            .unzip())
+    }
+
+    /// Computes, for each reader and each string sort field, the rank of the reader terms
+    /// among the terms of all of the readers.
+    fn compute_sort_term_ranks(
+        readers: &[SegmentReader],
+        sort_by_fields: &[IndexSortByField],
+    ) -> crate::Result<Vec<HashMap<Field, Vec<u64>>>> {
+        let mut per_reader_term_ranks: Vec<HashMap<Field, Vec<u64>>> =
+            readers.iter().map(|_| HashMap::new()).collect();
+        let schema = match readers.first() {
+            Some(reader) => reader.schema(),
+            None => return Ok(per_reader_term_ranks),
+        };
+        for sort_by_field in sort_by_fields {
+            let field = schema.get_field(&sort_by_field.field)?;
+            let field_type = schema.get_field_entry(field).field_type();
+            if !matches!(field_type, FieldType::Str(_)) {
+                continue;
+            }
+            let field_readers: Vec<Arc<InvertedIndexReader>> = readers
+                .iter()
+                .map(|reader| reader.inverted_index(field))
+                .collect::<crate::Result<Vec<_>>>()?;
+            let mut field_term_streams = Vec::new();
+            for (field_reader, term_ranks) in
+                field_readers.iter().zip(per_reader_term_ranks.iter_mut())
+            {
+                let terms = field_reader.terms();
+                field_term_streams.push(terms.stream()?);
+                term_ranks.insert(field, vec![0u64; terms.num_terms()]);
+            }
+            let mut merged_terms = TermMerger::new(field_term_streams);
+            let mut rank = 0u64;
+            while merged_terms.advance() {
+                for (segment_ord, term_ord) in merged_terms.matching_segments() {
+                    if let Some(term_ranks) = per_reader_term_ranks[segment_ord].get_mut(&field) {
+                        term_ranks[term_ord as usize] = rank;
+                    }
+                }
+                rank += 1;
+            }
+        }
+        Ok(per_reader_term_ranks)
     }
 
     fn write_fieldnorms(
@@ -247,6 +331,39 @@ impl IndexMerger {
         Ok(())
     }
 
+    /// Writes the vectors of the merged segment. Their HNSW graph is rebuilt from scratch.
+    fn write_vectors(
+        &self,
+        mut vectors_serializer: VectorsSerializer,
+        doc_id_mapping: &SegmentDocIdMapping,
+    ) -> crate::Result<()> {
+        for (field, field_entry) in self.schema.fields() {
+            let vector_options = if let FieldType::Vector(vector_options) = field_entry.field_type()
+            {
+                vector_options
+            } else {
+                continue;
+            };
+            let vector_readers: Vec<VectorReader> = self
+                .readers
+                .iter()
+                .map(|reader| reader.vector_reader(field))
+                .collect::<crate::Result<_>>()?;
+            let mut doc_ids = Vec::new();
+            let mut vectors = Vec::new();
+            for (new_doc_id, old_doc_addr) in doc_id_mapping.iter_old_doc_addrs().enumerate() {
+                let vector_reader = &vector_readers[old_doc_addr.segment_ord as usize];
+                if let Some(vector) = vector_reader.vector(old_doc_addr.doc_id) {
+                    doc_ids.push(new_doc_id as DocId);
+                    vectors.extend(vector);
+                }
+            }
+            vectors_serializer.serialize_field(field, vector_options, &doc_ids, &vectors)?;
+        }
+        vectors_serializer.close()?;
+        Ok(())
+    }
+
     fn write_fast_fields(
         &self,
         fast_field_serializer: &mut CompositeFastFieldSerializer,
@@ -319,6 +436,7 @@ impl IndexMerger {
                     // They can be implemented using what is done
                     // for facets in the future
                 }
+                FieldType::Vector(_) => {}
             }
         }
         Ok(())
@@ -448,78 +566,40 @@ impl IndexMerger {
 
     /// Checks if the readers are disjunct for their sort property and in the correct order to be
     /// able to just stack them.
-    pub(crate) fn is_disjunct_and_sorted_on_sort_property(
-        &self,
-        sort_by_field: &IndexSortByField,
-    ) -> crate::Result<bool> {
-        let reader_ordinal_and_field_accessors =
-            self.get_reader_with_sort_field_accessor(sort_by_field)?;
-
-        let everything_is_in_order = reader_ordinal_and_field_accessors
-            .into_iter()
-            .map(|(_, col)| Arc::new(col))
-            .tuple_windows()
-            .all(|(field_accessor1, field_accessor2)| {
-                if sort_by_field.order.is_asc() {
-                    field_accessor1.max_value() <= field_accessor2.min_value()
-                } else {
-                    field_accessor1.min_value() >= field_accessor2.max_value()
-                }
-            });
-        Ok(everything_is_in_order)
-    }
-
-    pub(crate) fn get_sort_field_accessor(
-        reader: &SegmentReader,
-        sort_by_field: &IndexSortByField,
-    ) -> crate::Result<Arc<dyn Column>> {
-        reader.schema().get_field(&sort_by_field.field)?;
-        let value_accessor = reader.fast_fields().u64_lenient(&sort_by_field.field)?;
-        Ok(value_accessor)
-    }
-    /// Collecting value_accessors into a vec to bind the lifetime.
-    pub(crate) fn get_reader_with_sort_field_accessor(
-        &self,
-        sort_by_field: &IndexSortByField,
-    ) -> crate::Result<Vec<(SegmentOrdinal, Arc<dyn Column>)>> {
-        let reader_ordinal_and_field_accessors = self
-            .readers
+    pub(crate) fn is_disjunct_and_sorted_on_sort_property(&self) -> bool {
+        self.readers
             .iter()
-            .enumerate()
-            .map(|(reader_ordinal, _)| reader_ordinal as SegmentOrdinal)
-            .map(|reader_ordinal: SegmentOrdinal| {
-                let value_accessor = Self::get_sort_field_accessor(
-                    &self.readers[reader_ordinal as usize],
-                    sort_by_field,
-                )?;
-                Ok((reader_ordinal, value_accessor))
+            .zip(self.sort_values.iter())
+            .filter_map(|(reader, sort_values)| {
+                let first_alive_doc = reader.doc_ids_alive().next()?;
+                let last_alive_doc = reader.doc_ids_alive().last()?;
+                Some((sort_values, first_alive_doc, last_alive_doc))
+            })
+            .tuple_windows()
+            .all(|((left, _, last_doc), (right, first_doc, _))| {
+                left.cmp_docs(last_doc, right, first_doc) != Ordering::Greater
             })
-            .collect::<crate::Result<Vec<_>>>()?;
-        Ok(reader_ordinal_and_field_accessors)
     }
 
     /// Generates the doc_id mapping where position in the vec=new
     /// doc_id.
     /// ReaderWithOrdinal will include the ordinal position of the
     /// reader in self.readers.
-    pub(crate) fn generate_doc_id_mapping(
-        &self,
-        sort_by_field: &IndexSortByField,
-    ) -> crate::Result<SegmentDocIdMapping> {
-        let reader_ordinal_and_field_accessors =
-            self.get_reader_with_sort_field_accessor(sort_by_field)?;
-        // Loading the field accessor on demand causes a 15x regression
-
-        // create iterators over segment/sort_accessor/doc_id  tuple
-        let doc_id_reader_pair =
-            reader_ordinal_and_field_accessors
-                .iter()
-                .map(|(reader_ord, ff_reader)| {
-                    let reader = &self.readers[*reader_ord as usize];
-                    reader
-                        .doc_ids_alive()
-                        .map(move |doc_id| (doc_id, reader_ord, ff_reader))
-                });
+    pub(crate) fn generate_doc_id_mapping(&self) -> SegmentDocIdMapping {
+        // The sort values read the field accessors opened once per reader in
+        // `sort_readers`. Loading the field accessor on demand causes a 15x regression
+
+        // create iterators over segment/sort_values/doc_id  tuple
+        let doc_id_reader_pair = self
+            .readers
+            .iter()
+            .zip(self.sort_values.iter())
+            .enumerate()
+            .map(|(reader_ord, (reader, sort_values))| {
+                reader
+                    .doc_ids_alive()
+                    .map(move |doc_id| (doc_id, reader_ord as SegmentOrdinal, sort_values))
+            });
 
         let total_num_new_docs = self
             .readers
@@ -533,21 +613,13 @@ impl IndexMerger {
         sorted_doc_ids.extend(
             doc_id_reader_pair
                 .into_iter()
-                .kmerge_by(|a, b| {
-                    let val1 = a.2.get_val(a.0);
-                    let val2 = b.2.get_val(b.0);
-                    if sort_by_field.order == Order::Asc {
-                        val1 < val2
-                    } else {
-                        val1 > val2
-                    }
-                })
-                .map(|(doc_id, &segment_ord, _)| DocAddress {
+                .kmerge_by(|a, b| a.2.cmp_docs(a.0, b.2, b.0) == Ordering::Less)
+                .map(|(doc_id, segment_ord, _)| DocAddress {
                     doc_id,
                     segment_ord,
                 }),
         );
-        Ok(SegmentDocIdMapping::new(sorted_doc_ids, false))
+        SegmentDocIdMapping::new(sorted_doc_ids, false)
     }
 
     // Creating the index file to point into the data, generic over `BytesFastFieldReader` and
@@ -1028,16 +1100,26 @@ impl IndexMerger {
     ///
     /// # Returns
     /// The number of documents in the resulting segment.
-    pub fn write(&self, mut serializer: SegmentSerializer) -> crate::Result<u32> {
-        let doc_id_mapping = if let Some(sort_by_field) = self.index_settings.sort_by_field.as_ref()
+    pub fn write(&self, serializer: SegmentSerializer) -> crate::Result<u32> {
+        let (num_docs, _soft_deletes) = self.write_with_soft_deletes(serializer)?;
+        Ok(num_docs)
+    }
+
+    /// Writes the merged segment, like `write`, and returns the soft deletes of the
+    /// merged segment, along with its number of documents.
+    ///
+    /// The soft-deleted documents are written to the merged segment as regular documents:
+    /// it is up to the caller to write the delete files of the merged segment.
+    pub(crate) fn write_with_soft_deletes(
+        &self,
+        mut serializer: SegmentSerializer,
+    ) -> crate::Result<(u32, SoftDeletes)> {
+        // If the documents are already sorted and stackable, we ignore the mapping and execute
+        // it as if there was no sorting
+        let doc_id_mapping = if !self.index_settings.sort_by.is_empty()
+            && !self.is_disjunct_and_sorted_on_sort_property()
         {
-            // If the documents are already sorted and stackable, we ignore the mapping and execute
-            // it as if there was no sorting
-            if self.is_disjunct_and_sorted_on_sort_property(sort_by_field)? {
-                self.get_doc_id_from_concatenated_data()?
-            } else {
-                self.generate_doc_id_mapping(sort_by_field)?
-            }
+            self.generate_doc_id_mapping()
         } else {
             self.get_doc_id_from_concatenated_data()?
         };
@@ -1045,6 +1127,10 @@ impl IndexMerger {
         if let Some(fieldnorms_serializer) = serializer.extract_fieldnorms_serializer() {
             self.write_fieldnorms(fieldnorms_serializer, &doc_id_mapping)?;
         }
+        debug!("write-vectors");
+        if let Some(vectors_serializer) = serializer.extract_vectors_serializer() {
+            self.write_vectors(vectors_serializer, &doc_id_mapping)?;
+        }
         debug!("write-postings");
         let fieldnorm_data = serializer
             .segment()
@@ -1065,7 +1151,14 @@ impl IndexMerger {
         self.write_storable_fields(serializer.get_store_writer(), &doc_id_mapping)?;
         debug!("close-serializer");
         serializer.close()?;
-        Ok(self.max_doc)
+        let mut soft_deletes = SoftDeletes::default();
+        for (new_doc_id, old_doc_addr) in doc_id_mapping.iter_old_doc_addrs().enumerate() {
+            let reader = &self.readers[old_doc_addr.segment_ord as usize];
+            if let Some(soft_delete) = reader.soft_deletes().get(old_doc_addr.doc_id) {
+                soft_deletes.insert(new_doc_id as DocId, *soft_delete);
+            }
+        }
+        Ok((self.max_doc, soft_deletes))
     }
 }
 
@@ -1289,7 +1382,7 @@ mod tests {
                 score_field => 2u64,
                 bytes_score_field => vec![0u8, 0, 0, 2],
             ))?;
-            index_writer.delete_term(Term::from_field_text(text_field, "c"));
+            index_writer.delete_term(Term::from_field_text(text_field, "c"))?;
             index_writer.add_document(doc!(
                 text_field => "c d",
                 score_field => 3u64,
@@ -1330,8 +1423,8 @@ mod tests {
                 score_field => 5_000u64,
                 bytes_score_field => vec![0u8, 0, 0, 5],
             ))?;
-            index_writer.delete_term(Term::from_field_text(text_field, "a"));
-            index_writer.delete_term(Term::from_field_text(text_field, "f"));
+            index_writer.delete_term(Term::from_field_text(text_field, "a"))?;
+            index_writer.delete_term(Term::from_field_text(text_field, "f"))?;
             index_writer.add_document(doc!(
                 text_field => "f g",
                 score_field => 6_000u64,
@@ -1445,7 +1538,7 @@ mod tests {
         }
         {
             // test a commit with only deletes
-            index_writer.delete_term(Term::from_field_text(text_field, "c"));
+            index_writer.delete_term(Term::from_field_text(text_field, "c"))?;
             index_writer.commit()?;
 
             reader.reload()?;
@@ -1540,7 +1633,7 @@ mod tests {
 
         {
             // Test removing all docs
-            index_writer.delete_term(Term::from_field_text(text_field, "g"));
+            index_writer.delete_term(Term::from_field_text(text_field, "g"))?;
             index_writer.commit()?;
             let segment_ids = index.searchable_segment_ids()?;
             reader.reload()?;
@@ -1562,10 +1655,10 @@ mod tests {
         // In the merge case this will go through the doc_id mapping code
         test_merge_facets(
             Some(IndexSettings {
-                sort_by_field: Some(IndexSortByField {
+                sort_by: vec![IndexSortByField {
                     field: "intval".to_string(),
                     order: Order::Desc,
-                }),
+                }],
                 ..Default::default()
             }),
             true,
@@ -1574,10 +1667,10 @@ mod tests {
         // sorted and disjunct
         test_merge_facets(
             Some(IndexSettings {
-                sort_by_field: Some(IndexSortByField {
+                sort_by: vec![IndexSortByField {
                     field: "intval".to_string(),
                     order: Order::Desc,
-                }),
+                }],
                 ..Default::default()
             }),
             false,
@@ -1589,10 +1682,10 @@ mod tests {
         // In the merge case this will go through the doc_id mapping code
         test_merge_facets(
             Some(IndexSettings {
-                sort_by_field: Some(IndexSortByField {
+                sort_by: vec![IndexSortByField {
                     field: "intval".to_string(),
                     order: Order::Desc,
-                }),
+                }],
                 ..Default::default()
             }),
             true,
@@ -1601,10 +1694,10 @@ mod tests {
         // sorted and disjunct
         test_merge_facets(
             Some(IndexSettings {
-                sort_by_field: Some(IndexSortByField {
+                sort_by: vec![IndexSortByField {
                     field: "intval".to_string(),
                     order: Order::Desc,
-                }),
+                }],
                 ..Default::default()
             }),
             false,
@@ -1737,7 +1830,7 @@ mod tests {
             let mut index_writer = index.writer_for_tests().unwrap();
             let facet = Facet::from_path(vec!["top", "a", "firstdoc"]);
             let facet_term = Term::from_facet(facet_field, &facet);
-            index_writer.delete_term(facet_term);
+            index_writer.delete_term(facet_term).unwrap();
             index_writer.commit().unwrap();
             reader.reload().unwrap();
             test_searcher(
@@ -1767,7 +1860,7 @@ mod tests {
         let reader = index.reader()?;
         let searcher = reader.searcher();
         assert_eq!(searcher.num_docs(), 2);
-        index_writer.delete_term(Term::from_field_u64(int_field, 1));
+        index_writer.delete_term(Term::from_field_u64(int_field, 1))?;
         let segment_ids = index
             .searchable_segment_ids()
             .expect("Searchable segments failed.");
@@ -1796,7 +1889,7 @@ mod tests {
             index_writer.commit()?;
             index_writer.add_document(doc)?;
             index_writer.commit()?;
-            index_writer.delete_term(Term::from_field_u64(int_field, 1));
+            index_writer.delete_term(Term::from_field_u64(int_field, 1))?;
             let segment_ids = index.searchable_segment_ids()?;
             index_writer.merge(&segment_ids).wait()?;
 
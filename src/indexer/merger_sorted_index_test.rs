@@ -130,7 +130,7 @@ mod tests {
                 doc!(int_field=>1_000u64, multi_numbers => 1001_u64, multi_numbers => 1002_u64, bytes_field => vec![5, 5],text_field => "the biggest num")
             )?;
 
-            index_writer.delete_term(Term::from_field_text(text_field, "deleteme"));
+            index_writer.delete_term(Term::from_field_text(text_field, "deleteme"))?;
             index_writer.commit()?;
         }
 
@@ -147,10 +147,10 @@ mod tests {
     #[test]
     fn test_merge_sorted_postinglist_sort_issue() {
         create_test_index_posting_list_issue(Some(IndexSettings {
-            sort_by_field: Some(IndexSortByField {
+            sort_by: vec![IndexSortByField {
                 field: "intval".to_string(),
                 order: Order::Desc,
-            }),
+            }],
             ..Default::default()
         }));
     }
@@ -167,10 +167,10 @@ mod tests {
     fn test_merge_sorted_index_desc_(force_disjunct_segment_sort_values: bool) {
         let index = create_test_index(
             Some(IndexSettings {
-                sort_by_field: Some(IndexSortByField {
+                sort_by: vec![IndexSortByField {
                     field: "intval".to_string(),
                     order: Order::Desc,
-                }),
+                }],
                 ..Default::default()
             }),
             force_disjunct_segment_sort_values,
@@ -353,10 +353,10 @@ mod tests {
     fn test_merge_sorted_index_asc() {
         let index = create_test_index(
             Some(IndexSettings {
-                sort_by_field: Some(IndexSortByField {
+                sort_by: vec![IndexSortByField {
                     field: "intval".to_string(),
                     order: Order::Asc,
-                }),
+                }],
                 ..Default::default()
             }),
             false,
@@ -471,6 +471,64 @@ mod tests {
             assert_eq!(doc.get_first(int_field).unwrap().as_u64(), Some(1_000));
         }
     }
+
+    #[test]
+    fn test_merge_sorted_index_multiple_fields() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let tenant = schema_builder.add_text_field("tenant", schema::STRING | schema::FAST);
+        let int_options = NumericOptions::default()
+            .set_fast(Cardinality::SingleValue)
+            .set_indexed();
+        let timestamp = schema_builder.add_u64_field("timestamp", int_options.clone());
+        let id = schema_builder.add_u64_field("id", int_options);
+        let schema = schema_builder.build();
+        let settings = IndexSettings {
+            sort_by: vec![
+                IndexSortByField {
+                    field: "tenant".to_string(),
+                    order: Order::Asc,
+                },
+                IndexSortByField {
+                    field: "timestamp".to_string(),
+                    order: Order::Desc,
+                },
+            ],
+            ..Default::default()
+        };
+        let index = Index::builder()
+            .schema(schema)
+            .settings(settings)
+            .create_in_ram()?;
+        {
+            let mut index_writer = index.writer_for_tests()?;
+            index_writer.add_document(doc!(id=>0u64, tenant=>"b", timestamp=>10u64))?;
+            index_writer.add_document(doc!(id=>1u64, tenant=>"a", timestamp=>5u64))?;
+            index_writer.add_document(doc!(id=>2u64, timestamp=>7u64))?;
+            index_writer.commit()?;
+            index_writer.add_document(doc!(id=>3u64, tenant=>"a", timestamp=>8u64))?;
+            index_writer.add_document(doc!(id=>4u64, tenant=>"b", timestamp=>12u64))?;
+            index_writer.add_document(doc!(id=>5u64, tenant=>"c", timestamp=>1u64))?;
+            index_writer.commit()?;
+            index_writer.add_document(doc!(id=>6u64, tenant=>"b", timestamp=>20u64))?;
+            index_writer.add_document(doc!(id=>7u64, timestamp=>1u64))?;
+            index_writer.delete_term(Term::from_field_u64(id, 5))?;
+            index_writer.commit()?;
+        }
+        {
+            let segment_ids = index.searchable_segment_ids()?;
+            let mut index_writer = index.writer_for_tests()?;
+            index_writer.merge(&segment_ids).wait()?;
+            index_writer.wait_merging_threads()?;
+        }
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let ids = searcher.segment_reader(0).fast_fields().u64("id")?;
+        assert_eq!(
+            (0..7).map(|doc| ids.get_val(doc)).collect::<Vec<_>>(),
+            vec![3, 1, 6, 4, 0, 2, 7]
+        );
+        Ok(())
+    }
 }
 
 #[cfg(all(test, feature = "unstable"))]
@@ -494,7 +552,7 @@ mod bench_sorted_index_merge {
         let schema = schema_builder.build();
 
         let index_builder = Index::builder().schema(schema).settings(IndexSettings {
-            sort_by_field,
+            sort_by: sort_by_field.into_iter().collect(),
             ..Default::default()
         });
         let index = index_builder.create_in_ram().unwrap();
@@ -524,11 +582,11 @@ mod bench_sorted_index_merge {
             field: "intval".to_string(),
             order: Order::Desc,
         };
-        let index = create_index(Some(sort_by_field.clone()));
+        let index = create_index(Some(sort_by_field));
         let segments = index.searchable_segments().unwrap();
         let merger: IndexMerger =
             IndexMerger::open(index.schema(), index.settings().clone(), &segments[..])?;
-        let doc_id_mapping = merger.generate_doc_id_mapping(&sort_by_field).unwrap();
+        let doc_id_mapping = merger.generate_doc_id_mapping();
         b.iter(|| {
             let sorted_doc_ids = doc_id_mapping.iter_old_doc_addrs().map(|doc_addr| {
                 let reader = &merger.readers[doc_addr.segment_ord as usize];
@@ -558,13 +616,13 @@ mod bench_sorted_index_merge {
             field: "intval".to_string(),
             order: Order::Desc,
         };
-        let index = create_index(Some(sort_by_field.clone()));
+        let index = create_index(Some(sort_by_field));
         // let field = index.schema().get_field("intval").unwrap();
         let segments = index.searchable_segments().unwrap();
         let merger: IndexMerger =
             IndexMerger::open(index.schema(), index.settings().clone(), &segments[..])?;
         b.iter(|| {
-            merger.generate_doc_id_mapping(&sort_by_field).unwrap();
+            merger.generate_doc_id_mapping();
         });
 
         Ok(())
@@ -22,6 +22,8 @@ mod segment_writer;
 mod sorted_doc_id_column;
 mod sorted_doc_id_multivalue_column;
 mod stamper;
+mod tiered_merge_policy;
+mod write_ahead_log;
 
 use crossbeam_channel as channel;
 use smallvec::SmallVec;
@@ -39,6 +41,7 @@ pub use self::segment_manager::SegmentManager;
 pub use self::segment_serializer::SegmentSerializer;
 pub use self::segment_updater::{merge_filtered_segments, merge_indices};
 pub use self::segment_writer::SegmentWriter;
+pub use self::tiered_merge_policy::TieredMergePolicy;
 use crate::indexer::operation::AddOperation;
 
 /// Alias for the default merge policy, which is the `LogMergePolicy`.
@@ -70,7 +73,7 @@ mod tests_mmap {
         let mut index_writer = index.writer_for_tests()?;
         // there must be one deleted document in the segment
         index_writer.add_document(doc!(text_field=>"b"))?;
-        index_writer.delete_term(Term::from_field_text(text_field, "b"));
+        index_writer.delete_term(Term::from_field_text(text_field, "b"))?;
         // we need enough data to trigger the bug (at least 32 documents)
         for _ in 0..32 {
             index_writer.add_document(doc!(text_field=>"c"))?;
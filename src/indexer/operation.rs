@@ -1,11 +1,16 @@
-use crate::query::Weight;
+use crate::query::{Query, Weight};
 use crate::schema::{Document, Term};
-use crate::Opstamp;
+use crate::{DateTime, Opstamp};
 
 /// Timestamped Delete operation.
 pub struct DeleteOperation {
     pub opstamp: Opstamp,
     pub target: Box<dyn Weight>,
+    /// Time at which the operation was issued, recorded along with soft deletes.
+    pub timestamp: DateTime,
+    /// If true, the operation restores the soft-deleted documents matching `target`
+    /// instead of deleting them.
+    pub undelete: bool,
 }
 
 /// Timestamped Add operation.
@@ -16,10 +21,64 @@ pub struct AddOperation {
 }
 
 /// UserOperation is an enum type that encapsulates other operation types.
-#[derive(Eq, PartialEq, Debug)]
+///
+/// Queries cannot be compared, so two `DeleteQuery` operations are only equal
+/// if they hold the same query instance.
+#[derive(Debug)]
 pub enum UserOperation {
     /// Add operation
     Add(Document),
     /// Delete operation
     Delete(Term),
+    /// Delete operation: deletes the documents matching the given query.
+    DeleteQuery(Box<dyn Query>),
+    /// Update operation: deletes the documents containing the given term,
+    /// and adds the new document.
+    Update(Term, Document),
+}
+
+impl PartialEq for UserOperation {
+    fn eq(&self, other: &UserOperation) -> bool {
+        match (self, other) {
+            (UserOperation::Add(left), UserOperation::Add(right)) => left == right,
+            (UserOperation::Delete(left), UserOperation::Delete(right)) => left == right,
+            (UserOperation::DeleteQuery(left), UserOperation::DeleteQuery(right)) => {
+                std::ptr::eq(left.as_ref(), right.as_ref())
+            }
+            (
+                UserOperation::Update(left_term, left_doc),
+                UserOperation::Update(right_term, right_doc),
+            ) => left_term == right_term && left_doc == right_doc,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for UserOperation {}
+
+#[cfg(test)]
+mod tests {
+    use super::UserOperation;
+    use crate::query::{AllQuery, Query, TermQuery};
+    use crate::schema::{Field, IndexRecordOption, Term};
+
+    #[test]
+    fn test_user_operation_eq() {
+        let term = Term::from_field_u64(Field::from_field_id(0), 1);
+        assert_eq!(
+            UserOperation::Delete(term.clone()),
+            UserOperation::Delete(term.clone())
+        );
+        assert_ne!(
+            UserOperation::Delete(term.clone()),
+            UserOperation::Add(Default::default())
+        );
+        let delete_query = UserOperation::DeleteQuery(Box::new(AllQuery));
+        assert_eq!(delete_query, delete_query);
+        let query: Box<dyn Query> = Box::new(TermQuery::new(term, IndexRecordOption::Basic));
+        assert_ne!(
+            UserOperation::DeleteQuery(query.box_clone()),
+            UserOperation::DeleteQuery(query)
+        );
+    }
 }
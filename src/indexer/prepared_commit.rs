@@ -35,7 +35,13 @@ impl<'a> PreparedCommit<'a> {
     /// Proceeds to commit.
     /// See `.commit_future()`.
     pub fn commit(self) -> crate::Result<Opstamp> {
-        self.commit_future().wait()
+        let write_ahead_log = self.index_writer.write_ahead_log().cloned();
+        let opstamp = self.commit_future().wait()?;
+        if let Some(write_ahead_log) = write_ahead_log {
+            // The logged operations are now part of the commit.
+            write_ahead_log.truncate(opstamp)?;
+        }
+        Ok(opstamp)
     }
 
     /// Proceeds to commit.
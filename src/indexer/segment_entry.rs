@@ -2,7 +2,7 @@ use std::fmt;
 
 use common::BitSet;
 
-use crate::core::{SegmentId, SegmentMeta};
+use crate::core::{SegmentId, SegmentMeta, SoftDeletes};
 use crate::indexer::delete_queue::DeleteCursor;
 
 /// A segment entry describes the state of
@@ -13,6 +13,8 @@ use crate::indexer::delete_queue::DeleteCursor;
 /// - `alive_bitset` is a bitset describing
 /// documents that were alive during the commit
 /// itself.
+/// - `soft_deletes` are the soft deletes matching the `alive_bitset`,
+/// if soft deletes are enabled.
 /// - `delete_cursor` is the position in the delete queue.
 /// Deletes happening before the cursor are reflected either
 /// in the .del file or in the `alive_bitset`.
@@ -20,6 +22,7 @@ use crate::indexer::delete_queue::DeleteCursor;
 pub struct SegmentEntry {
     meta: SegmentMeta,
     alive_bitset: Option<BitSet>,
+    soft_deletes: Option<SoftDeletes>,
     delete_cursor: DeleteCursor,
 }
 
@@ -33,10 +36,17 @@ impl SegmentEntry {
         SegmentEntry {
             meta: segment_meta,
             alive_bitset,
+            soft_deletes: None,
             delete_cursor,
         }
     }
 
+    /// Sets the soft deletes matching the entry deleted bitset.
+    pub(crate) fn with_soft_deletes(mut self, soft_deletes: Option<SoftDeletes>) -> SegmentEntry {
+        self.soft_deletes = soft_deletes;
+        self
+    }
+
     /// Return a reference to the segment entry deleted bitset.
     ///
     /// `DocId` in this bitset are flagged as deleted.
@@ -44,6 +54,18 @@ impl SegmentEntry {
         self.alive_bitset.as_ref()
     }
 
+    /// Return a reference to the soft deletes matching the segment entry deleted bitset.
+    pub(crate) fn soft_deletes(&self) -> Option<&SoftDeletes> {
+        self.soft_deletes.as_ref()
+    }
+
+    /// Drops the deleted bitset and soft deletes, once they have been written
+    /// to the segment delete files.
+    pub(crate) fn clear_deletes(&mut self) {
+        self.alive_bitset = None;
+        self.soft_deletes = None;
+    }
+
     /// Set the `SegmentMeta` for this segment.
     pub fn set_meta(&mut self, segment_meta: SegmentMeta) {
         self.meta = segment_meta;
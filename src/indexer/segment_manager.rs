@@ -114,13 +114,18 @@ impl SegmentManager {
     }
 
     /// Deletes all empty segments
+    ///
+    /// Segments holding soft-deleted documents are not considered empty, as their
+    /// documents may still be restored.
     fn remove_empty_segments(&self) {
         let mut registers_lock = self.write();
         registers_lock
             .committed
             .segment_entries()
             .iter()
-            .filter(|segment| segment.meta().num_docs() == 0)
+            .filter(|segment| {
+                segment.meta().num_docs() == 0 && segment.meta().num_soft_deleted_docs() == 0
+            })
             .for_each(|segment| {
                 registers_lock
                     .committed
@@ -3,6 +3,7 @@ use crate::fastfield::CompositeFastFieldSerializer;
 use crate::fieldnorm::FieldNormsSerializer;
 use crate::postings::InvertedIndexSerializer;
 use crate::store::StoreWriter;
+use crate::vector::VectorsSerializer;
 
 /// Segment serializer is in charge of laying out on disk
 /// the data accumulated and sorted by the `SegmentWriter`.
@@ -12,6 +13,7 @@ pub struct SegmentSerializer {
     fast_field_serializer: CompositeFastFieldSerializer,
     fieldnorms_serializer: Option<FieldNormsSerializer>,
     postings_serializer: InvertedIndexSerializer,
+    vectors_serializer: Option<VectorsSerializer>,
 }
 
 impl SegmentSerializer {
@@ -23,7 +25,7 @@ impl SegmentSerializer {
         // If the segment is going to be sorted, we stream the docs first to a temporary file.
         // In the merge case this is not necessary because we can kmerge the already sorted
         // segments
-        let remapping_required = segment.index().settings().sort_by_field.is_some() && !is_in_merge;
+        let remapping_required = !segment.index().settings().sort_by.is_empty() && !is_in_merge;
         let settings = segment.index().settings().clone();
         let store_writer = if remapping_required {
             let store_write = segment.open_write(SegmentComponent::TempStore)?;
@@ -52,6 +54,9 @@ impl SegmentSerializer {
         let fieldnorms_write = segment.open_write(SegmentComponent::FieldNorms)?;
         let fieldnorms_serializer = FieldNormsSerializer::from_write(fieldnorms_write)?;
 
+        let vectors_write = segment.open_write(SegmentComponent::Vectors)?;
+        let vectors_serializer = VectorsSerializer::from_write(vectors_write)?;
+
         let postings_serializer = InvertedIndexSerializer::open(&mut segment)?;
         Ok(SegmentSerializer {
             segment,
@@ -59,6 +64,7 @@ impl SegmentSerializer {
             fast_field_serializer,
             fieldnorms_serializer: Some(fieldnorms_serializer),
             postings_serializer,
+            vectors_serializer: Some(vectors_serializer),
         })
     }
 
@@ -92,6 +98,13 @@ impl SegmentSerializer {
         self.fieldnorms_serializer.take()
     }
 
+    /// Extract the vectors serializer.
+    ///
+    /// Note the vectors serializer can only be extracted once.
+    pub fn extract_vectors_serializer(&mut self) -> Option<VectorsSerializer> {
+        self.vectors_serializer.take()
+    }
+
     /// Accessor to the `StoreWriter`.
     pub fn get_store_writer(&mut self) -> &mut StoreWriter {
         &mut self.store_writer
@@ -102,6 +115,9 @@ impl SegmentSerializer {
         if let Some(fieldnorms_serializer) = self.extract_fieldnorms_serializer() {
             fieldnorms_serializer.close()?;
         }
+        if let Some(vectors_serializer) = self.extract_vectors_serializer() {
+            vectors_serializer.close()?;
+        }
         self.fast_field_serializer.close()?;
         self.postings_serializer.close()?;
         self.store_writer.close()?;
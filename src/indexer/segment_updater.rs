@@ -6,17 +6,19 @@ use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
 
+use common::BitSet;
 use fail::fail_point;
 use rayon::{ThreadPool, ThreadPoolBuilder};
 
 use super::segment_manager::SegmentManager;
 use crate::core::{
-    Index, IndexMeta, IndexSettings, Segment, SegmentId, SegmentMeta, META_FILEPATH,
+    commit_meta_filepath, CommitPoint, Index, IndexMeta, IndexSettings, Segment, SegmentComponent,
+    SegmentId, SegmentMeta, META_FILEPATH,
 };
-use crate::directory::{Directory, DirectoryClone, GarbageCollectionResult};
-use crate::fastfield::AliveBitSet;
+use crate::directory::{Directory, DirectoryClone, GarbageCollectionResult, TerminatingWrite};
+use crate::fastfield::{write_alive_bitset, AliveBitSet};
 use crate::indexer::delete_queue::DeleteCursor;
-use crate::indexer::index_writer::advance_deletes;
+use crate::indexer::index_writer::{advance_deletes, write_soft_deletes};
 use crate::indexer::merge_operation::MergeOperationInventory;
 use crate::indexer::merger::IndexMerger;
 use crate::indexer::segment_manager::SegmentsStatus;
@@ -25,7 +27,8 @@ use crate::indexer::{
     DefaultMergePolicy, MergeCandidate, MergeOperation, MergePolicy, SegmentEntry,
     SegmentSerializer,
 };
-use crate::{FutureResult, Opstamp};
+use crate::time::OffsetDateTime;
+use crate::{DateTime, FutureResult, Opstamp};
 
 const NUM_MERGE_THREADS: usize = 4;
 
@@ -37,6 +40,9 @@ const NUM_MERGE_THREADS: usize = 4;
 /// - it success, and `meta.json` is written
 /// and flushed.
 ///
+/// If the index retains commit points, a copy of the meta file is
+/// written beforehand for the commit.
+///
 /// This method is not part of tantivy's public API
 pub(crate) fn save_metas(metas: &IndexMeta, directory: &dyn Directory) -> crate::Result<()> {
     info!("save metas");
@@ -49,6 +55,9 @@ pub(crate) fn save_metas(metas: &IndexMeta, directory: &dyn Directory) -> crate:
             msg.unwrap_or_else(|| "Undefined".to_string())
         )
     )));
+    if metas.index_settings.num_retained_commits > 0 {
+        directory.atomic_write(&commit_meta_filepath(metas.opstamp), &buffer[..])?;
+    }
     directory.sync_directory()?;
     directory.atomic_write(&META_FILEPATH, &buffer[..])?;
     debug!("Saved metas {:?}", serde_json::to_string_pretty(&metas));
@@ -82,7 +91,7 @@ fn garbage_collect_files(
     let mut index = segment_updater.index.clone();
     index
         .directory_mut()
-        .garbage_collect(move || segment_updater.list_files())
+        .try_garbage_collect(move || segment_updater.list_files())
 }
 
 /// Merges a list of segments the list of segment givens in the `segment_entries`.
@@ -92,9 +101,10 @@ fn merge(
     mut segment_entries: Vec<SegmentEntry>,
     target_opstamp: Opstamp,
 ) -> crate::Result<Option<SegmentEntry>> {
+    // Soft-deleted documents may be retained in the merged segment.
     let num_docs = segment_entries
         .iter()
-        .map(|segment| segment.meta().num_docs() as u64)
+        .map(|segment| (segment.meta().num_docs() + segment.meta().num_soft_deleted_docs()) as u64)
         .sum::<u64>();
     if num_docs == 0 {
         return Ok(None);
@@ -117,18 +127,49 @@ fn merge(
         .collect();
 
     // An IndexMerger is like a "view" of our merged segments.
-    let merger: IndexMerger =
-        IndexMerger::open(index.schema(), index.settings().clone(), &segments[..])?;
+    let merger: IndexMerger = match index.settings().soft_deletes_retention.as_ref() {
+        Some(retention) => {
+            let now = DateTime::from_utc(OffsetDateTime::now_utc());
+            IndexMerger::open_retaining_soft_deletes(
+                index.schema(),
+                index.settings().clone(),
+                &segments[..],
+                |soft_delete| retention.retains(soft_delete, target_opstamp, now),
+            )?
+        }
+        None => IndexMerger::open(index.schema(), index.settings().clone(), &segments[..])?,
+    };
 
     // ... we just serialize this index merger in our new segment to merge the segments.
     let segment_serializer = SegmentSerializer::for_segment(merged_segment.clone(), true)?;
 
-    let num_docs = merger.write(segment_serializer)?;
+    let (num_docs, soft_deletes) = merger.write_with_soft_deletes(segment_serializer)?;
 
     let merged_segment_id = merged_segment.id();
 
-    let segment_meta = index.new_segment_meta(merged_segment_id, num_docs);
-    Ok(Some(SegmentEntry::new(segment_meta, delete_cursor, None)))
+    let mut segment = index
+        .segment(index.new_segment_meta(merged_segment_id, num_docs))
+        .with_num_bytes();
+    if !soft_deletes.is_empty() {
+        // The retained soft-deleted documents are deleted in the merged segment too.
+        let num_soft_deleted_docs = soft_deletes.len() as u32;
+        segment = segment
+            .with_delete_meta(num_soft_deleted_docs, target_opstamp)
+            .with_num_soft_deleted_docs(num_soft_deleted_docs);
+        let mut alive_bitset = BitSet::with_max_value_and_full(num_docs);
+        for (doc, _) in soft_deletes.iter() {
+            alive_bitset.remove(doc);
+        }
+        let mut alive_doc_file = segment.open_write(SegmentComponent::Delete)?;
+        write_alive_bitset(&alive_bitset, &mut alive_doc_file)?;
+        alive_doc_file.terminate()?;
+        write_soft_deletes(&mut segment, &soft_deletes)?;
+    }
+    Ok(Some(SegmentEntry::new(
+        segment.meta().clone(),
+        delete_cursor,
+        None,
+    )))
 }
 
 /// Advanced: Merges a list of segments from different indices in a new index.
@@ -231,7 +272,11 @@ pub fn merge_filtered_segments<T: Into<Box<dyn Directory>>>(
     let segment_serializer = SegmentSerializer::for_segment(merged_segment, true)?;
     let num_docs = merger.write(segment_serializer)?;
 
-    let segment_meta = merged_index.new_segment_meta(merged_segment_id, num_docs);
+    let segment_meta = merged_index
+        .segment(merged_index.new_segment_meta(merged_segment_id, num_docs))
+        .with_num_bytes()
+        .meta()
+        .clone();
 
     let stats = format!(
         "Segments Merge: [{}]",
@@ -251,6 +296,7 @@ pub fn merge_filtered_segments<T: Into<Box<dyn Directory>>>(
         schema: target_schema,
         opstamp: 0u64,
         payload: Some(stats),
+        retained_commits: Vec::new(),
     };
 
     // save the meta.json
@@ -409,6 +455,7 @@ impl SegmentUpdater {
                 schema: index.schema(),
                 opstamp,
                 payload: commit_message,
+                retained_commits: self.retained_commits(opstamp),
             };
             // TODO add context to the error.
             save_metas(&index_meta, directory.box_clone().borrow_mut())?;
@@ -417,6 +464,25 @@ impl SegmentUpdater {
         Ok(())
     }
 
+    /// Returns the commit points to retain, as of the commit with the given opstamp.
+    ///
+    /// The commit being replaced is retained, unless it is rewritten
+    /// with the same opstamp (e.g. after a merge).
+    fn retained_commits(&self, opstamp: Opstamp) -> Vec<CommitPoint> {
+        let num_retained_commits = self.index.settings().num_retained_commits;
+        let previous_metas = self.load_meta();
+        let mut retained_commits = previous_metas.retained_commits.clone();
+        // The meta file of the previous commit only exists if it was retaining commits.
+        if previous_metas.opstamp != opstamp
+            && previous_metas.index_settings.num_retained_commits > 0
+        {
+            retained_commits.push(previous_metas.commit_point());
+        }
+        let num_dropped_commits = retained_commits.len().saturating_sub(num_retained_commits);
+        retained_commits.drain(..num_dropped_commits);
+        retained_commits
+    }
+
     pub fn schedule_garbage_collect(&self) -> FutureResult<GarbageCollectionResult> {
         let self_clone = self.clone();
         self.schedule_task(move || garbage_collect_files(self_clone))
@@ -426,7 +492,11 @@ impl SegmentUpdater {
     ///
     /// This does not include lock files, or files that are obsolete
     /// but have not yet been deleted by the garbage collector.
-    fn list_files(&self) -> HashSet<PathBuf> {
+    ///
+    /// The files of the retained commit points are included. An error is returned
+    /// if the metas of one of them cannot be loaded, as its files would otherwise
+    /// be deleted.
+    fn list_files(&self) -> crate::Result<HashSet<PathBuf>> {
         let mut files: HashSet<PathBuf> = self
             .index
             .list_all_segment_metas()
@@ -434,7 +504,30 @@ impl SegmentUpdater {
             .flat_map(|segment_meta| segment_meta.list_files())
             .collect();
         files.insert(META_FILEPATH.to_path_buf());
-        files
+        let index_meta = self.load_meta();
+        if index_meta.index_settings.num_retained_commits > 0 {
+            files.insert(commit_meta_filepath(index_meta.opstamp));
+        }
+        for commit_point in &index_meta.retained_commits {
+            files.insert(commit_meta_filepath(commit_point.opstamp));
+            let commit_metas = match self.index.load_commit_metas(commit_point.opstamp) {
+                Ok(commit_metas) => commit_metas,
+                Err(err) => {
+                    error!(
+                        "Failed to load the metas of commit {}: {:?}",
+                        commit_point.opstamp, err
+                    );
+                    return Err(err);
+                }
+            };
+            files.extend(
+                commit_metas
+                    .segments
+                    .iter()
+                    .flat_map(|segment_meta| segment_meta.list_files()),
+            );
+        }
+        Ok(files)
     }
 
     pub(crate) fn schedule_commit(
@@ -705,7 +798,7 @@ mod tests {
         index_writer.commit()?;
 
         let term = Term::from_field_text(text_field, "a");
-        index_writer.delete_term(term);
+        index_writer.delete_term(term)?;
         index_writer.commit()?;
 
         let reader = index.reader()?;
@@ -739,11 +832,11 @@ mod tests {
         assert!(!seg_ids.is_empty());
 
         let term = Term::from_field_text(text_field, "a");
-        index_writer.delete_term(term);
+        index_writer.delete_term(term)?;
         index_writer.commit()?;
 
         let term = Term::from_field_text(text_field, "b");
-        index_writer.delete_term(term);
+        index_writer.delete_term(term)?;
         index_writer.commit()?;
 
         index_writer.wait_merging_threads()?;
@@ -795,7 +888,7 @@ mod tests {
         let term_vals = vec!["a", "b", "c", "d", "e", "f"];
         for term_val in term_vals {
             let term = Term::from_field_text(text_field, term_val);
-            index_writer.delete_term(term);
+            index_writer.delete_term(term)?;
             index_writer.commit()?;
         }
 
@@ -934,7 +1027,7 @@ mod tests {
             let mut index_writer = index.writer_for_tests()?;
             index_writer.add_document(doc!(text_field=>"some text 3"))?;
             index_writer.add_document(doc!(text_field=>"some text 4"))?;
-            index_writer.delete_term(Term::from_field_text(text_field, "4"));
+            index_writer.delete_term(Term::from_field_text(text_field, "4"))?;
 
             index_writer.commit()?;
             index
@@ -981,7 +1074,7 @@ mod tests {
             index_writer.add_document(doc!(text_field=>"some text 3"))?;
             index_writer.add_document(doc!(text_field=>"some text 4"))?;
 
-            index_writer.delete_term(Term::from_field_text(text_field, "4"));
+            index_writer.delete_term(Term::from_field_text(text_field, "4"))?;
 
             index_writer.commit()?;
             index
@@ -1044,7 +1137,7 @@ mod tests {
             index_writer.add_document(doc!(text_field=>"some text 3"))?;
             index_writer.add_document(doc!(text_field=>"some text 4"))?;
 
-            index_writer.delete_term(Term::from_field_text(text_field, "4"));
+            index_writer.delete_term(Term::from_field_text(text_field, "4"))?;
 
             index_writer.commit()?;
             index
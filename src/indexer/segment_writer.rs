@@ -1,7 +1,7 @@
 use fastfield_codecs::MonotonicallyMappableToU64;
 use itertools::Itertools;
 
-use super::doc_id_mapping::{get_doc_id_mapping_from_field, DocIdMapping};
+use super::doc_id_mapping::{get_doc_id_mapping_from_fields, DocIdMapping};
 use super::operation::AddOperation;
 use crate::core::Segment;
 use crate::fastfield::FastFieldsWriter;
@@ -15,6 +15,7 @@ use crate::postings::{
 use crate::schema::{FieldEntry, FieldType, Schema, Term, Value};
 use crate::store::{StoreReader, StoreWriter};
 use crate::tokenizer::{FacetTokenizer, PreTokenizedStream, TextAnalyzer, Tokenizer};
+use crate::vector::VectorsWriter;
 use crate::{DatePrecision, DocId, Document, Opstamp, SegmentComponent};
 
 /// Computes the initial size of the hash table.
@@ -62,6 +63,7 @@ pub struct SegmentWriter {
     pub(crate) segment_serializer: SegmentSerializer,
     pub(crate) fast_field_writers: FastFieldsWriter,
     pub(crate) fieldnorms_writer: FieldNormsWriter,
+    pub(crate) vectors_writer: VectorsWriter,
     pub(crate) doc_opstamps: Vec<Opstamp>,
     per_field_text_analyzers: Vec<TextAnalyzer>,
     term_buffer: Term,
@@ -112,6 +114,7 @@ impl SegmentWriter {
             fieldnorms_writer: FieldNormsWriter::for_schema(&schema),
             segment_serializer,
             fast_field_writers: FastFieldsWriter::from_schema(&schema),
+            vectors_writer: VectorsWriter::for_schema(&schema),
             doc_opstamps: Vec::with_capacity(1_000),
             per_field_text_analyzers,
             term_buffer: Term::with_capacity(16),
@@ -125,15 +128,18 @@ impl SegmentWriter {
     /// be used afterwards.
     pub fn finalize(mut self) -> crate::Result<Vec<u64>> {
         self.fieldnorms_writer.fill_up_to_max_doc(self.max_doc);
-        let mapping: Option<DocIdMapping> = self
-            .segment_serializer
-            .segment()
-            .index()
-            .settings()
-            .sort_by_field
-            .clone()
-            .map(|sort_by_field| get_doc_id_mapping_from_field(sort_by_field, &self))
-            .transpose()?;
+        let index_settings = self.segment_serializer.segment().index().settings().clone();
+        let sort_by_fields = &index_settings.sort_by;
+        let mapping: Option<DocIdMapping> = if sort_by_fields.is_empty() {
+            None
+        } else {
+            Some(get_doc_id_mapping_from_fields(sort_by_fields, &self)?)
+        };
+        if let Some(vectors_serializer) = self.segment_serializer.extract_vectors_serializer() {
+            debug!("vectors-serialize");
+            self.vectors_writer
+                .serialize(vectors_serializer, mapping.as_ref())?;
+        }
         remap_and_write(
             &self.per_field_postings_writers,
             self.ctx,
@@ -151,6 +157,7 @@ impl SegmentWriter {
         self.ctx.mem_usage()
             + self.fieldnorms_writer.mem_usage()
             + self.fast_field_writers.mem_usage()
+            + self.vectors_writer.mem_usage()
             + self.segment_serializer.mem_usage()
     }
 
@@ -333,6 +340,8 @@ impl SegmentWriter {
                         self.fieldnorms_writer.record(doc_id, field, num_vals);
                     }
                 }
+                // Vectors are not indexed: they are recorded by the `VectorsWriter`.
+                FieldType::Vector(_) => {}
             }
         }
         Ok(())
@@ -343,6 +352,8 @@ impl SegmentWriter {
     /// As a user, you should rather use `IndexWriter`'s add_document.
     pub fn add_document(&mut self, add_operation: AddOperation) -> crate::Result<()> {
         let doc = add_operation.document;
+        // Vectors are validated first, as an invalid vector rejects the whole document.
+        self.vectors_writer.add_document(self.max_doc, &doc)?;
         self.doc_opstamps.push(add_operation.opstamp);
         self.fast_field_writers.add_document(&doc)?;
         self.index_document(&doc)?;
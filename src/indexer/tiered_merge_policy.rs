@@ -0,0 +1,442 @@
+use std::cmp;
+
+use super::merge_policy::{MergeCandidate, MergePolicy};
+use crate::core::{SegmentId, SegmentMeta};
+
+const DEFAULT_MAX_MERGED_SEGMENT_BYTES: u64 = 5 * 1024 * 1024 * 1024;
+const DEFAULT_FLOOR_SEGMENT_BYTES: u64 = 2 * 1024 * 1024;
+const DEFAULT_SEGMENTS_PER_TIER: usize = 10;
+const DEFAULT_MAX_MERGE_AT_ONCE: usize = 10;
+const DEFAULT_DELETES_RATIO_ALLOWED: f32 = 0.33;
+const DEFAULT_RECLAIM_DELETES_WEIGHT: f64 = 2.0;
+
+/// `TieredMergePolicy` merges segments of similar byte size, while
+/// favoring merges reclaiming many deleted documents.
+///
+/// Segments are sorted by size, and organized in tiers of exponentially
+/// growing sizes. Once an index has more segments than the tiers allow, the
+/// policy picks the merges with the lowest score among the sequences of up to
+/// `max_merge_at_once` segments. The score favors merges of segments of similar
+/// size, small merges, and merges that reclaim many deleted documents.
+///
+/// The size of a segment is the number of bytes its files take on disk, pro-rated
+/// by its ratio of alive documents. For segments created by older versions of
+/// tantivy, that size is estimated from the number of documents of the segment.
+///
+/// Segments larger than half of the `max_merged_segment_bytes` are not merged with
+/// other segments anymore. They are rewritten on their own once their ratio of deleted
+/// documents exceeds `deletes_ratio_allowed`.
+#[derive(Debug, Clone)]
+pub struct TieredMergePolicy {
+    max_merged_segment_bytes: u64,
+    floor_segment_bytes: u64,
+    segments_per_tier: usize,
+    max_merge_at_once: usize,
+    deletes_ratio_allowed: f32,
+    reclaim_deletes_weight: f64,
+}
+
+impl TieredMergePolicy {
+    /// Set the maximum size, in bytes, of a segment produced by a merge.
+    ///
+    /// Segments can still be larger than this, if they are bigger when they
+    /// are created, or if they are merged explicitly.
+    pub fn set_max_merged_segment_bytes(&mut self, max_merged_segment_bytes: u64) {
+        self.max_merged_segment_bytes = max_merged_segment_bytes;
+    }
+
+    /// Set the size, in bytes, under which all segments are considered to be
+    /// of the same size.
+    ///
+    /// This avoids having a long tail of tiny segments.
+    pub fn set_floor_segment_bytes(&mut self, floor_segment_bytes: u64) {
+        self.floor_segment_bytes = floor_segment_bytes;
+    }
+
+    /// Set the number of segments allowed per tier.
+    ///
+    /// Lower values mean more merging, and fewer segments.
+    ///
+    /// # Panics
+    ///
+    /// Panics if segments_per_tier is lower than 2.
+    pub fn set_segments_per_tier(&mut self, segments_per_tier: usize) {
+        assert!(segments_per_tier >= 2);
+        self.segments_per_tier = segments_per_tier;
+    }
+
+    /// Set the maximum number of segments merged together by a single merge.
+    ///
+    /// # Panics
+    ///
+    /// Panics if max_merge_at_once is lower than 2.
+    pub fn set_max_merge_at_once(&mut self, max_merge_at_once: usize) {
+        assert!(max_merge_at_once >= 2);
+        self.max_merge_at_once = max_merge_at_once;
+    }
+
+    /// Set the ratio of deleted documents in a segment to tolerate.
+    ///
+    /// Segments exceeding this ratio are rewritten, even if they are too large
+    /// to be merged with other segments.
+    ///
+    /// # Panics
+    ///
+    /// Panics if deletes_ratio_allowed is not within (0..1].
+    pub fn set_deletes_ratio_allowed(&mut self, deletes_ratio_allowed: f32) {
+        assert!(deletes_ratio_allowed <= 1.0f32);
+        assert!(deletes_ratio_allowed > 0f32);
+        self.deletes_ratio_allowed = deletes_ratio_allowed;
+    }
+
+    /// Set how aggressively merges reclaiming deleted documents are favored.
+    ///
+    /// 0 means deletes are not taken in account when scoring merges.
+    ///
+    /// # Panics
+    ///
+    /// Panics if reclaim_deletes_weight is negative.
+    pub fn set_reclaim_deletes_weight(&mut self, reclaim_deletes_weight: f64) {
+        assert!(reclaim_deletes_weight >= 0f64);
+        self.reclaim_deletes_weight = reclaim_deletes_weight;
+    }
+
+    fn floor_size(&self, num_bytes: u64) -> u64 {
+        cmp::max(self.floor_segment_bytes, num_bytes)
+    }
+
+    /// Returns the number of segments the tiers allow for the given segments.
+    fn allowed_segment_count(&self, segments: &[SegmentSize]) -> usize {
+        let min_segment_bytes = segments
+            .iter()
+            .map(|segment| segment.alive_bytes)
+            .min()
+            .unwrap_or(0);
+        let mut level_bytes = self.floor_size(min_segment_bytes).max(1) as f64;
+        let mut bytes_left: f64 = segments
+            .iter()
+            .map(|segment| segment.alive_bytes as f64)
+            .sum();
+        let segments_per_tier = self.segments_per_tier as f64;
+        let mut allowed_segment_count = 0f64;
+        loop {
+            let level_segment_count = bytes_left / level_bytes;
+            if level_segment_count < segments_per_tier {
+                allowed_segment_count += level_segment_count.ceil();
+                break;
+            }
+            allowed_segment_count += segments_per_tier;
+            bytes_left -= segments_per_tier * level_bytes;
+            level_bytes *= self.max_merge_at_once as f64;
+        }
+        cmp::max(allowed_segment_count as usize, self.segments_per_tier)
+    }
+
+    /// Returns the indexes of the segments of the best merge, if any.
+    ///
+    /// `segments` is expected to be sorted by decreasing size.
+    fn find_best_merge(&self, segments: &[SegmentSize]) -> Option<Vec<usize>> {
+        let mut best_merge: Option<(f64, Vec<usize>)> = None;
+        for start in 0..segments.len() {
+            let mut merge = Vec::new();
+            let mut merge_alive_bytes = 0u64;
+            let mut hit_too_large = false;
+            for (ord, segment) in segments.iter().enumerate().skip(start) {
+                if merge.len() == self.max_merge_at_once {
+                    break;
+                }
+                if merge_alive_bytes + segment.alive_bytes > self.max_merged_segment_bytes {
+                    // Smaller segments may still fit in the merge.
+                    hit_too_large = true;
+                    continue;
+                }
+                merge.push(ord);
+                merge_alive_bytes += segment.alive_bytes;
+            }
+            if merge.len() < 2 {
+                continue;
+            }
+            let score = self.merge_score(segments, &merge, hit_too_large);
+            if best_merge
+                .as_ref()
+                .map(|(best_score, _)| score < *best_score)
+                .unwrap_or(true)
+            {
+                best_merge = Some((score, merge));
+            }
+        }
+        best_merge.map(|(_, merge)| merge)
+    }
+
+    /// Scores a merge. Lower is better.
+    fn merge_score(&self, segments: &[SegmentSize], merge: &[usize], hit_too_large: bool) -> f64 {
+        let mut num_bytes = 0u64;
+        let mut alive_bytes = 0u64;
+        let mut floored_alive_bytes = 0u64;
+        for &ord in merge {
+            num_bytes += segments[ord].num_bytes;
+            alive_bytes += segments[ord].alive_bytes;
+            floored_alive_bytes += self.floor_size(segments[ord].alive_bytes);
+        }
+        // Roughly measures how balanced the merge is.
+        let skew = if hit_too_large {
+            // The merge is close to the maximum segment size: it is as good
+            // as a perfectly balanced merge.
+            1.0 / self.max_merge_at_once as f64
+        } else {
+            self.floor_size(segments[merge[0]].alive_bytes) as f64
+                / floored_alive_bytes.max(1) as f64
+        };
+        // Gently favor smaller merges.
+        let size_score = (alive_bytes.max(1) as f64).powf(0.05);
+        // Favor merges reclaiming deletes.
+        let alive_ratio = alive_bytes as f64 / num_bytes.max(1) as f64;
+        skew * size_score * alive_ratio.powf(self.reclaim_deletes_weight)
+    }
+}
+
+impl Default for TieredMergePolicy {
+    fn default() -> TieredMergePolicy {
+        TieredMergePolicy {
+            max_merged_segment_bytes: DEFAULT_MAX_MERGED_SEGMENT_BYTES,
+            floor_segment_bytes: DEFAULT_FLOOR_SEGMENT_BYTES,
+            segments_per_tier: DEFAULT_SEGMENTS_PER_TIER,
+            max_merge_at_once: DEFAULT_MAX_MERGE_AT_ONCE,
+            deletes_ratio_allowed: DEFAULT_DELETES_RATIO_ALLOWED,
+            reclaim_deletes_weight: DEFAULT_RECLAIM_DELETES_WEIGHT,
+        }
+    }
+}
+
+struct SegmentSize {
+    segment_id: SegmentId,
+    num_bytes: u64,
+    alive_bytes: u64,
+    deletes_ratio: f32,
+}
+
+impl SegmentSize {
+    fn new(segment: &SegmentMeta, bytes_per_doc: f64) -> SegmentSize {
+        let num_bytes = estimated_num_bytes(segment, bytes_per_doc);
+        let deletes_ratio = deletes_ratio(segment);
+        SegmentSize {
+            segment_id: segment.id(),
+            num_bytes,
+            alive_bytes: (num_bytes as f64 * (1.0 - deletes_ratio as f64)) as u64,
+            deletes_ratio,
+        }
+    }
+}
+
+// Soft-deleted documents are not taken in account, as merging would not reclaim them
+// as long as they are retained.
+fn deletes_ratio(segment: &SegmentMeta) -> f32 {
+    if segment.max_doc() == 0 {
+        return 0f32;
+    }
+    segment.num_reclaimable_deleted_docs() as f32 / segment.max_doc() as f32
+}
+
+// On-disk size of the segment, estimated from `bytes_per_doc` if it was not recorded.
+pub(crate) fn estimated_num_bytes(segment: &SegmentMeta, bytes_per_doc: f64) -> u64 {
+    segment
+        .num_bytes()
+        .unwrap_or_else(|| (segment.max_doc() as f64 * bytes_per_doc) as u64)
+}
+
+// Average number of bytes per document of the segments for which it is known.
+//
+// Returns 1 if no segment has its size recorded, in which case segments are
+// compared by their number of documents.
+pub(crate) fn bytes_per_doc(segments: &[SegmentMeta]) -> f64 {
+    let (num_bytes, num_docs) = segments
+        .iter()
+        .filter_map(|segment| Some((segment.num_bytes()?, segment.max_doc() as u64)))
+        .fold(
+            (0u64, 0u64),
+            |(total_bytes, total_docs), (num_bytes, num_docs)| {
+                (total_bytes + num_bytes, total_docs + num_docs)
+            },
+        );
+    if num_docs == 0 {
+        return 1.0;
+    }
+    num_bytes as f64 / num_docs as f64
+}
+
+impl MergePolicy for TieredMergePolicy {
+    fn compute_merge_candidates(&self, segments: &[SegmentMeta]) -> Vec<MergeCandidate> {
+        let bytes_per_doc = bytes_per_doc(segments);
+        let mut segment_sizes: Vec<SegmentSize> = segments
+            .iter()
+            .map(|segment| SegmentSize::new(segment, bytes_per_doc))
+            .collect();
+        segment_sizes.sort_by_key(|segment| cmp::Reverse(segment.alive_bytes));
+
+        let mut merge_candidates = Vec::new();
+        let mut eligible_segments = Vec::new();
+        for segment in segment_sizes {
+            if segment.alive_bytes > self.max_merged_segment_bytes / 2 {
+                // The segment is too large to be merged with other segments,
+                // but we still reclaim its deletes.
+                if segment.deletes_ratio > self.deletes_ratio_allowed {
+                    merge_candidates.push(MergeCandidate(vec![segment.segment_id]));
+                }
+            } else {
+                eligible_segments.push(segment);
+            }
+        }
+
+        let allowed_segment_count = self.allowed_segment_count(&eligible_segments);
+        let mut num_segments_after_merges = eligible_segments.len();
+        while num_segments_after_merges > allowed_segment_count {
+            let merge = if let Some(merge) = self.find_best_merge(&eligible_segments) {
+                merge
+            } else {
+                break;
+            };
+            num_segments_after_merges -= merge.len() - 1;
+            // `merge` is sorted, so removing the segments from the end
+            // keeps the remaining indexes valid.
+            let segment_ids = merge
+                .iter()
+                .rev()
+                .map(|&ord| eligible_segments.remove(ord).segment_id)
+                .collect();
+            merge_candidates.push(MergeCandidate(segment_ids));
+        }
+
+        // The remaining segments with too many deletes are rewritten on their own.
+        merge_candidates.extend(
+            eligible_segments
+                .iter()
+                .filter(|segment| segment.deletes_ratio > self.deletes_ratio_allowed)
+                .map(|segment| MergeCandidate(vec![segment.segment_id])),
+        );
+        merge_candidates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use once_cell::sync::Lazy;
+
+    use super::*;
+    use crate::core::{SegmentId, SegmentMeta, SegmentMetaInventory};
+    use crate::indexer::merge_policy::MergePolicy;
+
+    static INVENTORY: Lazy<SegmentMetaInventory> = Lazy::new(SegmentMetaInventory::default);
+
+    const MB: u64 = 1024 * 1024;
+
+    fn create_segment_meta(max_doc: u32, num_deleted_docs: u32, num_bytes: u64) -> SegmentMeta {
+        let segment_meta = INVENTORY
+            .new_segment_meta(SegmentId::generate_random(), max_doc)
+            .with_num_bytes(num_bytes);
+        if num_deleted_docs > 0 {
+            segment_meta.with_delete_meta(num_deleted_docs, 0)
+        } else {
+            segment_meta
+        }
+    }
+
+    fn test_merge_policy() -> TieredMergePolicy {
+        let mut merge_policy = TieredMergePolicy::default();
+        merge_policy.set_segments_per_tier(3);
+        merge_policy.set_max_merge_at_once(3);
+        merge_policy.set_floor_segment_bytes(MB);
+        merge_policy.set_max_merged_segment_bytes(100 * MB);
+        merge_policy
+    }
+
+    #[test]
+    fn test_tiered_merge_policy_empty() {
+        let merge_candidates = test_merge_policy().compute_merge_candidates(&[]);
+        assert!(merge_candidates.is_empty());
+    }
+
+    #[test]
+    fn test_tiered_merge_policy_few_segments() {
+        let segments = vec![
+            create_segment_meta(10, 0, MB),
+            create_segment_meta(10, 0, MB),
+            create_segment_meta(10, 0, MB),
+        ];
+        let merge_candidates = test_merge_policy().compute_merge_candidates(&segments);
+        assert!(merge_candidates.is_empty());
+    }
+
+    #[test]
+    fn test_tiered_merge_policy_merges_similar_sizes() {
+        let mut segments = vec![create_segment_meta(1_000, 0, 40 * MB)];
+        segments.extend((0..10).map(|_| create_segment_meta(10, 0, MB / 2)));
+        let merge_candidates = test_merge_policy().compute_merge_candidates(&segments);
+        assert_eq!(merge_candidates.len(), 1);
+        let merged_segments = &merge_candidates[0].0;
+        assert_eq!(merged_segments.len(), 3);
+        assert!(!merged_segments.contains(&segments[0].id()));
+    }
+
+    #[test]
+    fn test_tiered_merge_policy_max_merged_segment_bytes() {
+        let segments: Vec<SegmentMeta> = (0..6)
+            .map(|_| create_segment_meta(1_000, 0, 40 * MB))
+            .collect();
+        let merge_candidates = test_merge_policy().compute_merge_candidates(&segments);
+        // Only 2 of these segments fit into a merged segment.
+        assert!(!merge_candidates.is_empty());
+        for merge_candidate in &merge_candidates {
+            assert_eq!(merge_candidate.0.len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_tiered_merge_policy_favors_deletes() {
+        let segments = vec![
+            create_segment_meta(100, 0, 10 * MB),
+            create_segment_meta(100, 0, 10 * MB),
+            create_segment_meta(100, 60, 10 * MB),
+            create_segment_meta(100, 60, 10 * MB),
+        ];
+        let mut merge_policy = test_merge_policy();
+        merge_policy.set_max_merge_at_once(2);
+        merge_policy.set_floor_segment_bytes(20 * MB);
+        merge_policy.set_deletes_ratio_allowed(1.0);
+        let merge_candidates = merge_policy.compute_merge_candidates(&segments);
+        assert_eq!(merge_candidates.len(), 1);
+        let merged_segments = &merge_candidates[0].0;
+        assert!(merged_segments.contains(&segments[2].id()));
+        assert!(merged_segments.contains(&segments[3].id()));
+    }
+
+    #[test]
+    fn test_tiered_merge_policy_expunges_deletes_of_large_segments() {
+        let segments = vec![
+            create_segment_meta(1_000, 400, 90 * MB),
+            create_segment_meta(1_000, 100, 90 * MB),
+        ];
+        let merge_candidates = test_merge_policy().compute_merge_candidates(&segments);
+        assert_eq!(merge_candidates.len(), 1);
+        assert_eq!(merge_candidates[0].0, vec![segments[0].id()]);
+    }
+
+    #[test]
+    fn test_tiered_merge_policy_ignores_soft_deletes() {
+        let segments = vec![
+            create_segment_meta(1_000, 400, 90 * MB).with_num_soft_deleted_docs(400),
+            create_segment_meta(1_000, 100, 90 * MB),
+        ];
+        let merge_candidates = test_merge_policy().compute_merge_candidates(&segments);
+        assert!(merge_candidates.is_empty());
+    }
+
+    #[test]
+    fn test_tiered_merge_policy_without_num_bytes() {
+        let segments: Vec<SegmentMeta> = (0..5)
+            .map(|_| INVENTORY.new_segment_meta(SegmentId::generate_random(), 10))
+            .collect();
+        let merge_candidates = test_merge_policy().compute_merge_candidates(&segments);
+        assert_eq!(merge_candidates.len(), 1);
+        assert_eq!(merge_candidates[0].0.len(), 3);
+    }
+}
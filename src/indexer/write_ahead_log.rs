@@ -0,0 +1,446 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use common::{BinarySerializable, VInt};
+use crc32fast::Hasher;
+use once_cell::sync::Lazy;
+
+use crate::directory::error::{DeleteError, OpenReadError};
+use crate::directory::{Directory, TerminatingWrite, WritePtr};
+use crate::error::DataCorruption;
+use crate::indexer::operation::UserOperation;
+use crate::schema::{Document, Term};
+use crate::{Opstamp, SegmentId, TantivyError};
+
+/// The manifest lists the files of the write-ahead log.
+///
+/// Like the log files, it is not managed: it is never garbage collected.
+static WAL_MANIFEST_FILEPATH: Lazy<&'static Path> = Lazy::new(|| Path::new("wal.json"));
+
+const OPERATIONS_RECORD: u8 = 0;
+const DELETE_ALL_DOCUMENTS_RECORD: u8 = 1;
+
+const ADD_OPERATION: u8 = 0;
+const DELETE_OPERATION: u8 = 1;
+const UPDATE_OPERATION: u8 = 2;
+
+/// An operation that can be appended to the write-ahead log.
+pub(crate) enum WalOperation<'a> {
+    Add(&'a Document),
+    Delete(&'a Term),
+    Update(&'a Term, &'a Document),
+}
+
+impl<'a> WalOperation<'a> {
+    /// Returns `None` for the operations that cannot be logged, as they
+    /// cannot be serialized.
+    pub fn from_user_operation(user_operation: &'a UserOperation) -> Option<WalOperation<'a>> {
+        match user_operation {
+            UserOperation::Add(document) => Some(WalOperation::Add(document)),
+            UserOperation::Delete(term) => Some(WalOperation::Delete(term)),
+            UserOperation::Update(term, document) => Some(WalOperation::Update(term, document)),
+            UserOperation::DeleteQuery(_) => None,
+        }
+    }
+
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            WalOperation::Add(document) => {
+                ADD_OPERATION.serialize(writer)?;
+                document.serialize(writer)?;
+            }
+            WalOperation::Delete(term) => {
+                DELETE_OPERATION.serialize(writer)?;
+                serialize_term(term, writer)?;
+            }
+            WalOperation::Update(term, document) => {
+                UPDATE_OPERATION.serialize(writer)?;
+                serialize_term(term, writer)?;
+                document.serialize(writer)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn serialize_term<W: Write>(term: &Term, writer: &mut W) -> io::Result<()> {
+    let term_bytes = term.as_slice();
+    VInt(term_bytes.len() as u64).serialize(writer)?;
+    writer.write_all(term_bytes)
+}
+
+fn deserialize_term(data: &mut &[u8]) -> io::Result<Term> {
+    let num_bytes = VInt::deserialize(data)?.val() as usize;
+    if data.len() < num_bytes {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+    }
+    let term = Term::wrap(data[..num_bytes].to_vec());
+    *data = &data[num_bytes..];
+    Ok(term)
+}
+
+fn deserialize_operation(data: &mut &[u8]) -> io::Result<UserOperation> {
+    match u8::deserialize(data)? {
+        ADD_OPERATION => Ok(UserOperation::Add(Document::deserialize(data)?)),
+        DELETE_OPERATION => Ok(UserOperation::Delete(deserialize_term(data)?)),
+        UPDATE_OPERATION => {
+            let term = deserialize_term(data)?;
+            let document = Document::deserialize(data)?;
+            Ok(UserOperation::Update(term, document))
+        }
+        code => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unknown write-ahead log operation code {}.", code),
+        )),
+    }
+}
+
+/// A record read from the write-ahead log, to be replayed.
+pub(crate) enum WalRecord {
+    /// A group of operations, applied atomically.
+    Operations(Vec<UserOperation>),
+    /// A call to `IndexWriter::delete_all_documents`.
+    DeleteAllDocuments,
+}
+
+impl WalRecord {
+    fn deserialize(mut data: &[u8]) -> io::Result<WalRecord> {
+        let data = &mut data;
+        let record_type = u8::deserialize(data)?;
+        // The opstamp of the record is only informative: replayed operations
+        // are stamped again.
+        let _opstamp = Opstamp::deserialize(data)?;
+        match record_type {
+            OPERATIONS_RECORD => {
+                let num_operations = VInt::deserialize(data)?.val() as usize;
+                let operations = (0..num_operations)
+                    .map(|_| deserialize_operation(data))
+                    .collect::<io::Result<Vec<UserOperation>>>()?;
+                Ok(WalRecord::Operations(operations))
+            }
+            DELETE_ALL_DOCUMENTS_RECORD => Ok(WalRecord::DeleteAllDocuments),
+            code => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown write-ahead log record code {}.", code),
+            )),
+        }
+    }
+}
+
+/// A file of the write-ahead log.
+///
+/// It holds operations that apply on top of the commit with opstamp `base_opstamp`.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct WalFile {
+    path: PathBuf,
+    base_opstamp: Opstamp,
+}
+
+impl WalFile {
+    fn new(base_opstamp: Opstamp) -> WalFile {
+        let path = PathBuf::from(format!(
+            "{}.wal",
+            SegmentId::generate_random().uuid_string()
+        ));
+        WalFile { path, base_opstamp }
+    }
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct WalManifest {
+    files: Vec<WalFile>,
+}
+
+fn load_manifest(directory: &dyn Directory) -> crate::Result<WalManifest> {
+    match directory.atomic_read(&WAL_MANIFEST_FILEPATH) {
+        Ok(data) => serde_json::from_slice(&data).map_err(|err| {
+            DataCorruption::new(
+                WAL_MANIFEST_FILEPATH.to_path_buf(),
+                format!(
+                    "Write-ahead log manifest cannot be deserialized. {:?}.",
+                    err
+                ),
+            )
+            .into()
+        }),
+        Err(OpenReadError::FileDoesNotExist(_)) => Ok(WalManifest::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn save_manifest(directory: &dyn Directory, files: &[WalFile]) -> crate::Result<()> {
+    let manifest = WalManifest {
+        files: files.to_vec(),
+    };
+    directory.sync_directory()?;
+    directory.atomic_write(&WAL_MANIFEST_FILEPATH, &serde_json::to_vec(&manifest)?)?;
+    Ok(())
+}
+
+fn delete_file(directory: &dyn Directory, path: &Path) -> crate::Result<()> {
+    match directory.delete(path) {
+        Ok(()) | Err(DeleteError::FileDoesNotExist(_)) => Ok(()),
+        Err(DeleteError::IoError { io_error, .. }) => Err(TantivyError::IoError(io_error)),
+    }
+}
+
+// Reads the records of a log file.
+//
+// Each record is prefixed with its length and its checksum. A truncated or corrupted
+// record can only be the last one, written as the process crashed: it is ignored,
+// along with anything that follows it.
+fn read_records(directory: &dyn Directory, path: &Path) -> crate::Result<Vec<WalRecord>> {
+    let bytes = match directory.open_read(path) {
+        Ok(file_slice) => file_slice.read_bytes()?,
+        Err(OpenReadError::FileDoesNotExist(_)) => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut data = bytes.as_slice();
+    let mut records = Vec::new();
+    while data.len() >= 8 {
+        let num_bytes = u32::deserialize(&mut data)? as usize;
+        let crc = u32::deserialize(&mut data)?;
+        if data.len() < num_bytes {
+            warn!(
+                "Truncated record at the end of the write-ahead log {:?}",
+                path
+            );
+            break;
+        }
+        let (payload, remaining) = data.split_at(num_bytes);
+        let mut hasher = Hasher::new();
+        hasher.update(payload);
+        if hasher.finalize() != crc {
+            warn!(
+                "Corrupted record at the end of the write-ahead log {:?}",
+                path
+            );
+            break;
+        }
+        records.push(WalRecord::deserialize(payload)?);
+        data = remaining;
+    }
+    Ok(records)
+}
+
+/// Appends records to the current file of the write-ahead log.
+pub(crate) struct WalWriter {
+    files: Vec<WalFile>,
+    write: Option<WritePtr>,
+}
+
+impl WalWriter {
+    /// Appends a group of operations, and syncs it to the directory.
+    pub fn append_operations(
+        &mut self,
+        opstamp: Opstamp,
+        operations: &[WalOperation],
+    ) -> io::Result<()> {
+        let mut payload = Vec::new();
+        OPERATIONS_RECORD.serialize(&mut payload)?;
+        opstamp.serialize(&mut payload)?;
+        VInt(operations.len() as u64).serialize(&mut payload)?;
+        for operation in operations {
+            operation.serialize(&mut payload)?;
+        }
+        self.append(&payload)
+    }
+
+    /// Appends the deletion of all of the documents, and syncs it to the directory.
+    pub fn append_delete_all_documents(&mut self, opstamp: Opstamp) -> io::Result<()> {
+        let mut payload = Vec::new();
+        DELETE_ALL_DOCUMENTS_RECORD.serialize(&mut payload)?;
+        opstamp.serialize(&mut payload)?;
+        self.append(&payload)
+    }
+
+    fn append(&mut self, payload: &[u8]) -> io::Result<()> {
+        let write = self.write.as_mut().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Other, "The write-ahead log was cleared.")
+        })?;
+        let mut hasher = Hasher::new();
+        hasher.update(payload);
+        let mut record = Vec::with_capacity(payload.len() + 8);
+        (payload.len() as u32).serialize(&mut record)?;
+        hasher.finalize().serialize(&mut record)?;
+        record.extend_from_slice(payload);
+        write.write_all(&record)?;
+        write.sync_data()
+    }
+}
+
+/// Write-ahead log of the index writer.
+///
+/// The log is made of files that each hold the operations applied on top of a given
+/// commit. A new file is started when a commit is prepared, and the files of the
+/// previous commits are deleted once the commit succeeds.
+///
+/// The log files and their manifest are written directly in the wrapped directory,
+/// without being managed.
+pub(crate) struct WriteAheadLog {
+    directory: Box<dyn Directory>,
+    writer: Mutex<WalWriter>,
+}
+
+impl WriteAheadLog {
+    /// Opens the write-ahead log, and returns the records that were not committed
+    /// by the commit with opstamp `committed_opstamp`.
+    ///
+    /// Replayed records are not appended to the log again: their files are kept
+    /// until the next commit succeeds.
+    pub fn open(
+        directory: Box<dyn Directory>,
+        committed_opstamp: Opstamp,
+    ) -> crate::Result<(WriteAheadLog, Vec<WalRecord>)> {
+        let manifest = load_manifest(directory.as_ref())?;
+        let (mut files, obsolete_files): (Vec<WalFile>, Vec<WalFile>) = manifest
+            .files
+            .into_iter()
+            .partition(|file| file.base_opstamp >= committed_opstamp);
+        let mut records = Vec::new();
+        for file in &files {
+            records.extend(read_records(directory.as_ref(), &file.path)?);
+        }
+        let file = WalFile::new(committed_opstamp);
+        let write = directory.open_write(&file.path)?;
+        files.push(file);
+        save_manifest(directory.as_ref(), &files)?;
+        for file in obsolete_files {
+            delete_file(directory.as_ref(), &file.path)?;
+        }
+        let write_ahead_log = WriteAheadLog {
+            directory,
+            writer: Mutex::new(WalWriter {
+                files,
+                write: Some(write),
+            }),
+        };
+        Ok((write_ahead_log, records))
+    }
+
+    /// Locks the log, in order to append records to it.
+    pub fn lock(&self) -> MutexGuard<'_, WalWriter> {
+        self.writer
+            .lock()
+            .expect("Write-ahead log lock poisoned. This is a bug, please report.")
+    }
+
+    /// Starts a new file, for the operations applied on top of the commit with opstamp
+    /// `base_opstamp`.
+    ///
+    /// This is called when the commit is prepared.
+    pub fn rotate(&self, base_opstamp: Opstamp, committed_opstamp: Opstamp) -> crate::Result<()> {
+        let mut writer = self.lock();
+        let file = WalFile::new(base_opstamp);
+        let write = self.directory.open_write(&file.path)?;
+        if let Some(previous_write) = writer.write.replace(write) {
+            previous_write.terminate()?;
+        }
+        writer.files.push(file);
+        self.remove_obsolete_files(&mut writer, committed_opstamp)
+    }
+
+    /// Deletes the files holding operations that are part of the commit with opstamp
+    /// `committed_opstamp`.
+    ///
+    /// This is called once the commit succeeded.
+    pub fn truncate(&self, committed_opstamp: Opstamp) -> crate::Result<()> {
+        let mut writer = self.lock();
+        self.remove_obsolete_files(&mut writer, committed_opstamp)
+    }
+
+    /// Deletes all of the files of the log.
+    ///
+    /// This is called on rollback. No record can be appended afterwards.
+    pub fn clear(&self) -> crate::Result<()> {
+        let mut writer = self.lock();
+        if let Some(write) = writer.write.take() {
+            write.terminate()?;
+        }
+        delete_file(self.directory.as_ref(), &WAL_MANIFEST_FILEPATH)?;
+        for file in std::mem::take(&mut writer.files) {
+            delete_file(self.directory.as_ref(), &file.path)?;
+        }
+        Ok(())
+    }
+
+    fn remove_obsolete_files(
+        &self,
+        writer: &mut WalWriter,
+        committed_opstamp: Opstamp,
+    ) -> crate::Result<()> {
+        let (files, obsolete_files): (Vec<WalFile>, Vec<WalFile>) =
+            std::mem::take(&mut writer.files)
+                .into_iter()
+                .partition(|file| file.base_opstamp >= committed_opstamp);
+        writer.files = files;
+        save_manifest(self.directory.as_ref(), &writer.files)?;
+        for file in obsolete_files {
+            delete_file(self.directory.as_ref(), &file.path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{WalOperation, WalRecord, WriteAheadLog};
+    use crate::directory::{RamDirectory, TerminatingWrite};
+    use crate::indexer::operation::UserOperation;
+    use crate::schema::{Schema, STRING};
+    use crate::Term;
+
+    #[test]
+    fn test_write_ahead_log_replay() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", STRING);
+        let directory = RamDirectory::create();
+        let term = Term::from_field_text(text_field, "hello");
+        let document = doc!(text_field => "hello");
+        {
+            let (write_ahead_log, records) = WriteAheadLog::open(Box::new(directory.clone()), 0)?;
+            assert!(records.is_empty());
+            let mut writer = write_ahead_log.lock();
+            writer.append_operations(1, &[WalOperation::Add(&document)])?;
+            writer.append_operations(
+                2,
+                &[
+                    WalOperation::Delete(&term),
+                    WalOperation::Update(&term, &document),
+                ],
+            )?;
+            writer.append_delete_all_documents(0)?;
+            // Simulates a record being written as the process crashes.
+            writer.write.as_mut().unwrap().write_all(&[3, 0, 0, 0, 1])?;
+            writer.write.take().unwrap().terminate()?;
+        }
+        let (write_ahead_log, records) = WriteAheadLog::open(Box::new(directory.clone()), 0)?;
+        assert_eq!(records.len(), 3);
+        assert!(matches!(
+            &records[0],
+            WalRecord::Operations(operations)
+                if matches!(&operations[..], [UserOperation::Add(doc)] if doc == &document)
+        ));
+        assert!(matches!(
+            &records[1],
+            WalRecord::Operations(operations)
+                if matches!(
+                    &operations[..],
+                    [UserOperation::Delete(t1), UserOperation::Update(t2, doc)]
+                        if t1 == &term && t2 == &term && doc == &document
+                )
+        ));
+        assert!(matches!(&records[2], WalRecord::DeleteAllDocuments));
+
+        // Once the commit with opstamp 3 succeeded, the records are not replayed anymore.
+        write_ahead_log.rotate(3, 0)?;
+        write_ahead_log.truncate(3)?;
+        drop(write_ahead_log);
+        let (_, records) = WriteAheadLog::open(Box::new(directory.clone()), 3)?;
+        assert!(records.is_empty());
+        Ok(())
+    }
+}
@@ -273,10 +273,12 @@ pub mod postings;
 
 /// Module containing the different query implementations.
 pub mod query;
+pub mod replication;
 pub mod schema;
 pub mod space_usage;
 pub mod store;
 pub mod termdict;
+pub mod vector;
 
 mod reader;
 
@@ -294,9 +296,10 @@ use serde::{Deserialize, Serialize};
 
 pub use self::docset::{DocSet, TERMINATED};
 pub use crate::core::{
-    Executor, Index, IndexBuilder, IndexMeta, IndexSettings, IndexSortByField, InvertedIndexReader,
-    Order, Searcher, SearcherGeneration, Segment, SegmentComponent, SegmentId, SegmentMeta,
-    SegmentReader, SingleSegmentIndexWriter,
+    CancellationToken, CommitPoint, Executor, Index, IndexBuilder, IndexMeta, IndexSettings,
+    IndexSnapshot, IndexSortByField, InvertedIndexReader, Order, SearchOutcome, Searcher,
+    SearcherGeneration, Segment, SegmentComponent, SegmentId, SegmentMeta, SegmentReader,
+    SingleSegmentIndexWriter, SnapshotFile, SoftDelete, SoftDeletes, SoftDeletesRetention,
 };
 pub use crate::directory::Directory;
 pub use crate::indexer::operation::UserOperation;
@@ -356,6 +359,7 @@ pub fn version_string() -> &'static str {
 pub mod merge_policy {
     pub use crate::indexer::{
         DefaultMergePolicy, LogMergePolicy, MergeCandidate, MergePolicy, NoMergePolicy,
+        TieredMergePolicy,
     };
 }
 
@@ -621,8 +625,8 @@ pub mod tests {
             // 3
             index_writer.add_document(doc!(text_field=>" b d"))?;
 
-            index_writer.delete_term(Term::from_field_text(text_field, "c"));
-            index_writer.delete_term(Term::from_field_text(text_field, "a"));
+            index_writer.delete_term(Term::from_field_text(text_field, "c"))?;
+            index_writer.delete_term(Term::from_field_text(text_field, "a"))?;
             // 4
             index_writer.add_document(doc!(text_field=>" b c"))?;
             // 5
@@ -662,7 +666,7 @@ pub mod tests {
             // 0
             index_writer.add_document(doc!(text_field=>"a b"))?;
             // 1
-            index_writer.delete_term(Term::from_field_text(text_field, "c"));
+            index_writer.delete_term(Term::from_field_text(text_field, "c"))?;
             index_writer.rollback()?;
         }
         {
@@ -697,9 +701,9 @@ pub mod tests {
             // writing the segment
             let mut index_writer = index.writer_for_tests()?;
             index_writer.add_document(doc!(text_field=>"a b"))?;
-            index_writer.delete_term(Term::from_field_text(text_field, "c"));
+            index_writer.delete_term(Term::from_field_text(text_field, "c"))?;
             index_writer.rollback()?;
-            index_writer.delete_term(Term::from_field_text(text_field, "a"));
+            index_writer.delete_term(Term::from_field_text(text_field, "a"))?;
             index_writer.commit()?;
         }
         {
@@ -848,8 +852,8 @@ pub mod tests {
         index_writer.add_document(doc!(text_field=>"33"))?;
         index_writer.add_document(doc!(text_field=>"40"))?;
         index_writer.add_document(doc!(text_field=>"17"))?;
-        index_writer.delete_term(Term::from_field_text(text_field, "38"));
-        index_writer.delete_term(Term::from_field_text(text_field, "34"));
+        index_writer.delete_term(Term::from_field_text(text_field, "38"))?;
+        index_writer.delete_term(Term::from_field_text(text_field, "34"))?;
         index_writer.commit()?;
         reader.reload()?;
         assert_eq!(reader.searcher().num_docs(), 6);
@@ -1083,7 +1087,7 @@ pub mod tests {
 
         // update the 10 elements by deleting and re-adding
         for doc_id in 0u64..DOC_COUNT {
-            index_writer.delete_term(Term::from_field_u64(id, doc_id));
+            index_writer.delete_term(Term::from_field_u64(id, doc_id))?;
             index_writer.commit()?;
             index_reader.reload()?;
             index_writer.add_document(doc!(id =>  doc_id))?;
@@ -1128,7 +1132,7 @@ pub mod tests {
         assert!(index.validate_checksum()?.is_empty());
 
         // delete few docs
-        writer.delete_term(Term::from_field_text(body, "foo"));
+        writer.delete_term(Term::from_field_text(body, "foo"))?;
         writer.commit()?;
         let segment_ids = index.searchable_segment_ids()?;
         writer.merge(&segment_ids).wait()?;
@@ -1,4 +1,6 @@
+use std::cell::Cell;
 use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use common::VInt;
 
@@ -10,6 +12,38 @@ use crate::query::Bm25Weight;
 use crate::schema::IndexRecordOption;
 use crate::{DocId, Score, TERMINATED};
 
+thread_local! {
+    // Number of blocks decoded by the current thread, reported by query profiling.
+    static NUM_DECODED_BLOCKS: Cell<u64> = const { Cell::new(0) };
+}
+
+// Number of live `DecodedBlockCounting` guards. Decoded blocks are only counted while
+// it is not zero, so that searches run without profiling do not update the counter.
+static NUM_BLOCK_COUNTING_GUARDS: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the number of postings blocks decoded so far by the current thread.
+///
+/// Blocks are only counted while a [`DecodedBlockCounting`] guard is alive.
+pub(crate) fn num_decoded_blocks() -> u64 {
+    NUM_DECODED_BLOCKS.with(Cell::get)
+}
+
+/// Enables the counting of decoded blocks until it is dropped.
+pub(crate) struct DecodedBlockCounting;
+
+impl DecodedBlockCounting {
+    pub(crate) fn start() -> DecodedBlockCounting {
+        NUM_BLOCK_COUNTING_GUARDS.fetch_add(1, Ordering::Relaxed);
+        DecodedBlockCounting
+    }
+}
+
+impl Drop for DecodedBlockCounting {
+    fn drop(&mut self) {
+        NUM_BLOCK_COUNTING_GUARDS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
 fn max_score<I: Iterator<Item = Score>>(mut it: I) -> Option<Score> {
     it.next().map(|first| it.fold(first, Score::max))
 }
@@ -278,6 +312,9 @@ impl BlockSegmentPostings {
             return;
         }
         self.loaded_offset = offset;
+        if NUM_BLOCK_COUNTING_GUARDS.load(Ordering::Relaxed) != 0 {
+            NUM_DECODED_BLOCKS.with(|num_blocks| num_blocks.set(num_blocks.get() + 1));
+        }
         match self.skip_reader.block_info() {
             BlockInfo::BitPacked {
                 doc_num_bits,
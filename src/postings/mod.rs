@@ -19,6 +19,7 @@ mod term_info;
 
 pub(crate) use stacker::compute_table_size;
 
+pub(crate) use self::block_segment_postings::{num_decoded_blocks, DecodedBlockCounting};
 pub use self::block_segment_postings::BlockSegmentPostings;
 pub(crate) use self::indexing_context::IndexingContext;
 pub(crate) use self::per_field_postings_writer::PerFieldPostingsWriter;
@@ -435,7 +436,7 @@ pub mod tests {
         // delete some of the documents
         {
             let mut index_writer = index.writer_for_tests()?;
-            index_writer.delete_term(term_0);
+            index_writer.delete_term(term_0)?;
             assert!(index_writer.commit().is_ok());
         }
         let searcher = index.reader()?.searcher();
@@ -486,7 +487,7 @@ pub mod tests {
         // delete everything else
         {
             let mut index_writer = index.writer_for_tests()?;
-            index_writer.delete_term(term_1);
+            index_writer.delete_term(term_1)?;
             assert!(index_writer.commit().is_ok());
         }
         let searcher = index.reader()?.searcher();
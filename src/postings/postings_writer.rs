@@ -94,6 +94,7 @@ pub(crate) fn serialize_postings(
             FieldType::Bytes(_) => {}
             FieldType::JsonObject(_) => {}
             FieldType::IpAddr(_) => {}
+            FieldType::Vector(_) => {}
         }
 
         let postings_writer = per_field_postings_writers.get_for_field(field);
@@ -145,10 +145,13 @@ fn advance_all_scorers_on_pivot(term_scorers: &mut Vec<TermScorerWithMaxScore>,
 /// Implements the WAND (Weak AND) algorithm for dynamic pruning
 /// described in the paper "Faster Top-k Document Retrieval Using Block-Max Indexes".
 /// Link: <http://engineering.nyu.edu/~suel/papers/bmw.pdf>
+///
+/// The iteration stops as soon as `is_cancelled` returns true. It is called once per pivot.
 pub fn block_wand(
     mut scorers: Vec<TermScorer>,
     mut threshold: Score,
     callback: &mut dyn FnMut(u32, Score) -> Score,
+    mut is_cancelled: impl FnMut() -> bool,
 ) {
     let mut scorers: Vec<TermScorerWithMaxScore> = scorers
         .iter_mut()
@@ -160,6 +163,9 @@ pub fn block_wand(
     while let Some((before_pivot_len, pivot_len, pivot_doc)) =
         find_pivot_doc(&scorers[..], threshold)
     {
+        if is_cancelled() {
+            return;
+        }
         debug_assert!(is_sorted(scorers.iter().map(|scorer| scorer.doc())));
         debug_assert_ne!(pivot_doc, TERMINATED);
         debug_assert!(before_pivot_len < pivot_len);
@@ -219,16 +225,22 @@ pub fn block_wand(
 ///   - While the block max score is under the `threshold`, go to the next block.
 ///   - On a block, advance until the end and execute `callback` when the doc score is greater or
 ///     equal to the `threshold`.
+///
+/// The iteration stops as soon as `is_cancelled` returns true. It is called once per block.
 pub fn block_wand_single_scorer(
     mut scorer: TermScorer,
     mut threshold: Score,
     callback: &mut dyn FnMut(u32, Score) -> Score,
+    mut is_cancelled: impl FnMut() -> bool,
 ) {
     let mut doc = scorer.doc();
     loop {
         // We position the scorer on a block that can reach
         // the threshold.
         while scorer.block_max_score() < threshold {
+            if is_cancelled() {
+                return;
+            }
             let last_doc_in_block = scorer.last_doc_in_block();
             if last_doc_in_block == TERMINATED {
                 return;
@@ -236,6 +248,9 @@ pub fn block_wand_single_scorer(
             doc = last_doc_in_block + 1;
             scorer.shallow_seek(doc);
         }
+        if is_cancelled() {
+            return;
+        }
         // Seek will effectively load that block.
         doc = scorer.seek(doc);
         if doc == TERMINATED {
@@ -361,9 +376,9 @@ mod tests {
 
         if term_scorers.len() == 1 {
             let scorer = term_scorers.pop().unwrap();
-            super::block_wand_single_scorer(scorer, Score::MIN, callback);
+            super::block_wand_single_scorer(scorer, Score::MIN, callback, || false);
         } else {
-            super::block_wand(term_scorers, Score::MIN, callback);
+            super::block_wand(term_scorers, Score::MIN, callback, || false);
         }
         checkpoints
     }
@@ -507,6 +522,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_block_wand_cancelled() {
+        let postings: Vec<(DocId, u32)> = (0..1_000).map(|doc| (doc, 1)).collect();
+        let fieldnorms = vec![1u32; 1_000];
+        let bm25_weight = Bm25Weight::for_one_term(1_000, 1_000, 1.0);
+        let term_scorer = TermScorer::create_for_test(&postings, &fieldnorms, bm25_weight);
+        let count_collected = |term_scorers: Vec<TermScorer>, max_num_checks: usize| {
+            let mut num_collected = 0;
+            let mut num_checks = 0;
+            let callback = &mut |_doc, _score| {
+                num_collected += 1;
+                Score::MIN
+            };
+            let is_cancelled = || {
+                num_checks += 1;
+                num_checks > max_num_checks
+            };
+            if term_scorers.len() == 1 {
+                let scorer = term_scorers.into_iter().next().unwrap();
+                super::block_wand_single_scorer(scorer, Score::MIN, callback, is_cancelled);
+            } else {
+                super::block_wand(term_scorers, Score::MIN, callback, is_cancelled);
+            }
+            num_collected
+        };
+        assert_eq!(
+            count_collected(vec![term_scorer.clone()], usize::MAX),
+            1_000
+        );
+        assert!(count_collected(vec![term_scorer.clone()], 2) < 1_000);
+        let term_scorers = vec![term_scorer.clone(), term_scorer];
+        assert_eq!(count_collected(term_scorers.clone(), usize::MAX), 1_000);
+        assert_eq!(count_collected(term_scorers, 2), 2);
+    }
+
     #[test]
     fn test_fn_reproduce_proptest() {
         let postings_lists = &[
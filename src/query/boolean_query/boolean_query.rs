@@ -1,5 +1,7 @@
 use super::boolean_weight::BooleanWeight;
-use crate::query::{EnableScoring, Occur, Query, SumWithCoordsCombiner, TermQuery, Weight};
+use crate::query::{
+    EnableScoring, Occur, Query, QueryProfiler, SumWithCoordsCombiner, TermQuery, Weight,
+};
 use crate::schema::{IndexRecordOption, Term};
 
 /// The boolean query returns a set of documents
@@ -155,6 +157,30 @@ impl Query for BooleanQuery {
         )))
     }
 
+    fn profiled_weight(
+        &self,
+        enable_scoring: EnableScoring<'_>,
+        profiler: &mut QueryProfiler,
+    ) -> crate::Result<Box<dyn Weight>> {
+        let mut sub_profiler = QueryProfiler::default();
+        let sub_weights = self
+            .subqueries
+            .iter()
+            .map(|(occur, subquery)| {
+                Ok((
+                    *occur,
+                    subquery.profiled_weight(enable_scoring, &mut sub_profiler)?,
+                ))
+            })
+            .collect::<crate::Result<_>>()?;
+        let weight = BooleanWeight::new(
+            sub_weights,
+            enable_scoring.is_scoring_enabled(),
+            Box::new(SumWithCoordsCombiner::default),
+        );
+        Ok(profiler.profile("BooleanQuery", Box::new(weight), sub_profiler))
+    }
+
     fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
         for (_occur, subquery) in &self.subqueries {
             subquery.query_terms(visitor);
@@ -1,11 +1,14 @@
 use std::collections::HashMap;
 
-use crate::core::SegmentReader;
+use crate::core::{CancellationCheck, SegmentReader};
 use crate::postings::FreqReadingOption;
 use crate::query::explanation::does_not_match;
 use crate::query::score_combiner::{DoNothingCombiner, ScoreCombiner};
 use crate::query::term_query::TermScorer;
-use crate::query::weight::{for_each_docset, for_each_pruning_scorer, for_each_scorer};
+use crate::query::weight::{
+    for_each_docset, for_each_pruning_scorer, for_each_pruning_scorer_until_cancelled,
+    for_each_scorer,
+};
 use crate::query::{
     intersect_scorers, EmptyScorer, Exclude, Explanation, Occur, RequiredOptionalScorer, Scorer,
     Union, Weight,
@@ -256,7 +259,7 @@ impl<TScoreCombiner: ScoreCombiner + Sync> Weight for BooleanWeight<TScoreCombin
         let scorer = self.complex_scorer(reader, 1.0, &self.score_combiner_fn)?;
         match scorer {
             SpecializedScorer::TermUnion(term_scorers) => {
-                super::block_wand(term_scorers, threshold, callback);
+                super::block_wand(term_scorers, threshold, callback, || false);
             }
             SpecializedScorer::Other(mut scorer) => {
                 for_each_pruning_scorer(scorer.as_mut(), threshold, callback);
@@ -264,6 +267,30 @@ impl<TScoreCombiner: ScoreCombiner + Sync> Weight for BooleanWeight<TScoreCombin
         }
         Ok(())
     }
+
+    fn for_each_pruning_until_cancelled(
+        &self,
+        threshold: Score,
+        reader: &SegmentReader,
+        callback: &mut dyn FnMut(DocId, Score) -> Score,
+        check: &mut CancellationCheck,
+    ) -> crate::Result<()> {
+        let scorer = self.complex_scorer(reader, 1.0, &self.score_combiner_fn)?;
+        match scorer {
+            SpecializedScorer::TermUnion(term_scorers) => {
+                super::block_wand(term_scorers, threshold, callback, || check.is_cancelled());
+            }
+            SpecializedScorer::Other(mut scorer) => {
+                for_each_pruning_scorer_until_cancelled(
+                    scorer.as_mut(),
+                    threshold,
+                    callback,
+                    &mut || check.is_cancelled(),
+                );
+            }
+        }
+        Ok(())
+    }
 }
 
 fn is_positive_occur(occur: Occur) -> bool {
@@ -2,7 +2,7 @@ use std::fmt;
 
 use crate::fastfield::AliveBitSet;
 use crate::query::explanation::does_not_match;
-use crate::query::{EnableScoring, Explanation, Query, Scorer, Weight};
+use crate::query::{EnableScoring, Explanation, Query, QueryProfiler, Scorer, Weight};
 use crate::{DocId, DocSet, Score, SegmentReader, Term};
 
 /// `BoostQuery` is a wrapper over a query used to boost its score.
@@ -48,6 +48,24 @@ impl Query for BoostQuery {
         Ok(boosted_weight)
     }
 
+    fn profiled_weight(
+        &self,
+        enable_scoring: EnableScoring<'_>,
+        profiler: &mut QueryProfiler,
+    ) -> crate::Result<Box<dyn Weight>> {
+        let mut sub_profiler = QueryProfiler::default();
+        let weight_without_boost = self
+            .query
+            .profiled_weight(enable_scoring, &mut sub_profiler)?;
+        let boosted_weight = if enable_scoring.is_scoring_enabled() {
+            Box::new(BoostWeight::new(weight_without_boost, self.boost))
+        } else {
+            weight_without_boost
+        };
+        let description = format!("Boost(boost={})", self.boost);
+        Ok(profiler.profile(description, boosted_weight, sub_profiler))
+    }
+
     fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
         self.query.query_terms(visitor)
     }
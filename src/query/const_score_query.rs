@@ -1,6 +1,6 @@
 use std::fmt;
 
-use crate::query::{EnableScoring, Explanation, Query, Scorer, Weight};
+use crate::query::{EnableScoring, Explanation, Query, QueryProfiler, Scorer, Weight};
 use crate::{DocId, DocSet, Score, SegmentReader, TantivyError, Term};
 
 /// `ConstScoreQuery` is a wrapper over a query to provide a constant score.
@@ -45,6 +45,24 @@ impl Query for ConstScoreQuery {
         })
     }
 
+    fn profiled_weight(
+        &self,
+        enable_scoring: EnableScoring<'_>,
+        profiler: &mut QueryProfiler,
+    ) -> crate::Result<Box<dyn Weight>> {
+        let mut sub_profiler = QueryProfiler::default();
+        let inner_weight = self
+            .query
+            .profiled_weight(enable_scoring, &mut sub_profiler)?;
+        let weight = if enable_scoring.is_scoring_enabled() {
+            Box::new(ConstWeight::new(inner_weight, self.score))
+        } else {
+            inner_weight
+        };
+        let description = format!("Const(score={})", self.score);
+        Ok(profiler.profile(description, weight, sub_profiler))
+    }
+
     fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
         self.query.query_terms(visitor);
     }
@@ -1,6 +1,8 @@
 use tantivy_query_grammar::Occur;
 
-use crate::query::{BooleanWeight, DisjunctionMaxCombiner, EnableScoring, Query, Weight};
+use crate::query::{
+    BooleanWeight, DisjunctionMaxCombiner, EnableScoring, Query, QueryProfiler, Weight,
+};
 use crate::{Score, Term};
 
 /// The disjunction max query returns documents matching one or more wrapped queries,
@@ -105,6 +107,32 @@ impl Query for DisjunctionMaxQuery {
         )))
     }
 
+    fn profiled_weight(
+        &self,
+        enable_scoring: EnableScoring<'_>,
+        profiler: &mut QueryProfiler,
+    ) -> crate::Result<Box<dyn Weight>> {
+        let mut sub_profiler = QueryProfiler::default();
+        let disjuncts = self
+            .disjuncts
+            .iter()
+            .map(|disjunct| {
+                Ok((
+                    Occur::Should,
+                    disjunct.profiled_weight(enable_scoring, &mut sub_profiler)?,
+                ))
+            })
+            .collect::<crate::Result<_>>()?;
+        let tie_breaker = self.tie_breaker;
+        let weight = BooleanWeight::new(
+            disjuncts,
+            enable_scoring.is_scoring_enabled(),
+            Box::new(move || DisjunctionMaxCombiner::with_tie_breaker(tie_breaker)),
+        );
+        let description = format!("DisjunctionMaxQuery(tie_breaker={})", tie_breaker);
+        Ok(profiler.profile(description, Box::new(weight), sub_profiler))
+    }
+
     fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
         for disjunct in &self.disjuncts {
             disjunct.query_terms(visitor);
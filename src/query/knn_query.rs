@@ -0,0 +1,501 @@
+use std::fmt;
+
+use common::BitSet;
+
+use crate::query::explanation::does_not_match;
+use crate::query::{EnableScoring, Explanation, Query, Scorer, Weight};
+use crate::schema::{Field, FieldType};
+use crate::{DocId, DocSet, Score, SegmentReader, TantivyError, TERMINATED};
+
+/// Default number of candidates considered when searching the HNSW graph of a segment.
+const DEFAULT_NUM_CANDIDATES: usize = 100;
+
+/// The `KnnQuery` matches the `k` documents whose vector is the most similar to a query
+/// vector, in each segment.
+///
+/// The score of a document is the similarity of its vector to the query vector, for
+/// the [metric](crate::schema::VectorMetric) of the vector field.
+///
+/// The nearest neighbours are searched in the HNSW graph of each segment, which is
+/// approximate. Segments with few vectors are scanned exhaustively instead, and
+/// [`KnnQuery::with_exact_search`] forces an exhaustive scan of all of the segments.
+///
+/// Since up to `k` documents are matched per segment, the query is typically combined
+/// with a [`TopDocs`](crate::collector::TopDocs) collector with a limit of `k`.
+///
+/// The `KnnQuery` can be combined with other queries in a
+/// [`BooleanQuery`](crate::query::BooleanQuery), e.g. to add its score to the BM25
+/// score of a text query. Requiring another query in a `BooleanQuery` filters the
+/// nearest neighbours *after* they have been found, which may leave less than `k`
+/// documents. [`KnnQuery::with_filter`] instead searches for the `k` nearest
+/// neighbours among the documents matching the filter.
+///
+/// ```rust
+/// use tantivy::collector::TopDocs;
+/// use tantivy::query::{BooleanQuery, KnnQuery, Occur, Query, QueryParser};
+/// use tantivy::schema::{Schema, VectorMetric, VectorOptions, STRING, TEXT};
+/// use tantivy::{doc, Index};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let lang = schema_builder.add_text_field("lang", STRING);
+/// let embedding = schema_builder.add_vector_field(
+///     "embedding",
+///     VectorOptions::new(2).set_metric(VectorMetric::L2),
+/// );
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 10_000_000)?;
+/// let add_doc = |doc_title: &str, doc_lang: &str, doc_embedding: Vec<f32>| {
+///     let mut doc = doc!(title => doc_title, lang => doc_lang);
+///     doc.add_vector(embedding, doc_embedding);
+///     index_writer.add_document(doc)
+/// };
+/// add_doc("The Name of the Wind", "en", vec![1.0, 1.0])?;
+/// add_doc("Le Nom du vent", "fr", vec![1.0, 1.1])?;
+/// add_doc("The Wise Man's Fear", "en", vec![5.0, 5.0])?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let knn_query = KnnQuery::new(embedding, vec![1.0, 1.2], 1);
+/// let top_docs = searcher.search(&knn_query, &TopDocs::with_limit(1))?;
+/// assert_eq!(top_docs[0].1.doc_id, 1);
+///
+/// // Nearest neighbour among the english books.
+/// let en_query = QueryParser::for_index(&index, vec![lang]).parse_query("en")?;
+/// let filtered_knn_query = knn_query.clone().with_filter(en_query);
+/// let top_docs = searcher.search(&filtered_knn_query, &TopDocs::with_limit(1))?;
+/// assert_eq!(top_docs[0].1.doc_id, 0);
+///
+/// // Hybrid search, adding the similarity to the BM25 score.
+/// let text_query = QueryParser::for_index(&index, vec![title]).parse_query("wind")?;
+/// let hybrid_query = BooleanQuery::new(vec![
+///     (Occur::Should, text_query),
+///     (Occur::Should, Box::new(knn_query.with_k(3)) as Box<dyn Query>),
+/// ]);
+/// let top_docs = searcher.search(&hybrid_query, &TopDocs::with_limit(3))?;
+/// assert_eq!(top_docs.len(), 3);
+/// assert_eq!(top_docs[0].1.doc_id, 0);
+/// # Ok(())
+/// # }
+/// ```
+pub struct KnnQuery {
+    field: Field,
+    vector: Vec<f32>,
+    k: usize,
+    num_candidates: usize,
+    exact: bool,
+    filter: Option<Box<dyn Query>>,
+}
+
+impl KnnQuery {
+    /// Creates a query matching the `k` documents whose vector in `field` is the most
+    /// similar to `vector`, in each segment.
+    pub fn new(field: Field, vector: Vec<f32>, k: usize) -> KnnQuery {
+        KnnQuery {
+            field,
+            vector,
+            k,
+            num_candidates: DEFAULT_NUM_CANDIDATES,
+            exact: false,
+            filter: None,
+        }
+    }
+
+    /// Sets the number of documents to match per segment.
+    #[must_use]
+    pub fn with_k(mut self, k: usize) -> KnnQuery {
+        self.k = k;
+        self
+    }
+
+    /// Sets the number of candidates considered when searching the HNSW graph of a
+    /// segment. It defaults to 100, and is raised to `k` if it is lower.
+    ///
+    /// More candidates give more accurate results, at the expense of speed.
+    #[must_use]
+    pub fn with_num_candidates(mut self, num_candidates: usize) -> KnnQuery {
+        self.num_candidates = num_candidates;
+        self
+    }
+
+    /// Scans all of the vectors of the segments instead of searching their HNSW graph.
+    ///
+    /// The result is exact, but the search is linear in the number of vectors.
+    #[must_use]
+    pub fn with_exact_search(mut self) -> KnnQuery {
+        self.exact = true;
+        self
+    }
+
+    /// Restricts the search to the documents matching `filter`.
+    #[must_use]
+    pub fn with_filter(mut self, filter: Box<dyn Query>) -> KnnQuery {
+        self.filter = Some(filter);
+        self
+    }
+}
+
+impl Clone for KnnQuery {
+    fn clone(&self) -> Self {
+        KnnQuery {
+            field: self.field,
+            vector: self.vector.clone(),
+            k: self.k,
+            num_candidates: self.num_candidates,
+            exact: self.exact,
+            filter: self.filter.as_ref().map(|filter| filter.box_clone()),
+        }
+    }
+}
+
+impl fmt::Debug for KnnQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "KnnQuery(field={:?}, k={}, vector={:?}",
+            self.field, self.k, self.vector
+        )?;
+        if let Some(filter) = &self.filter {
+            write!(f, ", filter={:?}", filter)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl Query for KnnQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let schema = enable_scoring.schema();
+        let field_entry = schema.get_field_entry(self.field);
+        let vector_options = if let FieldType::Vector(vector_options) = field_entry.field_type() {
+            vector_options
+        } else {
+            return Err(TantivyError::SchemaError(format!(
+                "Field {:?} is not a vector field.",
+                field_entry.name()
+            )));
+        };
+        if self.vector.len() != vector_options.dimension() {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Expected a query vector of dimension {} for field {:?}, got {}",
+                vector_options.dimension(),
+                field_entry.name(),
+                self.vector.len()
+            )));
+        }
+        let filter_weight = if let Some(filter) = &self.filter {
+            let filter_enable_scoring = if let Some(searcher) = enable_scoring.searcher() {
+                EnableScoring::disabled_from_searcher(searcher)
+            } else {
+                EnableScoring::disabled_from_schema(schema)
+            };
+            Some(filter.weight(filter_enable_scoring)?)
+        } else {
+            None
+        };
+        Ok(Box::new(KnnWeight {
+            query: self.clone(),
+            filter_weight,
+        }))
+    }
+}
+
+struct KnnWeight {
+    query: KnnQuery,
+    filter_weight: Option<Box<dyn Weight>>,
+}
+
+impl KnnWeight {
+    /// Returns the nearest neighbours of the segment, sorted by doc id.
+    fn nearest_neighbours(&self, reader: &SegmentReader) -> crate::Result<Vec<(DocId, Score)>> {
+        let vector_reader = reader.vector_reader(self.query.field)?;
+        let filter_bitset = if let Some(filter_weight) = &self.filter_weight {
+            let mut filter_bitset = BitSet::with_max_value(reader.max_doc());
+            filter_weight.for_each_no_score(reader, &mut |doc| filter_bitset.insert(doc))?;
+            Some(filter_bitset)
+        } else {
+            None
+        };
+        let accept = |doc: DocId| {
+            !reader.is_deleted(doc)
+                && filter_bitset
+                    .as_ref()
+                    .map(|filter_bitset| filter_bitset.contains(doc))
+                    .unwrap_or(true)
+        };
+        let mut hits = vector_reader.search(
+            &self.query.vector,
+            self.query.k,
+            self.query.num_candidates,
+            self.query.exact,
+            accept,
+        );
+        hits.sort_unstable_by_key(|(doc, _)| *doc);
+        Ok(hits)
+    }
+}
+
+impl Weight for KnnWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        let hits = self.nearest_neighbours(reader)?;
+        Ok(Box::new(KnnScorer {
+            hits,
+            cursor: 0,
+            boost,
+        }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let hits = self.nearest_neighbours(reader)?;
+        let (_, similarity) = hits
+            .binary_search_by_key(&doc, |(hit_doc, _)| *hit_doc)
+            .map(|hit_ord| hits[hit_ord])
+            .map_err(|_| does_not_match(doc))?;
+        let metric = reader.vector_reader(self.query.field)?.options().metric();
+        Ok(Explanation::new(
+            format!("KnnQuery, {:?} similarity", metric),
+            similarity,
+        ))
+    }
+}
+
+/// Scorer over the nearest neighbours of a segment.
+struct KnnScorer {
+    hits: Vec<(DocId, Score)>,
+    cursor: usize,
+    boost: Score,
+}
+
+impl DocSet for KnnScorer {
+    fn advance(&mut self) -> DocId {
+        if self.cursor < self.hits.len() {
+            self.cursor += 1;
+        }
+        self.doc()
+    }
+
+    fn doc(&self) -> DocId {
+        self.hits
+            .get(self.cursor)
+            .map(|(doc, _)| *doc)
+            .unwrap_or(TERMINATED)
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.hits.len() as u32
+    }
+}
+
+impl Scorer for KnnScorer {
+    fn score(&mut self) -> Score {
+        self.hits
+            .get(self.cursor)
+            .map(|(_, similarity)| self.boost * similarity)
+            .unwrap_or(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KnnQuery;
+    use crate::collector::TopDocs;
+    use crate::directory::Directory;
+    use crate::query::{BooleanQuery, Occur, Query, TermQuery};
+    use crate::schema::{
+        IndexRecordOption, Schema, Value, VectorMetric, VectorOptions, STORED, STRING,
+    };
+    use crate::{DocAddress, Document, Index, IndexWriter, SegmentComponent, Term};
+
+    fn vector(i: u32) -> Vec<f32> {
+        vec![(i % 40) as f32, (i / 40) as f32, 1.0]
+    }
+
+    #[test]
+    fn test_knn_query_hnsw_and_merge() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id = schema_builder.add_u64_field("id", STORED);
+        let parity = schema_builder.add_text_field("parity", STRING);
+        let embedding = schema_builder.add_vector_field(
+            "embedding",
+            VectorOptions::new(3)
+                .set_metric(VectorMetric::L2)
+                .set_stored(),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        // Two segments of 1,200 vectors each, which are searched with their HNSW graph.
+        for i in 0..2_400u32 {
+            let mut doc = doc!(
+                id => u64::from(i),
+                parity => if i % 2 == 0 { "even" } else { "odd" }
+            );
+            doc.add_vector(embedding, vector(i));
+            index_writer.add_document(doc)?;
+            if i == 1_199 {
+                index_writer.commit()?;
+            }
+        }
+        // A document without vector.
+        index_writer.add_document(doc!(id => 10_000u64))?;
+        index_writer.commit()?;
+        let reader = index.reader()?;
+
+        let query_vector = vec![12.1, 33.2, 1.0];
+        let expected_ids = |parity_filter: Option<u32>| {
+            let mut ids: Vec<u32> = (0..2_400)
+                .filter(|i| parity_filter.map(|parity| i % 2 == parity).unwrap_or(true))
+                .collect();
+            ids.sort_by(|&left, &right| {
+                let distance = |i: u32| {
+                    vector(i)
+                        .iter()
+                        .zip(&query_vector)
+                        .map(|(val, query_val)| (val - query_val) * (val - query_val))
+                        .sum::<f32>()
+                };
+                distance(left).partial_cmp(&distance(right)).unwrap()
+            });
+            ids.truncate(5);
+            ids
+        };
+        let search_ids = |query: &dyn Query| -> crate::Result<Vec<u32>> {
+            let searcher = reader.searcher();
+            let top_docs = searcher.search(query, &TopDocs::with_limit(5))?;
+            top_docs
+                .into_iter()
+                .map(|(_, doc_address)| {
+                    let doc = searcher.doc(doc_address)?;
+                    Ok(doc.get_first(id).and_then(Value::as_u64).unwrap() as u32)
+                })
+                .collect()
+        };
+
+        let knn_query = KnnQuery::new(embedding, query_vector.clone(), 5);
+        assert_eq!(search_ids(&knn_query)?, expected_ids(None));
+        assert_eq!(
+            search_ids(&knn_query.clone().with_exact_search())?,
+            expected_ids(None)
+        );
+        let odd_query = TermQuery::new(
+            Term::from_field_text(parity, "odd"),
+            IndexRecordOption::Basic,
+        );
+        let filtered_knn_query = knn_query.clone().with_filter(Box::new(odd_query));
+        assert_eq!(search_ids(&filtered_knn_query)?, expected_ids(Some(1)));
+
+        // The HNSW graph is rebuilt when merging the segments.
+        let segment_ids = index.searchable_segment_ids()?;
+        index_writer.merge(&segment_ids).wait()?;
+        index_writer.wait_merging_threads()?;
+        reader.reload()?;
+        assert_eq!(reader.searcher().segment_readers().len(), 1);
+        let segment_reader = reader.searcher().segment_reader(0).clone();
+        let vector_reader = segment_reader.vector_reader(embedding)?;
+        assert_eq!(vector_reader.num_vectors(), 2_400);
+        assert_eq!(search_ids(&knn_query)?, expected_ids(None));
+        assert_eq!(search_ids(&filtered_knn_query)?, expected_ids(Some(1)));
+
+        // Vectors are stored.
+        let searcher = reader.searcher();
+        let top_docs = searcher.search(&knn_query, &TopDocs::with_limit(1))?;
+        let doc: Document = searcher.doc(top_docs[0].1)?;
+        let doc_id = doc.get_first(id).and_then(Value::as_u64).unwrap() as u32;
+        assert_eq!(
+            doc.get_first(embedding).and_then(Value::as_vector),
+            Some(&vector(doc_id)[..])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_knn_query_in_boolean_query() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let color = schema_builder.add_text_field("color", STRING);
+        let embedding = schema_builder.add_vector_field("embedding", VectorOptions::new(2));
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for (doc_color, doc_embedding) in [
+            ("red", vec![1.0, 0.0]),
+            ("blue", vec![0.9, 0.1]),
+            ("red", vec![0.0, 1.0]),
+            ("blue", vec![-1.0, 0.0]),
+        ] {
+            let mut doc = doc!(color => doc_color);
+            doc.add_vector(embedding, doc_embedding);
+            index_writer.add_document(doc)?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let knn_query = KnnQuery::new(embedding, vec![1.0, 0.0], 2);
+        let top_docs = searcher.search(&knn_query, &TopDocs::with_limit(10))?;
+        assert_eq!(top_docs.len(), 2);
+        assert_eq!(top_docs[0], (1.0, DocAddress::new(0, 0)));
+        assert_eq!(top_docs[1].1, DocAddress::new(0, 1));
+        let explanation = knn_query.explain(&searcher, DocAddress::new(0, 0))?;
+        assert_eq!(explanation.value(), 1.0);
+        assert!(knn_query.explain(&searcher, DocAddress::new(0, 2)).is_err());
+
+        // Post-filtering: only one of the two nearest neighbours is red.
+        let red_query = TermQuery::new(
+            Term::from_field_text(color, "red"),
+            IndexRecordOption::Basic,
+        );
+        let boolean_query = BooleanQuery::new(vec![
+            (Occur::Must, Box::new(knn_query.clone()) as Box<dyn Query>),
+            (Occur::Must, Box::new(red_query.clone())),
+        ]);
+        let top_docs = searcher.search(&boolean_query, &TopDocs::with_limit(10))?;
+        assert_eq!(top_docs.len(), 1);
+        assert_eq!(top_docs[0].1, DocAddress::new(0, 0));
+
+        // Pre-filtering: the two nearest red neighbours.
+        let filtered_knn_query = knn_query.clone().with_filter(Box::new(red_query));
+        let top_docs = searcher.search(&filtered_knn_query, &TopDocs::with_limit(10))?;
+        let doc_ids: Vec<u32> = top_docs
+            .iter()
+            .map(|(_, doc_address)| doc_address.doc_id)
+            .collect();
+        assert_eq!(doc_ids, vec![0, 2]);
+
+        // Invalid queries.
+        let invalid_query = KnnQuery::new(embedding, vec![1.0, 0.0, 0.0], 2);
+        assert!(searcher
+            .search(&invalid_query, &TopDocs::with_limit(10))
+            .is_err());
+        let invalid_query = KnnQuery::new(color, vec![1.0, 0.0], 2);
+        assert!(searcher
+            .search(&invalid_query, &TopDocs::with_limit(10))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_or_corrupted_vectors_file() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let embedding = schema_builder.add_vector_field("embedding", VectorOptions::new(3));
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        let mut doc = Document::default();
+        doc.add_vector(embedding, vector(1));
+        index_writer.add_document(doc)?;
+        index_writer.commit()?;
+        let segment = index.searchable_segments()?.into_iter().next().unwrap();
+        let vectors_path = segment.relative_path(SegmentComponent::Vectors);
+
+        // A corrupted vectors file is an error, rather than a segment without vectors.
+        index
+            .directory()
+            .atomic_write(&vectors_path, b"corrupted")?;
+        assert!(index.reader().is_err());
+
+        // Segments written before vector fields existed have no vectors file.
+        index.directory().delete(&vectors_path).unwrap();
+        let searcher = index.reader()?.searcher();
+        let knn_query = KnnQuery::new(embedding, vector(1), 1);
+        assert!(searcher
+            .search(&knn_query, &TopDocs::with_limit(1))?
+            .is_empty());
+        Ok(())
+    }
+}
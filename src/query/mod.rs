@@ -11,8 +11,10 @@ mod exclude;
 mod explanation;
 mod fuzzy_query;
 mod intersection;
+mod knn_query;
 mod more_like_this;
 mod phrase_query;
+mod profile;
 mod query;
 mod query_parser;
 mod range_query;
@@ -46,8 +48,10 @@ pub use self::explanation::Explanation;
 pub(crate) use self::fuzzy_query::DfaWrapper;
 pub use self::fuzzy_query::FuzzyTermQuery;
 pub use self::intersection::{intersect_scorers, Intersection};
+pub use self::knn_query::KnnQuery;
 pub use self::more_like_this::{MoreLikeThisQuery, MoreLikeThisQueryBuilder};
 pub use self::phrase_query::PhraseQuery;
+pub use self::profile::{QueryProfile, QueryProfiler, SearchProfile, SegmentProfile};
 pub use self::query::{EnableScoring, Query, QueryClone};
 pub use self::query_parser::{QueryParser, QueryParserError};
 pub use self::range_query::RangeQuery;
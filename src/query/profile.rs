@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::postings::{num_decoded_blocks, DecodedBlockCounting};
+use crate::query::{Explanation, Scorer, Weight};
+use crate::{DocId, DocSet, Score, SegmentId, SegmentOrdinal, SegmentReader, TERMINATED};
+
+/// Counters recorded by a profiled node for one segment.
+#[derive(Clone, Copy, Default)]
+struct ProfileStats {
+    build_scorer_nanos: u64,
+    advance_nanos: u64,
+    seek_nanos: u64,
+    score_nanos: u64,
+    num_docs_visited: u64,
+    num_blocks_decoded: u64,
+}
+
+impl ProfileStats {
+    fn add(&mut self, other: &ProfileStats) {
+        self.build_scorer_nanos += other.build_scorer_nanos;
+        self.advance_nanos += other.advance_nanos;
+        self.seek_nanos += other.seek_nanos;
+        self.score_nanos += other.score_nanos;
+        self.num_docs_visited += other.num_docs_visited;
+        self.num_blocks_decoded += other.num_blocks_decoded;
+    }
+
+    fn query_nanos(&self) -> u64 {
+        self.build_scorer_nanos + self.advance_nanos + self.seek_nanos + self.score_nanos
+    }
+}
+
+struct ProfileNode {
+    description: String,
+    children: Vec<Arc<ProfileNode>>,
+    segment_stats: Mutex<HashMap<SegmentId, ProfileStats>>,
+}
+
+impl ProfileNode {
+    fn record(&self, segment_id: SegmentId, stats: &ProfileStats) {
+        let mut segment_stats = self
+            .segment_stats
+            .lock()
+            .expect("Profile lock poisoned. This should never happen.");
+        segment_stats.entry(segment_id).or_default().add(stats);
+    }
+
+    fn stats(&self, segment_id: SegmentId) -> ProfileStats {
+        let segment_stats = self
+            .segment_stats
+            .lock()
+            .expect("Profile lock poisoned. This should never happen.");
+        segment_stats.get(&segment_id).copied().unwrap_or_default()
+    }
+
+    fn profile(&self, segment_id: SegmentId) -> QueryProfile {
+        let stats = self.stats(segment_id);
+        QueryProfile {
+            description: self.description.clone(),
+            build_scorer_nanos: stats.build_scorer_nanos,
+            advance_nanos: stats.advance_nanos,
+            seek_nanos: stats.seek_nanos,
+            score_nanos: stats.score_nanos,
+            num_docs_visited: stats.num_docs_visited,
+            num_blocks_decoded: stats.num_blocks_decoded,
+            children: self
+                .children
+                .iter()
+                .map(|child| child.profile(segment_id))
+                .collect(),
+        }
+    }
+}
+
+/// Builds the tree of profiled weights of a query.
+///
+/// See [`Query::profiled_weight`](crate::query::Query::profiled_weight).
+#[derive(Default)]
+pub struct QueryProfiler {
+    nodes: Vec<Arc<ProfileNode>>,
+}
+
+impl QueryProfiler {
+    /// Wraps `weight` so that the time spent in its scorers is recorded under a node
+    /// described by `description`.
+    ///
+    /// The nodes recorded by `children` become the children of this node.
+    pub fn profile<T: ToString>(
+        &mut self,
+        description: T,
+        weight: Box<dyn Weight>,
+        children: QueryProfiler,
+    ) -> Box<dyn Weight> {
+        let node = Arc::new(ProfileNode {
+            description: description.to_string(),
+            children: children.nodes,
+            segment_stats: Mutex::default(),
+        });
+        self.nodes.push(node.clone());
+        Box::new(ProfiledWeight {
+            weight,
+            node,
+            _decoded_block_counting: DecodedBlockCounting::start(),
+        })
+    }
+
+    /// Returns the recorded profile of the first node for the given segment, along with
+    /// the time spent in the scorers of this node.
+    pub(crate) fn root_profile(&self, segment_id: SegmentId) -> Option<(QueryProfile, u64)> {
+        let root = self.nodes.first()?;
+        Some((
+            root.profile(segment_id),
+            root.stats(segment_id).query_nanos(),
+        ))
+    }
+}
+
+/// Profile of a query node for one segment.
+///
+/// The counters of a node include the ones of its children.
+#[derive(Clone, Debug, Serialize)]
+pub struct QueryProfile {
+    /// Description of the query node.
+    pub description: String,
+    /// Time spent building the scorers, in nanoseconds.
+    pub build_scorer_nanos: u64,
+    /// Time spent advancing the scorers, in nanoseconds.
+    pub advance_nanos: u64,
+    /// Time spent seeking the scorers, in nanoseconds.
+    pub seek_nanos: u64,
+    /// Time spent computing scores, in nanoseconds.
+    pub score_nanos: u64,
+    /// Number of documents on which the scorers were positioned.
+    pub num_docs_visited: u64,
+    /// Number of postings blocks decoded.
+    pub num_blocks_decoded: u64,
+    /// Profiles of the sub-queries.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<QueryProfile>,
+}
+
+/// Profile of the search of one segment.
+#[derive(Clone, Debug, Serialize)]
+pub struct SegmentProfile {
+    /// Ordinal of the segment in the searcher.
+    pub segment_ord: SegmentOrdinal,
+    /// Id of the segment.
+    pub segment_id: SegmentId,
+    /// Time spent searching the segment, in nanoseconds.
+    pub total_nanos: u64,
+    /// Time spent outside of the scorers, mostly in the collector, in nanoseconds.
+    pub collect_nanos: u64,
+    /// Profile of the query.
+    pub query: QueryProfile,
+}
+
+/// Profile returned by
+/// [`Searcher::search_with_profile`](crate::Searcher::search_with_profile).
+///
+/// `.to_pretty_json()` can be useful to print out a human readable representation of
+/// the profile.
+#[derive(Clone, Debug, Serialize)]
+pub struct SearchProfile {
+    /// Profiles of the segments.
+    pub segments: Vec<SegmentProfile>,
+}
+
+impl SearchProfile {
+    /// Returns an indented json representation of the profile.
+    pub fn to_pretty_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+struct ProfiledWeight {
+    weight: Box<dyn Weight>,
+    node: Arc<ProfileNode>,
+    // Decoded blocks are only counted while a profiled weight is alive.
+    _decoded_block_counting: DecodedBlockCounting,
+}
+
+impl Weight for ProfiledWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        let start = Instant::now();
+        let num_blocks_start = num_decoded_blocks();
+        let scorer = self.weight.scorer(reader, boost)?;
+        let stats = ProfileStats {
+            build_scorer_nanos: start.elapsed().as_nanos() as u64,
+            // A scorer is positioned on its first document when built.
+            num_docs_visited: u64::from(scorer.doc() != TERMINATED),
+            num_blocks_decoded: num_decoded_blocks() - num_blocks_start,
+            ..ProfileStats::default()
+        };
+        Ok(Box::new(ProfiledScorer {
+            scorer,
+            node: self.node.clone(),
+            segment_id: reader.segment_id(),
+            stats,
+        }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        self.weight.explain(reader, doc)
+    }
+}
+
+/// Records its counters in its node when dropped.
+struct ProfiledScorer {
+    scorer: Box<dyn Scorer>,
+    node: Arc<ProfileNode>,
+    segment_id: SegmentId,
+    stats: ProfileStats,
+}
+
+impl ProfiledScorer {
+    fn record_move(&mut self, doc: DocId, start: Instant, num_blocks_start: u64) -> u64 {
+        if doc != TERMINATED {
+            self.stats.num_docs_visited += 1;
+        }
+        self.stats.num_blocks_decoded += num_decoded_blocks() - num_blocks_start;
+        start.elapsed().as_nanos() as u64
+    }
+}
+
+impl DocSet for ProfiledScorer {
+    fn advance(&mut self) -> DocId {
+        let start = Instant::now();
+        let num_blocks_start = num_decoded_blocks();
+        let doc = self.scorer.advance();
+        self.stats.advance_nanos += self.record_move(doc, start, num_blocks_start);
+        doc
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        let start = Instant::now();
+        let num_blocks_start = num_decoded_blocks();
+        let doc = self.scorer.seek(target);
+        self.stats.seek_nanos += self.record_move(doc, start, num_blocks_start);
+        doc
+    }
+
+    fn doc(&self) -> DocId {
+        self.scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorer.size_hint()
+    }
+}
+
+impl Scorer for ProfiledScorer {
+    fn score(&mut self) -> Score {
+        let start = Instant::now();
+        let num_blocks_start = num_decoded_blocks();
+        let score = self.scorer.score();
+        self.stats.score_nanos += start.elapsed().as_nanos() as u64;
+        self.stats.num_blocks_decoded += num_decoded_blocks() - num_blocks_start;
+        score
+    }
+}
+
+impl Drop for ProfiledScorer {
+    fn drop(&mut self) {
+        self.node.record(self.segment_id, &self.stats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::collector::{Count, TopDocs};
+    use crate::query::{BooleanQuery, Occur, Query, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, TEXT};
+    use crate::{Index, IndexWriter, Term};
+
+    #[test]
+    fn test_search_with_profile() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for i in 0..1_000 {
+            let content = if i % 3 == 0 { "a b" } else { "a c" };
+            index_writer.add_document(doc!(text => content))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let term_query = |token: &str| -> Box<dyn Query> {
+            Box::new(TermQuery::new(
+                Term::from_field_text(text, token),
+                IndexRecordOption::WithFreqs,
+            ))
+        };
+        let query = BooleanQuery::new(vec![
+            (Occur::Must, term_query("a")),
+            (Occur::Must, term_query("b")),
+        ]);
+        let ((count, top_docs), profile) =
+            searcher.search_with_profile(&query, &(Count, TopDocs::with_limit(3)))?;
+        assert_eq!(count, 334);
+        assert_eq!(top_docs.len(), 3);
+        assert_eq!(profile.segments.len(), 1);
+
+        let segment_profile = &profile.segments[0];
+        assert_eq!(segment_profile.segment_ord, 0);
+        assert!(segment_profile.total_nanos >= segment_profile.collect_nanos);
+        let query_profile = &segment_profile.query;
+        assert_eq!(query_profile.description, "BooleanQuery");
+        assert_eq!(query_profile.num_docs_visited, 334);
+        assert_eq!(query_profile.children.len(), 2);
+        let term_profile = &query_profile.children[0];
+        assert!(term_profile.description.starts_with("TermQuery"));
+        assert!(term_profile.num_blocks_decoded > 0);
+        assert!(query_profile.num_blocks_decoded >= term_profile.num_blocks_decoded);
+        assert!(profile
+            .to_pretty_json()
+            .contains("\"num_docs_visited\": 334"));
+        Ok(())
+    }
+}
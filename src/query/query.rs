@@ -4,7 +4,7 @@ use downcast_rs::impl_downcast;
 
 use super::Weight;
 use crate::core::searcher::Searcher;
-use crate::query::Explanation;
+use crate::query::{Explanation, QueryProfiler};
 use crate::schema::Schema;
 use crate::{DocAddress, Term};
 
@@ -110,6 +110,22 @@ pub trait Query: QueryClone + Send + Sync + downcast_rs::Downcast + fmt::Debug {
     /// See [`Weight`].
     fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>>;
 
+    /// Creates the weight associated with a query, recording the time spent in its
+    /// scorers with `profiler`.
+    ///
+    /// The default implementation profiles the query as a single node. Queries wrapping
+    /// other queries should override it to profile their sub-queries as children.
+    ///
+    /// See [`Searcher::search_with_profile`].
+    fn profiled_weight(
+        &self,
+        enable_scoring: EnableScoring<'_>,
+        profiler: &mut QueryProfiler,
+    ) -> crate::Result<Box<dyn Weight>> {
+        let weight = self.weight(enable_scoring)?;
+        Ok(profiler.profile(format!("{:?}", self), weight, QueryProfiler::default()))
+    }
+
     /// Returns an `Explanation` for the score of the document.
     fn explain(&self, searcher: &Searcher, doc_address: DocAddress) -> crate::Result<Explanation> {
         let weight = self.weight(EnableScoring::enabled_from_searcher(searcher))?;
@@ -157,6 +173,14 @@ impl Query for Box<dyn Query> {
         self.as_ref().weight(enabled_scoring)
     }
 
+    fn profiled_weight(
+        &self,
+        enable_scoring: EnableScoring<'_>,
+        profiler: &mut QueryProfiler,
+    ) -> crate::Result<Box<dyn Weight>> {
+        self.as_ref().profiled_weight(enable_scoring, profiler)
+    }
+
     fn count(&self, searcher: &Searcher) -> crate::Result<usize> {
         self.as_ref().count(searcher)
     }
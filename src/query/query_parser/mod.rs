@@ -1,4 +1,5 @@
 mod query_parser;
+mod token_graph;
 
 pub mod logical_ast;
 pub use self::query_parser::{QueryParser, QueryParserError};
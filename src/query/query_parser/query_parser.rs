@@ -9,6 +9,7 @@ use rustc_hash::FxHashMap;
 use tantivy_query_grammar::{UserInputAst, UserInputBound, UserInputLeaf, UserInputLiteral};
 
 use super::logical_ast::*;
+use super::token_graph::{TokenGraph, MAX_TOKEN_GRAPH_PATHS};
 use crate::core::Index;
 use crate::indexer::{
     convert_to_fast_value_and_get_term, set_string_and_get_terms, JsonTermWriter,
@@ -376,13 +377,13 @@ impl QueryParser {
                     // This should have been seen earlier really.
                     QueryParserError::FieldNotIndexed(field_entry.name().to_string())
                 })?;
-                let text_analyzer =
-                    self.tokenizer_manager
-                        .get(option.tokenizer())
-                        .ok_or_else(|| QueryParserError::UnknownTokenizer {
-                            field: field_entry.name().to_string(),
-                            tokenizer: option.tokenizer().to_string(),
-                        })?;
+                let text_analyzer = self
+                    .tokenizer_manager
+                    .get(option.search_tokenizer())
+                    .ok_or_else(|| QueryParserError::UnknownTokenizer {
+                        field: field_entry.name().to_string(),
+                        tokenizer: option.search_tokenizer().to_string(),
+                    })?;
                 let mut terms: Vec<Term> = Vec::new();
                 let mut token_stream = text_analyzer.token_stream(phrase);
                 token_stream.process(&mut |token| {
@@ -416,6 +417,9 @@ impl QueryParser {
                 let ip_v6 = IpAddr::from_str(phrase)?.into_ipv6_addr();
                 Ok(Term::from_field_ip_addr(field, ip_v6))
             }
+            FieldType::Vector(_) => Err(QueryParserError::UnsupportedQuery(
+                "Range query are not supported on vector field.".to_string(),
+            )),
         }
     }
 
@@ -469,24 +473,22 @@ impl QueryParser {
                     // This should have been seen earlier really.
                     QueryParserError::FieldNotIndexed(field_name.to_string())
                 })?;
-                let text_analyzer =
-                    self.tokenizer_manager
-                        .get(option.tokenizer())
-                        .ok_or_else(|| QueryParserError::UnknownTokenizer {
-                            field: field_name.to_string(),
-                            tokenizer: option.tokenizer().to_string(),
-                        })?;
+                let text_analyzer = self
+                    .tokenizer_manager
+                    .get(option.search_tokenizer())
+                    .ok_or_else(|| QueryParserError::UnknownTokenizer {
+                        field: field_name.to_string(),
+                        tokenizer: option.search_tokenizer().to_string(),
+                    })?;
                 let index_record_option = option.index_option();
-                Ok(generate_literals_for_str(
+                generate_literals_for_str(
                     field_name,
                     field,
                     phrase,
                     slop,
                     &text_analyzer,
                     index_record_option,
-                )?
-                .into_iter()
-                .collect())
+                )
             }
             FieldType::JsonObject(ref json_options) => generate_literals_for_json_object(
                 field_name,
@@ -515,6 +517,9 @@ impl QueryParser {
                 let term = Term::from_field_ip_addr(field, ip_v6);
                 Ok(vec![LogicalLiteral::Term(term)])
             }
+            FieldType::Vector(_) => Err(QueryParserError::UnsupportedQuery(
+                "Vector fields can only be searched with a KnnQuery.".to_string(),
+            )),
         }
     }
 
@@ -755,26 +760,53 @@ fn generate_literals_for_str(
     slop: u32,
     text_analyzer: &TextAnalyzer,
     index_record_option: IndexRecordOption,
-) -> Result<Option<LogicalLiteral>, QueryParserError> {
-    let mut terms: Vec<(usize, Term)> = Vec::new();
+) -> Result<Vec<LogicalLiteral>, QueryParserError> {
+    let mut token_graph = TokenGraph::default();
     let mut token_stream = text_analyzer.token_stream(phrase);
     token_stream.process(&mut |token| {
         let term = Term::from_field_text(field, &token.text);
-        terms.push((token.position, term));
+        token_graph.add_term(token.position, token.position_length, term);
     });
-    if terms.len() <= 1 {
-        let term_literal_opt = terms
-            .into_iter()
-            .next()
-            .map(|(_, term)| LogicalLiteral::Term(term));
-        return Ok(term_literal_opt);
-    }
-    if !index_record_option.has_positions() {
-        return Err(QueryParserError::FieldDoesNotHavePositionsIndexed(
-            field_name.to_string(),
-        ));
-    }
-    Ok(Some(LogicalLiteral::Phrase(terms, slop)))
+    generate_literals_for_token_graph(field_name, token_graph, slop, index_record_option)
+}
+
+/// Converts the terms emitted by a token stream into a term or phrase literal.
+///
+/// If the token stream is a graph, one literal is returned per path of the graph.
+fn generate_literals_for_token_graph(
+    field_name: &str,
+    token_graph: TokenGraph,
+    slop: u32,
+    index_record_option: IndexRecordOption,
+) -> Result<Vec<LogicalLiteral>, QueryParserError> {
+    let paths = if token_graph.is_linear() {
+        vec![token_graph.into_terms()]
+    } else {
+        token_graph.paths(MAX_TOKEN_GRAPH_PATHS).ok_or_else(|| {
+            QueryParserError::UnsupportedQuery(format!(
+                "The query for field '{field_name}' has more than {MAX_TOKEN_GRAPH_PATHS} \
+                 alternative token paths."
+            ))
+        })?
+    };
+    let mut logical_literals = Vec::with_capacity(paths.len());
+    for terms in paths {
+        if terms.len() <= 1 {
+            logical_literals.extend(
+                terms
+                    .into_iter()
+                    .map(|(_, term)| LogicalLiteral::Term(term)),
+            );
+            continue;
+        }
+        if !index_record_option.has_positions() {
+            return Err(QueryParserError::FieldDoesNotHavePositionsIndexed(
+                field_name.to_string(),
+            ));
+        }
+        logical_literals.push(LogicalLiteral::Phrase(terms, slop));
+    }
+    Ok(logical_literals)
 }
 
 fn generate_literals_for_json_object(
@@ -790,10 +822,10 @@ fn generate_literals_for_json_object(
         QueryParserError::FieldNotIndexed(field_name.to_string())
     })?;
     let text_analyzer = tokenizer_manager
-        .get(text_options.tokenizer())
+        .get(text_options.search_tokenizer())
         .ok_or_else(|| QueryParserError::UnknownTokenizer {
             field: field_name.to_string(),
-            tokenizer: text_options.tokenizer().to_string(),
+            tokenizer: text_options.search_tokenizer().to_string(),
         })?;
     let index_record_option = text_options.index_option();
     let mut logical_literals = Vec::new();
@@ -809,18 +841,16 @@ fn generate_literals_for_json_object(
     }
     let terms = set_string_and_get_terms(&mut json_term_writer, phrase, &text_analyzer);
     drop(json_term_writer);
-    if terms.len() <= 1 {
-        for (_, term) in terms {
-            logical_literals.push(LogicalLiteral::Term(term));
-        }
-        return Ok(logical_literals);
-    }
-    if !index_record_option.has_positions() {
-        return Err(QueryParserError::FieldDoesNotHavePositionsIndexed(
-            field_name.to_string(),
-        ));
-    }
-    logical_literals.push(LogicalLiteral::Phrase(terms, 0));
+    let mut token_graph = TokenGraph::default();
+    for (position, position_length, term) in terms {
+        token_graph.add_term(position, position_length, term);
+    }
+    logical_literals.extend(generate_literals_for_token_graph(
+        field_name,
+        token_graph,
+        0,
+        index_record_option,
+    )?);
     Ok(logical_literals)
 }
 
@@ -855,13 +885,15 @@ mod test {
 
     use super::super::logical_ast::*;
     use super::{QueryParser, QueryParserError};
+    use crate::collector::Count;
     use crate::query::Query;
     use crate::schema::{
         FacetOptions, Field, IndexRecordOption, Schema, Term, TextFieldIndexing, TextOptions, FAST,
         INDEXED, STORED, STRING, TEXT,
     };
     use crate::tokenizer::{
-        LowerCaser, SimpleTokenizer, StopWordFilter, TextAnalyzer, TokenizerManager,
+        EdgeNgramFilter, LowerCaser, SimpleTokenizer, StopWordFilter, TextAnalyzer,
+        TokenizerManager, WhitespaceTokenizer, WordDelimiterFilter,
     };
     use crate::Index;
 
@@ -969,6 +1001,33 @@ mod test {
         );
     }
 
+    #[test]
+    pub fn test_parse_query_range_normalized_bounds() {
+        let mut schema_builder = Schema::builder();
+        let country = schema_builder.add_text_field(
+            "country",
+            TextOptions::default()
+                .set_indexing_options(TextFieldIndexing::default().set_tokenizer("lowercase")),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_for_tests().unwrap();
+        for country_name in ["US", "us", "Us", "UY", "FR"] {
+            index_writer
+                .add_document(doc!(country => country_name))
+                .unwrap();
+        }
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        let query_parser = QueryParser::for_index(&index, vec![country]);
+        let query = query_parser.parse_query("country:[US TO UZ]").unwrap();
+        assert_eq!(
+            format!("{:?}", query),
+            "RangeQuery { field: \"country\", value_type: Str, left_bound: Included([117, \
+             115]), right_bound: Included([117, 122]) }"
+        );
+        assert_eq!(searcher.search(&query, &Count).unwrap(), 4);
+    }
+
     #[test]
     pub fn test_parse_query_with_default_boost_and_custom_boost() {
         let mut query_parser = make_query_parser();
@@ -1532,6 +1591,95 @@ mod test {
         );
     }
 
+    #[test]
+    pub fn test_query_parser_token_graph() {
+        let mut schema_builder = Schema::builder();
+        let text_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer("word_delimiter")
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+        );
+        let title = schema_builder.add_text_field("title", text_options);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        index.tokenizers().register(
+            "word_delimiter",
+            TextAnalyzer::from(WhitespaceTokenizer)
+                .filter(WordDelimiterFilter::default().catenate_all(true))
+                .filter(LowerCaser),
+        );
+        let mut index_writer = index.writer_for_tests().unwrap();
+        index_writer
+            .add_document(doc!(title => "wifi network"))
+            .unwrap();
+        index_writer
+            .add_document(doc!(title => "wi-fi network"))
+            .unwrap();
+        index_writer
+            .add_document(doc!(title => "wi network fi"))
+            .unwrap();
+        index_writer
+            .add_document(doc!(title => "network wi-fi"))
+            .unwrap();
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        let query_parser = QueryParser::for_index(&index, vec![title]);
+
+        let query = query_parser.parse_query("wi-fi").unwrap();
+        assert_eq!(
+            format!("{:?}", query),
+            r#"BooleanQuery { subqueries: [(Should, TermQuery(Term(type=Str, field=0, "wifi"))), (Should, PhraseQuery { field: Field(0), phrase_terms: [(0, Term(type=Str, field=0, "wi")), (1, Term(type=Str, field=0, "fi"))], slop: 0 })] }"#
+        );
+        let count = |query: &str| {
+            let query = query_parser.parse_query(query).unwrap();
+            searcher.search(&query, &Count).unwrap()
+        };
+        assert_eq!(count("\"wi-fi network\""), 2);
+        assert_eq!(count("\"wi fi network\""), 1);
+        assert_eq!(count("\"network wi-fi\""), 1);
+        assert_eq!(count("\"network wifi\""), 1);
+        assert_eq!(count("\"network wi fi\""), 1);
+        // Multi-position tokens are only indexed at their first position.
+        assert_eq!(count("\"wifi network\""), 1);
+        assert_eq!(count("\"wi wifi\""), 0);
+    }
+
+    #[test]
+    pub fn test_query_parser_search_tokenizer() {
+        let mut schema_builder = Schema::builder();
+        let text_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer("edge_ngram")
+                .set_search_tokenizer("default")
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+        );
+        let title = schema_builder.add_text_field("title", text_options);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        index.tokenizers().register(
+            "edge_ngram",
+            TextAnalyzer::from(SimpleTokenizer)
+                .filter(LowerCaser)
+                .filter(EdgeNgramFilter::new(1, 10)),
+        );
+        let mut index_writer = index.writer_for_tests().unwrap();
+        index_writer
+            .add_document(doc!(title => "Hello World"))
+            .unwrap();
+        index_writer.add_document(doc!(title => "Help")).unwrap();
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        let query_parser = QueryParser::for_index(&index, vec![title]);
+        let count = |query: &str| {
+            let query = query_parser.parse_query(query).unwrap();
+            searcher.search(&query, &Count).unwrap()
+        };
+        assert_eq!(count("hel"), 2);
+        assert_eq!(count("hell"), 1);
+        assert_eq!(count("\"hel wor\""), 1);
+        assert_eq!(count("\"hel w\""), 1);
+    }
+
     #[test]
     fn test_and_default_regardless_of_default_conjunctive() {
         for &default_conjunction in &[false, true] {
@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+
+use crate::schema::Term;
+
+/// Maximum number of paths a token graph can be expanded into.
+pub(crate) const MAX_TOKEN_GRAPH_PATHS: usize = 64;
+
+/// Terms emitted by a token stream, together with the positions they span.
+///
+/// Token filters such as synonym or word delimiter filters can emit
+/// tokens spanning several positions (`position_length > 1`), alongside
+/// the tokens they are an alternative to. For instance, `wi-fi` can be
+/// analyzed as
+///
+/// ```text
+///  0     1     2
+///  --wi--*--fi--
+///  \---wifi----/
+/// ```
+///
+/// Such a graph cannot be searched as a single phrase. Instead, each of its
+/// paths (`wi fi` and `wifi`) is searched as a separate phrase.
+#[derive(Default)]
+pub(crate) struct TokenGraph {
+    // terms, indexed by their start position.
+    edges: BTreeMap<usize, Vec<(usize, Term)>>,
+    // terms in the order they were added.
+    terms: Vec<(usize, Term)>,
+    has_multi_position_terms: bool,
+}
+
+impl TokenGraph {
+    /// Adds a term starting at `position` and spanning `position_length` positions.
+    pub fn add_term(&mut self, position: usize, position_length: usize, term: Term) {
+        let position_length = position_length.max(1);
+        self.has_multi_position_terms |= position_length > 1;
+        self.edges
+            .entry(position)
+            .or_default()
+            .push((position + position_length, term.clone()));
+        self.terms.push((position, term));
+    }
+
+    /// Returns true if the graph is a single path, possibly with
+    /// several terms stacked at the same position.
+    ///
+    /// Stacked terms of length 1 are not considered as alternatives
+    /// (and are searched together), so that the grams emitted by
+    /// n-gram tokenizers and filters all need to match.
+    pub fn is_linear(&self) -> bool {
+        !self.has_multi_position_terms
+    }
+
+    /// Returns the terms and their positions, in the order they were added.
+    pub fn into_terms(self) -> Vec<(usize, Term)> {
+        self.terms
+    }
+
+    /// Returns all of the paths of the graph, with the offsets of their terms
+    /// relative to the beginning of the path.
+    ///
+    /// Each term of a path is assumed to take a single position in the indexed
+    /// documents, whatever its `position_length`. Gaps between terms
+    /// (left by removed stop words for instance) are preserved.
+    ///
+    /// Returns `None` if the graph has more than `max_paths` paths.
+    pub fn paths(&self, max_paths: usize) -> Option<Vec<Vec<(usize, Term)>>> {
+        let mut paths = Vec::new();
+        let start = *self.edges.keys().next()?;
+        let mut current_path = Vec::new();
+        if !self.collect_paths(start, 0, &mut current_path, &mut paths, max_paths) {
+            return None;
+        }
+        Some(paths)
+    }
+
+    // Appends the paths starting at `position` to `paths`.
+    //
+    // Returns false if the number of paths exceeds `max_paths`.
+    fn collect_paths(
+        &self,
+        position: usize,
+        offset: usize,
+        current_path: &mut Vec<(usize, Term)>,
+        paths: &mut Vec<Vec<(usize, Term)>>,
+        max_paths: usize,
+    ) -> bool {
+        let (next_position, edges) = if let Some(next) = self.edges.range(position..).next() {
+            next
+        } else {
+            if !paths.contains(current_path) {
+                if paths.len() == max_paths {
+                    return false;
+                }
+                paths.push(current_path.clone());
+            }
+            return true;
+        };
+        // Terms may have been removed, leaving a gap before the next term.
+        let offset = offset + (next_position - position);
+        for (end_position, term) in edges {
+            current_path.push((offset, term.clone()));
+            let is_within_limit =
+                self.collect_paths(*end_position, offset + 1, current_path, paths, max_paths);
+            current_path.pop();
+            if !is_within_limit {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TokenGraph;
+    use crate::schema::{Field, Term};
+
+    fn term(text: &str) -> Term {
+        Term::from_field_text(Field::from_field_id(0), text)
+    }
+
+    fn path_texts(path: &[(usize, Term)]) -> Vec<(usize, &str)> {
+        path.iter()
+            .map(|(offset, term)| (*offset, term.as_str().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_token_graph_linear() {
+        let mut token_graph = TokenGraph::default();
+        token_graph.add_term(0, 1, term("he"));
+        token_graph.add_term(0, 1, term("hel"));
+        token_graph.add_term(1, 1, term("wo"));
+        assert!(token_graph.is_linear());
+        assert_eq!(token_graph.into_terms().len(), 3);
+    }
+
+    #[test]
+    fn test_token_graph_paths() {
+        let mut token_graph = TokenGraph::default();
+        token_graph.add_term(0, 2, term("wifi"));
+        token_graph.add_term(0, 1, term("wi"));
+        token_graph.add_term(1, 1, term("fi"));
+        // position 2 was a removed stop word.
+        token_graph.add_term(3, 1, term("network"));
+        assert!(!token_graph.is_linear());
+        let paths = token_graph.paths(10).unwrap();
+        assert_eq!(paths.len(), 2);
+        assert_eq!(path_texts(&paths[0]), &[(0, "wifi"), (2, "network")]);
+        assert_eq!(
+            path_texts(&paths[1]),
+            &[(0, "wi"), (1, "fi"), (3, "network")]
+        );
+    }
+
+    #[test]
+    fn test_token_graph_too_many_paths() {
+        let mut token_graph = TokenGraph::default();
+        for position in 0..4 {
+            token_graph.add_term(position, 2, term("long"));
+            token_graph.add_term(position, 1, term("short"));
+        }
+        assert!(token_graph.paths(4).is_none());
+        assert!(token_graph.paths(100).is_some());
+    }
+}
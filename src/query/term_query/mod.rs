@@ -136,7 +136,7 @@ mod tests {
         let mut index_writer = index.writer_for_tests()?;
         index_writer.add_document(doc!(text_field=>"a b"))?;
         index_writer.add_document(doc!(text_field=>"a c"))?;
-        index_writer.delete_term(Term::from_field_text(text_field, "b"));
+        index_writer.delete_term(Term::from_field_text(text_field, "b"))?;
         index_writer.commit()?;
         let term_a = Term::from_field_text(text_field, "a");
         let term_query = TermQuery::new(term_a, IndexRecordOption::Basic);
@@ -1,9 +1,10 @@
 use super::term_scorer::TermScorer;
-use crate::core::SegmentReader;
+use crate::core::{CancellationCheck, SegmentReader};
 use crate::docset::DocSet;
 use crate::fieldnorm::FieldNormReader;
 use crate::postings::SegmentPostings;
 use crate::query::bm25::Bm25Weight;
+use crate::query::boolean_query::block_wand_single_scorer;
 use crate::query::explanation::does_not_match;
 use crate::query::weight::{for_each_docset, for_each_scorer};
 use crate::query::{Explanation, Scorer, Weight};
@@ -85,7 +86,19 @@ impl Weight for TermWeight {
         callback: &mut dyn FnMut(DocId, Score) -> Score,
     ) -> crate::Result<()> {
         let scorer = self.specialized_scorer(reader, 1.0)?;
-        crate::query::boolean_query::block_wand_single_scorer(scorer, threshold, callback);
+        block_wand_single_scorer(scorer, threshold, callback, || false);
+        Ok(())
+    }
+
+    fn for_each_pruning_until_cancelled(
+        &self,
+        threshold: Score,
+        reader: &SegmentReader,
+        callback: &mut dyn FnMut(DocId, Score) -> Score,
+        check: &mut CancellationCheck,
+    ) -> crate::Result<()> {
+        let scorer = self.specialized_scorer(reader, 1.0)?;
+        block_wand_single_scorer(scorer, threshold, callback, || check.is_cancelled());
         Ok(())
     }
 }
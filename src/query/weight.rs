@@ -1,5 +1,5 @@
 use super::Scorer;
-use crate::core::SegmentReader;
+use crate::core::{CancellationCheck, SegmentReader};
 use crate::query::Explanation;
 use crate::{DocId, DocSet, Score, TERMINATED};
 
@@ -51,6 +51,24 @@ pub(crate) fn for_each_pruning_scorer<TScorer: Scorer + ?Sized>(
     }
 }
 
+/// Same as [`for_each_pruning_scorer`], except that the iteration stops as soon as
+/// `is_cancelled` returns true. It is called once per document.
+pub(crate) fn for_each_pruning_scorer_until_cancelled<TScorer: Scorer + ?Sized>(
+    scorer: &mut TScorer,
+    mut threshold: Score,
+    callback: &mut dyn FnMut(DocId, Score) -> Score,
+    is_cancelled: &mut dyn FnMut() -> bool,
+) {
+    let mut doc = scorer.doc();
+    while doc != TERMINATED && !is_cancelled() {
+        let score = scorer.score();
+        if score > threshold {
+            threshold = callback(doc, score);
+        }
+        doc = scorer.advance();
+    }
+}
+
 /// A Weight is the specialization of a `Query`
 /// for a given set of segments.
 ///
@@ -120,4 +138,28 @@ pub trait Weight: Send + Sync + 'static {
         for_each_pruning_scorer(scorer.as_mut(), threshold, callback);
         Ok(())
     }
+
+    /// Same as [`Weight::for_each_pruning`], except that the iteration stops as soon as
+    /// `check` reports that the search was cancelled.
+    ///
+    /// `check` is called for every document or block of documents visited,
+    /// and not only for the ones exceeding the threshold.
+    ///
+    /// This is an implementation detail of the cancellable searches:
+    /// `CancellationCheck` cannot be named outside of tantivy, so this method
+    /// can only be called and specialized by tantivy's own weights.
+    #[doc(hidden)]
+    fn for_each_pruning_until_cancelled(
+        &self,
+        threshold: Score,
+        reader: &SegmentReader,
+        callback: &mut dyn FnMut(DocId, Score) -> Score,
+        check: &mut CancellationCheck,
+    ) -> crate::Result<()> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+        for_each_pruning_scorer_until_cancelled(scorer.as_mut(), threshold, callback, &mut || {
+            check.is_cancelled()
+        });
+        Ok(())
+    }
 }
@@ -0,0 +1,322 @@
+//! Incremental replication of an index, from a leader to read-only followers.
+//!
+//! Segment files are immutable: once written, a file is never modified. A follower
+//! therefore only needs to fetch the files of the leader's last commit that it does
+//! not already have, before installing the leader's `meta.json`.
+//!
+//! The leader is accessed through a [`ReplicationSource`]. [`LocalReplicationSource`]
+//! replicates from an [`Index`] of the same process, typically opened on a local
+//! directory.
+//!
+//! ```rust
+//! use tantivy::directory::RamDirectory;
+//! use tantivy::replication::{Follower, LocalReplicationSource};
+//! use tantivy::schema::{Schema, TEXT};
+//! use tantivy::{doc, Index};
+//!
+//! # fn main() -> tantivy::Result<()> {
+//! let mut schema_builder = Schema::builder();
+//! let title = schema_builder.add_text_field("title", TEXT);
+//! let leader = Index::create_in_ram(schema_builder.build());
+//! let mut index_writer = leader.writer(50_000_000)?;
+//! index_writer.add_document(doc!(title => "The Old Man and the Sea"))?;
+//! index_writer.commit()?;
+//!
+//! let source = LocalReplicationSource::new(leader);
+//! let mut follower = Follower::open(RamDirectory::create())?;
+//! follower.replicate(&source)?;
+//! let searcher = follower.index()?.reader()?.searcher();
+//! assert_eq!(searcher.num_docs(), 1);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crc32fast::Hasher;
+
+use crate::core::{commit_meta_filepath, SegmentMetaInventory, META_FILEPATH};
+use crate::directory::error::DeleteError;
+use crate::directory::{Directory, ManagedDirectory, OwnedBytes, TerminatingWrite};
+use crate::error::{DataCorruption, TantivyError};
+use crate::indexer::segment_updater::save_metas;
+use crate::{Index, IndexMeta, IndexSnapshot, Opstamp, SnapshotFile};
+
+/// A commit of the leader, as fetched by a follower.
+#[derive(Clone, Debug)]
+pub struct ReplicationCommit {
+    /// Opstamp of the commit.
+    pub opstamp: Opstamp,
+    /// Content of the `meta.json` file of the commit.
+    pub meta_json: String,
+    /// Segment files of the commit.
+    pub files: Vec<SnapshotFile>,
+}
+
+/// Transport used by a [`Follower`] to fetch the files of the leader.
+pub trait ReplicationSource: Send + Sync {
+    /// Returns the last commit of the leader.
+    ///
+    /// The files of the returned commit should remain available on the leader
+    /// until the next call to `fetch_commit`.
+    fn fetch_commit(&self) -> crate::Result<ReplicationCommit>;
+
+    /// Returns the content of a segment file of the leader, without its footer.
+    fn fetch_file(&self, path: &Path) -> crate::Result<OwnedBytes>;
+}
+
+/// Replicates an [`Index`] of the same process.
+///
+/// The files of the last fetched commit are pinned with an [`IndexSnapshot`].
+/// They are protected from garbage collection by the index writers created from
+/// the same `Index`, until the next commit is fetched.
+pub struct LocalReplicationSource {
+    index: Index,
+    snapshot: Mutex<Option<IndexSnapshot>>,
+}
+
+impl LocalReplicationSource {
+    /// Creates a replication source for the given leader index.
+    pub fn new(index: Index) -> LocalReplicationSource {
+        LocalReplicationSource {
+            index,
+            snapshot: Mutex::new(None),
+        }
+    }
+}
+
+impl ReplicationSource for LocalReplicationSource {
+    fn fetch_commit(&self) -> crate::Result<ReplicationCommit> {
+        let snapshot = self.index.create_snapshot()?;
+        let commit = ReplicationCommit {
+            opstamp: snapshot.meta().opstamp,
+            meta_json: serde_json::to_string(snapshot.meta())?,
+            files: snapshot.files().to_vec(),
+        };
+        *self.snapshot.lock().unwrap() = Some(snapshot);
+        Ok(commit)
+    }
+
+    fn fetch_file(&self, path: &Path) -> crate::Result<OwnedBytes> {
+        let bytes = self.index.directory().open_read(path)?.read_bytes()?;
+        Ok(bytes)
+    }
+}
+
+/// Statistics of a [`Follower::replicate`] call.
+#[derive(Clone, Debug, Default)]
+pub struct ReplicationResult {
+    /// Opstamp of the commit installed on the follower.
+    pub opstamp: Opstamp,
+    /// Segment files that were copied from the leader.
+    pub copied_files: Vec<PathBuf>,
+    /// Number of bytes copied from the leader, footers excluded.
+    pub num_copied_bytes: u64,
+    /// Files of the previous commits that were removed from the follower.
+    pub deleted_files: Vec<PathBuf>,
+}
+
+/// Read-only copy of a leader index, kept up to date by calling
+/// [`Follower::replicate`].
+///
+/// The follower index must not be written to: no [`IndexWriter`](crate::IndexWriter)
+/// should be created on its directory.
+pub struct Follower {
+    directory: Box<dyn Directory>,
+    managed_directory: ManagedDirectory,
+}
+
+impl Follower {
+    /// Opens a follower on the given directory.
+    ///
+    /// The directory is either empty or holds an index previously replicated from
+    /// the same leader.
+    pub fn open<T: Into<Box<dyn Directory>>>(directory: T) -> crate::Result<Follower> {
+        let directory = directory.into();
+        let managed_directory = ManagedDirectory::wrap(directory.box_clone())?;
+        Ok(Follower {
+            directory,
+            managed_directory,
+        })
+    }
+
+    /// Opens the replicated index.
+    ///
+    /// Readers created with [`ReloadPolicy::OnCommit`](crate::ReloadPolicy::OnCommit)
+    /// pick up the commits installed by [`Follower::replicate`]. Other readers need to
+    /// be reloaded with [`IndexReader::reload`](crate::IndexReader::reload).
+    ///
+    /// Returns an error if no commit has been replicated yet.
+    pub fn index(&self) -> crate::Result<Index> {
+        Index::open(self.directory.box_clone())
+    }
+
+    /// Returns the opstamp of the commit installed on the follower, if any.
+    pub fn opstamp(&self) -> crate::Result<Option<Opstamp>> {
+        Ok(self.load_metas()?.map(|meta| meta.opstamp))
+    }
+
+    fn load_metas(&self) -> crate::Result<Option<IndexMeta>> {
+        if !Index::exists(&self.managed_directory)? {
+            return Ok(None);
+        }
+        let meta_data = self.managed_directory.atomic_read(&META_FILEPATH)?;
+        let meta_json = String::from_utf8_lossy(&meta_data);
+        let inventory = SegmentMetaInventory::default();
+        let meta = IndexMeta::deserialize(&meta_json, &inventory).map_err(|err| {
+            DataCorruption::new(
+                META_FILEPATH.to_path_buf(),
+                format!("Meta file cannot be deserialized. {:?}.", err),
+            )
+        })?;
+        Ok(Some(meta))
+    }
+
+    /// Replicates the last commit of the leader.
+    ///
+    /// The segment files the follower lacks are copied first, and their checksums
+    /// are verified. The leader's `meta.json` is then atomically installed, and the
+    /// files that are not used by the new commit are removed.
+    ///
+    /// If the replication fails, the previous commit remains installed and the call
+    /// can be retried.
+    pub fn replicate(
+        &mut self,
+        source: &dyn ReplicationSource,
+    ) -> crate::Result<ReplicationResult> {
+        let commit = source.fetch_commit()?;
+        let inventory = SegmentMetaInventory::default();
+        let mut meta = IndexMeta::deserialize(&commit.meta_json, &inventory).map_err(|err| {
+            DataCorruption::comment_only(format!(
+                "Meta of the leader cannot be deserialized. {:?}.",
+                err
+            ))
+        })?;
+        // Past commits of the leader are not replicated.
+        meta.retained_commits.clear();
+
+        let current_meta = self.load_metas()?;
+        let current_files: HashSet<PathBuf> = current_meta
+            .iter()
+            .flat_map(|current_meta| current_meta.segments.iter())
+            .flat_map(|segment_meta| segment_meta.list_files())
+            .collect();
+
+        let mut result = ReplicationResult {
+            opstamp: meta.opstamp,
+            ..Default::default()
+        };
+        for file in &commit.files {
+            if self.managed_directory.exists(&file.path)? {
+                if current_files.contains(&file.path) {
+                    continue;
+                }
+                // Leftover of an interrupted replication.
+                match self.managed_directory.delete(&file.path) {
+                    Ok(()) | Err(DeleteError::FileDoesNotExist(_)) => {}
+                    Err(DeleteError::IoError { io_error, .. }) => {
+                        return Err(TantivyError::IoError(io_error));
+                    }
+                }
+            }
+            let bytes = source.fetch_file(&file.path)?;
+            let mut hasher = Hasher::new();
+            hasher.update(bytes.as_slice());
+            if hasher.finalize() != file.crc {
+                return Err(DataCorruption::new(
+                    file.path.clone(),
+                    "Checksum mismatch while replicating the file.".to_string(),
+                )
+                .into());
+            }
+            let mut write = self.managed_directory.open_write(&file.path)?;
+            write.write_all(bytes.as_slice())?;
+            write.terminate()?;
+            result.copied_files.push(file.path.clone());
+            result.num_copied_bytes += bytes.len() as u64;
+        }
+
+        let current_opstamp = current_meta.map(|current_meta| current_meta.opstamp);
+        let is_up_to_date = result.copied_files.is_empty() && current_opstamp == Some(meta.opstamp);
+        if is_up_to_date {
+            return Ok(result);
+        }
+        save_metas(&meta, &self.managed_directory)?;
+
+        let mut living_files: HashSet<PathBuf> =
+            commit.files.iter().map(|file| file.path.clone()).collect();
+        living_files.insert(META_FILEPATH.to_path_buf());
+        if meta.index_settings.num_retained_commits > 0 {
+            living_files.insert(commit_meta_filepath(meta.opstamp));
+        }
+        result.deleted_files = self
+            .managed_directory
+            .garbage_collect(|| living_files)?
+            .deleted_files;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Follower, LocalReplicationSource};
+    use crate::directory::{Directory, RamDirectory};
+    use crate::schema::{Schema, INDEXED};
+    use crate::{Index, IndexWriter, ReloadPolicy};
+
+    #[test]
+    fn test_incremental_replication() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let num_field = schema_builder.add_u64_field("num", INDEXED);
+        let leader = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = leader.writer_for_tests()?;
+        index_writer.add_document(doc!(num_field => 0u64))?;
+        index_writer.commit()?;
+
+        let source = LocalReplicationSource::new(leader.clone());
+        let follower_directory = RamDirectory::create();
+        let mut follower = Follower::open(follower_directory.clone())?;
+        assert_eq!(follower.opstamp()?, None);
+        let result = follower.replicate(&source)?;
+        assert!(!result.copied_files.is_empty());
+        assert_eq!(follower.opstamp()?, Some(result.opstamp));
+        let first_files = result.copied_files;
+
+        let reader = follower
+            .index()?
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        assert_eq!(reader.searcher().num_docs(), 1);
+
+        index_writer.add_document(doc!(num_field => 1u64))?;
+        index_writer.commit()?;
+        let result = follower.replicate(&source)?;
+        assert!(!result.copied_files.is_empty());
+        assert!(result
+            .copied_files
+            .iter()
+            .all(|path| !first_files.contains(path)));
+        reader.reload()?;
+        assert_eq!(reader.searcher().num_docs(), 2);
+
+        let result = follower.replicate(&source)?;
+        assert!(result.copied_files.is_empty());
+        assert!(result.deleted_files.is_empty());
+
+        let segment_ids = leader.searchable_segment_ids()?;
+        index_writer.merge(&segment_ids).wait()?;
+        let result = follower.replicate(&source)?;
+        assert!(result.deleted_files.len() > first_files.len());
+        for path in &first_files {
+            assert!(!follower_directory.exists(path)?);
+        }
+        reader.reload()?;
+        assert_eq!(reader.searcher().segment_readers().len(), 1);
+        assert_eq!(reader.searcher().num_docs(), 2);
+        Ok(())
+    }
+}
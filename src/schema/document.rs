@@ -137,6 +137,11 @@ impl Document {
         self.add_field_value(field, json_object);
     }
 
+    /// Add a vector field
+    pub fn add_vector(&mut self, field: Field, vector: Vec<f32>) {
+        self.add_field_value(field, Value::Vector(vector));
+    }
+
     /// Add a (field, value) to the document.
     pub fn add_field_value<T: Into<Value>>(&mut self, field: Field, typed_val: T) {
         let value = typed_val.into();
@@ -4,7 +4,7 @@ use super::ip_options::IpAddrOptions;
 use crate::schema::bytes_options::BytesOptions;
 use crate::schema::{
     is_valid_field_name, DateOptions, FacetOptions, FieldType, JsonObjectOptions, NumericOptions,
-    TextOptions,
+    TextOptions, VectorOptions,
 };
 
 /// A `FieldEntry` represents a field and its configuration.
@@ -81,6 +81,11 @@ impl FieldEntry {
         Self::new(field_name, FieldType::JsonObject(json_object_options))
     }
 
+    /// Creates a field entry for a vector field
+    pub fn new_vector(field_name: String, vector_options: VectorOptions) -> FieldEntry {
+        Self::new(field_name, FieldType::Vector(vector_options))
+    }
+
     /// Returns the name of the field
     pub fn name(&self) -> &str {
         &self.name
@@ -121,6 +126,7 @@ impl FieldEntry {
             FieldType::Bytes(ref options) => options.is_stored(),
             FieldType::JsonObject(ref options) => options.is_stored(),
             FieldType::IpAddr(ref options) => options.is_stored(),
+            FieldType::Vector(ref options) => options.is_stored(),
         }
     }
 }
@@ -13,7 +13,7 @@ use crate::schema::bytes_options::BytesOptions;
 use crate::schema::facet_options::FacetOptions;
 use crate::schema::{
     DateOptions, Facet, IndexRecordOption, JsonObjectOptions, NumericOptions, TextFieldIndexing,
-    TextOptions, Value,
+    TextOptions, Value, VectorOptions,
 };
 use crate::time::format_description::well_known::Rfc3339;
 use crate::time::OffsetDateTime;
@@ -70,9 +70,11 @@ pub enum Type {
     Json = b'j',
     /// IpAddr
     IpAddr = b'p',
+    /// `Vec<f32>`
+    Vector = b'v',
 }
 
-const ALL_TYPES: [Type; 10] = [
+const ALL_TYPES: [Type; 11] = [
     Type::Str,
     Type::U64,
     Type::I64,
@@ -83,6 +85,7 @@ const ALL_TYPES: [Type; 10] = [
     Type::Bytes,
     Type::Json,
     Type::IpAddr,
+    Type::Vector,
 ];
 
 impl Type {
@@ -110,6 +113,7 @@ impl Type {
             Type::Bytes => "Bytes",
             Type::Json => "Json",
             Type::IpAddr => "IpAddr",
+            Type::Vector => "Vector",
         }
     }
 
@@ -127,6 +131,7 @@ impl Type {
             b'b' => Some(Type::Bytes),
             b'j' => Some(Type::Json),
             b'p' => Some(Type::IpAddr),
+            b'v' => Some(Type::Vector),
             _ => None,
         }
     }
@@ -159,6 +164,8 @@ pub enum FieldType {
     JsonObject(JsonObjectOptions),
     /// IpAddr field
     IpAddr(IpAddrOptions),
+    /// Dense vector field
+    Vector(VectorOptions),
 }
 
 impl FieldType {
@@ -175,6 +182,7 @@ impl FieldType {
             FieldType::Bytes(_) => Type::Bytes,
             FieldType::JsonObject(_) => Type::Json,
             FieldType::IpAddr(_) => Type::IpAddr,
+            FieldType::Vector(_) => Type::Vector,
         }
     }
 
@@ -201,6 +209,7 @@ impl FieldType {
             FieldType::Bytes(ref bytes_options) => bytes_options.is_indexed(),
             FieldType::JsonObject(ref json_object_options) => json_object_options.is_indexed(),
             FieldType::IpAddr(ref ip_addr_options) => ip_addr_options.is_indexed(),
+            FieldType::Vector(_) => false,
         }
     }
 
@@ -237,7 +246,7 @@ impl FieldType {
             FieldType::Date(ref date_options) => date_options.is_fast(),
             FieldType::IpAddr(ref ip_addr_options) => ip_addr_options.is_fast(),
             FieldType::Facet(_) => true,
-            FieldType::JsonObject(_) => false,
+            FieldType::JsonObject(_) | FieldType::Vector(_) => false,
         }
     }
 
@@ -258,6 +267,7 @@ impl FieldType {
             FieldType::Facet(_) => Some(Cardinality::MultiValues),
             FieldType::JsonObject(_) => None,
             FieldType::IpAddr(ref ip_addr_options) => ip_addr_options.get_fastfield_cardinality(),
+            FieldType::Vector(_) => None,
         }
     }
 
@@ -277,6 +287,7 @@ impl FieldType {
             FieldType::Bytes(ref bytes_options) => bytes_options.fieldnorms(),
             FieldType::JsonObject(ref _json_object_options) => false,
             FieldType::IpAddr(ref ip_addr_options) => ip_addr_options.fieldnorms(),
+            FieldType::Vector(_) => false,
         }
     }
 
@@ -328,6 +339,7 @@ impl FieldType {
                     None
                 }
             }
+            FieldType::Vector(_) => None,
         }
     }
 
@@ -368,6 +380,10 @@ impl FieldType {
                         expected: "a json object",
                         json: JsonValue::String(field_text),
                     }),
+                    FieldType::Vector(_) => Err(ValueParsingError::TypeError {
+                        expected: "an array of numbers",
+                        json: JsonValue::String(field_text),
+                    }),
                     FieldType::IpAddr(_) => {
                         let ip_addr: IpAddr = IpAddr::from_str(&field_text).map_err(|err| {
                             ValueParsingError::ParseError {
@@ -429,6 +445,10 @@ impl FieldType {
                     expected: "a string with an ip addr",
                     json: JsonValue::Number(field_val_num),
                 }),
+                FieldType::Vector(_) => Err(ValueParsingError::TypeError {
+                    expected: "an array of numbers",
+                    json: JsonValue::Number(field_val_num),
+                }),
             },
             JsonValue::Object(json_map) => match self {
                 FieldType::Str(_) => {
@@ -449,6 +469,35 @@ impl FieldType {
                     json: JsonValue::Object(json_map),
                 }),
             },
+            JsonValue::Array(json_items) => match self {
+                FieldType::Vector(vector_options) => {
+                    let vector: Option<Vec<f32>> = json_items
+                        .iter()
+                        .map(|json_item| json_item.as_f64().map(|val| val as f32))
+                        .collect();
+                    match vector {
+                        Some(vector) if vector.len() == vector_options.dimension() => {
+                            Ok(Value::Vector(vector))
+                        }
+                        Some(vector) => Err(ValueParsingError::ParseError {
+                            error: format!(
+                                "Expected a vector of dimension {}, got {}",
+                                vector_options.dimension(),
+                                vector.len()
+                            ),
+                            json: JsonValue::Array(json_items),
+                        }),
+                        None => Err(ValueParsingError::TypeError {
+                            expected: "an array of numbers",
+                            json: JsonValue::Array(json_items),
+                        }),
+                    }
+                }
+                _ => Err(ValueParsingError::TypeError {
+                    expected: self.value_type().name(),
+                    json: JsonValue::Array(json_items),
+                }),
+            },
             JsonValue::Bool(json_bool_val) => match self {
                 FieldType::Bool(_) => Ok(Value::Bool(json_bool_val)),
                 _ => Err(ValueParsingError::TypeError {
@@ -125,6 +125,7 @@ mod named_field_document;
 mod numeric_options;
 mod text_options;
 mod value;
+mod vector_options;
 
 pub use self::bytes_options::BytesOptions;
 pub use self::date_time_options::{DateOptions, DatePrecision};
@@ -148,6 +149,7 @@ pub use self::schema::{DocParsingError, Schema, SchemaBuilder};
 pub use self::term::Term;
 pub use self::text_options::{TextFieldIndexing, TextOptions, STRING, TEXT};
 pub use self::value::Value;
+pub use self::vector_options::{VectorMetric, VectorOptions};
 
 /// Validator for a potential `field_name`.
 /// Returns true if the name can be use for a field name.
@@ -197,6 +197,17 @@ impl SchemaBuilder {
         self.add_field(field_entry)
     }
 
+    /// Adds a vector field.
+    /// Returns the associated field handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics when field already exists.
+    pub fn add_vector_field(&mut self, field_name: &str, field_options: VectorOptions) -> Field {
+        let field_entry = FieldEntry::new_vector(field_name.to_string(), field_options);
+        self.add_field(field_entry)
+    }
+
     /// Adds a field entry to the schema in build.
     pub fn add_field(&mut self, field_entry: FieldEntry) -> Field {
         let field = Field::from_field_id(self.fields.len() as u32);
@@ -369,7 +380,11 @@ impl Schema {
                 let field_entry = self.get_field_entry(field);
                 let field_type = field_entry.field_type();
                 match json_value {
-                    JsonValue::Array(json_items) => {
+                    // The vectors of a vector field are json arrays themselves.
+                    JsonValue::Array(json_items)
+                        if !matches!(field_type, FieldType::Vector(_))
+                            || json_items.iter().all(JsonValue::is_array) =>
+                    {
                         for json_item in json_items {
                             let value = field_type
                                 .value_from_json(json_item)
@@ -449,6 +449,8 @@ fn debug_value_bytes(typ: Type, bytes: &[u8], f: &mut fmt::Formatter) -> fmt::Re
         Type::IpAddr => {
             write!(f, "")?; // TODO change once we actually have IP address terms.
         }
+        // Vectors are not indexed.
+        Type::Vector => {}
     }
     Ok(())
 }
@@ -45,7 +45,8 @@ impl TextOptions {
     /// The effective cardinality depends on the tokenizer. When creating fast fields on text
     /// fields it is recommended to use the "raw" tokenizer, since it will store the original text
     /// unchanged. The "default" tokenizer will store the terms as lower case and this will be
-    /// reflected in the dictionary.
+    /// reflected in the dictionary. The "lowercase" and "lowercase_ascii" normalizers
+    /// keep the text as a single token, but make it case-insensitive.
     ///
     /// The original text can be retrieved via
     /// [`TermDictionary::ord_to_term()`](crate::termdict::TermDictionary::ord_to_term)
@@ -103,6 +104,8 @@ impl TokenizerName {
 /// Essentially, should we store the term frequency and/or the positions (See
 /// [`IndexRecordOption`]).
 /// - The name of the `Tokenizer` that should be used to process the field.
+/// - Optionally, the name of a distinct `Tokenizer` that should be used to process queries
+///   targeting the field.
 /// - Flag indicating, if fieldnorms should be stored (See [fieldnorm](crate::fieldnorm)). Defaults
 ///   to `true`.
 #[derive(Clone, PartialEq, Debug, Eq, Serialize, Deserialize)]
@@ -113,6 +116,9 @@ pub struct TextFieldIndexing {
     fieldnorms: bool,
     #[serde(default)]
     tokenizer: TokenizerName,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    search_tokenizer: Option<TokenizerName>,
 }
 
 pub(crate) fn default_fieldnorms() -> bool {
@@ -123,6 +129,7 @@ impl Default for TextFieldIndexing {
     fn default() -> TextFieldIndexing {
         TextFieldIndexing {
             tokenizer: TokenizerName::default(),
+            search_tokenizer: None,
             record: IndexRecordOption::default(),
             fieldnorms: default_fieldnorms(),
         }
@@ -142,6 +149,27 @@ impl TextFieldIndexing {
         self.tokenizer.name()
     }
 
+    /// Sets the tokenizer to be used to process queries targeting a given field.
+    ///
+    /// By default, queries are processed by the same tokenizer as the indexed documents.
+    /// A distinct search tokenizer makes it possible for instance to index edge n-grams
+    /// while searching whole words, or to add synonyms at search time only.
+    #[must_use]
+    pub fn set_search_tokenizer(mut self, tokenizer_name: &str) -> TextFieldIndexing {
+        self.search_tokenizer = Some(TokenizerName::from_name(tokenizer_name));
+        self
+    }
+
+    /// Returns the tokenizer that will be used to process queries targeting this field.
+    ///
+    /// Defaults to the tokenizer used for indexing if no search tokenizer was set.
+    pub fn search_tokenizer(&self) -> &str {
+        self.search_tokenizer
+            .as_ref()
+            .unwrap_or(&self.tokenizer)
+            .name()
+    }
+
     /// Sets fieldnorms
     #[must_use]
     pub fn set_fieldnorms(mut self, fieldnorms: bool) -> TextFieldIndexing {
@@ -175,6 +203,7 @@ impl TextFieldIndexing {
 pub const STRING: TextOptions = TextOptions {
     indexing: Some(TextFieldIndexing {
         tokenizer: TokenizerName::from_static(NO_TOKENIZER_NAME),
+        search_tokenizer: None,
         fieldnorms: true,
         record: IndexRecordOption::Basic,
     }),
@@ -186,6 +215,7 @@ pub const STRING: TextOptions = TextOptions {
 pub const TEXT: TextOptions = TextOptions {
     indexing: Some(TextFieldIndexing {
         tokenizer: TokenizerName::from_static(DEFAULT_TOKENIZER_NAME),
+        search_tokenizer: None,
         fieldnorms: true,
         record: IndexRecordOption::WithFreqsAndPositions,
     }),
@@ -287,4 +317,21 @@ mod tests {
         let options3: TextOptions = serde_json::from_str("{}").unwrap();
         assert_eq!(options3.indexing, None);
     }
+
+    #[test]
+    fn test_search_tokenizer() {
+        let indexing = TextFieldIndexing::default().set_tokenizer("edge_ngram");
+        assert_eq!(indexing.search_tokenizer(), "edge_ngram");
+        assert!(!serde_json::to_string(&indexing)
+            .unwrap()
+            .contains("search_tokenizer"));
+
+        let indexing = indexing.set_search_tokenizer("default");
+        assert_eq!(indexing.tokenizer(), "edge_ngram");
+        assert_eq!(indexing.search_tokenizer(), "default");
+        let json = serde_json::to_string(&indexing).unwrap();
+        assert!(json.contains(r#""search_tokenizer":"default""#));
+        let deserialized: TextFieldIndexing = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, indexing);
+    }
 }
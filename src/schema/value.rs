@@ -37,6 +37,8 @@ pub enum Value {
     JsonObject(serde_json::Map<String, serde_json::Value>),
     /// IpV6 Address. Internally there is no IpV4, it needs to be converted to `Ipv6Addr`.
     IpAddr(Ipv6Addr),
+    /// Dense vector of `f32`.
+    Vector(Vec<f32>),
 }
 
 impl Eq for Value {}
@@ -63,6 +65,7 @@ impl Serialize for Value {
                     obj.serialize(serializer)
                 }
             }
+            Value::Vector(ref vector) => vector.serialize(serializer),
         }
     }
 }
@@ -224,6 +227,17 @@ impl Value {
             None
         }
     }
+
+    /// Returns the vector, provided the value is of the `Vector` type.
+    ///
+    /// Returns `None` if the value is not of type `Vector`.
+    pub fn as_vector(&self) -> Option<&[f32]> {
+        if let Value::Vector(vector) = self {
+            Some(vector)
+        } else {
+            None
+        }
+    }
 }
 
 impl From<String> for Value {
@@ -338,6 +352,7 @@ mod binary_serialize {
     const JSON_OBJ_CODE: u8 = 8;
     const BOOL_CODE: u8 = 9;
     const IP_CODE: u8 = 10;
+    const VECTOR_CODE: u8 = 11;
 
     // extended types
 
@@ -402,6 +417,10 @@ mod binary_serialize {
                     IP_CODE.serialize(writer)?;
                     ip.to_u128().serialize(writer)
                 }
+                Value::Vector(ref vector) => {
+                    VECTOR_CODE.serialize(writer)?;
+                    vector.serialize(writer)
+                }
             }
         }
 
@@ -476,6 +495,7 @@ mod binary_serialize {
                     let value = u128::deserialize(reader)?;
                     Ok(Value::IpAddr(Ipv6Addr::from_u128(value)))
                 }
+                VECTOR_CODE => Ok(Value::Vector(Vec::<f32>::deserialize(reader)?)),
 
                 _ => Err(io::Error::new(
                     io::ErrorKind::InvalidData,
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+/// Similarity metric used to compare the vectors of a vector field.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VectorMetric {
+    /// Cosine of the angle between the two vectors.
+    #[default]
+    Cosine,
+    /// Dot product of the two vectors.
+    ///
+    /// This is equivalent to the cosine similarity if all vectors are normalized, but
+    /// cheaper to compute.
+    Dot,
+    /// Euclidean distance between the two vectors.
+    L2,
+}
+
+/// Define how a vector field should be handled by tantivy.
+///
+/// A vector field associates at most one dense vector of `f32` to each document.
+/// All of the vectors of the field must have the same dimension.
+///
+/// Vectors are not indexed in the inverted index. They are written in their own
+/// segment component, along with a HNSW graph used to search for their nearest
+/// neighbours with a [`KnnQuery`](crate::query::KnnQuery).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorOptions {
+    dimension: usize,
+    #[serde(default)]
+    metric: VectorMetric,
+    #[serde(default)]
+    stored: bool,
+}
+
+impl VectorOptions {
+    /// Creates the options of a vector field of the given dimension.
+    ///
+    /// The similarity metric is [`VectorMetric::Cosine`] by default.
+    pub fn new(dimension: usize) -> VectorOptions {
+        VectorOptions {
+            dimension,
+            metric: VectorMetric::default(),
+            stored: false,
+        }
+    }
+
+    /// Returns the dimension of the vectors.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Returns the similarity metric used to compare the vectors.
+    pub fn metric(&self) -> VectorMetric {
+        self.metric
+    }
+
+    /// Returns `true` if the vector should be stored in the doc store.
+    pub fn is_stored(&self) -> bool {
+        self.stored
+    }
+
+    /// Sets the similarity metric used to compare the vectors.
+    #[must_use]
+    pub fn set_metric(mut self, metric: VectorMetric) -> VectorOptions {
+        self.metric = metric;
+        self
+    }
+
+    /// Sets the field as stored.
+    #[must_use]
+    pub fn set_stored(mut self) -> VectorOptions {
+        self.stored = true;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VectorMetric, VectorOptions};
+
+    #[test]
+    fn test_vector_options_serialization() {
+        let options = VectorOptions::new(3).set_metric(VectorMetric::L2);
+        let json = serde_json::to_string(&options).unwrap();
+        assert_eq!(json, r#"{"dimension":3,"metric":"l2","stored":false}"#);
+        let options_deser: VectorOptions = serde_json::from_str(r#"{"dimension":3}"#).unwrap();
+        assert_eq!(options_deser, VectorOptions::new(3));
+    }
+}
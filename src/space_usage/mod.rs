@@ -75,6 +75,10 @@ pub struct SegmentSpaceUsage {
     store: StoreSpaceUsage,
 
     deletes: ByteCount,
+    #[serde(default)]
+    soft_deletes: ByteCount,
+    #[serde(default)]
+    vectors: PerFieldSpaceUsage,
 
     total: ByteCount,
 }
@@ -90,6 +94,8 @@ impl SegmentSpaceUsage {
         fieldnorms: PerFieldSpaceUsage,
         store: StoreSpaceUsage,
         deletes: ByteCount,
+        soft_deletes: ByteCount,
+        vectors: PerFieldSpaceUsage,
     ) -> SegmentSpaceUsage {
         let total = termdict.total()
             + postings.total()
@@ -97,7 +103,9 @@ impl SegmentSpaceUsage {
             + fast_fields.total()
             + fieldnorms.total()
             + store.total()
-            + deletes;
+            + deletes
+            + soft_deletes
+            + vectors.total();
         SegmentSpaceUsage {
             num_docs,
             termdict,
@@ -107,6 +115,8 @@ impl SegmentSpaceUsage {
             fieldnorms,
             store,
             deletes,
+            soft_deletes,
+            vectors,
             total,
         }
     }
@@ -127,6 +137,8 @@ impl SegmentSpaceUsage {
             SegmentComponent::Store => ComponentSpaceUsage::Store(self.store().clone()),
             SegmentComponent::TempStore => ComponentSpaceUsage::Store(self.store().clone()),
             Delete => Basic(self.deletes()),
+            SoftDeletes => Basic(self.soft_deletes()),
+            Vectors => PerField(self.vectors().clone()),
         }
     }
 
@@ -170,6 +182,16 @@ impl SegmentSpaceUsage {
         self.deletes
     }
 
+    /// Space usage for the log of soft-deleted documents
+    pub fn soft_deletes(&self) -> ByteCount {
+        self.soft_deletes
+    }
+
+    /// Space usage for vectors
+    pub fn vectors(&self) -> &PerFieldSpaceUsage {
+        &self.vectors
+    }
+
     /// Total space usage in bytes for this segment.
     pub fn total(&self) -> ByteCount {
         self.total
@@ -212,7 +234,7 @@ impl StoreSpaceUsage {
 ///
 /// A field can appear with a single index (typically 0) or with multiple indexes.
 /// Multiple indexes are used to handle variable length things, where
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct PerFieldSpaceUsage {
     fields: HashMap<Field, FieldUsage>,
     total: ByteCount,
@@ -452,8 +474,8 @@ mod test {
 
         {
             let mut index_writer2 = index.writer(50_000_000)?;
-            index_writer2.delete_term(Term::from_field_u64(name, 2u64));
-            index_writer2.delete_term(Term::from_field_u64(name, 3u64));
+            index_writer2.delete_term(Term::from_field_u64(name, 2u64))?;
+            index_writer2.delete_term(Term::from_field_u64(name, 3u64))?;
             // ok, now we should have a deleted doc
             index_writer2.commit()?;
         }
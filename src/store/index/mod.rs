@@ -141,7 +141,7 @@ mod tests {
             index_writer.add_document(doc!(text=>"testd", body=>long_text.clone()))?;
         }
         index_writer.commit()?;
-        index_writer.delete_term(Term::from_field_text(text, "testb"));
+        index_writer.delete_term(Term::from_field_text(text, "testb"))?;
         index_writer.commit()?;
         let segment_ids = index.searchable_segment_ids()?;
         index_writer.merge(&segment_ids).wait().unwrap();
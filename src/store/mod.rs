@@ -247,7 +247,7 @@ pub mod tests {
             index_writer.add_document(doc!(text_field=> "deletemenot"))?;
             index_writer.add_document(doc!(text_field=> "deleteme"))?;
 
-            index_writer.delete_term(Term::from_field_text(text_field, "deleteme"));
+            index_writer.delete_term(Term::from_field_text(text_field, "deleteme"))?;
             index_writer.commit()?;
         }
 
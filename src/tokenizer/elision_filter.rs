@@ -0,0 +1,155 @@
+//! # Example
+//! ```rust
+//! use tantivy::tokenizer::*;
+//!
+//! let tokenizer = TextAnalyzer::from(WhitespaceTokenizer)
+//!   .filter(LowerCaser)
+//!   .filter(ElisionFilter::default());
+//!
+//! let mut stream = tokenizer.token_stream("L'avion d'Air France");
+//! assert_eq!(stream.next().unwrap().text, "avion");
+//! assert_eq!(stream.next().unwrap().text, "air");
+//! assert_eq!(stream.next().unwrap().text, "france");
+//! assert!(stream.next().is_none());
+//! ```
+use std::sync::Arc;
+
+use rustc_hash::FxHashSet;
+
+use super::{BoxTokenStream, Token, TokenFilter, TokenStream};
+
+/// Elided articles removed by [`ElisionFilter::default()`].
+///
+/// This is the list of French articles used by Lucene's `FrenchAnalyzer`.
+const FRENCH_ARTICLES: [&str; 13] = [
+    "l", "m", "t", "qu", "n", "s", "j", "d", "c", "jusqu", "quoiqu", "lorsqu", "puisqu",
+];
+
+/// `ElisionFilter` removes elided articles from the beginning of tokens.
+/// (`l'avion` → `avion`)
+///
+/// Both the ASCII apostrophe `'` and the typographic apostrophe `’` are
+/// recognized. Articles are matched case-insensitively.
+///
+/// The tokenizer needs to keep apostrophes within tokens for this filter to
+/// have any effect. [`SimpleTokenizer`](super::SimpleTokenizer) for instance
+/// splits on apostrophes.
+#[derive(Clone)]
+pub struct ElisionFilter {
+    articles: Arc<FxHashSet<String>>,
+}
+
+impl ElisionFilter {
+    /// Creates an `ElisionFilter` removing the given articles.
+    pub fn from_articles<W: IntoIterator<Item = String>>(articles: W) -> ElisionFilter {
+        ElisionFilter {
+            articles: Arc::new(
+                articles
+                    .into_iter()
+                    .map(|article| article.to_lowercase())
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Default for ElisionFilter {
+    /// Creates an `ElisionFilter` for French articles.
+    fn default() -> ElisionFilter {
+        ElisionFilter::from_articles(FRENCH_ARTICLES.iter().map(|&article| article.to_string()))
+    }
+}
+
+impl TokenFilter for ElisionFilter {
+    fn transform<'a>(&self, token_stream: BoxTokenStream<'a>) -> BoxTokenStream<'a> {
+        BoxTokenStream::from(ElisionFilterStream {
+            articles: self.articles.clone(),
+            tail: token_stream,
+        })
+    }
+}
+
+pub struct ElisionFilterStream<'a> {
+    articles: Arc<FxHashSet<String>>,
+    tail: BoxTokenStream<'a>,
+}
+
+impl<'a> ElisionFilterStream<'a> {
+    // Returns the byte length of the elided article, including its apostrophe.
+    fn elision_len(&self, text: &str) -> Option<usize> {
+        let (apostrophe_pos, apostrophe) = text.char_indices().find(|(_, c)| is_apostrophe(*c))?;
+        let article = &text[..apostrophe_pos];
+        let elision_len = apostrophe_pos + apostrophe.len_utf8();
+        if elision_len == text.len() {
+            return None;
+        }
+        let is_article = if article.chars().all(char::is_lowercase) {
+            self.articles.contains(article)
+        } else {
+            self.articles.contains(&article.to_lowercase())
+        };
+        is_article.then_some(elision_len)
+    }
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '’'
+}
+
+impl<'a> TokenStream for ElisionFilterStream<'a> {
+    fn advance(&mut self) -> bool {
+        if !self.tail.advance() {
+            return false;
+        }
+        if let Some(elision_len) = self.elision_len(&self.tail.token().text) {
+            self.tail.token_mut().text.drain(..elision_len);
+        }
+        true
+    }
+
+    fn token(&self) -> &Token {
+        self.tail.token()
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.tail.token_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tokenizer::tests::assert_token;
+    use crate::tokenizer::{ElisionFilter, TextAnalyzer, Token, WhitespaceTokenizer};
+
+    fn token_stream_helper(text: &str) -> Vec<Token> {
+        let analyzer = TextAnalyzer::from(WhitespaceTokenizer).filter(ElisionFilter::default());
+        let mut token_stream = analyzer.token_stream(text);
+        let mut tokens: Vec<Token> = vec![];
+        let mut add_token = |token: &Token| {
+            tokens.push(token.clone());
+        };
+        token_stream.process(&mut add_token);
+        tokens
+    }
+
+    #[test]
+    fn test_elision() {
+        let tokens = token_stream_helper("l'avion L’Hôtel jusqu'ici aujourd'hui l'");
+        assert_eq!(tokens.len(), 5);
+        assert_token(&tokens[0], 0, "avion", 0, 7);
+        assert_token(&tokens[1], 1, "Hôtel", 8, 18);
+        assert_token(&tokens[2], 2, "ici", 19, 28);
+        assert_token(&tokens[3], 3, "aujourd'hui", 29, 40);
+        assert_token(&tokens[4], 4, "l'", 41, 43);
+    }
+
+    #[test]
+    fn test_elision_custom_articles() {
+        let analyzer = TextAnalyzer::from(WhitespaceTokenizer)
+            .filter(ElisionFilter::from_articles(vec!["dell".to_string()]));
+        let mut token_stream = analyzer.token_stream("dell'arte l'arte");
+        assert_eq!(token_stream.next().unwrap().text, "arte");
+        assert_eq!(token_stream.next().unwrap().text, "l'arte");
+        assert!(token_stream.next().is_none());
+    }
+}
@@ -0,0 +1,82 @@
+//! # Example
+//! ```rust
+//! use tantivy::tokenizer::*;
+//!
+//! let tokenizer = TextAnalyzer::from(WhitespaceTokenizer)
+//!   .filter(EnglishPossessiveFilter);
+//!
+//! let mut stream = tokenizer.token_stream("Paul's book");
+//! assert_eq!(stream.next().unwrap().text, "Paul");
+//! assert_eq!(stream.next().unwrap().text, "book");
+//! assert!(stream.next().is_none());
+//! ```
+use super::{BoxTokenStream, Token, TokenFilter, TokenStream};
+
+/// `EnglishPossessiveFilter` removes the trailing possessive `'s` from tokens.
+///
+/// Both the ASCII apostrophe `'` and the typographic apostrophe `’` are
+/// recognized.
+#[derive(Clone)]
+pub struct EnglishPossessiveFilter;
+
+impl TokenFilter for EnglishPossessiveFilter {
+    fn transform<'a>(&self, token_stream: BoxTokenStream<'a>) -> BoxTokenStream<'a> {
+        BoxTokenStream::from(EnglishPossessiveFilterStream { tail: token_stream })
+    }
+}
+
+pub struct EnglishPossessiveFilterStream<'a> {
+    tail: BoxTokenStream<'a>,
+}
+
+// Returns the byte length of the possessive suffix of `text`, if any.
+fn possessive_len(text: &str) -> Option<usize> {
+    ["'s", "'S", "’s", "’S"]
+        .iter()
+        .find(|suffix| text.len() > suffix.len() && text.ends_with(*suffix))
+        .map(|suffix| suffix.len())
+}
+
+impl<'a> TokenStream for EnglishPossessiveFilterStream<'a> {
+    fn advance(&mut self) -> bool {
+        if !self.tail.advance() {
+            return false;
+        }
+        let text = &mut self.tail.token_mut().text;
+        if let Some(possessive_len) = possessive_len(text) {
+            text.truncate(text.len() - possessive_len);
+        }
+        true
+    }
+
+    fn token(&self) -> &Token {
+        self.tail.token()
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.tail.token_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tokenizer::tests::assert_token;
+    use crate::tokenizer::{EnglishPossessiveFilter, TextAnalyzer, Token, WhitespaceTokenizer};
+
+    #[test]
+    fn test_english_possessive() {
+        let analyzer = TextAnalyzer::from(WhitespaceTokenizer).filter(EnglishPossessiveFilter);
+        let mut token_stream = analyzer.token_stream("JOHN'S dog’s 's it's boss");
+        let mut tokens: Vec<Token> = vec![];
+        let mut add_token = |token: &Token| {
+            tokens.push(token.clone());
+        };
+        token_stream.process(&mut add_token);
+        assert_eq!(tokens.len(), 5);
+        assert_token(&tokens[0], 0, "JOHN", 0, 6);
+        assert_token(&tokens[1], 1, "dog", 7, 14);
+        assert_token(&tokens[2], 2, "'s", 15, 17);
+        assert_token(&tokens[3], 3, "it", 18, 22);
+        assert_token(&tokens[4], 4, "boss", 23, 27);
+    }
+}
@@ -0,0 +1,145 @@
+//! # Example
+//! ```rust
+//! use tantivy::tokenizer::*;
+//!
+//! let tokenizer = TextAnalyzer::from(SimpleTokenizer)
+//!   .filter(LowerCaser)
+//!   .filter(GermanNormalizationFilter);
+//!
+//! let mut stream = tokenizer.token_stream("Müller Mueller Straße");
+//! assert_eq!(stream.next().unwrap().text, "muller");
+//! assert_eq!(stream.next().unwrap().text, "muller");
+//! assert_eq!(stream.next().unwrap().text, "strasse");
+//! assert!(stream.next().is_none());
+//! ```
+use std::mem;
+
+use super::{BoxTokenStream, Token, TokenFilter, TokenStream};
+
+/// `GermanNormalizationFilter` normalizes German umlauts and their
+/// common transliterations.
+///
+/// - `ä`, `ö` and `ü` are replaced by `a`, `o` and `u`.
+/// - `ae`, `oe` and `ue` are replaced by `a`, `o` and `u`, unless
+///   the `u` is part of another vowel sequence (`queue`, `aue`).
+/// - `ß` is replaced by `ss`.
+///
+/// This follows the heuristic of Lucene's `GermanNormalizationFilter`.
+/// Tokens are expected to be lowercased beforehand.
+#[derive(Clone)]
+pub struct GermanNormalizationFilter;
+
+impl TokenFilter for GermanNormalizationFilter {
+    fn transform<'a>(&self, token_stream: BoxTokenStream<'a>) -> BoxTokenStream<'a> {
+        BoxTokenStream::from(GermanNormalizationFilterStream {
+            tail: token_stream,
+            buffer: String::with_capacity(100),
+        })
+    }
+}
+
+pub struct GermanNormalizationFilterStream<'a> {
+    buffer: String,
+    tail: BoxTokenStream<'a>,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum State {
+    // Previous char is not a vowel that can be followed by a transliteration `e`.
+    Other,
+    // Previous char is a vowel that can be followed by a transliteration `e`.
+    Umlaut,
+    // Previous char is a vowel that cannot be followed by a transliteration `e`.
+    Vowel,
+}
+
+// writes a normalized version of text into output.
+fn normalize(text: &str, output: &mut String) {
+    output.clear();
+    let mut state = State::Other;
+    for c in text.chars() {
+        match c {
+            'a' | 'o' => {
+                output.push(c);
+                state = State::Umlaut;
+            }
+            'u' => {
+                output.push(c);
+                state = if state == State::Other {
+                    State::Umlaut
+                } else {
+                    State::Vowel
+                };
+            }
+            'e' => {
+                if state != State::Umlaut {
+                    output.push(c);
+                }
+                state = State::Vowel;
+            }
+            'i' | 'q' | 'y' => {
+                output.push(c);
+                state = State::Vowel;
+            }
+            'ä' | 'ö' | 'ü' => {
+                output.push(match c {
+                    'ä' => 'a',
+                    'ö' => 'o',
+                    _ => 'u',
+                });
+                state = State::Vowel;
+            }
+            'ß' => {
+                output.push_str("ss");
+                state = State::Other;
+            }
+            _ => {
+                output.push(c);
+                state = State::Other;
+            }
+        }
+    }
+}
+
+impl<'a> TokenStream for GermanNormalizationFilterStream<'a> {
+    fn advance(&mut self) -> bool {
+        if !self.tail.advance() {
+            return false;
+        }
+        normalize(&self.tail.token().text, &mut self.buffer);
+        mem::swap(&mut self.tail.token_mut().text, &mut self.buffer);
+        true
+    }
+
+    fn token(&self) -> &Token {
+        self.tail.token()
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.tail.token_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize;
+
+    fn normalized(text: &str) -> String {
+        let mut output = String::new();
+        normalize(text, &mut output);
+        output
+    }
+
+    #[test]
+    fn test_german_normalization() {
+        assert_eq!(normalized("schaltflächen"), "schaltflachen");
+        assert_eq!(normalized("schaltflaechen"), "schaltflachen");
+        assert_eq!(normalized("übersetzung"), "ubersetzung");
+        assert_eq!(normalized("uebersetzung"), "ubersetzung");
+        assert_eq!(normalized("göttingen"), "gottingen");
+        assert_eq!(normalized("goettingen"), "gottingen");
+        assert_eq!(normalized("weißbier"), "weissbier");
+        assert_eq!(normalized("queue"), "queue");
+        assert_eq!(normalized("blauer"), "blauer");
+    }
+}
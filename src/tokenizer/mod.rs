@@ -45,6 +45,17 @@
 //! Does not actual tokenizer your text. It keeps it entirely unprocessed.
 //! It can be useful to index uuids, or urls for instance.
 //!
+//! ## `lowercase`, `lowercase_ascii`
+//!
+//! Normalizers: like `raw`, they emit the whole text as a single token,
+//! but this token is lowercased (and, for `lowercase_ascii`, ascii folded).
+//! They are typically used on fast string fields, so that `US`, `us` and `Us`
+//! end up being the same term in the dictionary, and hence in the fast field,
+//! term aggregations, and the terms and ranges generated by the `QueryParser`.
+//!
+//! A custom normalizer can be defined with a [`Normalizer`], which only
+//! accepts filters transforming the token text, and always emits a single token.
+//!
 //! ## `en_stem`
 //!
 //! In addition to what `default` does, the `en_stem` tokenizer also
@@ -52,6 +63,18 @@
 //! remove their inflection. This tokenizer is slower than the default one,
 //! but is recommended to improve recall.
 //!
+//! ## `en_full`, `fr_full`, `de_full`
+//!
+//! Language specific analyzers. They chop the text on whitespaces and
+//! punctuation while keeping intra-word apostrophes, remove tokens that are
+//! longer than 40 chars, lowercase the text, remove stop words and apply stemming.
+//! - `en_full` also removes possessives (`John's` → `john`).
+//! - `fr_full` also removes elided articles (`l'avion` → `avion`).
+//! - `de_full` also normalizes umlauts (`Mueller`, `Müller` → `muller`).
+//!
+//! Stop words are only removed with the `stopwords` feature, which is enabled by default.
+//! Without it, these analyzers keep the stop words.
+//!
 //! # Custom tokenizer Library
 //! Avoid using tantivy as dependency and prefer `tantivy-tokenizer-api` instead.
 //!
@@ -121,10 +144,15 @@
 //! ```
 mod alphanum_only;
 mod ascii_folding_filter;
+mod elision_filter;
 mod empty_tokenizer;
+mod english_possessive_filter;
 mod facet_tokenizer;
+mod german_normalization_filter;
 mod lower_caser;
+mod ngram_filter;
 mod ngram_tokenizer;
+mod normalizer;
 mod raw_tokenizer;
 mod regex_tokenizer;
 mod remove_long;
@@ -136,6 +164,7 @@ mod tokenized_string;
 mod tokenizer;
 mod tokenizer_manager;
 mod whitespace_tokenizer;
+mod word_delimiter_filter;
 
 pub use tokenizer_api::{
     BoxTokenFilter, BoxTokenStream, Token, TokenFilter, TokenStream, Tokenizer,
@@ -143,9 +172,14 @@ pub use tokenizer_api::{
 
 pub use self::alphanum_only::AlphaNumOnlyFilter;
 pub use self::ascii_folding_filter::AsciiFoldingFilter;
+pub use self::elision_filter::ElisionFilter;
+pub use self::english_possessive_filter::EnglishPossessiveFilter;
 pub use self::facet_tokenizer::FacetTokenizer;
+pub use self::german_normalization_filter::GermanNormalizationFilter;
 pub use self::lower_caser::LowerCaser;
+pub use self::ngram_filter::{EdgeNgramFilter, NgramFilter};
 pub use self::ngram_tokenizer::NgramTokenizer;
+pub use self::normalizer::Normalizer;
 pub use self::raw_tokenizer::RawTokenizer;
 pub use self::regex_tokenizer::RegexTokenizer;
 pub use self::remove_long::RemoveLongFilter;
@@ -157,6 +191,7 @@ pub use self::tokenized_string::{PreTokenizedStream, PreTokenizedString};
 pub use self::tokenizer::TextAnalyzer;
 pub use self::tokenizer_manager::TokenizerManager;
 pub use self::whitespace_tokenizer::WhitespaceTokenizer;
+pub use self::word_delimiter_filter::WordDelimiterFilter;
 
 /// Maximum authorized len (in bytes) for a token.
 ///
@@ -232,6 +267,60 @@ pub mod tests {
         assert_token(&tokens[3], 3, "payer", 17, 22);
     }
 
+    #[test]
+    fn test_normalizers() {
+        let tokenizer_manager = TokenizerManager::default();
+        let normalize = |normalizer_name: &str, text: &str| -> Vec<Token> {
+            let normalizer = tokenizer_manager.get(normalizer_name).unwrap();
+            let mut tokens: Vec<Token> = vec![];
+            let mut add_token = |token: &Token| {
+                tokens.push(token.clone());
+            };
+            normalizer.token_stream(text).process(&mut add_token);
+            tokens
+        };
+
+        let tokens = normalize("lowercase", "Crème Brûlée");
+        assert_eq!(tokens.len(), 1);
+        assert_token(&tokens[0], 0, "crème brûlée", 0, 15);
+
+        let tokens = normalize("lowercase_ascii", "Crème Brûlée");
+        assert_eq!(tokens.len(), 1);
+        assert_token(&tokens[0], 0, "creme brulee", 0, 15);
+    }
+
+    #[cfg(feature = "stopwords")]
+    #[test]
+    fn test_language_analyzers() {
+        let tokenizer_manager = TokenizerManager::default();
+        let analyze = |tokenizer_name: &str, text: &str| -> Vec<Token> {
+            let analyzer = tokenizer_manager.get(tokenizer_name).unwrap();
+            let mut tokens: Vec<Token> = vec![];
+            let mut add_token = |token: &Token| {
+                tokens.push(token.clone());
+            };
+            analyzer.token_stream(text).process(&mut add_token);
+            tokens
+        };
+
+        let tokens = analyze("en_full", "The taxpayer's happy payers");
+        assert_eq!(tokens.len(), 3);
+        assert_token(&tokens[0], 1, "taxpay", 4, 14);
+        assert_token(&tokens[1], 2, "happi", 15, 20);
+        assert_token(&tokens[2], 3, "payer", 21, 27);
+
+        let tokens = analyze("fr_full", "L'avion d’Air France");
+        assert_eq!(tokens.len(), 3);
+        assert_token(&tokens[0], 0, "avion", 0, 7);
+        assert_token(&tokens[1], 1, "air", 8, 15);
+        assert_token(&tokens[2], 2, "franc", 16, 22);
+
+        let tokens = analyze("de_full", "Die Müller, die Mueller");
+        assert_eq!(tokens.len(), 2);
+        assert_token(&tokens[0], 1, "mull", 4, 11);
+        assert_token(&tokens[1], 3, "mull", 17, 24);
+    }
+
     #[test]
     fn test_non_en_tokenizer() {
         let tokenizer_manager = TokenizerManager::default();
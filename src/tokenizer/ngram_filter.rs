@@ -0,0 +1,249 @@
+//! # Example
+//! ```rust
+//! use tantivy::tokenizer::*;
+//!
+//! let tokenizer = TextAnalyzer::from(SimpleTokenizer)
+//!   .filter(LowerCaser)
+//!   .filter(AsciiFoldingFilter)
+//!   .filter(EdgeNgramFilter::new(2, 4));
+//!
+//! let mut stream = tokenizer.token_stream("Crème brûlée");
+//! assert_eq!(stream.next().unwrap().text, "cr");
+//! assert_eq!(stream.next().unwrap().text, "cre");
+//! assert_eq!(stream.next().unwrap().text, "crem");
+//! assert_eq!(stream.next().unwrap().text, "br");
+//! assert_eq!(stream.next().unwrap().text, "bru");
+//! assert_eq!(stream.next().unwrap().text, "brul");
+//! assert!(stream.next().is_none());
+//! ```
+use super::{BoxTokenStream, Token, TokenFilter, TokenStream};
+
+/// `NgramFilter` replaces each token by all of its n-grams of the given size(s).
+///
+/// Contrary to the [`NgramTokenizer`](super::NgramTokenizer), it can be applied after
+/// other filters. All of the n-grams of a token are emitted at the position of
+/// this token, and keep its offsets.
+/// Tokens shorter than `min_gram` are removed.
+///
+/// `hello` would be filtered as (min_gram: 2, max_gram: 3)
+///
+/// | Term     | he  | hel | el  | ell | ll  | llo | lo |
+/// |----------|-----|-----|-----|-----|-----|-----|----|
+/// | Position | 0   | 0   | 0   | 0   | 0   | 0   | 0  |
+/// | Offsets  | 0,5 | 0,5 | 0,5 | 0,5 | 0,5 | 0,5 | 0,5|
+#[derive(Clone)]
+pub struct NgramFilter {
+    min_gram: usize,
+    max_gram: usize,
+}
+
+impl NgramFilter {
+    /// Creates a `NgramFilter` emitting n-grams of `min_gram` to `max_gram` chars.
+    pub fn new(min_gram: usize, max_gram: usize) -> NgramFilter {
+        check_gram_sizes(min_gram, max_gram);
+        NgramFilter { min_gram, max_gram }
+    }
+}
+
+impl TokenFilter for NgramFilter {
+    fn transform<'a>(&self, token_stream: BoxTokenStream<'a>) -> BoxTokenStream<'a> {
+        BoxTokenStream::from(NgramFilterStream::new(
+            token_stream,
+            self.min_gram,
+            self.max_gram,
+            false,
+        ))
+    }
+}
+
+/// `EdgeNgramFilter` replaces each token by its prefixes of the given size(s).
+///
+/// It is typically used for autocomplete, after lowercasing and ascii folding
+/// the words emitted by a tokenizer. All of the prefixes of a token are emitted
+/// at the position of this token, and keep its offsets.
+/// Tokens shorter than `min_gram` are removed.
+///
+/// `hello` would be filtered as (min_gram: 2, max_gram: 5)
+///
+/// | Term     | he  | hel | hell  | hello |
+/// |----------|-----|-----|-------|-------|
+/// | Position | 0   | 0   | 0     | 0     |
+/// | Offsets  | 0,5 | 0,5 | 0,5   | 0,5   |
+#[derive(Clone)]
+pub struct EdgeNgramFilter {
+    min_gram: usize,
+    max_gram: usize,
+}
+
+impl EdgeNgramFilter {
+    /// Creates a `EdgeNgramFilter` emitting prefixes of `min_gram` to `max_gram` chars.
+    pub fn new(min_gram: usize, max_gram: usize) -> EdgeNgramFilter {
+        check_gram_sizes(min_gram, max_gram);
+        EdgeNgramFilter { min_gram, max_gram }
+    }
+}
+
+impl TokenFilter for EdgeNgramFilter {
+    fn transform<'a>(&self, token_stream: BoxTokenStream<'a>) -> BoxTokenStream<'a> {
+        BoxTokenStream::from(NgramFilterStream::new(
+            token_stream,
+            self.min_gram,
+            self.max_gram,
+            true,
+        ))
+    }
+}
+
+fn check_gram_sizes(min_gram: usize, max_gram: usize) {
+    assert!(min_gram > 0, "min_gram must be greater than 0");
+    assert!(
+        min_gram <= max_gram,
+        "min_gram must not be greater than max_gram"
+    );
+}
+
+/// TokenStream associated to the `NgramFilter` and the `EdgeNgramFilter`.
+pub struct NgramFilterStream<'a> {
+    tail: BoxTokenStream<'a>,
+    min_gram: usize,
+    max_gram: usize,
+    prefix_only: bool,
+    /// Byte offsets of the char boundaries of the current tail token.
+    char_boundaries: Vec<usize>,
+    /// Index, in `char_boundaries`, of the start of the next gram.
+    start: usize,
+    /// Length, in chars, of the next gram.
+    gram_len: usize,
+    token: Token,
+}
+
+impl<'a> NgramFilterStream<'a> {
+    fn new(
+        tail: BoxTokenStream<'a>,
+        min_gram: usize,
+        max_gram: usize,
+        prefix_only: bool,
+    ) -> NgramFilterStream<'a> {
+        NgramFilterStream {
+            tail,
+            min_gram,
+            max_gram,
+            prefix_only,
+            char_boundaries: Vec::new(),
+            start: 0,
+            gram_len: min_gram,
+            token: Token::default(),
+        }
+    }
+
+    fn reset(&mut self) {
+        let text = &self.tail.token().text;
+        self.char_boundaries.clear();
+        self.char_boundaries
+            .extend(text.char_indices().map(|(offset, _)| offset));
+        self.char_boundaries.push(text.len());
+        self.start = 0;
+        self.gram_len = self.min_gram;
+    }
+
+    // Returns the byte range of the next gram of the current tail token.
+    fn next_gram(&mut self) -> Option<(usize, usize)> {
+        let num_chars = self.char_boundaries.len().saturating_sub(1);
+        while self.start + self.min_gram <= num_chars && (!self.prefix_only || self.start == 0) {
+            if self.gram_len <= self.max_gram && self.start + self.gram_len <= num_chars {
+                let gram = (
+                    self.char_boundaries[self.start],
+                    self.char_boundaries[self.start + self.gram_len],
+                );
+                self.gram_len += 1;
+                return Some(gram);
+            }
+            self.start += 1;
+            self.gram_len = self.min_gram;
+        }
+        None
+    }
+}
+
+impl<'a> TokenStream for NgramFilterStream<'a> {
+    fn advance(&mut self) -> bool {
+        loop {
+            if let Some((from, to)) = self.next_gram() {
+                let tail_token = self.tail.token();
+                self.token.offset_from = tail_token.offset_from;
+                self.token.offset_to = tail_token.offset_to;
+                self.token.position = tail_token.position;
+                self.token.position_length = tail_token.position_length;
+                self.token.text.clear();
+                self.token.text.push_str(&tail_token.text[from..to]);
+                return true;
+            }
+            if !self.tail.advance() {
+                return false;
+            }
+            self.reset();
+        }
+    }
+
+    fn token(&self) -> &Token {
+        &self.token
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tokenizer::tests::assert_token;
+    use crate::tokenizer::{
+        BoxTokenFilter, EdgeNgramFilter, NgramFilter, SimpleTokenizer, TextAnalyzer, Token,
+    };
+
+    fn token_stream_helper(text: &str, filter: impl Into<BoxTokenFilter>) -> Vec<Token> {
+        let analyzer = TextAnalyzer::from(SimpleTokenizer).filter(filter);
+        let mut token_stream = analyzer.token_stream(text);
+        let mut tokens: Vec<Token> = vec![];
+        let mut add_token = |token: &Token| {
+            tokens.push(token.clone());
+        };
+        token_stream.process(&mut add_token);
+        tokens
+    }
+
+    #[test]
+    fn test_edge_ngram_filter() {
+        let tokens = token_stream_helper("a hεllo you", EdgeNgramFilter::new(2, 4));
+        assert_eq!(tokens.len(), 5);
+        assert_token(&tokens[0], 1, "hε", 2, 8);
+        assert_token(&tokens[1], 1, "hεl", 2, 8);
+        assert_token(&tokens[2], 1, "hεll", 2, 8);
+        assert_token(&tokens[3], 2, "yo", 9, 12);
+        assert_token(&tokens[4], 2, "you", 9, 12);
+    }
+
+    #[test]
+    fn test_ngram_filter() {
+        let tokens = token_stream_helper("hello hi", NgramFilter::new(2, 3));
+        let texts: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+        assert_eq!(texts, &["he", "hel", "el", "ell", "ll", "llo", "lo", "hi"]);
+        assert!(tokens[..7]
+            .iter()
+            .all(|token| token.position == 0 && token.offset_to == 5));
+        assert_token(&tokens[7], 1, "hi", 6, 8);
+    }
+
+    #[test]
+    fn test_ngram_filter_single_size() {
+        let tokens = token_stream_helper("abc", NgramFilter::new(1, 1));
+        let texts: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+        assert_eq!(texts, &["a", "b", "c"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "min_gram must not be greater than max_gram")]
+    fn test_edge_ngram_filter_invalid_sizes() {
+        EdgeNgramFilter::new(3, 2);
+    }
+}
@@ -0,0 +1,128 @@
+use super::{BoxTokenFilter, RawTokenizer, TextAnalyzer, Token, TokenFilter, TokenStream};
+use crate::tokenizer::BoxTokenStream;
+
+/// A `Normalizer` is a [`TextAnalyzer`] restricted to single-token output.
+///
+/// Like the [`RawTokenizer`], it emits the whole text as a single token,
+/// and its filters may only transform the text of this token
+/// (lowercasing, ascii folding, ...).
+/// It is typically used on raw-indexed and fast string fields, to make terms,
+/// term aggregations and range queries case-insensitive.
+///
+/// If a filter emits several tokens, only the first one is kept.
+///
+/// ```rust
+/// use tantivy::tokenizer::*;
+///
+/// let normalizer: TextAnalyzer = Normalizer::new()
+///     .filter(LowerCaser)
+///     .filter(AsciiFoldingFilter)
+///     .into();
+/// let mut token_stream = normalizer.token_stream("Crème Brûlée");
+/// assert!(token_stream.advance());
+/// assert_eq!(token_stream.token().text, "creme brulee");
+/// assert!(!token_stream.advance());
+/// ```
+#[derive(Clone)]
+pub struct Normalizer {
+    text_analyzer: TextAnalyzer,
+}
+
+impl Default for Normalizer {
+    fn default() -> Normalizer {
+        Normalizer {
+            text_analyzer: TextAnalyzer::from(RawTokenizer),
+        }
+    }
+}
+
+impl Normalizer {
+    /// Creates a new `Normalizer`, emitting the text unchanged.
+    pub fn new() -> Normalizer {
+        Normalizer::default()
+    }
+
+    /// Appends a token filter to the current normalizer.
+    #[must_use]
+    pub fn filter<F: Into<BoxTokenFilter>>(self, token_filter: F) -> Self {
+        Normalizer {
+            text_analyzer: self.text_analyzer.filter(token_filter),
+        }
+    }
+}
+
+impl From<Normalizer> for TextAnalyzer {
+    fn from(normalizer: Normalizer) -> TextAnalyzer {
+        normalizer.text_analyzer.filter(SingleTokenFilter)
+    }
+}
+
+/// Token filter only keeping the first token of the stream.
+#[derive(Clone)]
+struct SingleTokenFilter;
+
+impl TokenFilter for SingleTokenFilter {
+    fn transform<'a>(&self, token_stream: BoxTokenStream<'a>) -> BoxTokenStream<'a> {
+        BoxTokenStream::from(SingleTokenStream {
+            tail: token_stream,
+            consumed: false,
+        })
+    }
+}
+
+struct SingleTokenStream<'a> {
+    tail: BoxTokenStream<'a>,
+    consumed: bool,
+}
+
+impl<'a> TokenStream for SingleTokenStream<'a> {
+    fn advance(&mut self) -> bool {
+        if self.consumed {
+            return false;
+        }
+        self.consumed = true;
+        self.tail.advance()
+    }
+
+    fn token(&self) -> &Token {
+        self.tail.token()
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.tail.token_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tokenizer::tests::assert_token;
+    use crate::tokenizer::{LowerCaser, Normalizer, TextAnalyzer, Token, WordDelimiterFilter};
+
+    fn normalize(normalizer: Normalizer, text: &str) -> Vec<Token> {
+        let analyzer = TextAnalyzer::from(normalizer);
+        let mut tokens: Vec<Token> = vec![];
+        analyzer
+            .token_stream(text)
+            .process(&mut |token: &Token| tokens.push(token.clone()));
+        tokens
+    }
+
+    #[test]
+    fn test_normalizer() {
+        let tokens = normalize(Normalizer::new().filter(LowerCaser), "Hello World");
+        assert_eq!(tokens.len(), 1);
+        assert_token(&tokens[0], 0, "hello world", 0, 11);
+    }
+
+    #[test]
+    fn test_normalizer_emits_a_single_token() {
+        let tokens = normalize(
+            Normalizer::new()
+                .filter(WordDelimiterFilter::default())
+                .filter(LowerCaser),
+            "Wi-Fi",
+        );
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].text, "wi");
+    }
+}
@@ -1,18 +1,35 @@
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
+use once_cell::sync::Lazy;
+
 use crate::tokenizer::stemmer::Language;
 use crate::tokenizer::tokenizer::TextAnalyzer;
+#[cfg(feature = "stopwords")]
+use crate::tokenizer::StopWordFilter;
 use crate::tokenizer::{
-    LowerCaser, RawTokenizer, RemoveLongFilter, SimpleTokenizer, Stemmer, WhitespaceTokenizer,
+    AsciiFoldingFilter, ElisionFilter, EnglishPossessiveFilter, GermanNormalizationFilter,
+    LowerCaser, Normalizer, RawTokenizer, RegexTokenizer, RemoveLongFilter, SimpleTokenizer,
+    Stemmer, WhitespaceTokenizer,
 };
 
+/// Splits the text on whitespaces and punctuation like `SimpleTokenizer`,
+/// but keeps apostrophes within words (`l'avion`, `John's`), so that
+/// they can be handled by language specific filters.
+static WORD_TOKENIZER: Lazy<RegexTokenizer> = Lazy::new(|| {
+    RegexTokenizer::new(r"[\p{Alphabetic}\p{N}]+(?:['’][\p{Alphabetic}\p{N}]+)*")
+        .expect("The word tokenizer regex should be valid")
+});
+
 /// The tokenizer manager serves as a store for
 /// all of the pre-configured tokenizer pipelines.
 ///
 /// By default, it is populated with the following managers.
 ///
 ///  * `raw` : does not process nor tokenize the text.
+///  * `lowercase` : normalizer emitting the whole text as a single lowercased token.
+///  * `lowercase_ascii` : Like `lowercase`, but also folds non-ASCII chars to
+///    their ASCII equivalent.
 ///  * `default` : Chops the text on according to whitespace and
 ///  punctuation, removes tokens that are too long, and lowercases
 ///  tokens
@@ -20,6 +37,14 @@ use crate::tokenizer::{
 ///  resulting tokens. Stemming can improve the recall of your
 ///  search engine.
 /// * `whitespace` : Splits the text on whitespaces.
+/// * `en_full` : Like `en_stem`, but keeps apostrophes within words,
+///   removes English possessives and stop words.
+/// * `fr_full` : French analyzer removing elided articles (`l'`, `d'`...),
+///   stop words, and applying French stemming.
+/// * `de_full` : German analyzer removing stop words, normalizing
+///   umlauts, and applying German stemming.
+///
+/// The `*_full` analyzers only remove stop words if the `stopwords` feature is enabled.
 #[derive(Clone)]
 pub struct TokenizerManager {
     tokenizers: Arc<RwLock<HashMap<String, TextAnalyzer>>>,
@@ -59,6 +84,13 @@ impl Default for TokenizerManager {
     fn default() -> TokenizerManager {
         let manager = TokenizerManager::new();
         manager.register("raw", RawTokenizer);
+        manager.register("lowercase", Normalizer::new().filter(LowerCaser));
+        manager.register(
+            "lowercase_ascii",
+            Normalizer::new()
+                .filter(LowerCaser)
+                .filter(AsciiFoldingFilter),
+        );
         manager.register(
             "default",
             TextAnalyzer::from(SimpleTokenizer)
@@ -73,6 +105,54 @@ impl Default for TokenizerManager {
                 .filter(Stemmer::new(Language::English)),
         );
         manager.register("whitespace", WhitespaceTokenizer);
+        manager.register(
+            "en_full",
+            remove_stop_words(
+                TextAnalyzer::from(WORD_TOKENIZER.clone())
+                    .filter(RemoveLongFilter::limit(40))
+                    .filter(EnglishPossessiveFilter)
+                    .filter(LowerCaser),
+                Language::English,
+            )
+            .filter(Stemmer::new(Language::English)),
+        );
+        manager.register(
+            "fr_full",
+            remove_stop_words(
+                TextAnalyzer::from(WORD_TOKENIZER.clone())
+                    .filter(RemoveLongFilter::limit(40))
+                    .filter(LowerCaser)
+                    .filter(ElisionFilter::default()),
+                Language::French,
+            )
+            .filter(Stemmer::new(Language::French)),
+        );
+        manager.register(
+            "de_full",
+            remove_stop_words(
+                TextAnalyzer::from(WORD_TOKENIZER.clone())
+                    .filter(RemoveLongFilter::limit(40))
+                    .filter(LowerCaser),
+                Language::German,
+            )
+            .filter(GermanNormalizationFilter)
+            .filter(Stemmer::new(Language::German)),
+        );
         manager
     }
 }
+
+/// Appends the stop word filter of `language`, if there is one.
+#[cfg(feature = "stopwords")]
+fn remove_stop_words(text_analyzer: TextAnalyzer, language: Language) -> TextAnalyzer {
+    match StopWordFilter::new(language) {
+        Some(stop_word_filter) => text_analyzer.filter(stop_word_filter),
+        None => text_analyzer,
+    }
+}
+
+/// Does nothing: stop word lists are only available with the `stopwords` feature.
+#[cfg(not(feature = "stopwords"))]
+fn remove_stop_words(text_analyzer: TextAnalyzer, _language: Language) -> TextAnalyzer {
+    text_analyzer
+}
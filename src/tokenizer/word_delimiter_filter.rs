@@ -0,0 +1,355 @@
+//! # Example
+//! ```rust
+//! use tantivy::tokenizer::*;
+//!
+//! let tokenizer = TextAnalyzer::from(WhitespaceTokenizer)
+//!   .filter(WordDelimiterFilter::default())
+//!   .filter(LowerCaser);
+//!
+//! let mut stream = tokenizer.token_stream("PowerShot2000-X getUserName");
+//! assert_eq!(stream.next().unwrap().text, "power");
+//! assert_eq!(stream.next().unwrap().text, "shot");
+//! assert_eq!(stream.next().unwrap().text, "2000");
+//! assert_eq!(stream.next().unwrap().text, "x");
+//! assert_eq!(stream.next().unwrap().text, "get");
+//! assert_eq!(stream.next().unwrap().text, "user");
+//! assert_eq!(stream.next().unwrap().text, "name");
+//! assert!(stream.next().is_none());
+//! ```
+use super::{BoxTokenStream, Token, TokenFilter, TokenStream};
+
+/// `WordDelimiterFilter` splits tokens into subwords on case changes,
+/// letter/digit transitions and intra-word punctuation.
+///
+/// `PowerShot2000-X` becomes `Power`, `Shot`, `2000`, `X`, and
+/// `getUserName` becomes `get`, `User`, `Name`. Runs of uppercase letters
+/// are kept together, so that `HTTPServer` becomes `HTTP`, `Server`.
+///
+/// Subwords are emitted at consecutive positions, and the positions of the
+/// following tokens are shifted accordingly.
+/// Optionally, the original token and the concatenation of all its subwords
+/// can be emitted as well. They are then emitted at the position of the first
+/// subword, with a `position_length` spanning all of the subwords.
+/// The [`QueryParser`](crate::query::QueryParser) searches each of these
+/// alternatives as a separate phrase.
+///
+/// This filter is typically placed right after a [`WhitespaceTokenizer`](super::WhitespaceTokenizer)
+/// and before the [`LowerCaser`](super::LowerCaser).
+#[derive(Clone, Debug)]
+pub struct WordDelimiterFilter {
+    split_on_case_change: bool,
+    split_on_numerics: bool,
+    preserve_original: bool,
+    catenate_all: bool,
+}
+
+impl Default for WordDelimiterFilter {
+    /// Creates a `WordDelimiterFilter` that splits on case changes,
+    /// on letter/digit transitions and on punctuation, and
+    /// only emits the subwords.
+    fn default() -> WordDelimiterFilter {
+        WordDelimiterFilter {
+            split_on_case_change: true,
+            split_on_numerics: true,
+            preserve_original: false,
+            catenate_all: false,
+        }
+    }
+}
+
+impl WordDelimiterFilter {
+    /// Sets whether tokens should be split on lowercase to uppercase transitions.
+    /// (`getUserName` → `get`, `User`, `Name`)
+    #[must_use]
+    pub fn split_on_case_change(mut self, split_on_case_change: bool) -> WordDelimiterFilter {
+        self.split_on_case_change = split_on_case_change;
+        self
+    }
+
+    /// Sets whether tokens should be split on letter/digit transitions.
+    /// (`PowerShot2000` → `PowerShot`, `2000`)
+    #[must_use]
+    pub fn split_on_numerics(mut self, split_on_numerics: bool) -> WordDelimiterFilter {
+        self.split_on_numerics = split_on_numerics;
+        self
+    }
+
+    /// Sets whether the original token should be emitted in addition to its subwords.
+    #[must_use]
+    pub fn preserve_original(mut self, preserve_original: bool) -> WordDelimiterFilter {
+        self.preserve_original = preserve_original;
+        self
+    }
+
+    /// Sets whether the concatenation of all subwords should be emitted in addition
+    /// to the subwords. (`Power-Shot` → `PowerShot`)
+    #[must_use]
+    pub fn catenate_all(mut self, catenate_all: bool) -> WordDelimiterFilter {
+        self.catenate_all = catenate_all;
+        self
+    }
+}
+
+impl TokenFilter for WordDelimiterFilter {
+    fn transform<'a>(&self, token_stream: BoxTokenStream<'a>) -> BoxTokenStream<'a> {
+        BoxTokenStream::from(WordDelimiterTokenStream {
+            config: self.clone(),
+            tail: token_stream,
+            position_shift: 0,
+            cuts: Vec::new(),
+            parts: Vec::new(),
+            token: Token::default(),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum CharType {
+    Lower,
+    Upper,
+    Digit,
+    Delimiter,
+}
+
+impl CharType {
+    fn of(c: char) -> CharType {
+        if c.is_numeric() {
+            CharType::Digit
+        } else if c.is_uppercase() {
+            CharType::Upper
+        } else if c.is_alphabetic() {
+            CharType::Lower
+        } else {
+            CharType::Delimiter
+        }
+    }
+
+    fn is_alpha(self) -> bool {
+        matches!(self, CharType::Lower | CharType::Upper)
+    }
+}
+
+impl WordDelimiterFilter {
+    // Appends the byte ranges of the subwords of `text` to `cuts`.
+    fn split(&self, text: &str, cuts: &mut Vec<(usize, usize)>) {
+        cuts.clear();
+        let mut chars = text
+            .char_indices()
+            .map(|(offset, c)| (offset, CharType::of(c)))
+            .peekable();
+        let mut start: Option<usize> = None;
+        let mut previous = CharType::Delimiter;
+        while let Some((offset, current)) = chars.next() {
+            if current == CharType::Delimiter {
+                if let Some(start) = start.take() {
+                    cuts.push((start, offset));
+                }
+                previous = current;
+                continue;
+            }
+            let is_boundary = match (previous, current) {
+                (CharType::Delimiter, _) => false,
+                (CharType::Lower, CharType::Upper) => self.split_on_case_change,
+                // The last letter of a run of uppercase letters followed by
+                // a lowercase letter starts a new subword. (`HTTPServer`)
+                (CharType::Upper, CharType::Upper) => {
+                    self.split_on_case_change && matches!(chars.peek(), Some((_, CharType::Lower)))
+                }
+                (CharType::Digit, next) | (next, CharType::Digit) if next.is_alpha() => {
+                    self.split_on_numerics
+                }
+                _ => false,
+            };
+            if is_boundary {
+                if let Some(start) = start.take() {
+                    cuts.push((start, offset));
+                }
+            }
+            if start.is_none() {
+                start = Some(offset);
+            }
+            previous = current;
+        }
+        if let Some(start) = start {
+            cuts.push((start, text.len()));
+        }
+    }
+}
+
+struct WordDelimiterTokenStream<'a> {
+    config: WordDelimiterFilter,
+    tail: BoxTokenStream<'a>,
+    position_shift: usize,
+    cuts: Vec<(usize, usize)>,
+    // Pending tokens, in reverse order.
+    parts: Vec<Token>,
+    token: Token,
+}
+
+impl<'a> WordDelimiterTokenStream<'a> {
+    // Fills `self.parts` with the tokens derived from `self.tail.token()`.
+    fn split(&mut self) {
+        let token = self.tail.token();
+        let position = token.position + self.position_shift;
+        self.config.split(&token.text, &mut self.cuts);
+
+        if self.cuts.len() == 1 && self.cuts[0] == (0, token.text.len()) {
+            self.parts.push(Token {
+                position,
+                ..token.clone()
+            });
+            return;
+        }
+
+        // Offsets can only be derived from the subwords if no
+        // previous filter altered the length of the text.
+        let has_raw_offsets = token.offset_to - token.offset_from == token.text.len();
+        let num_parts = self.cuts.len();
+        for (ord, &(from, to)) in self.cuts.iter().enumerate().rev() {
+            let (offset_from, offset_to) = if has_raw_offsets {
+                (token.offset_from + from, token.offset_from + to)
+            } else {
+                (token.offset_from, token.offset_to)
+            };
+            self.parts.push(Token {
+                offset_from,
+                offset_to,
+                position: position + ord,
+                text: token.text[from..to].to_string(),
+                position_length: 1,
+            });
+        }
+        if self.config.catenate_all && num_parts > 1 {
+            let catenated: String = self
+                .cuts
+                .iter()
+                .map(|&(from, to)| &token.text[from..to])
+                .collect();
+            self.parts.push(Token {
+                position,
+                text: catenated,
+                position_length: num_parts,
+                ..token.clone()
+            });
+        }
+        if self.config.preserve_original {
+            self.parts.push(Token {
+                position,
+                position_length: num_parts.max(1),
+                ..token.clone()
+            });
+        }
+        self.position_shift += num_parts.saturating_sub(1);
+    }
+}
+
+impl<'a> TokenStream for WordDelimiterTokenStream<'a> {
+    fn advance(&mut self) -> bool {
+        loop {
+            if let Some(token) = self.parts.pop() {
+                self.token = token;
+                return true;
+            }
+            if !self.tail.advance() {
+                return false;
+            }
+            self.split();
+        }
+    }
+
+    fn token(&self) -> &Token {
+        &self.token
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tokenizer::tests::assert_token;
+    use crate::tokenizer::{
+        LowerCaser, TextAnalyzer, Token, WhitespaceTokenizer, WordDelimiterFilter,
+    };
+
+    fn token_stream_helper(text: &str, filter: WordDelimiterFilter) -> Vec<Token> {
+        let analyzer = TextAnalyzer::from(WhitespaceTokenizer).filter(filter);
+        let mut token_stream = analyzer.token_stream(text);
+        let mut tokens: Vec<Token> = vec![];
+        let mut add_token = |token: &Token| {
+            tokens.push(token.clone());
+        };
+        token_stream.process(&mut add_token);
+        tokens
+    }
+
+    #[test]
+    fn test_word_delimiter_default() {
+        let tokens = token_stream_helper("PowerShot2000-X is fine", WordDelimiterFilter::default());
+        assert_eq!(tokens.len(), 6);
+        assert_token(&tokens[0], 0, "Power", 0, 5);
+        assert_token(&tokens[1], 1, "Shot", 5, 9);
+        assert_token(&tokens[2], 2, "2000", 9, 13);
+        assert_token(&tokens[3], 3, "X", 14, 15);
+        assert_token(&tokens[4], 4, "is", 16, 18);
+        assert_token(&tokens[5], 5, "fine", 19, 23);
+    }
+
+    #[test]
+    fn test_word_delimiter_case_change() {
+        let tokens = token_stream_helper("getUserName HTTPServer", WordDelimiterFilter::default());
+        assert_eq!(tokens.len(), 5);
+        assert_token(&tokens[0], 0, "get", 0, 3);
+        assert_token(&tokens[1], 1, "User", 3, 7);
+        assert_token(&tokens[2], 2, "Name", 7, 11);
+        assert_token(&tokens[3], 3, "HTTP", 12, 16);
+        assert_token(&tokens[4], 4, "Server", 16, 22);
+    }
+
+    #[test]
+    fn test_word_delimiter_disabled_splits() {
+        let filter = WordDelimiterFilter::default()
+            .split_on_case_change(false)
+            .split_on_numerics(false);
+        let tokens = token_stream_helper("PowerShot2000-X", filter);
+        assert_eq!(tokens.len(), 2);
+        assert_token(&tokens[0], 0, "PowerShot2000", 0, 13);
+        assert_token(&tokens[1], 1, "X", 14, 15);
+    }
+
+    #[test]
+    fn test_word_delimiter_only_delimiters() {
+        let tokens = token_stream_helper("a -- b", WordDelimiterFilter::default());
+        assert_eq!(tokens.len(), 2);
+        assert_token(&tokens[0], 0, "a", 0, 1);
+        assert_token(&tokens[1], 2, "b", 5, 6);
+    }
+
+    #[test]
+    fn test_word_delimiter_preserve_original_and_catenate() {
+        let filter = WordDelimiterFilter::default()
+            .preserve_original(true)
+            .catenate_all(true);
+        let tokens = token_stream_helper("wi-fi on", filter);
+        assert_eq!(tokens.len(), 5);
+        assert_token(&tokens[0], 0, "wi-fi", 0, 5);
+        assert_eq!(tokens[0].position_length, 2);
+        assert_token(&tokens[1], 0, "wifi", 0, 5);
+        assert_eq!(tokens[1].position_length, 2);
+        assert_token(&tokens[2], 0, "wi", 0, 2);
+        assert_token(&tokens[3], 1, "fi", 3, 5);
+        assert_token(&tokens[4], 2, "on", 6, 8);
+    }
+
+    #[test]
+    fn test_word_delimiter_after_lowercaser_keeps_offsets() {
+        let analyzer = TextAnalyzer::from(WhitespaceTokenizer)
+            .filter(LowerCaser)
+            .filter(WordDelimiterFilter::default());
+        let mut token_stream = analyzer.token_stream("user_name");
+        assert_token(token_stream.next().unwrap(), 0, "user", 0, 4);
+        assert_token(token_stream.next().unwrap(), 1, "name", 5, 9);
+        assert!(token_stream.next().is_none());
+    }
+}
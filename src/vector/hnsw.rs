@@ -0,0 +1,486 @@
+//! Hierarchical Navigable Small World graph, as described in
+//! "Efficient and robust approximate nearest neighbor search using Hierarchical
+//! Navigable Small World graphs" by Malkov and Yashunin.
+//!
+//! The graph is built once all of the vectors of a segment are known, that is when the
+//! segment is serialized or merged. Nodes are identified by the ordinal of their vector.
+//!
+//! # Format
+//!
+//! - entry point: `u32` (`u32::MAX` if the graph is empty)
+//! - top layer: `u32`
+//! - for each layer, from the bottom one:
+//!   - number of nodes: `u32`
+//!   - nodes: `u32` ordinals (omitted for the bottom layer, which contains every node)
+//!   - neighbours offsets: `num_nodes + 1` times `u32`
+//!   - neighbours: `u32` ordinals
+//!
+//! All integers are little endian.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::io::{self, Write};
+
+use common::BinarySerializable;
+
+use crate::directory::OwnedBytes;
+use crate::Score;
+
+/// Maximum number of neighbours of a node in the upper layers.
+/// The bottom layer allows twice as many.
+const MAX_NEIGHBOURS: usize = 16;
+/// Number of candidates considered when inserting a node.
+const EF_CONSTRUCTION: usize = 100;
+const MAX_LAYER: usize = 16;
+
+/// A node, along with its similarity to the searched vector.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Candidate {
+    pub similarity: Score,
+    pub node: u32,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Candidate) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Candidate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Candidate) -> Ordering {
+        self.similarity
+            .total_cmp(&other.similarity)
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+/// Read access to the layers of a HNSW graph.
+pub(crate) trait HnswGraph {
+    fn num_nodes(&self) -> u32;
+
+    /// Returns the entry point of the graph along with its top layer.
+    fn entry_point(&self) -> Option<(u32, usize)>;
+
+    fn for_each_neighbour<F: FnMut(u32)>(&self, layer: usize, node: u32, f: F);
+}
+
+fn max_neighbours(layer: usize) -> usize {
+    if layer == 0 {
+        2 * MAX_NEIGHBOURS
+    } else {
+        MAX_NEIGHBOURS
+    }
+}
+
+/// Draws the top layer of a node from an exponentially decaying distribution.
+///
+/// The draw is a hash of the node ordinal, so that building a graph is deterministic.
+fn random_layer(node: u32) -> usize {
+    // splitmix64
+    let mut z = u64::from(node).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    // Uniform in ]0, 1].
+    let uniform = ((z >> 11) + 1) as f64 / (1u64 << 53) as f64;
+    let layer = (-uniform.ln() / (MAX_NEIGHBOURS as f64).ln()) as usize;
+    layer.min(MAX_LAYER)
+}
+
+/// Set of the nodes visited by a search, which can be cleared in constant time.
+struct VisitedNodes {
+    generations: Vec<u32>,
+    generation: u32,
+}
+
+impl VisitedNodes {
+    fn new(num_nodes: u32) -> VisitedNodes {
+        VisitedNodes {
+            generations: vec![0; num_nodes as usize],
+            generation: 1,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.generation += 1;
+    }
+
+    /// Returns `false` if the node was already visited.
+    fn insert(&mut self, node: u32) -> bool {
+        let generation = &mut self.generations[node as usize];
+        if *generation == self.generation {
+            return false;
+        }
+        *generation = self.generation;
+        true
+    }
+}
+
+/// Returns the (at most) `ef` nodes of `layer` the most similar to the searched vector,
+/// sorted by decreasing similarity.
+fn search_layer<G: HnswGraph>(
+    graph: &G,
+    entry_points: &[Candidate],
+    ef: usize,
+    layer: usize,
+    similarity: &impl Fn(u32) -> Score,
+    visited: &mut VisitedNodes,
+) -> Vec<Candidate> {
+    visited.clear();
+    let mut candidates: BinaryHeap<Candidate> = BinaryHeap::new();
+    let mut results: BinaryHeap<Reverse<Candidate>> = BinaryHeap::new();
+    for &entry_point in entry_points {
+        visited.insert(entry_point.node);
+        candidates.push(entry_point);
+        results.push(Reverse(entry_point));
+    }
+    while results.len() > ef {
+        results.pop();
+    }
+    while let Some(candidate) = candidates.pop() {
+        let worst_result = results.peek().map(|Reverse(result)| *result);
+        if matches!(worst_result, Some(worst) if results.len() >= ef && candidate < worst) {
+            break;
+        }
+        graph.for_each_neighbour(layer, candidate.node, |neighbour| {
+            if !visited.insert(neighbour) {
+                return;
+            }
+            let neighbour = Candidate {
+                similarity: similarity(neighbour),
+                node: neighbour,
+            };
+            let is_competitive = results.len() < ef
+                || matches!(results.peek(), Some(Reverse(worst)) if neighbour > *worst);
+            if is_competitive {
+                candidates.push(neighbour);
+                results.push(Reverse(neighbour));
+                if results.len() > ef {
+                    results.pop();
+                }
+            }
+        });
+    }
+    let mut results: Vec<Candidate> = results.into_iter().map(|Reverse(result)| result).collect();
+    results.sort_unstable_by(|left, right| right.cmp(left));
+    results
+}
+
+/// Returns the (at most) `ef` nodes the most similar to the searched vector,
+/// sorted by decreasing similarity.
+pub(crate) fn search<G: HnswGraph>(
+    graph: &G,
+    ef: usize,
+    similarity: &impl Fn(u32) -> Score,
+) -> Vec<Candidate> {
+    let (entry_node, top_layer) = if let Some(entry_point) = graph.entry_point() {
+        entry_point
+    } else {
+        return Vec::new();
+    };
+    let mut visited = VisitedNodes::new(graph.num_nodes());
+    let mut entry_point = Candidate {
+        similarity: similarity(entry_node),
+        node: entry_node,
+    };
+    for layer in (1..=top_layer).rev() {
+        entry_point = search_layer(graph, &[entry_point], 1, layer, similarity, &mut visited)[0];
+    }
+    search_layer(graph, &[entry_point], ef, 0, similarity, &mut visited)
+}
+
+/// In-memory HNSW graph, built by inserting the nodes one after the other.
+pub(crate) struct HnswBuilder {
+    // node -> layer -> neighbours
+    neighbours: Vec<Vec<Vec<u32>>>,
+    entry_point: Option<(u32, usize)>,
+}
+
+impl HnswGraph for HnswBuilder {
+    fn num_nodes(&self) -> u32 {
+        self.neighbours.len() as u32
+    }
+
+    fn entry_point(&self) -> Option<(u32, usize)> {
+        self.entry_point
+    }
+
+    fn for_each_neighbour<F: FnMut(u32)>(&self, layer: usize, node: u32, f: F) {
+        self.neighbours[node as usize][layer]
+            .iter()
+            .copied()
+            .for_each(f);
+    }
+}
+
+impl HnswBuilder {
+    /// Builds the graph of `num_nodes` nodes, given the similarity between two nodes.
+    pub fn build(num_nodes: u32, similarity: impl Fn(u32, u32) -> Score) -> HnswBuilder {
+        let mut builder = HnswBuilder {
+            neighbours: Vec::with_capacity(num_nodes as usize),
+            entry_point: None,
+        };
+        let mut visited = VisitedNodes::new(num_nodes);
+        for node in 0..num_nodes {
+            builder.insert(node, &similarity, &mut visited);
+        }
+        builder
+    }
+
+    fn insert(
+        &mut self,
+        node: u32,
+        similarity: &impl Fn(u32, u32) -> Score,
+        visited: &mut VisitedNodes,
+    ) {
+        let node_layer = random_layer(node);
+        self.neighbours.push(vec![Vec::new(); node_layer + 1]);
+        let (entry_node, top_layer) = if let Some(entry_point) = self.entry_point {
+            entry_point
+        } else {
+            self.entry_point = Some((node, node_layer));
+            return;
+        };
+        let similarity_to_node = |other: u32| similarity(node, other);
+        let mut entry_points = vec![Candidate {
+            similarity: similarity_to_node(entry_node),
+            node: entry_node,
+        }];
+        for layer in (node_layer + 1..=top_layer).rev() {
+            entry_points =
+                search_layer(self, &entry_points, 1, layer, &similarity_to_node, visited);
+        }
+        for layer in (0..=node_layer.min(top_layer)).rev() {
+            entry_points = search_layer(
+                self,
+                &entry_points,
+                EF_CONSTRUCTION,
+                layer,
+                &similarity_to_node,
+                visited,
+            );
+            let max_neighbours = max_neighbours(layer);
+            let node_neighbours: Vec<u32> = entry_points
+                .iter()
+                .take(max_neighbours)
+                .map(|candidate| candidate.node)
+                .collect();
+            for &neighbour in &node_neighbours {
+                let neighbour_neighbours = &mut self.neighbours[neighbour as usize][layer];
+                neighbour_neighbours.push(node);
+                if neighbour_neighbours.len() > max_neighbours {
+                    // Keeps the closest neighbours.
+                    neighbour_neighbours.sort_by_cached_key(|&other| {
+                        Reverse(Candidate {
+                            similarity: similarity(neighbour, other),
+                            node: other,
+                        })
+                    });
+                    neighbour_neighbours.truncate(max_neighbours);
+                }
+            }
+            self.neighbours[node as usize][layer] = node_neighbours;
+        }
+        if node_layer > top_layer {
+            self.entry_point = Some((node, node_layer));
+        }
+    }
+
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let (entry_node, top_layer) = self.entry_point.unwrap_or((u32::MAX, 0));
+        entry_node.serialize(writer)?;
+        (top_layer as u32).serialize(writer)?;
+        for layer in 0..=top_layer {
+            let layer_nodes: Vec<u32> = (0..self.num_nodes())
+                .filter(|&node| self.neighbours[node as usize].len() > layer)
+                .collect();
+            (layer_nodes.len() as u32).serialize(writer)?;
+            if layer > 0 {
+                for &node in &layer_nodes {
+                    node.serialize(writer)?;
+                }
+            }
+            let mut offset = 0u32;
+            offset.serialize(writer)?;
+            for &node in &layer_nodes {
+                offset += self.neighbours[node as usize][layer].len() as u32;
+                offset.serialize(writer)?;
+            }
+            for &node in &layer_nodes {
+                for &neighbour in &self.neighbours[node as usize][layer] {
+                    neighbour.serialize(writer)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn read_u32(bytes: &[u8], idx: usize) -> u32 {
+    let start = idx * 4;
+    u32::from_le_bytes([
+        bytes[start],
+        bytes[start + 1],
+        bytes[start + 2],
+        bytes[start + 3],
+    ])
+}
+
+/// Binary searches `node` within the sorted `u32` ordinals of `nodes`.
+fn find_node(nodes: &[u8], num_nodes: usize, node: u32) -> Option<usize> {
+    let (mut start, mut end) = (0, num_nodes);
+    while start < end {
+        let mid = (start + end) / 2;
+        match read_u32(nodes, mid).cmp(&node) {
+            Ordering::Less => start = mid + 1,
+            Ordering::Greater => end = mid,
+            Ordering::Equal => return Some(mid),
+        }
+    }
+    None
+}
+
+#[derive(Clone)]
+struct HnswLayer {
+    nodes: OwnedBytes,
+    num_nodes: usize,
+    offsets: OwnedBytes,
+    neighbours: OwnedBytes,
+}
+
+/// HNSW graph read from its serialized form.
+#[derive(Clone)]
+pub(crate) struct HnswReader {
+    num_nodes: u32,
+    entry_point: Option<(u32, usize)>,
+    layers: Vec<HnswLayer>,
+}
+
+impl HnswReader {
+    pub fn empty() -> HnswReader {
+        HnswReader {
+            num_nodes: 0,
+            entry_point: None,
+            layers: Vec::new(),
+        }
+    }
+
+    pub fn open(num_nodes: u32, mut data: OwnedBytes) -> io::Result<HnswReader> {
+        let entry_node = u32::deserialize(&mut data)?;
+        let top_layer = u32::deserialize(&mut data)? as usize;
+        let entry_point = if entry_node == u32::MAX {
+            None
+        } else {
+            Some((entry_node, top_layer))
+        };
+        let mut layers = Vec::new();
+        if entry_point.is_some() {
+            for layer in 0..=top_layer {
+                let layer_num_nodes = u32::deserialize(&mut data)? as usize;
+                let nodes_len = if layer == 0 { 0 } else { layer_num_nodes * 4 };
+                let (nodes, rest) = data.split(nodes_len);
+                let (offsets, rest) = rest.split((layer_num_nodes + 1) * 4);
+                let num_neighbours = read_u32(offsets.as_slice(), layer_num_nodes) as usize;
+                let (neighbours, rest) = rest.split(num_neighbours * 4);
+                data = rest;
+                layers.push(HnswLayer {
+                    nodes,
+                    num_nodes: layer_num_nodes,
+                    offsets,
+                    neighbours,
+                });
+            }
+        }
+        Ok(HnswReader {
+            num_nodes,
+            entry_point,
+            layers,
+        })
+    }
+}
+
+impl HnswGraph for HnswReader {
+    fn num_nodes(&self) -> u32 {
+        self.num_nodes
+    }
+
+    fn entry_point(&self) -> Option<(u32, usize)> {
+        self.entry_point
+    }
+
+    fn for_each_neighbour<F: FnMut(u32)>(&self, layer: usize, node: u32, mut f: F) {
+        let layer_data = &self.layers[layer];
+        let idx = if layer == 0 {
+            node as usize
+        } else {
+            match find_node(layer_data.nodes.as_slice(), layer_data.num_nodes, node) {
+                Some(idx) => idx,
+                None => return,
+            }
+        };
+        let offsets = layer_data.offsets.as_slice();
+        let start = read_u32(offsets, idx) as usize;
+        let end = read_u32(offsets, idx + 1) as usize;
+        let neighbours = layer_data.neighbours.as_slice();
+        for neighbour_idx in start..end {
+            f(read_u32(neighbours, neighbour_idx));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{search, HnswBuilder, HnswReader};
+    use crate::directory::OwnedBytes;
+    use crate::Score;
+
+    fn point(node: u32) -> (f32, f32) {
+        ((node % 50) as f32, (node / 50) as f32)
+    }
+
+    fn similarity(left: (f32, f32), right: (f32, f32)) -> Score {
+        let (dx, dy) = (left.0 - right.0, left.1 - right.1);
+        1.0 / (1.0 + dx * dx + dy * dy)
+    }
+
+    #[test]
+    fn test_hnsw_search() {
+        // A 50x40 grid.
+        let num_nodes = 2_000;
+        let builder = HnswBuilder::build(num_nodes, |left, right| {
+            similarity(point(left), point(right))
+        });
+        let mut buffer = Vec::new();
+        builder.serialize(&mut buffer).unwrap();
+        let reader = HnswReader::open(num_nodes, OwnedBytes::new(buffer)).unwrap();
+        let query = (12.2, 30.1);
+        let node_similarity = |node: u32| similarity(point(node), query);
+        for results in [
+            search(&builder, 10, &node_similarity),
+            search(&reader, 10, &node_similarity),
+        ] {
+            assert_eq!(results.len(), 10);
+            assert_eq!(results[0].node, 30 * 50 + 12);
+            assert!(results
+                .windows(2)
+                .all(|window| window[0].similarity >= window[1].similarity));
+        }
+    }
+
+    #[test]
+    fn test_hnsw_empty() {
+        let builder = HnswBuilder::build(0, |_, _| 0.0);
+        let mut buffer = Vec::new();
+        builder.serialize(&mut buffer).unwrap();
+        let reader = HnswReader::open(0, OwnedBytes::new(buffer)).unwrap();
+        assert!(search(&reader, 10, &|_| 0.0).is_empty());
+    }
+}
@@ -0,0 +1,87 @@
+//! Dense vectors of the vector fields, and their nearest neighbour search.
+//!
+//! The vectors of a segment are written in the
+//! [vectors](crate::SegmentComponent::Vectors) component, with one section per
+//! vector field:
+//!
+//! - number of vectors: `u32`
+//! - doc ids of the documents having a vector, in increasing order: `u32` each
+//! - vectors, in the same order: `dimension` times `f32` each
+//! - [HNSW graph](self::hnsw) of the vectors, whose nodes are the vector ordinals
+//!
+//! All numbers are little endian.
+//!
+//! Vectors are searched with a [`KnnQuery`](crate::query::KnnQuery).
+
+mod hnsw;
+mod reader;
+mod serializer;
+mod writer;
+
+pub use self::reader::{VectorReader, VectorReaders};
+pub use self::serializer::VectorsSerializer;
+pub(crate) use self::writer::validate_vectors;
+pub use self::writer::VectorsWriter;
+use crate::schema::VectorMetric;
+use crate::Score;
+
+/// Returns the similarity of two vectors of the same dimension, for the given metric.
+///
+/// The similarity is higher for closer vectors:
+/// - `Cosine`: `(1 + cosine) / 2`, within `[0, 1]`.
+/// - `Dot`: `(1 + dot_product) / 2`, within `[0, 1]` for normalized vectors.
+/// - `L2`: `1 / (1 + squared_distance)`, within `]0, 1]`.
+pub(crate) fn similarity(
+    metric: VectorMetric,
+    left: impl Iterator<Item = f32>,
+    right: impl Iterator<Item = f32>,
+) -> Score {
+    match metric {
+        VectorMetric::Cosine => {
+            let (mut dot_product, mut left_norm, mut right_norm) = (0f32, 0f32, 0f32);
+            for (left_val, right_val) in left.zip(right) {
+                dot_product += left_val * right_val;
+                left_norm += left_val * left_val;
+                right_norm += right_val * right_val;
+            }
+            let norm = (left_norm * right_norm).sqrt();
+            let cosine = if norm > 0.0 { dot_product / norm } else { 0.0 };
+            (1.0 + cosine) / 2.0
+        }
+        VectorMetric::Dot => {
+            let dot_product: f32 = left
+                .zip(right)
+                .map(|(left_val, right_val)| left_val * right_val)
+                .sum();
+            (1.0 + dot_product) / 2.0
+        }
+        VectorMetric::L2 => {
+            let squared_distance: f32 = left
+                .zip(right)
+                .map(|(left_val, right_val)| (left_val - right_val) * (left_val - right_val))
+                .sum();
+            1.0 / (1.0 + squared_distance)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::similarity;
+    use crate::schema::VectorMetric;
+
+    #[test]
+    fn test_similarity() {
+        let sim = |metric: VectorMetric, left: &[f32], right: &[f32]| {
+            similarity(metric, left.iter().copied(), right.iter().copied())
+        };
+        assert_eq!(sim(VectorMetric::Cosine, &[1.0, 0.0], &[2.0, 0.0]), 1.0);
+        assert_eq!(sim(VectorMetric::Cosine, &[1.0, 0.0], &[0.0, 3.0]), 0.5);
+        assert_eq!(sim(VectorMetric::Cosine, &[1.0, 0.0], &[-1.0, 0.0]), 0.0);
+        assert_eq!(sim(VectorMetric::Cosine, &[1.0, 0.0], &[0.0, 0.0]), 0.5);
+        assert_eq!(sim(VectorMetric::Dot, &[0.0, 1.0], &[0.0, 1.0]), 1.0);
+        assert_eq!(sim(VectorMetric::Dot, &[1.0, 0.0], &[0.0, 1.0]), 0.5);
+        assert_eq!(sim(VectorMetric::L2, &[1.0, 2.0], &[1.0, 2.0]), 1.0);
+        assert_eq!(sim(VectorMetric::L2, &[1.0, 2.0], &[2.0, 4.0]), 1.0 / 6.0);
+    }
+}
@@ -0,0 +1,218 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+
+use common::BinarySerializable;
+
+use super::hnsw::{self, read_u32, Candidate, HnswReader};
+use super::similarity;
+use crate::directory::{CompositeFile, FileSlice, OwnedBytes};
+use crate::schema::{Field, FieldType, Schema, VectorOptions};
+use crate::space_usage::PerFieldSpaceUsage;
+use crate::{DocId, Score};
+
+/// Segments with at most this number of vectors are searched exhaustively.
+const EXACT_SEARCH_MAX_NUM_VECTORS: u32 = 1_000;
+
+/// Reader for the vectors of all of the vector fields of a segment.
+#[derive(Clone)]
+pub struct VectorReaders {
+    schema: Schema,
+    data: Arc<CompositeFile>,
+}
+
+impl VectorReaders {
+    /// Creates a vectors reader.
+    pub fn open(file: FileSlice, schema: Schema) -> crate::Result<VectorReaders> {
+        let data = CompositeFile::open(&file)?;
+        Ok(VectorReaders {
+            schema,
+            data: Arc::new(data),
+        })
+    }
+
+    /// Creates a vectors reader for a segment without any vector.
+    pub(crate) fn empty(schema: Schema) -> VectorReaders {
+        VectorReaders {
+            schema,
+            data: Arc::new(CompositeFile::empty()),
+        }
+    }
+
+    /// Returns the `VectorReader` for a specific field.
+    ///
+    /// Returns `None` if the field is not a vector field.
+    pub fn get_field(&self, field: Field) -> crate::Result<Option<VectorReader>> {
+        let options =
+            if let FieldType::Vector(options) = self.schema.get_field_entry(field).field_type() {
+                options.clone()
+            } else {
+                return Ok(None);
+            };
+        if let Some(file) = self.data.open_read(field) {
+            Ok(Some(VectorReader::open(options, file)?))
+        } else {
+            Ok(Some(VectorReader::empty(options)))
+        }
+    }
+
+    /// Return a break down of the space usage per field.
+    pub fn space_usage(&self) -> PerFieldSpaceUsage {
+        self.data.space_usage()
+    }
+}
+
+/// Reads the vectors of a vector field, and searches for their nearest neighbours.
+#[derive(Clone)]
+pub struct VectorReader {
+    options: VectorOptions,
+    num_vectors: u32,
+    doc_ids: OwnedBytes,
+    vectors: OwnedBytes,
+    graph: HnswReader,
+}
+
+impl VectorReader {
+    fn open(options: VectorOptions, file: FileSlice) -> crate::Result<VectorReader> {
+        let mut data = file.read_bytes()?;
+        let num_vectors = u32::deserialize(&mut data)?;
+        let (doc_ids, rest) = data.split(num_vectors as usize * 4);
+        let (vectors, graph_data) = rest.split(num_vectors as usize * options.dimension() * 4);
+        let graph = HnswReader::open(num_vectors, graph_data)?;
+        Ok(VectorReader {
+            options,
+            num_vectors,
+            doc_ids,
+            vectors,
+            graph,
+        })
+    }
+
+    fn empty(options: VectorOptions) -> VectorReader {
+        VectorReader {
+            options,
+            num_vectors: 0,
+            doc_ids: OwnedBytes::empty(),
+            vectors: OwnedBytes::empty(),
+            graph: HnswReader::empty(),
+        }
+    }
+
+    /// Returns the options of the vector field.
+    pub fn options(&self) -> &VectorOptions {
+        &self.options
+    }
+
+    /// Returns the number of documents having a vector, including the deleted ones.
+    pub fn num_vectors(&self) -> u32 {
+        self.num_vectors
+    }
+
+    fn doc(&self, ord: u32) -> DocId {
+        read_u32(self.doc_ids.as_slice(), ord as usize)
+    }
+
+    fn ord(&self, doc: DocId) -> Option<u32> {
+        let (mut start, mut end) = (0, self.num_vectors);
+        while start < end {
+            let mid = (start + end) / 2;
+            let mid_doc = self.doc(mid);
+            if mid_doc < doc {
+                start = mid + 1;
+            } else if mid_doc > doc {
+                end = mid;
+            } else {
+                return Some(mid);
+            }
+        }
+        None
+    }
+
+    fn vector_vals(&self, ord: u32) -> impl Iterator<Item = f32> + '_ {
+        let dimension = self.options.dimension();
+        let start = ord as usize * dimension * 4;
+        self.vectors.as_slice()[start..start + dimension * 4]
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Returns the vector of a document, or `None` if the document has no vector.
+    pub fn vector(&self, doc: DocId) -> Option<Vec<f32>> {
+        self.ord(doc).map(|ord| self.vector_vals(ord).collect())
+    }
+
+    /// Returns the similarity between the vector of a document and `query`,
+    /// or `None` if the document has no vector.
+    pub fn similarity(&self, doc: DocId, query: &[f32]) -> Option<Score> {
+        self.ord(doc).map(|ord| self.similarity_to_ord(ord, query))
+    }
+
+    fn similarity_to_ord(&self, ord: u32, query: &[f32]) -> Score {
+        similarity(
+            self.options.metric(),
+            query.iter().copied(),
+            self.vector_vals(ord),
+        )
+    }
+
+    /// Returns the (at most) `k` accepted documents whose vector is the most similar to
+    /// `query`, along with their similarity, sorted by decreasing similarity.
+    ///
+    /// Unless `exact` is set, the HNSW graph is searched with `num_candidates`
+    /// candidates. The number of candidates is raised if too few of them are accepted,
+    /// up until it becomes cheaper to scan all of the vectors.
+    pub(crate) fn search(
+        &self,
+        query: &[f32],
+        k: usize,
+        num_candidates: usize,
+        exact: bool,
+        accept: impl Fn(DocId) -> bool,
+    ) -> Vec<(DocId, Score)> {
+        if !exact && self.num_vectors > EXACT_SEARCH_MAX_NUM_VECTORS {
+            let similarity_to_query = |ord: u32| self.similarity_to_ord(ord, query);
+            let mut ef = num_candidates.max(k);
+            while ef <= self.num_vectors as usize / 2 {
+                let candidates = hnsw::search(&self.graph, ef, &similarity_to_query);
+                let num_candidates_found = candidates.len();
+                let hits: Vec<(DocId, Score)> = candidates
+                    .into_iter()
+                    .map(|candidate| (self.doc(candidate.node), candidate.similarity))
+                    .filter(|(doc, _)| accept(*doc))
+                    .take(k)
+                    .collect();
+                if hits.len() >= k || num_candidates_found < ef {
+                    return hits;
+                }
+                ef *= 4;
+            }
+        }
+        self.exact_search(query, k, accept)
+    }
+
+    fn exact_search(
+        &self,
+        query: &[f32],
+        k: usize,
+        accept: impl Fn(DocId) -> bool,
+    ) -> Vec<(DocId, Score)> {
+        let mut top_k: BinaryHeap<Reverse<Candidate>> = BinaryHeap::with_capacity(k + 1);
+        for ord in 0..self.num_vectors {
+            if !accept(self.doc(ord)) {
+                continue;
+            }
+            top_k.push(Reverse(Candidate {
+                similarity: self.similarity_to_ord(ord, query),
+                node: ord,
+            }));
+            if top_k.len() > k {
+                top_k.pop();
+            }
+        }
+        top_k
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(candidate)| (self.doc(candidate.node), candidate.similarity))
+            .collect()
+    }
+}
@@ -0,0 +1,64 @@
+use std::io;
+use std::io::Write;
+
+use common::BinarySerializable;
+
+use super::hnsw::HnswBuilder;
+use super::similarity;
+use crate::directory::{CompositeWrite, WritePtr};
+use crate::schema::{Field, VectorOptions};
+use crate::DocId;
+
+/// The vectors serializer is in charge of the serialization of the vectors
+/// of all vector fields, along with their HNSW graph.
+pub struct VectorsSerializer {
+    composite_write: CompositeWrite,
+}
+
+impl VectorsSerializer {
+    /// Constructor
+    pub fn from_write(write: WritePtr) -> io::Result<VectorsSerializer> {
+        let composite_write = CompositeWrite::wrap(write);
+        Ok(VectorsSerializer { composite_write })
+    }
+
+    /// Serializes the vectors of the given field, and builds their HNSW graph.
+    ///
+    /// `doc_ids` must be sorted, and `vectors` contains the vector of each of these
+    /// documents, one after the other.
+    pub fn serialize_field(
+        &mut self,
+        field: Field,
+        vector_options: &VectorOptions,
+        doc_ids: &[DocId],
+        vectors: &[f32],
+    ) -> io::Result<()> {
+        let dimension = vector_options.dimension();
+        let metric = vector_options.metric();
+        debug_assert_eq!(doc_ids.len() * dimension, vectors.len());
+        let vector = |ord: u32| {
+            let start = ord as usize * dimension;
+            vectors[start..start + dimension].iter().copied()
+        };
+        let graph = HnswBuilder::build(doc_ids.len() as u32, |left, right| {
+            similarity(metric, vector(left), vector(right))
+        });
+        let write = self.composite_write.for_field(field);
+        (doc_ids.len() as u32).serialize(write)?;
+        for doc_id in doc_ids {
+            doc_id.serialize(write)?;
+        }
+        for val in vectors {
+            val.serialize(write)?;
+        }
+        graph.serialize(write)?;
+        write.flush()?;
+        Ok(())
+    }
+
+    /// Clean up / flush / close
+    pub fn close(self) -> io::Result<()> {
+        self.composite_write.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+use std::{io, iter};
+
+use super::VectorsSerializer;
+use crate::indexer::doc_id_mapping::DocIdMapping;
+use crate::schema::{Field, FieldType, Schema, VectorOptions};
+use crate::{DocId, Document, TantivyError};
+
+struct FieldVectors {
+    options: VectorOptions,
+    doc_ids: Vec<DocId>,
+    vectors: Vec<f32>,
+}
+
+/// The `VectorsWriter` is in charge of buffering the vectors of the vector fields
+/// until the segment gets serialized.
+pub struct VectorsWriter {
+    per_field_vectors: Vec<Option<FieldVectors>>,
+}
+
+impl VectorsWriter {
+    /// Initialize with state for tracking the vector fields specified in the schema.
+    pub fn for_schema(schema: &Schema) -> VectorsWriter {
+        let mut per_field_vectors: Vec<Option<FieldVectors>> = iter::repeat_with(|| None)
+            .take(schema.num_fields())
+            .collect();
+        for (field, field_entry) in schema.fields() {
+            if let FieldType::Vector(vector_options) = field_entry.field_type() {
+                per_field_vectors[field.field_id() as usize] = Some(FieldVectors {
+                    options: vector_options.clone(),
+                    doc_ids: Vec::new(),
+                    vectors: Vec::new(),
+                });
+            }
+        }
+        VectorsWriter { per_field_vectors }
+    }
+
+    /// The memory used inclusive childs
+    pub fn mem_usage(&self) -> usize {
+        self.per_field_vectors
+            .iter()
+            .flatten()
+            .map(|field_vectors| {
+                field_vectors.doc_ids.capacity() * std::mem::size_of::<DocId>()
+                    + field_vectors.vectors.capacity() * std::mem::size_of::<f32>()
+            })
+            .sum()
+    }
+
+    /// Records the vectors of a document.
+    ///
+    /// The document is rejected if one of its vectors does not have the dimension of its
+    /// field, or if it has more than one vector for a field. In that case, none of its
+    /// vectors is recorded.
+    pub fn add_document(&mut self, doc_id: DocId, doc: &Document) -> crate::Result<()> {
+        let doc_vectors = doc_vectors(doc, |field| {
+            self.per_field_vectors
+                .get(field.field_id() as usize)
+                .and_then(Option::as_ref)
+                .map(|field_vectors| &field_vectors.options)
+        })?;
+        for (field, vector) in doc_vectors {
+            if let Some(field_vectors) = self.per_field_vectors[field.field_id() as usize].as_mut()
+            {
+                field_vectors.doc_ids.push(doc_id);
+                field_vectors.vectors.extend_from_slice(vector);
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize the vectors of all fields, building their HNSW graph.
+    pub fn serialize(
+        &self,
+        mut vectors_serializer: VectorsSerializer,
+        doc_id_map: Option<&DocIdMapping>,
+    ) -> io::Result<()> {
+        for (field_id, field_vectors) in self.per_field_vectors.iter().enumerate() {
+            let field_vectors = if let Some(field_vectors) = field_vectors {
+                field_vectors
+            } else {
+                continue;
+            };
+            let field = Field::from_field_id(field_id as u32);
+            let dimension = field_vectors.options.dimension();
+            if let Some(doc_id_map) = doc_id_map {
+                let mut ords: Vec<usize> = (0..field_vectors.doc_ids.len()).collect();
+                ords.sort_by_key(|&ord| doc_id_map.get_new_doc_id(field_vectors.doc_ids[ord]));
+                let doc_ids: Vec<DocId> = ords
+                    .iter()
+                    .map(|&ord| doc_id_map.get_new_doc_id(field_vectors.doc_ids[ord]))
+                    .collect();
+                let vectors: Vec<f32> = ords
+                    .iter()
+                    .flat_map(|&ord| &field_vectors.vectors[ord * dimension..(ord + 1) * dimension])
+                    .copied()
+                    .collect();
+                vectors_serializer.serialize_field(
+                    field,
+                    &field_vectors.options,
+                    &doc_ids,
+                    &vectors,
+                )?;
+            } else {
+                vectors_serializer.serialize_field(
+                    field,
+                    &field_vectors.options,
+                    &field_vectors.doc_ids,
+                    &field_vectors.vectors,
+                )?;
+            }
+        }
+        vectors_serializer.close()?;
+        Ok(())
+    }
+}
+
+/// Checks that the vectors of a document match the options of their field.
+///
+/// Returns an error if one of the vectors does not have the dimension of its field,
+/// or if the document has more than one vector for a field.
+pub(crate) fn validate_vectors(schema: &Schema, doc: &Document) -> crate::Result<()> {
+    doc_vectors(doc, |field| {
+        if let FieldType::Vector(vector_options) = schema.get_field_entry(field).field_type() {
+            Some(vector_options)
+        } else {
+            None
+        }
+    })?;
+    Ok(())
+}
+
+/// Returns the vectors of the vector fields of a document, given the options of
+/// these fields.
+fn doc_vectors<'a, 'b>(
+    doc: &'a Document,
+    vector_options: impl Fn(Field) -> Option<&'b VectorOptions>,
+) -> crate::Result<Vec<(Field, &'a [f32])>> {
+    let mut doc_vectors: Vec<(Field, &[f32])> = Vec::new();
+    for field_value in doc.field_values() {
+        let field = field_value.field();
+        let options = if let Some(options) = vector_options(field) {
+            options
+        } else {
+            continue;
+        };
+        let vector = field_value.value().as_vector().ok_or_else(|| {
+            TantivyError::SchemaError(format!("Expected a Vector for field {:?}", field))
+        })?;
+        if vector.len() != options.dimension() {
+            return Err(TantivyError::SchemaError(format!(
+                "Expected a vector of dimension {} for field {:?}, got {}",
+                options.dimension(),
+                field,
+                vector.len()
+            )));
+        }
+        if doc_vectors
+            .iter()
+            .any(|(other_field, _)| *other_field == field)
+        {
+            return Err(TantivyError::SchemaError(format!(
+                "A document cannot have more than one vector for field {:?}",
+                field
+            )));
+        }
+        doc_vectors.push((field, vector));
+    }
+    Ok(doc_vectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_vectors, VectorsWriter};
+    use crate::schema::{Schema, VectorOptions};
+    use crate::Document;
+
+    #[test]
+    fn test_vectors_writer_validates_vectors() {
+        let mut schema_builder = Schema::builder();
+        let embedding = schema_builder.add_vector_field("embedding", VectorOptions::new(2));
+        let schema = schema_builder.build();
+        let mut vectors_writer = VectorsWriter::for_schema(&schema);
+
+        let mut doc = Document::default();
+        doc.add_vector(embedding, vec![1.0, 2.0, 3.0]);
+        assert!(validate_vectors(&schema, &doc).is_err());
+        assert!(vectors_writer.add_document(0, &doc).is_err());
+        let mut doc = Document::default();
+        doc.add_vector(embedding, vec![1.0, 2.0]);
+        doc.add_vector(embedding, vec![3.0, 4.0]);
+        assert!(validate_vectors(&schema, &doc).is_err());
+        assert!(vectors_writer.add_document(0, &doc).is_err());
+        let doc = doc!(embedding => 1u64);
+        assert!(validate_vectors(&schema, &doc).is_err());
+        assert!(vectors_writer.add_document(0, &doc).is_err());
+        assert_eq!(vectors_writer.mem_usage(), 0);
+
+        let doc = schema
+            .parse_document(r#"{"embedding": [1.0, 2.0]}"#)
+            .unwrap();
+        assert!(validate_vectors(&schema, &doc).is_ok());
+        assert!(vectors_writer.add_document(0, &doc).is_ok());
+        assert!(vectors_writer.mem_usage() > 0);
+        assert!(schema
+            .parse_document(r#"{"embedding": [1.0, 2.0, 3.0]}"#)
+            .is_err());
+        assert!(schema
+            .parse_document(r#"{"embedding": [[1.0, 2.0], [3.0, 4.0]]}"#)
+            .is_ok());
+    }
+}